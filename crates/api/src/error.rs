@@ -14,6 +14,7 @@ pub enum ApiError {
     InternalError(String),
     VaultError(String),
     ValidationError(String),
+    PayloadTooLarge(String),
 }
 
 impl IntoResponse for ApiError {
@@ -30,6 +31,7 @@ impl IntoResponse for ApiError {
             ApiError::InternalError(msg) => (StatusCode::INTERNAL_SERVER_ERROR, "INTERNAL_ERROR", msg),
             ApiError::VaultError(msg) => (StatusCode::BAD_REQUEST, "VAULT_ERROR", msg),
             ApiError::ValidationError(msg) => (StatusCode::UNPROCESSABLE_ENTITY, "VALIDATION_ERROR", msg),
+            ApiError::PayloadTooLarge(msg) => (StatusCode::PAYLOAD_TOO_LARGE, "PAYLOAD_TOO_LARGE", msg),
         };
 
         let body = Json(json!({
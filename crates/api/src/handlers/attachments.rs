@@ -0,0 +1,229 @@
+use axum::Json;
+use axum::body::{Body, Bytes};
+use axum::extract::{Path, Query, State};
+use axum::http::header::{CONTENT_DISPOSITION, CONTENT_LENGTH, CONTENT_TYPE};
+use axum::response::Response;
+use chamber_vault::{Attachment, MAX_ATTACHMENT_SIZE};
+use chrono::{DateTime, Utc};
+use futures_util::StreamExt;
+use std::sync::Arc;
+
+use crate::auth::AuthenticatedUser;
+use crate::error::{ApiError, ApiResult};
+use crate::models::{ApiResponse, AttachmentResponse, AttachmentUploadParams, ItemAttachmentsResponse};
+use crate::server::AppState;
+
+impl From<Attachment> for AttachmentResponse {
+    fn from(attachment: Attachment) -> Self {
+        Self {
+            id: attachment.id,
+            item_id: attachment.item_id,
+            file_name: attachment.file_name,
+            size: attachment.size,
+            created_at: DateTime::from_timestamp(attachment.created_at.unix_timestamp(), 0).unwrap_or(Utc::now()),
+        }
+    }
+}
+
+/// Lists the attachments of an item without their contents.
+///
+/// # Errors
+/// - `ApiError::Forbidden`: Returned if the authenticated user lacks the required `read:items` scope.
+/// - `ApiError::BadRequest`: Returned if the vault is locked and cannot be accessed.
+/// - `ApiError::NotFound`: Returned if the item with the specified ID is not found.
+/// - `ApiError::VaultError`: Returned if the attachments cannot be read or decrypted.
+pub async fn list_attachments(
+    State(state): State<Arc<AppState>>,
+    AuthenticatedUser(claims): AuthenticatedUser,
+    Path(id): Path<u64>,
+) -> ApiResult<Json<ApiResponse<ItemAttachmentsResponse>>> {
+    if !claims.has_scope("read:items") {
+        return Err(ApiError::Forbidden);
+    }
+
+    if !state.auth.is_vault_unlocked() {
+        return Err(ApiError::BadRequest("Vault is locked".to_string()));
+    }
+
+    let vault = state.vault.lock().await;
+    let item = vault
+        .get_item_by_id(id)
+        .map_err(|e| ApiError::VaultError(e.to_string()))?
+        .ok_or_else(|| ApiError::NotFound("Item not found".to_string()))?;
+    let attachments = vault
+        .list_attachments(id)
+        .map_err(|e| ApiError::VaultError(e.to_string()))?;
+    drop(vault);
+
+    Ok(Json(ApiResponse::new(ItemAttachmentsResponse {
+        id: item.id,
+        name: item.name,
+        attachments: attachments.into_iter().map(AttachmentResponse::from).collect(),
+    })))
+}
+
+/// Attaches the request body to an item as a file called `file_name`.
+///
+/// The body is streamed into the vault and encrypted chunk by chunk, so uploads are not
+/// subject to the JSON body limit and are never buffered in full. They are limited to
+/// [`MAX_ATTACHMENT_SIZE`] bytes instead.
+///
+/// # Errors
+/// - `ApiError::Forbidden`: Returned if the authenticated user lacks the required `write:items` scope.
+/// - `ApiError::BadRequest`: Returned if the vault is locked or the upload is interrupted.
+/// - `ApiError::NotFound`: Returned if the item with the specified ID is not found.
+/// - `ApiError::ValidationError`: Returned if the file name is invalid or already used on the item.
+/// - `ApiError::PayloadTooLarge`: Returned if the body exceeds the maximum attachment size.
+/// - `ApiError::VaultError`: Returned if the attachment cannot be encrypted or stored.
+pub async fn upload_attachment(
+    State(state): State<Arc<AppState>>,
+    AuthenticatedUser(claims): AuthenticatedUser,
+    Path(id): Path<u64>,
+    Query(params): Query<AttachmentUploadParams>,
+    body: Body,
+) -> ApiResult<Json<ApiResponse<AttachmentResponse>>> {
+    if !claims.has_scope("write:items") {
+        return Err(ApiError::Forbidden);
+    }
+
+    if !state.auth.is_vault_unlocked() {
+        return Err(ApiError::BadRequest("Vault is locked".to_string()));
+    }
+
+    let mut upload = state
+        .vault
+        .lock()
+        .await
+        .begin_attachment(id, &params.file_name)
+        .map_err(|e| {
+            if e.to_string() == "Item not found" {
+                ApiError::NotFound("Item not found".to_string())
+            } else {
+                ApiError::ValidationError(e.to_string())
+            }
+        })?;
+
+    // The vault is only locked while a chunk is stored, so other requests are not blocked by
+    // a slow upload
+    let mut stream = body.into_data_stream();
+    while let Some(data) = stream.next().await {
+        let written = match data {
+            Ok(data) if upload.size() + data.len() as u64 > MAX_ATTACHMENT_SIZE => Err(ApiError::PayloadTooLarge(
+                format!("Attachments are limited to {} MiB", MAX_ATTACHMENT_SIZE / (1024 * 1024)),
+            )),
+            Ok(data) => state
+                .vault
+                .lock()
+                .await
+                .write_attachment(&mut upload, &data)
+                .map_err(|e| ApiError::VaultError(e.to_string())),
+            Err(e) => Err(ApiError::BadRequest(format!("Failed to read upload: {e}"))),
+        };
+        if let Err(e) = written {
+            let _ = state.vault.lock().await.abort_attachment(&upload);
+            return Err(e);
+        }
+    }
+
+    let attachment = state
+        .vault
+        .lock()
+        .await
+        .finish_attachment(upload)
+        .map_err(|e| ApiError::VaultError(e.to_string()))?;
+
+    Ok(Json(ApiResponse::new(AttachmentResponse::from(attachment))))
+}
+
+/// Streams the decrypted contents of an attachment.
+///
+/// Chunks are decrypted one at a time as the response is sent.
+///
+/// # Errors
+/// - `ApiError::Forbidden`: Returned if the authenticated user lacks the required `reveal:values` scope.
+/// - `ApiError::BadRequest`: Returned if the vault is locked and cannot be accessed.
+/// - `ApiError::NotFound`: Returned if the item has no attachment with the specified ID.
+/// - `ApiError::VaultError`: Returned if the attachment metadata cannot be decrypted.
+pub async fn download_attachment(
+    State(state): State<Arc<AppState>>,
+    AuthenticatedUser(claims): AuthenticatedUser,
+    Path((id, attachment_id)): Path<(u64, u64)>,
+) -> ApiResult<Response> {
+    if !claims.has_scope("reveal:values") {
+        return Err(ApiError::Forbidden);
+    }
+
+    if !state.auth.is_vault_unlocked() {
+        return Err(ApiError::BadRequest("Vault is locked".to_string()));
+    }
+
+    let attachment = state
+        .vault
+        .lock()
+        .await
+        .get_attachment(attachment_id)
+        .map_err(|e| ApiError::VaultError(e.to_string()))?
+        .filter(|a| a.item_id == id)
+        .ok_or_else(|| ApiError::NotFound("Attachment not found".to_string()))?;
+
+    let disposition = format!(
+        "attachment; filename=\"{}\"",
+        attachment.file_name.replace(['"', '\\'], "_")
+    );
+    let size = attachment.size;
+    let chunks = futures_util::stream::unfold(0, move |index| {
+        let state = Arc::clone(&state);
+        let attachment = attachment.clone();
+        async move {
+            if index >= attachment.chunks {
+                return None;
+            }
+            match state.vault.lock().await.read_attachment_chunk(&attachment, index) {
+                Ok(chunk) => Some((Ok(Bytes::from(chunk.to_vec())), index + 1)),
+                // End the stream after reporting the error, which aborts the response
+                Err(e) => Some((Err(std::io::Error::other(e.to_string())), attachment.chunks)),
+            }
+        }
+    });
+
+    Response::builder()
+        .header(CONTENT_TYPE, "application/octet-stream")
+        .header(CONTENT_LENGTH, size)
+        .header(CONTENT_DISPOSITION, disposition)
+        .body(Body::from_stream(chunks))
+        .map_err(|e| ApiError::InternalError(e.to_string()))
+}
+
+/// Removes an attachment from an item.
+///
+/// # Errors
+/// - `ApiError::Forbidden`: Returned if the authenticated user lacks the required `write:items` scope.
+/// - `ApiError::BadRequest`: Returned if the vault is locked and cannot be accessed.
+/// - `ApiError::NotFound`: Returned if the item has no attachment with the specified ID.
+/// - `ApiError::VaultError`: Returned if the attachment cannot be read or deleted.
+pub async fn delete_attachment(
+    State(state): State<Arc<AppState>>,
+    AuthenticatedUser(claims): AuthenticatedUser,
+    Path((id, attachment_id)): Path<(u64, u64)>,
+) -> ApiResult<Json<ApiResponse<String>>> {
+    if !claims.has_scope("write:items") {
+        return Err(ApiError::Forbidden);
+    }
+
+    if !state.auth.is_vault_unlocked() {
+        return Err(ApiError::BadRequest("Vault is locked".to_string()));
+    }
+
+    let mut vault = state.vault.lock().await;
+    vault
+        .get_attachment(attachment_id)
+        .map_err(|e| ApiError::VaultError(e.to_string()))?
+        .filter(|a| a.item_id == id)
+        .ok_or_else(|| ApiError::NotFound("Attachment not found".to_string()))?;
+    vault
+        .delete_attachment(attachment_id)
+        .map_err(|e| ApiError::VaultError(e.to_string()))?;
+    drop(vault);
+
+    Ok(Json(ApiResponse::new("Attachment deleted successfully".to_string())))
+}
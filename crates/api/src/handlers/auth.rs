@@ -1,6 +1,7 @@
 use axum::Json;
 use axum::extract::State;
 use chrono::{DateTime, Duration, Utc};
+use std::path::{Component, Path, PathBuf};
 use std::sync::Arc;
 
 use crate::auth::AuthenticatedUser;
@@ -19,13 +20,7 @@ pub async fn login(
     Json(request): Json<LoginRequest>,
 ) -> ApiResult<Json<ApiResponse<LoginResponse>>> {
     // Try to unlock the vault with the provided password
-
-    state
-        .vault
-        .lock()
-        .await
-        .unlock(&request.master_password)
-        .map_err(|_| ApiError::Unauthorized)?;
+    let tamper_warning = unlock_vault(&state, &request).await?;
 
     // Set vault as unlocked
     state.auth.set_vault_unlocked(true);
@@ -53,6 +48,7 @@ pub async fn login(
         token,
         expires_at,
         scopes,
+        tamper_warning,
     };
 
     Ok(Json(ApiResponse::new(response)))
@@ -99,16 +95,74 @@ pub async fn session_unlock(
     Json(request): Json<LoginRequest>,
 ) -> ApiResult<Json<ApiResponse<String>>> {
     // Try to unlock the vault with the provided password
-
-    state
-        .vault
-        .lock()
-        .await
-        .unlock(&request.master_password)
-        .map_err(|_| ApiError::Unauthorized)?;
+    let tamper_warning = unlock_vault(&state, &request).await?;
 
     // Set vault as unlocked
     state.auth.set_vault_unlocked(true);
 
-    Ok(Json(ApiResponse::new("Session unlocked successfully".to_string())))
+    let message = match tamper_warning {
+        Some(warning) => format!("Session unlocked successfully; vault tamper warning: {warning}"),
+        None => "Session unlocked successfully".to_string(),
+    };
+    Ok(Json(ApiResponse::new(message)))
+}
+
+/// Unlocks the vault with the request's password and, when given, the keyfile it names in the
+/// server's keyfile directory. A keyfile that does not unlock the vault is not kept.
+///
+/// Returns the vault's tamper warning, if unlocking found one.
+async fn unlock_vault(state: &AppState, request: &LoginRequest) -> ApiResult<Option<String>> {
+    let keyfile = request
+        .keyfile
+        .as_deref()
+        .map(|name| resolve_keyfile(state.keyfile_dir.as_deref(), name))
+        .transpose()?;
+    let master_password = request.master_password.clone();
+    let mut vault = Arc::clone(&state.vault).lock_owned().await;
+    // Reading the keyfile and deriving the key block; keep them off the async workers
+    let (result, tamper_warning) = tokio::task::spawn_blocking(move || {
+        let registered = vault.keyfile().map(Path::to_path_buf);
+        if let Some(keyfile) = keyfile {
+            vault.set_keyfile(Some(keyfile));
+        }
+        let result = vault.unlock(&master_password);
+        if result.is_err() {
+            vault.set_keyfile(registered);
+        }
+        (result, vault.tamper_warning().map(ToString::to_string))
+    })
+    .await
+    .map_err(|e| ApiError::InternalError(e.to_string()))?;
+    result.map_err(|_| ApiError::Unauthorized)?;
+    if let Some(warning) = &tamper_warning {
+        tracing::warn!("Vault tamper warning: {warning}");
+    }
+    Ok(tamper_warning)
+}
+
+/// Resolves a keyfile named in a request to a file in the server's keyfile directory.
+///
+/// Only plain file names are accepted, and the file must still lie in the directory once
+/// symlinks are resolved, so requests cannot point the server at arbitrary files.
+fn resolve_keyfile(dir: Option<&Path>, name: &str) -> ApiResult<PathBuf> {
+    let dir =
+        dir.ok_or_else(|| ApiError::BadRequest("This server does not accept keyfiles from requests".to_string()))?;
+    let mut components = Path::new(name).components();
+    if !matches!(
+        (components.next(), components.next()),
+        (Some(Component::Normal(_)), None)
+    ) {
+        return Err(ApiError::BadRequest(
+            "The keyfile must be the name of a file in the server's keyfile directory".to_string(),
+        ));
+    }
+    let dir = dir
+        .canonicalize()
+        .map_err(|e| ApiError::InternalError(format!("Keyfile directory is not accessible: {e}")))?;
+    let unknown = || ApiError::BadRequest(format!("Unknown keyfile '{name}'"));
+    let path = dir.join(name).canonicalize().map_err(|_| unknown())?;
+    if !path.starts_with(&dir) {
+        return Err(unknown());
+    }
+    Ok(path)
 }
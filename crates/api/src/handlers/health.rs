@@ -213,6 +213,7 @@ pub async fn stats(
             | ItemKind::Document
             | ItemKind::Recovery
             | ItemKind::OAuth
+            | ItemKind::Totp
             | ItemKind::License
             | ItemKind::WifiPassword
             | ItemKind::Server => {
@@ -8,13 +8,21 @@ use crate::auth::AuthenticatedUser;
 use crate::error::{ApiError, ApiResult};
 use crate::models::ApiResponse;
 use crate::server::AppState;
-use chamber_import_export::{ExportFormat, export_items, import_items};
+use chamber_import_export::{
+    BackupKeySource, BackupSecret, ExportFormat, chamber_backup_key_source, export_chamber_backup, export_items,
+    import_chamber_backup, import_items,
+};
+use chamber_vault::{NewItem, Vault};
 use serde::{Deserialize, Serialize};
+use std::path::Path;
 
 #[derive(Debug, Deserialize)]
 pub struct ImportRequest {
     pub format: String,
     pub path: String,
+    /// Passphrase for encrypted backups; backups sealed with the vault key do not need one
+    #[serde(default)]
+    pub passphrase: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -23,6 +31,9 @@ pub struct ExportRequest {
     pub path: String,
     #[serde(default)]
     pub filter: Option<ExportFilter>,
+    /// Passphrase for `backup` exports; when omitted the vault's backup key is used
+    #[serde(default)]
+    pub passphrase: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -88,16 +99,19 @@ pub async fn import_items_handler(
         return Err(ApiError::BadRequest("File does not exist".to_string()));
     }
 
+    let mut vault = state.vault.lock().await;
+
     // Import items from file
-    let new_items = import_items(&path, &format).map_err(|e| ApiError::InternalError(format!("Import failed: {e}")))?;
+    let new_items = read_items(&vault, &path, &format, request.passphrase.as_deref())?;
 
     if new_items.is_empty() {
         return Err(ApiError::BadRequest("No items found in file".to_string()));
     }
 
     // Get existing items to check for conflicts
-    let mut vault = state.vault.lock().await;
-    let existing_items = vault.list_items().map_err(|e| ApiError::VaultError(e.to_string()))?;
+    let existing_items = vault
+        .list_item_headers()
+        .map_err(|e| ApiError::VaultError(e.to_string()))?;
 
     let existing_names: HashSet<String> = existing_items.iter().map(|item| item.name.clone()).collect();
 
@@ -165,12 +179,8 @@ pub async fn export_items_handler(
         }
     }
 
-    let mut items = state
-        .vault
-        .lock()
-        .await
-        .list_items()
-        .map_err(|e| ApiError::VaultError(e.to_string()))?;
+    let vault = state.vault.lock().await;
+    let mut items = vault.list_items().map_err(|e| ApiError::VaultError(e.to_string()))?;
 
     // Apply filters if provided
     if let Some(filter) = &request.filter {
@@ -188,7 +198,18 @@ pub async fn export_items_handler(
 
     let count = items.len();
 
-    export_items(&items, &format, &path).map_err(|e| ApiError::InternalError(format!("Export failed: {e}")))?;
+    let exported = if matches!(format, ExportFormat::ChamberBackup) {
+        if let Some(passphrase) = request.passphrase.as_deref() {
+            export_chamber_backup(&items, &path, &BackupSecret::Passphrase(passphrase))
+        } else {
+            let key = vault.backup_key().map_err(|e| ApiError::VaultError(e.to_string()))?;
+            export_chamber_backup(&items, &path, &BackupSecret::VaultKey(&key))
+        }
+    } else {
+        export_items(&items, &format, &path)
+    };
+    drop(vault);
+    exported.map_err(|e| ApiError::InternalError(format!("Export failed: {e}")))?;
 
     let response = ExportResponse {
         count,
@@ -226,16 +247,16 @@ pub async fn dry_run_import(
         return Err(ApiError::BadRequest("File does not exist".to_string()));
     }
 
+    let vault = state.vault.lock().await;
+
     // Parse items from file
-    let new_items = import_items(&path, &format).map_err(|e| ApiError::InternalError(format!("Import failed: {e}")))?;
+    let new_items = read_items(&vault, &path, &format, request.passphrase.as_deref())?;
 
     // Get existing items to check for conflicts
-    let existing_items = state
-        .vault
-        .lock()
-        .await
-        .list_items()
+    let existing_items = vault
+        .list_item_headers()
         .map_err(|e| ApiError::VaultError(e.to_string()))?;
+    drop(vault);
 
     let existing_names: HashSet<String> = existing_items.iter().map(|item| item.name.clone()).collect();
 
@@ -275,6 +296,29 @@ pub async fn dry_run_import(
     Ok(Json(ApiResponse::new(response)))
 }
 
+fn read_items(vault: &Vault, path: &Path, format: &ExportFormat, passphrase: Option<&str>) -> ApiResult<Vec<NewItem>> {
+    let import_failed = |e: color_eyre::eyre::Error| ApiError::InternalError(format!("Import failed: {e}"));
+
+    if !matches!(format, ExportFormat::ChamberBackup) {
+        return import_items(path, format).map_err(import_failed);
+    }
+
+    match chamber_backup_key_source(path).map_err(import_failed)? {
+        None => import_chamber_backup(path, None).map_err(import_failed),
+        Some(BackupKeySource::Passphrase) => {
+            let passphrase = passphrase
+                .ok_or_else(|| ApiError::BadRequest("This backup is encrypted with a passphrase".to_string()))?;
+            import_chamber_backup(path, Some(&BackupSecret::Passphrase(passphrase)))
+                .map_err(|e| ApiError::BadRequest(format!("Import failed: {e}")))
+        }
+        Some(BackupKeySource::VaultKey) => {
+            let key = vault.backup_key().map_err(|e| ApiError::VaultError(e.to_string()))?;
+            import_chamber_backup(path, Some(&BackupSecret::VaultKey(&key)))
+                .map_err(|e| ApiError::BadRequest(format!("Import failed: {e}")))
+        }
+    }
+}
+
 fn parse_export_format(format_str: &str) -> ApiResult<ExportFormat> {
     match format_str.to_lowercase().as_str() {
         "json" => Ok(ExportFormat::Json),
@@ -8,11 +8,12 @@ use tokio::time::Instant;
 use crate::auth::AuthenticatedUser;
 use crate::error::{ApiError, ApiResult};
 use crate::models::{
-    ApiResponse, CountsResponse, CreateItemRequest, ItemResponse, ItemWithValueResponse, QueryParams, UpdateItemRequest,
+    ApiResponse, CountsResponse, CreateItemRequest, ItemHistoryResponse, ItemResponse, ItemRevisionResponse,
+    ItemWithValueResponse, QueryParams, TotpResponse, UpdateItemRequest,
 };
 use crate::server::AppState;
 use crate::{SearchParams, SearchResponse};
-use chamber_vault::{ItemKind, NewItem};
+use chamber_vault::{Item, ItemHeader, ItemKind, NewItem, Vault, folder_matches, validate_fields};
 
 /// # Errors
 /// - `ApiError::Forbidden`: Returned if the authenticated user lacks the required `read:items` scope.
@@ -31,21 +32,27 @@ pub async fn list_items(
         return Err(ApiError::BadRequest("Vault is locked".to_string()));
     }
 
-    let items = state
-        .vault
-        .lock()
-        .await
-        .list_items()
+    let vault = state.vault.lock().await;
+    let headers = vault
+        .list_item_headers()
         .map_err(|e| ApiError::VaultError(e.to_string()))?;
 
     // Apply filters
-    let mut filtered_items = items;
+    let mut filtered_items = headers;
 
     // Filter by kind
     if let Some(kind) = &params.kind {
         filtered_items.retain(|item| item.kind.as_str().eq_ignore_ascii_case(kind));
     }
 
+    // Filter by tag and folder
+    if let Some(tag) = &params.tag {
+        filtered_items.retain(|item| item.has_tag(tag));
+    }
+    if let Some(folder) = &params.folder {
+        filtered_items.retain(|item| folder_matches(item.folder.as_deref(), folder));
+    }
+
     // Filter by search query
     if let Some(query) = &params.query {
         let query_lower = query.to_lowercase();
@@ -56,7 +63,7 @@ pub async fn list_items(
     match params.sort.as_deref() {
         Some("name") => filtered_items.sort_by(|a, b| a.name.cmp(&b.name)),
         Some("kind") => filtered_items.sort_by(|a, b| a.kind.as_str().cmp(b.kind.as_str())),
-        Some("updated_at") => filtered_items.sort_by(|a, b| a.updated_at.cmp(&b.updated_at)),
+        Some("updated_at") => filtered_items.sort_by_key(|item| item.updated_at),
         _ => filtered_items.sort_by(|a, b| a.name.cmp(&b.name)), // default
     }
 
@@ -66,26 +73,18 @@ pub async fn list_items(
 
     let total = filtered_items.len();
 
-    // Apply pagination
-    let items: Vec<ItemResponse> = filtered_items
+    // Apply pagination; only the values of the returned page are decrypted
+    let items = filtered_items
         .into_iter()
         .skip(params.offset)
         .take(params.limit)
-        .map(|item| ItemResponse {
-            id: item.id,
-            name: item.name,
-            kind: item.kind.as_str().to_string(),
-            created_at: DateTime::from_timestamp(item.created_at.unix_timestamp(), 0).unwrap_or(Utc::now()),
-            updated_at: DateTime::from_timestamp(item.updated_at.unix_timestamp(), 0).unwrap_or(Utc::now()),
-            has_value: !item.value.is_empty(),
-            value_length: item.value.len(),
-            preview: if item.value.len() > 20 {
-                Some(format!("{}...", &item.value[..17]))
-            } else {
-                None
-            },
+        .map(|header| {
+            let item = load_item(&vault, header.id)?;
+            let preview = list_preview(&item.value);
+            Ok(item_response(item, preview))
         })
-        .collect();
+        .collect::<ApiResult<Vec<_>>>()?;
+    drop(vault);
 
     let response = crate::models::ListItemsResponse { items, total };
 
@@ -110,43 +109,60 @@ pub async fn get_item(
         return Err(ApiError::BadRequest("Vault is locked".to_string()));
     }
 
-    let vault = state.vault.lock().await;
-    let items = vault.list_items().map_err(|e| ApiError::VaultError(e.to_string()))?;
-    drop(vault);
+    let item = load_item(&*state.vault.lock().await, id)?;
+    let preview = list_preview(&item.value);
+    let response = item_response(item, preview);
 
-    let item = items
-        .into_iter()
-        .find(|item| item.id == id)
-        .ok_or_else(|| ApiError::NotFound("Item not found".to_string()))?;
+    Ok(Json(ApiResponse::new(response)))
+}
 
-    let response = ItemResponse {
+/// # Errors
+/// - `ApiError::Forbidden`: Returned if the authenticated user lacks the required `reveal:values` scope.
+/// - `ApiError::BadRequest`: Returned if the vault is locked and cannot be accessed.
+/// - `ApiError::VaultError`: Returned if there is an error accessing or listing items from the vault.
+/// - `ApiError::NotFound`: Returned if the item with the specified ID is not found.
+pub async fn get_item_value(
+    State(state): State<Arc<AppState>>,
+    AuthenticatedUser(claims): AuthenticatedUser,
+    Path(id): Path<u64>,
+) -> ApiResult<Json<ApiResponse<ItemWithValueResponse>>> {
+    if !claims.has_scope("reveal:values") {
+        return Err(ApiError::Forbidden);
+    }
+
+    if !state.auth.is_vault_unlocked() {
+        return Err(ApiError::BadRequest("Vault is locked".to_string()));
+    }
+
+    let item = load_item(&*state.vault.lock().await, id)?;
+
+    let response = ItemWithValueResponse {
         id: item.id,
         name: item.name,
         kind: item.kind.as_str().to_string(),
+        value: item.value,
         created_at: DateTime::from_timestamp(item.created_at.unix_timestamp(), 0).unwrap_or(Utc::now()),
         updated_at: DateTime::from_timestamp(item.updated_at.unix_timestamp(), 0).unwrap_or(Utc::now()),
-        has_value: !item.value.is_empty(),
-        value_length: item.value.len(),
-        preview: if item.value.len() > 20 {
-            Some(format!("{}...", &item.value[..17]))
-        } else {
-            None
-        },
+        tags: item.tags,
+        folder: item.folder,
+        fields: item.fields,
     };
 
     Ok(Json(ApiResponse::new(response)))
 }
 
+/// Returns the previous values of an item, oldest first.
+///
 /// # Errors
 /// - `ApiError::Forbidden`: Returned if the authenticated user lacks the required `reveal:values` scope.
 /// - `ApiError::BadRequest`: Returned if the vault is locked and cannot be accessed.
-/// - `ApiError::VaultError`: Returned if there is an error accessing or listing items from the vault.
 /// - `ApiError::NotFound`: Returned if the item with the specified ID is not found.
-pub async fn get_item_value(
+/// - `ApiError::VaultError`: Returned if the history cannot be read or decrypted.
+pub async fn get_item_history(
     State(state): State<Arc<AppState>>,
     AuthenticatedUser(claims): AuthenticatedUser,
     Path(id): Path<u64>,
-) -> ApiResult<Json<ApiResponse<ItemWithValueResponse>>> {
+) -> ApiResult<Json<ApiResponse<ItemHistoryResponse>>> {
     if !claims.has_scope("reveal:values") {
         return Err(ApiError::Forbidden);
     }
@@ -155,28 +171,68 @@ pub async fn get_item_value(
         return Err(ApiError::BadRequest("Vault is locked".to_string()));
     }
 
-    let items = state
-        .vault
-        .lock()
-        .await
-        .list_items()
+    let vault = state.vault.lock().await;
+    let item = load_item(&vault, id)?;
+    let history = vault
+        .item_history(id)
         .map_err(|e| ApiError::VaultError(e.to_string()))?;
+    drop(vault);
 
-    let item = items
+    let revisions = history
         .into_iter()
-        .find(|item| item.id == id)
-        .ok_or_else(|| ApiError::NotFound("Item not found".to_string()))?;
+        .map(|rev| ItemRevisionResponse {
+            revision: rev.revision,
+            value: rev.value,
+            created_at: DateTime::from_timestamp(rev.created_at.unix_timestamp(), 0).unwrap_or(Utc::now()),
+            archived_at: DateTime::from_timestamp(rev.archived_at.unix_timestamp(), 0).unwrap_or(Utc::now()),
+        })
+        .collect();
 
-    let response = ItemWithValueResponse {
+    Ok(Json(ApiResponse::new(ItemHistoryResponse {
         id: item.id,
         name: item.name,
-        kind: item.kind.as_str().to_string(),
-        value: item.value,
-        created_at: DateTime::from_timestamp(item.created_at.unix_timestamp(), 0).unwrap_or(Utc::now()),
-        updated_at: DateTime::from_timestamp(item.updated_at.unix_timestamp(), 0).unwrap_or(Utc::now()),
-    };
+        revisions,
+    })))
+}
 
-    Ok(Json(ApiResponse::new(response)))
+/// Returns the current one-time code of a TOTP item, or of an item carrying a `totp` field.
+///
+/// # Errors
+/// - `ApiError::Forbidden`: Returned if the authenticated user lacks the required `reveal:values` scope.
+/// - `ApiError::BadRequest`: Returned if the vault is locked or the item has no TOTP secret.
+/// - `ApiError::NotFound`: Returned if the item with the specified ID is not found.
+/// - `ApiError::VaultError`: Returned if the vault cannot be read or the TOTP secret is invalid.
+pub async fn get_item_totp(
+    State(state): State<Arc<AppState>>,
+    AuthenticatedUser(claims): AuthenticatedUser,
+    Path(id): Path<u64>,
+) -> ApiResult<Json<ApiResponse<TotpResponse>>> {
+    if !claims.has_scope("reveal:values") {
+        return Err(ApiError::Forbidden);
+    }
+
+    if !state.auth.is_vault_unlocked() {
+        return Err(ApiError::BadRequest("Vault is locked".to_string()));
+    }
+
+    let item = load_item(&*state.vault.lock().await, id)?;
+
+    let totp = item
+        .totp()
+        .map_err(|e| ApiError::VaultError(e.to_string()))?
+        .ok_or_else(|| ApiError::BadRequest("Item has no TOTP secret".to_string()))?;
+    let current = totp.now().map_err(|e| ApiError::InternalError(e.to_string()))?;
+
+    Ok(Json(ApiResponse::new(TotpResponse {
+        id: item.id,
+        name: item.name,
+        code: current.code,
+        digits: totp.digits,
+        period: current.period,
+        remaining_seconds: current.remaining_seconds,
+        issuer: totp.issuer,
+        account: totp.account,
+    })))
 }
 
 /// # Errors
@@ -186,6 +242,7 @@ pub async fn get_item_value(
 /// - The vault is locked
 /// - The item name or value is empty
 /// - The item kind is invalid
+/// - A field is invalid for the item kind, e.g. a non-numeric database port
 /// - There are issues with accessing or updating the vault
 /// - The created item cannot be retrieved
 pub async fn create_item(
@@ -216,10 +273,15 @@ pub async fn create_item(
         .find(|&k| k.as_str().eq_ignore_ascii_case(&request.kind))
         .ok_or_else(|| ApiError::ValidationError("Invalid item kind".to_string()))?;
 
+    validate_fields(kind, &request.fields).map_err(|e| ApiError::ValidationError(e.to_string()))?;
+
     let new_item = NewItem {
         name: request.name.trim().to_string(),
         kind,
         value: request.value,
+        tags: request.tags,
+        folder: request.folder,
+        fields: request.fields,
     };
 
     state
@@ -230,28 +292,15 @@ pub async fn create_item(
         .map_err(|e| ApiError::VaultError(e.to_string()))?;
 
     // Get the created item to return
-    let items = state
+    let created_item = state
         .vault
         .lock()
         .await
-        .list_items()
-        .map_err(|e| ApiError::VaultError(e.to_string()))?;
-
-    let created_item = items
-        .into_iter()
-        .find(|item| item.name == new_item.name && item.kind == new_item.kind)
+        .get_item_by_name(&new_item.name)
+        .map_err(|e| ApiError::VaultError(e.to_string()))?
         .ok_or_else(|| ApiError::InternalError("Failed to retrieve created item".to_string()))?;
 
-    let response = ItemResponse {
-        id: created_item.id,
-        name: created_item.name,
-        kind: created_item.kind.as_str().to_string(),
-        created_at: DateTime::from_timestamp(created_item.created_at.unix_timestamp(), 0).unwrap_or(Utc::now()),
-        updated_at: DateTime::from_timestamp(created_item.updated_at.unix_timestamp(), 0).unwrap_or(Utc::now()),
-        has_value: !created_item.value.is_empty(),
-        value_length: created_item.value.len(),
-        preview: None,
-    };
+    let response = item_response(created_item, None);
 
     Ok(Json(ApiResponse::new(response)))
 }
@@ -262,6 +311,7 @@ pub async fn create_item(
 /// - The user does not have the required 'write:items' scope
 /// - The vault is locked
 /// - The item value is empty
+/// - A field is invalid for the item kind
 /// - No fields are provided for update
 /// - The item is not found
 /// - There are issues with accessing or updating the vault
@@ -279,49 +329,52 @@ pub async fn update_item(
         return Err(ApiError::BadRequest("Vault is locked".to_string()));
     }
 
-    // For now, we can only update the value
-    if let Some(value) = request.value {
-        if value.trim().is_empty() {
-            return Err(ApiError::ValidationError("Value cannot be empty".to_string()));
+    if request.value.is_none() && request.fields.is_none() && request.tags.is_none() && request.folder.is_none() {
+        return Err(ApiError::ValidationError("No fields to update".to_string()));
+    }
+
+    let not_found_or_vault_error = |e: color_eyre::Report| {
+        let error_msg = e.to_string();
+        if error_msg.contains("Item not found") || error_msg.contains("not found") {
+            ApiError::NotFound("Item not found".to_string())
+        } else {
+            ApiError::VaultError(error_msg)
         }
+    };
 
-        state.vault.lock().await.update_item(id, &value).map_err(|e| {
-            let error_msg = e.to_string();
-            if error_msg.contains("Item not found") || error_msg.contains("not found") {
-                ApiError::NotFound("Item not found".to_string())
-            } else {
-                ApiError::VaultError(error_msg)
-            }
-        })?;
+    let mut vault = state.vault.lock().await;
+
+    if request.value.as_deref().is_some_and(|value| value.trim().is_empty()) {
+        return Err(ApiError::ValidationError("Value cannot be empty".to_string()));
+    }
 
-        // Get the updated item
-        let items = state
-            .vault
-            .lock()
-            .await
-            .list_items()
-            .map_err(|e| ApiError::VaultError(e.to_string()))?;
+    if let Some(fields) = &request.fields {
+        let current = load_item(&vault, id)?;
+        validate_fields(current.kind, fields).map_err(|e| ApiError::ValidationError(e.to_string()))?;
+        let value = request.value.as_deref().unwrap_or(&current.value);
+        vault
+            .update_item_with_fields(id, value, fields)
+            .map_err(not_found_or_vault_error)?;
+    } else if let Some(value) = &request.value {
+        vault.update_item(id, value).map_err(not_found_or_vault_error)?;
+    }
 
-        let updated_item = items
-            .into_iter()
-            .find(|item| item.id == id)
-            .ok_or_else(|| ApiError::NotFound("Item not found".to_string()))?;
-
-        let response = ItemResponse {
-            id: updated_item.id,
-            name: updated_item.name,
-            kind: updated_item.kind.as_str().to_string(),
-            created_at: DateTime::from_timestamp(updated_item.created_at.unix_timestamp(), 0).unwrap_or(Utc::now()),
-            updated_at: DateTime::from_timestamp(updated_item.updated_at.unix_timestamp(), 0).unwrap_or(Utc::now()),
-            has_value: !updated_item.value.is_empty(),
-            value_length: updated_item.value.len(),
-            preview: None,
-        };
-
-        Ok(Json(ApiResponse::new(response)))
-    } else {
-        Err(ApiError::ValidationError("No fields to update".to_string()))
+    if request.tags.is_some() || request.folder.is_some() {
+        let current = load_item(&vault, id)?;
+        let tags = request.tags.as_deref().unwrap_or(&current.tags);
+        let folder = request.folder.as_deref().or(current.folder.as_deref());
+        vault
+            .set_item_labels(id, tags, folder)
+            .map_err(not_found_or_vault_error)?;
     }
+
+    // Get the updated item
+    let updated_item = load_item(&vault, id)?;
+    drop(vault);
+
+    let response = item_response(updated_item, None);
+
+    Ok(Json(ApiResponse::new(response)))
 }
 
 /// # Errors
@@ -350,7 +403,7 @@ pub async fn delete_item(
         .delete_item(id)
         .map_err(|e| ApiError::VaultError(e.to_string()))?;
 
-    Ok(Json(ApiResponse::new("Item deleted successfully".to_string())))
+    Ok(Json(ApiResponse::new("Item moved to the trash".to_string())))
 }
 
 /// # Errors
@@ -371,9 +424,12 @@ pub async fn get_counts(
         return Err(ApiError::BadRequest("Vault is locked".to_string()));
     }
 
-    let vault = state.vault.lock().await;
-    let items = vault.list_items().map_err(|e| ApiError::VaultError(e.to_string()))?;
-    drop(vault);
+    let items = state
+        .vault
+        .lock()
+        .await
+        .list_item_headers()
+        .map_err(|e| ApiError::VaultError(e.to_string()))?;
 
     let mut by_kind: HashMap<String, usize> = HashMap::new();
     for item in &items {
@@ -409,17 +465,7 @@ pub async fn copy_item_to_clipboard(
         return Err(ApiError::BadRequest("Vault is locked".to_string()));
     }
 
-    let items = state
-        .vault
-        .lock()
-        .await
-        .list_items()
-        .map_err(|e| ApiError::VaultError(e.to_string()))?;
-
-    let item = items
-        .into_iter()
-        .find(|item| item.id == id)
-        .ok_or_else(|| ApiError::NotFound("Item not found".to_string()))?;
+    let item = load_item(&*state.vault.lock().await, id)?;
 
     // Copy to clipboard using arboard
     let mut clipboard =
@@ -454,44 +500,15 @@ pub async fn search_items(
     }
 
     let vault = state.vault.lock().await;
-    let all_items = vault.list_items().map_err(|e| ApiError::VaultError(e.to_string()))?;
-    drop(vault);
-
-    // Convert to ItemResponse format
-    let mut items: Vec<ItemResponse> = all_items
-        .into_iter()
-        .map(|item| {
-            let preview = if item.value.len() > 50 {
-                Some(format!("{}...", &item.value[..47]))
-            } else if !item.value.is_empty() {
-                Some(item.value.clone())
-            } else {
-                None
-            };
-
-            ItemResponse {
-                id: item.id,
-                name: item.name,
-                kind: item.kind.as_str().to_string(),
-                created_at: DateTime::from_timestamp(item.created_at.unix_timestamp(), 0).unwrap_or(Utc::now()),
-                updated_at: DateTime::from_timestamp(item.updated_at.unix_timestamp(), 0).unwrap_or(Utc::now()),
-                has_value: !item.value.is_empty(),
-                value_length: item.value.len(),
-                preview,
-            }
-        })
-        .collect();
-
-    let total_available = items.len();
-
-    // Apply filtering
-    items = apply_search_filters(items, &params);
-    let total_found = items.len();
+    let headers = vault
+        .list_item_headers()
+        .map_err(|e| ApiError::VaultError(e.to_string()))?;
+    let total_available = headers.len();
 
-    // Apply sorting
-    items = apply_search_sorting(items, &params)?;
+    // Filter and sort on metadata, so only the values of the returned page are decrypted
+    let mut matches = apply_search_filters(headers, &params);
+    let total_found = matches.len();
 
-    // Apply pagination
     let has_more = params.offset + params.limit < total_found;
     let next_offset = if has_more {
         Some(params.offset + params.limit)
@@ -499,7 +516,39 @@ pub async fn search_items(
         None
     };
 
-    let paginated_items: Vec<ItemResponse> = items.into_iter().skip(params.offset).take(params.limit).collect();
+    let page: Vec<Item> = if params.sort.as_deref() == Some("value_length") {
+        // Sorting by value length needs the value of every match
+        let mut items = matches
+            .into_iter()
+            .map(|header| load_item(&vault, header.id))
+            .collect::<ApiResult<Vec<_>>>()?;
+        items.sort_by(|a, b| {
+            let cmp = a.value.len().cmp(&b.value.len());
+            if params.order.as_deref() == Some("desc") {
+                cmp.reverse()
+            } else {
+                cmp
+            }
+        });
+        items.into_iter().skip(params.offset).take(params.limit).collect()
+    } else {
+        apply_search_sorting(&mut matches, &params)?;
+        matches
+            .into_iter()
+            .skip(params.offset)
+            .take(params.limit)
+            .map(|header| load_item(&vault, header.id))
+            .collect::<ApiResult<_>>()?
+    };
+    drop(vault);
+
+    let paginated_items: Vec<ItemResponse> = page
+        .into_iter()
+        .map(|item| {
+            let preview = search_preview(&item.value);
+            item_response(item, preview)
+        })
+        .collect();
 
     let query_time_ms = u64::try_from(start_time.elapsed().as_millis())
         .map_err(|_| ApiError::InternalError("Failed to calculate query time".to_string()))?
@@ -517,7 +566,7 @@ pub async fn search_items(
     Ok(Json(ApiResponse::new(response)))
 }
 
-fn apply_search_filters(mut items: Vec<ItemResponse>, params: &SearchParams) -> Vec<ItemResponse> {
+fn apply_search_filters(mut items: Vec<ItemHeader>, params: &SearchParams) -> Vec<ItemHeader> {
     // Get search query (prefer 'q' over 'query')
     let search_query = params.q.as_ref().or(params.query.as_ref());
     let case_sensitive = params.case_sensitive.unwrap_or(false);
@@ -527,71 +576,55 @@ fn apply_search_filters(mut items: Vec<ItemResponse>, params: &SearchParams) ->
     if let Some(ref kind_filter) = params.kind {
         let kind_lower = kind_filter.to_lowercase();
         items.retain(|item| {
-            let item_kind = item.kind.to_lowercase();
+            let item_kind = item.kind.as_str();
             item_kind == kind_lower || item_kind.contains(&kind_lower)
         });
     }
 
+    // Filter by tag and folder
+    if let Some(ref tag_filter) = params.tag {
+        items.retain(|item| item.has_tag(tag_filter));
+    }
+    if let Some(ref folder_filter) = params.folder {
+        items.retain(|item| folder_matches(item.folder.as_deref(), folder_filter));
+    }
+
     // Filter by name-only search
     if let Some(ref name_filter) = params.name {
-        items.retain(|item| {
-            if case_sensitive {
-                if fuzzy {
-                    fuzzy_match(&item.name, name_filter)
-                } else {
-                    item.name.contains(name_filter)
-                }
-            } else if fuzzy {
-                fuzzy_match(&item.name.to_lowercase(), &name_filter.to_lowercase())
-            } else {
-                item.name.to_lowercase().contains(&name_filter.to_lowercase())
-            }
-        });
+        items.retain(|item| text_matches(&item.name, name_filter, case_sensitive, fuzzy));
     }
 
-    // Apply general search query (searches name, kind, and preview)
+    // Apply general search query (searches name and kind; values are never decrypted for searching)
     if let Some(query) = search_query {
         items.retain(|item| {
-            let search_in_name = if case_sensitive {
-                if fuzzy {
-                    fuzzy_match(&item.name, query)
-                } else {
-                    item.name.contains(query)
-                }
-            } else if fuzzy {
-                fuzzy_match(&item.name.to_lowercase(), &query.to_lowercase())
-            } else {
-                item.name.to_lowercase().contains(&query.to_lowercase())
-            };
-
             let search_in_kind = if case_sensitive {
-                item.kind.contains(query)
+                item.kind.as_str().contains(query.as_str())
             } else {
-                item.kind.to_lowercase().contains(&query.to_lowercase())
+                item.kind.as_str().contains(&query.to_lowercase())
             };
 
-            let search_in_preview = item.preview.as_ref().is_some_and(|preview| {
-                if case_sensitive {
-                    if fuzzy {
-                        fuzzy_match(preview, query)
-                    } else {
-                        preview.contains(query)
-                    }
-                } else if fuzzy {
-                    fuzzy_match(&preview.to_lowercase(), &query.to_lowercase())
-                } else {
-                    preview.to_lowercase().contains(&query.to_lowercase())
-                }
-            });
-
-            search_in_name || search_in_kind || search_in_preview
+            text_matches(&item.name, query, case_sensitive, fuzzy) || search_in_kind
         });
     }
 
     items
 }
 
-fn apply_search_sorting(mut items: Vec<ItemResponse>, params: &SearchParams) -> ApiResult<Vec<ItemResponse>> {
+fn text_matches(text: &str, query: &str, case_sensitive: bool, fuzzy: bool) -> bool {
+    if case_sensitive {
+        if fuzzy {
+            fuzzy_match(text, query)
+        } else {
+            text.contains(query)
+        }
+    } else if fuzzy {
+        fuzzy_match(&text.to_lowercase(), &query.to_lowercase())
+    } else {
+        text.to_lowercase().contains(&query.to_lowercase())
+    }
+}
+
+fn apply_search_sorting(items: &mut [ItemHeader], params: &SearchParams) -> ApiResult<()> {
     let sort_field = params.sort.as_deref().unwrap_or("name");
     let sort_order = params.order.as_deref().unwrap_or("asc");
 
@@ -604,7 +637,7 @@ fn apply_search_sorting(mut items: Vec<ItemResponse>, params: &SearchParams) ->
         }
         "kind" => {
             items.sort_by(|a, b| {
-                let cmp = a.kind.cmp(&b.kind);
+                let cmp = a.kind.as_str().cmp(b.kind.as_str());
                 if sort_order == "desc" { cmp.reverse() } else { cmp }
             });
         }
@@ -620,18 +653,53 @@ fn apply_search_sorting(mut items: Vec<ItemResponse>, params: &SearchParams) ->
                 if sort_order == "desc" { cmp.reverse() } else { cmp }
             });
         }
-        "value_length" => {
-            items.sort_by(|a, b| {
-                let cmp = a.value_length.cmp(&b.value_length);
-                if sort_order == "desc" { cmp.reverse() } else { cmp }
-            });
-        }
         _ => {
             return Err(ApiError::ValidationError(format!("Invalid sort field: {sort_field}")));
         }
     }
 
-    Ok(items)
+    Ok(())
+}
+
+/// Fetches and decrypts a single item, mapping a missing item to `ApiError::NotFound`.
+fn load_item(vault: &Vault, id: u64) -> ApiResult<Item> {
+    vault
+        .get_item_by_id(id)
+        .map_err(|e| ApiError::VaultError(e.to_string()))?
+        .ok_or_else(|| ApiError::NotFound("Item not found".to_string()))
+}
+
+pub(crate) fn item_response(item: Item, preview: Option<String>) -> ItemResponse {
+    ItemResponse {
+        id: item.id,
+        name: item.name,
+        kind: item.kind.as_str().to_string(),
+        created_at: DateTime::from_timestamp(item.created_at.unix_timestamp(), 0).unwrap_or(Utc::now()),
+        updated_at: DateTime::from_timestamp(item.updated_at.unix_timestamp(), 0).unwrap_or(Utc::now()),
+        has_value: !item.value.is_empty(),
+        value_length: item.value.len(),
+        preview,
+        tags: item.tags,
+        folder: item.folder,
+    }
+}
+
+fn list_preview(value: &str) -> Option<String> {
+    if value.len() > 20 {
+        Some(format!("{}...", &value[..17]))
+    } else {
+        None
+    }
+}
+
+fn search_preview(value: &str) -> Option<String> {
+    if value.len() > 50 {
+        Some(format!("{}...", &value[..47]))
+    } else if !value.is_empty() {
+        Some(value.to_string())
+    } else {
+        None
+    }
 }
 
 // Simple fuzzy matching algorithm (Levenshtein distance-based)
@@ -668,8 +736,8 @@ fn levenshtein_distance(s1: &str, s2: &str) -> usize {
     for i in 0..=len1 {
         matrix[i][0] = i;
     }
-    for j in 0..=len2 {
-        matrix[0][j] = j;
+    for (j, cell) in matrix[0].iter_mut().enumerate() {
+        *cell = j;
     }
 
     let s1_chars: Vec<char> = s1.chars().collect();
@@ -0,0 +1,99 @@
+use axum::Json;
+use axum::extract::State;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use crate::auth::AuthenticatedUser;
+use crate::error::{ApiError, ApiResult};
+use crate::handlers::items::item_response;
+use crate::models::{ApiResponse, GenerateSshKeyRequest, SshKeyResponse};
+use crate::server::AppState;
+use chamber_password_gen::{SshKeyConfig, SshKeyType};
+use chamber_vault::{ItemField, ItemKind, NewItem};
+
+/// Generates an SSH key pair and stores it as an `SshKey` item: the private key is the item's
+/// value and the public key is kept in its `public_key` field.
+///
+/// # Errors
+///
+/// This function returns an error if:
+/// - The user does not have the required 'write:items' scope
+/// - The vault is locked
+/// - The name is empty, the key type is unknown or the RSA key size is out of range
+/// - An item with the same name already exists
+/// - There are issues with accessing or updating the vault
+pub async fn generate_ssh_key(
+    State(state): State<Arc<AppState>>,
+    AuthenticatedUser(claims): AuthenticatedUser,
+    Json(request): Json<GenerateSshKeyRequest>,
+) -> ApiResult<Json<ApiResponse<SshKeyResponse>>> {
+    if !claims.has_scope("write:items") {
+        return Err(ApiError::Forbidden);
+    }
+
+    if !state.auth.is_vault_unlocked() {
+        return Err(ApiError::BadRequest("Vault is locked".to_string()));
+    }
+
+    let name = request.name.trim().to_string();
+    if name.is_empty() {
+        return Err(ApiError::ValidationError("Name cannot be empty".to_string()));
+    }
+    let key_type = SshKeyType::from_str(&request.key_type).map_err(|e| ApiError::ValidationError(e.to_string()))?;
+
+    let exists = state
+        .vault
+        .lock()
+        .await
+        .get_item_by_name(&name)
+        .map_err(|e| ApiError::VaultError(e.to_string()))?
+        .is_some();
+    if exists {
+        return Err(ApiError::ValidationError(format!("Item '{name}' already exists")));
+    }
+
+    let config = SshKeyConfig::new()
+        .with_key_type(key_type)
+        .with_bits(request.bits)
+        .with_comment(request.comment.unwrap_or_else(|| name.clone()))
+        .with_passphrase(request.passphrase);
+    // RSA key generation can take seconds; keep it off the async workers
+    let (config, key) = tokio::task::spawn_blocking(move || {
+        let key = config.generate();
+        (config, key)
+    })
+    .await
+    .map_err(|e| ApiError::InternalError(e.to_string()))?;
+    let key = key.map_err(|e| ApiError::ValidationError(e.to_string()))?;
+
+    let mut fields = vec![ItemField::new("public_key", key.public_key.clone(), false)];
+    if let Some(passphrase) = config.passphrase {
+        fields.push(ItemField::new("passphrase", passphrase, true));
+    }
+    let new_item = NewItem {
+        name,
+        kind: ItemKind::SshKey,
+        value: key.private_key,
+        tags: request.tags,
+        folder: request.folder,
+        fields,
+    };
+
+    let created_item = {
+        let mut vault = state.vault.lock().await;
+        vault
+            .create_item(&new_item)
+            .map_err(|e| ApiError::VaultError(e.to_string()))?;
+        vault
+            .get_item_by_name(&new_item.name)
+            .map_err(|e| ApiError::VaultError(e.to_string()))?
+            .ok_or_else(|| ApiError::InternalError("Failed to retrieve created item".to_string()))?
+    };
+
+    let response = SshKeyResponse {
+        item: item_response(created_item, None),
+        public_key: key.public_key,
+        fingerprint: key.fingerprint,
+    };
+    Ok(Json(ApiResponse::new(response)))
+}
@@ -1,16 +1,22 @@
+pub mod attachments;
 pub mod auth;
 pub mod health;
 pub mod import_export;
 pub mod items;
+pub mod keys;
 pub mod passwords;
+pub mod trash;
 pub mod vault;
 
+pub use attachments::{delete_attachment, download_attachment, list_attachments, upload_attachment};
 pub use auth::{login, logout, session_lock, session_unlock};
 pub use health::{health, health_report, stats};
 pub use import_export::{dry_run_import, export_items_handler, import_items_handler};
 pub use items::{
-    copy_item_to_clipboard, create_item, delete_item, get_counts, get_item, get_item_value, list_items, search_items,
-    update_item,
+    copy_item_to_clipboard, create_item, delete_item, get_counts, get_item, get_item_history, get_item_totp,
+    get_item_value, list_items, search_items, update_item,
 };
+pub use keys::generate_ssh_key;
 pub use passwords::{generate_memorable_password_handler, generate_password};
-pub use vault::{create_vault, delete_vault, list_vaults, switch_vault, update_vault};
+pub use trash::{empty_trash, list_trash, purge_trashed_item, restore_trashed_item};
+pub use vault::{create_vault, delete_vault, list_vaults, switch_vault, update_vault, vault_integrity};
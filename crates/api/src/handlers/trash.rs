@@ -0,0 +1,144 @@
+use axum::Json;
+use axum::extract::{Path, State};
+use chrono::{DateTime, Utc};
+use std::sync::Arc;
+
+use crate::auth::AuthenticatedUser;
+use crate::error::{ApiError, ApiResult};
+use crate::handlers::items::item_response;
+use crate::models::{ApiResponse, EmptyTrashResponse, ItemResponse, TrashResponse, TrashedItemResponse};
+use crate::server::AppState;
+use chamber_vault::Vault;
+
+/// # Errors
+/// - `ApiError::Forbidden`: Returned if the authenticated user lacks the required `read:items` scope.
+/// - `ApiError::BadRequest`: Returned if the vault is locked.
+/// - `ApiError::VaultError`: Returned if the trash cannot be listed.
+pub async fn list_trash(
+    State(state): State<Arc<AppState>>,
+    AuthenticatedUser(claims): AuthenticatedUser,
+) -> ApiResult<Json<ApiResponse<TrashResponse>>> {
+    if !claims.has_scope("read:items") {
+        return Err(ApiError::Forbidden);
+    }
+
+    if !state.auth.is_vault_unlocked() {
+        return Err(ApiError::BadRequest("Vault is locked".to_string()));
+    }
+
+    let vault = state.vault.lock().await;
+    let trash = vault.list_trash().map_err(|e| ApiError::VaultError(e.to_string()))?;
+    let retention_days = vault
+        .trash_retention_days()
+        .map_err(|e| ApiError::VaultError(e.to_string()))?;
+    drop(vault);
+
+    let items = trash
+        .into_iter()
+        .map(|item| TrashedItemResponse {
+            id: item.id,
+            name: item.name,
+            kind: item.kind.as_str().to_string(),
+            deleted_at: DateTime::from_timestamp(item.deleted_at.unix_timestamp(), 0).unwrap_or(Utc::now()),
+            purge_at: item
+                .purge_at
+                .and_then(|t| DateTime::from_timestamp(t.unix_timestamp(), 0)),
+        })
+        .collect();
+
+    Ok(Json(ApiResponse::new(TrashResponse { items, retention_days })))
+}
+
+/// # Errors
+/// - `ApiError::Forbidden`: Returned if the authenticated user lacks the required `write:items` scope.
+/// - `ApiError::BadRequest`: Returned if the vault is locked or another item has taken the
+///   item's name.
+/// - `ApiError::NotFound`: Returned if the item is not in the trash.
+/// - `ApiError::VaultError`: Returned if the trash cannot be read.
+pub async fn restore_trashed_item(
+    State(state): State<Arc<AppState>>,
+    AuthenticatedUser(claims): AuthenticatedUser,
+    Path(id): Path<u64>,
+) -> ApiResult<Json<ApiResponse<ItemResponse>>> {
+    if !claims.has_scope("write:items") {
+        return Err(ApiError::Forbidden);
+    }
+
+    if !state.auth.is_vault_unlocked() {
+        return Err(ApiError::BadRequest("Vault is locked".to_string()));
+    }
+
+    let mut vault = state.vault.lock().await;
+    ensure_trashed(&vault, id)?;
+    vault
+        .restore_item(id)
+        .map_err(|e| ApiError::BadRequest(e.to_string()))?;
+    let item = vault
+        .get_item_by_id(id)
+        .map_err(|e| ApiError::VaultError(e.to_string()))?
+        .ok_or_else(|| ApiError::NotFound("Item not found".to_string()))?;
+    drop(vault);
+
+    Ok(Json(ApiResponse::new(item_response(item, None))))
+}
+
+/// # Errors
+/// - `ApiError::Forbidden`: Returned if the authenticated user lacks the required `write:items` scope.
+/// - `ApiError::BadRequest`: Returned if the vault is locked.
+/// - `ApiError::NotFound`: Returned if the item is not in the trash.
+/// - `ApiError::VaultError`: Returned if the item cannot be deleted.
+pub async fn purge_trashed_item(
+    State(state): State<Arc<AppState>>,
+    AuthenticatedUser(claims): AuthenticatedUser,
+    Path(id): Path<u64>,
+) -> ApiResult<Json<ApiResponse<String>>> {
+    if !claims.has_scope("write:items") {
+        return Err(ApiError::Forbidden);
+    }
+
+    if !state.auth.is_vault_unlocked() {
+        return Err(ApiError::BadRequest("Vault is locked".to_string()));
+    }
+
+    let mut vault = state.vault.lock().await;
+    ensure_trashed(&vault, id)?;
+    vault.purge_item(id).map_err(|e| ApiError::VaultError(e.to_string()))?;
+    drop(vault);
+
+    Ok(Json(ApiResponse::new("Item permanently deleted".to_string())))
+}
+
+/// # Errors
+/// - `ApiError::Forbidden`: Returned if the authenticated user lacks the required `write:items` scope.
+/// - `ApiError::BadRequest`: Returned if the vault is locked.
+/// - `ApiError::VaultError`: Returned if the trash cannot be emptied.
+pub async fn empty_trash(
+    State(state): State<Arc<AppState>>,
+    AuthenticatedUser(claims): AuthenticatedUser,
+) -> ApiResult<Json<ApiResponse<EmptyTrashResponse>>> {
+    if !claims.has_scope("write:items") {
+        return Err(ApiError::Forbidden);
+    }
+
+    if !state.auth.is_vault_unlocked() {
+        return Err(ApiError::BadRequest("Vault is locked".to_string()));
+    }
+
+    let purged = state
+        .vault
+        .lock()
+        .await
+        .empty_trash()
+        .map_err(|e| ApiError::VaultError(e.to_string()))?;
+
+    Ok(Json(ApiResponse::new(EmptyTrashResponse { purged })))
+}
+
+fn ensure_trashed(vault: &Vault, id: u64) -> ApiResult<()> {
+    let trash = vault.list_trash().map_err(|e| ApiError::VaultError(e.to_string()))?;
+    if trash.iter().any(|item| item.id == id) {
+        Ok(())
+    } else {
+        Err(ApiError::NotFound("Item not found in the trash".to_string()))
+    }
+}
@@ -9,7 +9,7 @@ use crate::auth::AuthenticatedUser;
 use crate::error::{ApiError, ApiResult};
 use crate::models::ApiResponse;
 use crate::server::AppState;
-use chamber_vault::VaultCategory;
+use chamber_vault::{IntegrityIssue, Vault, VaultCategory};
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Serialize)]
@@ -45,6 +45,34 @@ pub struct DeleteVaultRequest {
     pub delete_file: bool,
 }
 
+#[derive(Debug, Serialize)]
+pub struct IntegrityIssueInfo {
+    pub row: String,
+    pub problem: String,
+}
+
+impl From<&IntegrityIssue> for IntegrityIssueInfo {
+    fn from(issue: &IntegrityIssue) -> Self {
+        Self {
+            row: issue.row.to_string(),
+            problem: issue.problem.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct IntegrityResponse {
+    pub healthy: bool,
+    pub database: Vec<String>,
+    pub key_material: Vec<String>,
+    /// Item contents are only decrypted when the vault is unlocked on the server.
+    pub decryption_checked: bool,
+    pub items_checked: usize,
+    pub undecryptable: Vec<IntegrityIssueInfo>,
+    pub orphans: Vec<IntegrityIssueInfo>,
+    pub quarantined: usize,
+}
+
 /// # Errors
 ///
 /// This function returns an error if:
@@ -261,3 +289,61 @@ pub async fn delete_vault(
 
     Ok(Json(ApiResponse::new(format!("Deleted vault: {vault_id}"))))
 }
+
+/// Checks a registered vault for corruption and tampering.
+///
+/// Item contents are decrypted when the vault is open in the vault manager, or is the vault
+/// of the unlocked session; otherwise only the checks that need no key are run.
+///
+/// # Errors
+///
+/// This function returns an error if:
+/// - The user does not have the required 'vault:health' scope
+/// - The vault is not registered or its file does not exist
+/// - The vault cannot be opened or queried
+pub async fn vault_integrity(
+    State(state): State<Arc<AppState>>,
+    AuthenticatedUser(claims): AuthenticatedUser,
+    Path(vault_id): Path<String>,
+) -> ApiResult<Json<ApiResponse<IntegrityResponse>>> {
+    if !claims.has_scope("vault:health") {
+        return Err(ApiError::Forbidden);
+    }
+
+    let mut vault_manager = state.vault_manager.lock().await;
+    let path = vault_manager
+        .registry
+        .get_vault(&vault_id)
+        .ok_or_else(|| ApiError::NotFound(format!("Vault '{vault_id}' not found")))?
+        .path
+        .clone();
+    let open_report = vault_manager.get_vault(&vault_id).map(|vault| vault.check_integrity());
+    drop(vault_manager);
+
+    let report = if let Some(report) = open_report {
+        report
+    } else {
+        let session_vault = state.vault.lock().await;
+        if state.auth.is_vault_unlocked() && session_vault.db_path() == path {
+            session_vault.check_integrity()
+        } else {
+            drop(session_vault);
+            if !path.exists() {
+                return Err(ApiError::NotFound(format!("Vault file {} not found", path.display())));
+            }
+            Vault::open_or_create(Some(&path)).and_then(|vault| vault.check_integrity())
+        }
+    }
+    .map_err(|e| ApiError::VaultError(e.to_string()))?;
+
+    Ok(Json(ApiResponse::new(IntegrityResponse {
+        healthy: report.is_healthy(),
+        database: report.database,
+        key_material: report.key_material,
+        decryption_checked: report.decryption_checked,
+        items_checked: report.items_checked,
+        undecryptable: report.undecryptable.iter().map(IntegrityIssueInfo::from).collect(),
+        orphans: report.orphans.iter().map(IntegrityIssueInfo::from).collect(),
+        quarantined: report.quarantined,
+    })))
+}
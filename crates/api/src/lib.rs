@@ -24,6 +24,7 @@ use color_eyre::Result;
 ///
 /// * `vault` - An instance of `chamber_vault::Vault` used to manage secrets or configuration needed by the server.
 /// * `bind_address` - A `&str` that specifies the address and port the server will bind to (e.g., "0.0.0.0:8080").
+/// * `keyfile_dir` - Directory whose keyfiles clients may name when logging in, if any.
 ///
 /// # Returns
 ///
@@ -39,6 +40,7 @@ pub async fn init_api_server(
     vault: chamber_vault::Vault,
     vault_manager: VaultManager,
     bind_address: &str,
+    keyfile_dir: Option<std::path::PathBuf>,
 ) -> Result<ApiServer> {
-    ApiServer::new(vault, vault_manager, bind_address).await
+    ApiServer::new(vault, vault_manager, bind_address, keyfile_dir).await
 }
@@ -1,3 +1,4 @@
+use chamber_vault::ItemField;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
@@ -30,6 +31,10 @@ pub struct ItemResponse {
     pub value_length: usize,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub preview: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub folder: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -40,6 +45,84 @@ pub struct ItemWithValueResponse {
     pub value: String,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub folder: Option<String>,
+    #[serde(default)]
+    pub fields: Vec<ItemField>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ItemRevisionResponse {
+    pub revision: u64,
+    pub value: String,
+    pub created_at: DateTime<Utc>,
+    pub archived_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ItemHistoryResponse {
+    pub id: u64,
+    pub name: String,
+    pub revisions: Vec<ItemRevisionResponse>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TrashedItemResponse {
+    pub id: u64,
+    pub name: String,
+    pub kind: String,
+    pub deleted_at: DateTime<Utc>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub purge_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TrashResponse {
+    pub items: Vec<TrashedItemResponse>,
+    /// Days deleted items are kept; 0 keeps them until the trash is emptied.
+    pub retention_days: u32,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct EmptyTrashResponse {
+    pub purged: usize,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TotpResponse {
+    pub id: u64,
+    pub name: String,
+    pub code: String,
+    pub digits: u32,
+    pub period: u64,
+    pub remaining_seconds: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub issuer: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub account: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AttachmentResponse {
+    pub id: u64,
+    pub item_id: u64,
+    pub file_name: String,
+    pub size: u64,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ItemAttachmentsResponse {
+    pub id: u64,
+    pub name: String,
+    pub attachments: Vec<AttachmentResponse>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AttachmentUploadParams {
+    pub file_name: String,
 }
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -47,6 +130,13 @@ pub struct CreateItemRequest {
     pub name: String,
     pub kind: String,
     pub value: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub folder: Option<String>,
+    /// Typed fields of the item's kind and custom fields, validated against the kind's schema.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub fields: Vec<ItemField>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -57,11 +147,24 @@ pub struct UpdateItemRequest {
     pub kind: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub value: Option<String>,
+    /// Replaces the item's tags when present.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tags: Option<Vec<String>>,
+    /// Replaces the item's folder when present; an empty string removes the folder.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub folder: Option<String>,
+    /// Replaces all fields of the item when present.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fields: Option<Vec<ItemField>>,
 }
 
 #[derive(Debug, Deserialize, Serialize)]
 pub struct LoginRequest {
     pub master_password: String,
+    /// File name of the vault's keyfile in the server's keyfile directory (`chamber api
+    /// --keyfile-dir`), for vaults that require one and have none registered.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub keyfile: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -69,6 +172,10 @@ pub struct LoginResponse {
     pub token: String,
     pub expires_at: DateTime<Utc>,
     pub scopes: Vec<String>,
+    /// Set when the vault's items do not match its signed manifest, e.g. because items were
+    /// deleted from the file or the file was replaced by an older copy
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tamper_warning: Option<String>,
 }
 
 #[allow(clippy::struct_excessive_bools)]
@@ -94,6 +201,36 @@ pub struct PasswordResponse {
     pub strength: String,
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GenerateSshKeyRequest {
+    /// Name of the `SshKey` item that will hold the key pair.
+    pub name: String,
+    /// `ed25519` or `rsa`.
+    #[serde(default = "default_ssh_key_type")]
+    pub key_type: String,
+    /// Key size in bits; only used for RSA keys.
+    #[serde(default = "default_ssh_key_bits")]
+    pub bits: usize,
+    /// Comment embedded in the public key; defaults to the item name.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
+    /// Encrypts the private key; it is also stored in the item's `passphrase` field.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub passphrase: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub folder: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SshKeyResponse {
+    pub item: ItemResponse,
+    /// The public key as an `authorized_keys` line.
+    pub public_key: String,
+    pub fingerprint: String,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct HealthResponse {
     pub status: String,
@@ -113,6 +250,10 @@ pub struct QueryParams {
     pub query: Option<String>,
     #[serde(default)]
     pub kind: Option<String>,
+    #[serde(default)]
+    pub tag: Option<String>,
+    #[serde(default)]
+    pub folder: Option<String>,
     #[serde(default = "default_limit")]
     pub limit: usize,
     #[serde(default)]
@@ -129,6 +270,12 @@ const fn default_length() -> usize {
 const fn default_true() -> bool {
     true
 }
+fn default_ssh_key_type() -> String {
+    "ed25519".to_string()
+}
+const fn default_ssh_key_bits() -> usize {
+    4096
+}
 const fn default_limit() -> usize {
     50
 }
@@ -173,19 +320,23 @@ pub struct StatsResponse {
 #[derive(Debug, Deserialize)]
 pub struct SearchParams {
     #[serde(default)]
-    pub q: Option<String>, // Search query (matches name, kind, or value preview)
+    pub q: Option<String>, // Search query (matches name or kind)
     #[serde(default)]
     pub query: Option<String>, // Alias for 'q' for compatibility
     #[serde(default)]
     pub kind: Option<String>, // Filter by item type
     #[serde(default)]
     pub name: Option<String>, // Search in item names only
+    #[serde(default)]
+    pub tag: Option<String>, // Filter by tag
+    #[serde(default)]
+    pub folder: Option<String>, // Filter by folder (includes subfolders)
     #[serde(default = "default_limit")]
     pub limit: usize, // Maximum results to return
     #[serde(default)]
     pub offset: usize, // Pagination offset
     #[serde(default)]
-    pub sort: Option<String>, // Sort field (name, created_at, updated_at, kind)
+    pub sort: Option<String>, // Sort field (name, created_at, updated_at, kind, value_length)
     #[serde(default)]
     pub order: Option<String>, // Sort order (asc, desc)
     #[serde(default)]
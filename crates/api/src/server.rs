@@ -5,6 +5,7 @@ use axum::{
     middleware,
     routing::{delete, get, patch, post, put},
 };
+use std::path::PathBuf;
 use std::sync::Arc;
 use tokio::net::TcpListener;
 use tower_http::cors::{Any, CorsLayer};
@@ -24,6 +25,9 @@ pub struct AppState {
     pub vault: Arc<tokio::sync::Mutex<Vault>>,
     pub vault_manager: Arc<tokio::sync::Mutex<VaultManager>>,
     pub auth: AuthState,
+    /// Directory whose keyfiles clients may name when logging in. Without one, only the
+    /// keyfile registered for the vault is used.
+    pub keyfile_dir: Option<PathBuf>,
 }
 
 impl ApiServer {
@@ -31,11 +35,17 @@ impl ApiServer {
     /// This function will return an error if:
     /// - The TCP binding to the specified address fails.
     /// - There are issues configuring the router.
-    pub async fn new(vault: Vault, vault_manager: VaultManager, bind_address: &str) -> color_eyre::Result<Self> {
+    pub async fn new(
+        vault: Vault,
+        vault_manager: VaultManager,
+        bind_address: &str,
+        keyfile_dir: Option<PathBuf>,
+    ) -> color_eyre::Result<Self> {
         let state = AppState {
             vault: Arc::new(tokio::sync::Mutex::new(vault)),
             vault_manager: Arc::new(tokio::sync::Mutex::new(vault_manager)),
             auth: AuthState::new(),
+            keyfile_dir,
         };
 
         let app = build_router(Arc::new(state))?;
@@ -93,13 +103,36 @@ pub fn build_router(state: Arc<AppState>) -> color_eyre::Result<Router> {
         .route("/api/v1/items/{id}", put(handlers::update_item))
         .route("/api/v1/items/{id}", delete(handlers::delete_item))
         .route("/api/v1/items/{id}/value", get(handlers::get_item_value))
+        .route("/api/v1/items/{id}/history", get(handlers::get_item_history))
+        .route("/api/v1/items/{id}/totp", get(handlers::get_item_totp))
         .route("/api/v1/items/{id}/copy", post(handlers::copy_item_to_clipboard))
+        .route("/api/v1/items/{id}/attachments", get(handlers::list_attachments))
+        // Uploads are streamed into the vault and capped there, not by the JSON body limit
+        .route(
+            "/api/v1/items/{id}/attachments",
+            post(handlers::upload_attachment).layer(DefaultBodyLimit::disable()),
+        )
+        .route(
+            "/api/v1/items/{id}/attachments/{attachment_id}",
+            get(handlers::download_attachment),
+        )
+        .route(
+            "/api/v1/items/{id}/attachments/{attachment_id}",
+            delete(handlers::delete_attachment),
+        )
+        // Trash
+        .route("/api/v1/trash", get(handlers::list_trash))
+        .route("/api/v1/trash", delete(handlers::empty_trash))
+        .route("/api/v1/trash/{id}", delete(handlers::purge_trashed_item))
+        .route("/api/v1/trash/{id}/restore", post(handlers::restore_trashed_item))
         // Password generation
         .route("/api/v1/passwords/generate", post(handlers::generate_password))
         .route(
             "/api/v1/passwords/memorable",
             post(handlers::generate_memorable_password_handler),
         )
+        // Key generation
+        .route("/api/v1/keys/ssh", post(handlers::generate_ssh_key))
         // Import/Export
         .route("/api/v1/import", post(handlers::import_items_handler))
         .route("/api/v1/export", post(handlers::export_items_handler))
@@ -110,6 +143,7 @@ pub fn build_router(state: Arc<AppState>) -> color_eyre::Result<Router> {
         .route("/api/v1/vaults/{id}/switch", post(handlers::switch_vault))
         .route("/api/v1/vaults/{id}", patch(handlers::update_vault))
         .route("/api/v1/vaults/{id}", delete(handlers::delete_vault))
+        .route("/api/v1/vaults/{id}/integrity", get(handlers::vault_integrity))
         .route("/api/v1/health/report", get(handlers::health_report))
         .route("/api/v1/stats", get(handlers::stats))
         .layer(middleware::from_fn_with_state(Arc::clone(&state), auth_middleware))
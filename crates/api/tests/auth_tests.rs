@@ -42,6 +42,7 @@ async fn test_login_invalid_password() -> color_eyre::Result<()> {
 
     let login_request = LoginRequest {
         master_password: "wrong_password".to_string(),
+        keyfile: None,
     };
 
     let response = ctx.server.post("/api/v1/auth/login").json(&login_request).await;
@@ -56,6 +57,7 @@ async fn test_login_empty_password() -> color_eyre::Result<()> {
 
     let login_request = LoginRequest {
         master_password: String::new(),
+        keyfile: None,
     };
 
     let response = ctx.server.post("/api/v1/auth/login").json(&login_request).await;
@@ -64,6 +66,68 @@ async fn test_login_empty_password() -> color_eyre::Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn test_login_with_keyfile() -> color_eyre::Result<()> {
+    let (ctx, keyfile) = TestContext::with_keyfile()?;
+
+    let without_keyfile = sample_login_request();
+    let response = ctx.server.post("/api/v1/auth/login").json(&without_keyfile).await;
+    response.assert_status_unauthorized();
+
+    let missing_keyfile = LoginRequest {
+        keyfile: Some("missing.key".to_string()),
+        ..sample_login_request()
+    };
+    let response = ctx.server.post("/api/v1/auth/login").json(&missing_keyfile).await;
+    response.assert_status_bad_request();
+
+    // Files outside the keyfile directory cannot be named, not even the keyfile itself
+    let outside = ctx.temp_dir.path().join("outside.key");
+    std::fs::copy(&keyfile, &outside)?;
+    for name in [
+        outside.display().to_string(),
+        "../outside.key".to_string(),
+        keyfile.display().to_string(),
+    ] {
+        let request = LoginRequest {
+            keyfile: Some(name),
+            ..sample_login_request()
+        };
+        let response = ctx.server.post("/api/v1/auth/login").json(&request).await;
+        response.assert_status_bad_request();
+    }
+
+    let with_keyfile = LoginRequest {
+        keyfile: Some("test_vault.key".to_string()),
+        ..sample_login_request()
+    };
+    let response = ctx.server.post("/api/v1/auth/login").json(&with_keyfile).await;
+    response.assert_status_ok();
+
+    // The keyfile stays in use for the session, so unlocking again needs only the password
+    let token = response.json::<ApiResponse<LoginResponse>>().data.token;
+    let response = ctx
+        .server
+        .post("/api/v1/session/unlock")
+        .authorization_bearer(&token)
+        .json(&sample_login_request())
+        .await;
+    response.assert_status_ok();
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_login_refuses_keyfiles_without_keyfile_directory() -> color_eyre::Result<()> {
+    let ctx = TestContext::new()?;
+    let request = LoginRequest {
+        keyfile: Some("/dev/zero".to_string()),
+        ..sample_login_request()
+    };
+    let response = ctx.server.post("/api/v1/auth/login").json(&request).await;
+    response.assert_status_bad_request();
+    Ok(())
+}
+
 #[tokio::test]
 async fn test_login_malformed_request() -> color_eyre::Result<()> {
     let ctx = TestContext::new()?;
@@ -12,6 +12,7 @@ use tempfile::TempDir;
 pub fn sample_login_request() -> LoginRequest {
     LoginRequest {
         master_password: "test_master_password_123".to_string(),
+        keyfile: None,
     }
 }
 
@@ -20,6 +21,9 @@ pub fn sample_create_item_request() -> CreateItemRequest {
         name: "Test Password".to_string(),
         kind: "password".to_string(),
         value: "super_secret_password_123".to_string(),
+        tags: Vec::new(),
+        folder: None,
+        fields: Vec::new(),
     }
 }
 
@@ -28,6 +32,9 @@ pub fn sample_update_item_request() -> UpdateItemRequest {
         name: Some("Updated Password".to_string()),
         kind: Some("password".to_string()),
         value: Some("new_super_secret_password_456".to_string()),
+        tags: None,
+        folder: None,
+        fields: None,
     }
 }
 
@@ -3,6 +3,7 @@ use axum::Router;
 use axum_test::TestServer;
 use chamber_api::{AuthState, models::*, server::AppState};
 use chamber_vault::{Vault, VaultManager};
+use std::path::PathBuf;
 use std::sync::Arc;
 use tempfile::TempDir;
 use time::OffsetDateTime;
@@ -30,7 +31,7 @@ impl TestContext {
         vault.initialize(master_password)?;
 
         // Create a test router with isolated state
-        let app = create_test_router(vault, vault_manager)?;
+        let app = create_test_router(vault, vault_manager, None)?;
         let server = TestServer::new(app).unwrap();
 
         Ok(Self {
@@ -41,9 +42,42 @@ impl TestContext {
         })
     }
 
+    /// Like [`TestContext::new`], but the vault also requires a keyfile that is not registered
+    /// with the server, so clients have to name it. The keyfile lies in the server's keyfile
+    /// directory, a `keys` directory in the temp dir. Returns the keyfile path.
+    pub fn with_keyfile() -> color_eyre::Result<(Self, PathBuf)> {
+        let temp_dir = tempfile::tempdir()?;
+        let vault_path = temp_dir.path().join("test_vault.db");
+        let keyfile_dir = temp_dir.path().join("keys");
+        std::fs::create_dir(&keyfile_dir)?;
+        let keyfile = keyfile_dir.join("test_vault.key");
+        chamber_vault::generate_keyfile(&keyfile)?;
+
+        let vault_manager = create_isolated_vault_manager(&temp_dir);
+        let mut vault = Vault::open_or_create(Some(&vault_path))?;
+        let master_password = "test_master_password_123";
+        vault.set_keyfile(Some(keyfile.clone()));
+        vault.initialize(master_password)?;
+        vault.set_keyfile(None);
+
+        let app = create_test_router(vault, vault_manager, Some(keyfile_dir))?;
+        let server = TestServer::new(app).unwrap();
+
+        Ok((
+            Self {
+                server,
+                temp_dir,
+                master_password: master_password.to_string(),
+                auth_token: None,
+            },
+            keyfile,
+        ))
+    }
+
     pub async fn login(&mut self) -> color_eyre::Result<String> {
         let login_request = LoginRequest {
             master_password: self.master_password.clone(),
+            keyfile: None,
         };
 
         let response = self.server.post("/api/v1/auth/login").json(&login_request).await;
@@ -99,6 +133,9 @@ impl TestContext {
             name: name.to_string(),
             kind: kind.to_string(),
             value: value.to_string(),
+            tags: Vec::new(),
+            folder: None,
+            fields: Vec::new(),
         };
 
         let response = self
@@ -139,11 +176,16 @@ impl TestContext {
 }
 
 // Helper function to create a test router
-fn create_test_router(vault: Vault, vault_manager: VaultManager) -> color_eyre::Result<Router> {
+fn create_test_router(
+    vault: Vault,
+    vault_manager: VaultManager,
+    keyfile_dir: Option<PathBuf>,
+) -> color_eyre::Result<Router> {
     let state = AppState {
         vault: Arc::new(tokio::sync::Mutex::new(vault)),
         vault_manager: Arc::new(tokio::sync::Mutex::new(vault_manager)),
         auth: AuthState::new(),
+        keyfile_dir,
     };
 
     // Use the build_router function from your server
@@ -169,6 +211,8 @@ fn create_isolated_vault_manager(temp_dir: &TempDir) -> VaultManager {
         category: VaultCategory::Testing,
         is_active: true,
         is_favorite: false,
+        keyfile: None,
+        manifest_version: None,
     };
 
     // Create the registry with only our test vault
@@ -4,9 +4,11 @@ use crate::common::TestContext;
 use crate::common::fixtures::sample_create_item_request;
 use chamber_api::SearchResponse;
 use chamber_api::models::{
-    ApiResponse, CountsResponse, CreateItemRequest, ItemResponse, ItemWithValueResponse, ListItemsResponse,
+    ApiResponse, AttachmentResponse, CountsResponse, CreateItemRequest, EmptyTrashResponse, ItemAttachmentsResponse,
+    ItemHistoryResponse, ItemResponse, ItemWithValueResponse, ListItemsResponse, TotpResponse, TrashResponse,
     UpdateItemRequest,
 };
+use chamber_vault::ItemField;
 use http::StatusCode;
 
 // ============================================================================
@@ -52,6 +54,9 @@ async fn test_create_item_different_types() -> color_eyre::Result<()> {
             name: format!("Test {kind} Item"),
             kind: (*kind).to_string(),
             value: format!("test_value_{i}"),
+            tags: Vec::new(),
+            folder: None,
+            fields: Vec::new(),
         };
 
         let response = ctx
@@ -118,6 +123,9 @@ async fn test_create_item_validation_errors() -> color_eyre::Result<()> {
         name: String::new(),
         kind: "password".to_string(),
         value: "test_value".to_string(),
+        tags: Vec::new(),
+        folder: None,
+        fields: Vec::new(),
     };
 
     let response = ctx
@@ -134,6 +142,9 @@ async fn test_create_item_validation_errors() -> color_eyre::Result<()> {
         name: "Test Item".to_string(),
         kind: "password".to_string(),
         value: String::new(),
+        tags: Vec::new(),
+        folder: None,
+        fields: Vec::new(),
     };
 
     let response = ctx
@@ -150,6 +161,9 @@ async fn test_create_item_validation_errors() -> color_eyre::Result<()> {
         name: "Test Item".to_string(),
         kind: "invalid_type".to_string(),
         value: "test_value".to_string(),
+        tags: Vec::new(),
+        folder: None,
+        fields: Vec::new(),
     };
 
     let response = ctx
@@ -174,6 +188,9 @@ async fn test_create_item_with_special_characters() -> color_eyre::Result<()> {
         name: "Test🔑Item with émojis & spëciål chars!@#$%".to_string(),
         kind: "password".to_string(),
         value: "pássw0rd!@#$%^&*()_+-=[]{}|;:,.<>?/~`".to_string(),
+        tags: Vec::new(),
+        folder: None,
+        fields: Vec::new(),
     };
 
     let response = ctx
@@ -280,6 +297,117 @@ async fn test_list_items_filtering_by_kind() -> color_eyre::Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn test_list_items_filtering_by_tag_and_folder() -> color_eyre::Result<()> {
+    let mut ctx = TestContext::new()?;
+    ctx.login().await?;
+    ctx.unlock_session().await?;
+
+    for (name, tags, folder) in [
+        ("Prod DB", vec!["prod", "db"], Some("work/prod")),
+        ("Staging DB", vec!["staging", "db"], Some("work/staging")),
+        ("Home Wifi", vec![], None),
+    ] {
+        let create_request = CreateItemRequest {
+            name: name.to_string(),
+            kind: "password".to_string(),
+            value: "secret".to_string(),
+            tags: tags.into_iter().map(str::to_string).collect(),
+            folder: folder.map(str::to_string),
+            fields: Vec::new(),
+        };
+        ctx.server
+            .post("/api/v1/items")
+            .authorization_bearer(ctx.auth_token.as_ref().unwrap())
+            .json(&create_request)
+            .await
+            .assert_status_ok();
+    }
+
+    let response = ctx
+        .server
+        .get("/api/v1/items?tag=PROD")
+        .authorization_bearer(ctx.auth_token.as_ref().unwrap())
+        .await;
+    response.assert_status_ok();
+    let body: ApiResponse<ListItemsResponse> = response.json();
+    assert_eq!(body.data.items.len(), 1);
+    assert_eq!(body.data.items[0].name, "Prod DB");
+    assert_eq!(body.data.items[0].tags, vec!["prod".to_string(), "db".to_string()]);
+    assert_eq!(body.data.items[0].folder.as_deref(), Some("work/prod"));
+
+    let response = ctx
+        .server
+        .get("/api/v1/items?folder=work")
+        .authorization_bearer(ctx.auth_token.as_ref().unwrap())
+        .await;
+    response.assert_status_ok();
+    let body: ApiResponse<ListItemsResponse> = response.json();
+    assert_eq!(body.data.total, 2);
+
+    let response = ctx
+        .server
+        .get("/api/v1/items/search?tag=db&folder=work/staging")
+        .authorization_bearer(ctx.auth_token.as_ref().unwrap())
+        .await;
+    response.assert_status_ok();
+    let body: ApiResponse<SearchResponse> = response.json();
+    assert_eq!(body.data.total_found, 1);
+    assert_eq!(body.data.items[0].name, "Staging DB");
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_update_item_labels() -> color_eyre::Result<()> {
+    let mut ctx = TestContext::new()?;
+    ctx.login().await?;
+    ctx.unlock_session().await?;
+
+    let item_id = ctx.create_test_item("Labeled", "password", "value").await?;
+
+    let update_request = UpdateItemRequest {
+        name: None,
+        kind: None,
+        value: None,
+        tags: Some(vec!["prod".to_string()]),
+        folder: Some("work".to_string()),
+        fields: None,
+    };
+    let response = ctx
+        .server
+        .put(&format!("/api/v1/items/{item_id}"))
+        .authorization_bearer(ctx.auth_token.as_ref().unwrap())
+        .json(&update_request)
+        .await;
+    response.assert_status_ok();
+    let body: ApiResponse<ItemResponse> = response.json();
+    assert_eq!(body.data.tags, vec!["prod".to_string()]);
+    assert_eq!(body.data.folder.as_deref(), Some("work"));
+
+    // An empty folder removes it while keeping the tags
+    let update_request = UpdateItemRequest {
+        name: None,
+        kind: None,
+        value: None,
+        tags: None,
+        folder: Some(String::new()),
+        fields: None,
+    };
+    let response = ctx
+        .server
+        .put(&format!("/api/v1/items/{item_id}"))
+        .authorization_bearer(ctx.auth_token.as_ref().unwrap())
+        .json(&update_request)
+        .await;
+    response.assert_status_ok();
+    let body: ApiResponse<ItemResponse> = response.json();
+    assert_eq!(body.data.tags, vec!["prod".to_string()]);
+    assert_eq!(body.data.folder, None);
+
+    Ok(())
+}
+
 #[tokio::test]
 async fn test_list_items_sorting() -> color_eyre::Result<()> {
     let mut ctx = TestContext::new()?;
@@ -392,6 +520,60 @@ async fn test_get_item_value() -> color_eyre::Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn test_create_item_with_typed_fields() -> color_eyre::Result<()> {
+    let mut ctx = TestContext::new()?;
+    ctx.login().await?;
+    ctx.unlock_session().await?;
+
+    let create_request = CreateItemRequest {
+        name: "Orders DB".to_string(),
+        kind: "database".to_string(),
+        value: "db_password".to_string(),
+        tags: Vec::new(),
+        folder: None,
+        fields: vec![
+            ItemField::new("host", "db.internal", false),
+            ItemField::new("port", "5432", false),
+            ItemField::new("replica", "db-ro.internal", false),
+        ],
+    };
+    let response = ctx
+        .server
+        .post("/api/v1/items")
+        .authorization_bearer(ctx.auth_token.as_ref().unwrap())
+        .json(&create_request)
+        .await;
+    response.assert_status_ok();
+    let item_id = response.json::<ApiResponse<ItemResponse>>().data.id;
+
+    let response = ctx
+        .server
+        .get(&format!("/api/v1/items/{item_id}/value"))
+        .authorization_bearer(ctx.auth_token.as_ref().unwrap())
+        .await;
+    response.assert_status_ok();
+    let body: ApiResponse<ItemWithValueResponse> = response.json();
+    assert_eq!(body.data.value, "db_password");
+    assert_eq!(body.data.fields, create_request.fields);
+
+    // Typed fields are validated against the kind's schema
+    let invalid_request = CreateItemRequest {
+        name: "Broken DB".to_string(),
+        fields: vec![ItemField::new("port", "not-a-port", false)],
+        ..create_request
+    };
+    let response = ctx
+        .server
+        .post("/api/v1/items")
+        .authorization_bearer(ctx.auth_token.as_ref().unwrap())
+        .json(&invalid_request)
+        .await;
+    response.assert_status(StatusCode::UNPROCESSABLE_ENTITY);
+
+    Ok(())
+}
+
 #[tokio::test]
 async fn test_get_item_value_without_reveal_scope() -> color_eyre::Result<()> {
     // This test would require creating a token without reveal:values scope
@@ -432,6 +614,9 @@ async fn test_update_item_value() -> color_eyre::Result<()> {
         name: None,
         kind: None,
         value: Some("updated_value_123".to_string()),
+        tags: None,
+        folder: None,
+        fields: None,
     };
 
     let response = ctx
@@ -462,6 +647,92 @@ async fn test_update_item_value() -> color_eyre::Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn test_get_item_history() -> color_eyre::Result<()> {
+    let mut ctx = TestContext::new()?;
+    ctx.login().await?;
+    ctx.unlock_session().await?;
+
+    let item_id = ctx.create_test_item("Rotated Item", "password", "first_value").await?;
+
+    for value in ["second_value", "third_value"] {
+        let update_request = UpdateItemRequest {
+            name: None,
+            kind: None,
+            value: Some(value.to_string()),
+            tags: None,
+            folder: None,
+            fields: None,
+        };
+        ctx.server
+            .put(&format!("/api/v1/items/{item_id}"))
+            .authorization_bearer(ctx.auth_token.as_ref().unwrap())
+            .json(&update_request)
+            .await
+            .assert_status_ok();
+    }
+
+    let response = ctx
+        .server
+        .get(&format!("/api/v1/items/{item_id}/history"))
+        .authorization_bearer(ctx.auth_token.as_ref().unwrap())
+        .await;
+
+    response.assert_status_ok();
+    let body: ApiResponse<ItemHistoryResponse> = response.json();
+    assert_eq!(body.data.id, item_id);
+    let values: Vec<_> = body.data.revisions.iter().map(|r| r.value.as_str()).collect();
+    assert_eq!(values, vec!["first_value", "second_value"]);
+    assert_eq!(body.data.revisions[0].revision, 1);
+
+    let missing = ctx
+        .server
+        .get("/api/v1/items/99999/history")
+        .authorization_bearer(ctx.auth_token.as_ref().unwrap())
+        .await;
+    missing.assert_status_not_found();
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_get_item_totp() -> color_eyre::Result<()> {
+    let mut ctx = TestContext::new()?;
+    ctx.login().await?;
+    ctx.unlock_session().await?;
+
+    let totp_id = ctx
+        .create_test_item(
+            "GitHub 2FA",
+            "totp",
+            "otpauth://totp/GitHub:octocat?secret=JBSWY3DPEHPK3PXP&issuer=GitHub",
+        )
+        .await?;
+    let plain_id = ctx.create_test_item("Plain", "password", "value").await?;
+
+    let response = ctx
+        .server
+        .get(&format!("/api/v1/items/{totp_id}/totp"))
+        .authorization_bearer(ctx.auth_token.as_ref().unwrap())
+        .await;
+    response.assert_status_ok();
+    let body: ApiResponse<TotpResponse> = response.json();
+    assert_eq!(body.data.code.len(), 6);
+    assert!(body.data.code.chars().all(|c| c.is_ascii_digit()));
+    assert!((1..=30).contains(&body.data.remaining_seconds));
+    assert_eq!(body.data.issuer.as_deref(), Some("GitHub"));
+    assert_eq!(body.data.account.as_deref(), Some("octocat"));
+
+    let response = ctx
+        .server
+        .get(&format!("/api/v1/items/{plain_id}/totp"))
+        .authorization_bearer(ctx.auth_token.as_ref().unwrap())
+        .await;
+    response.assert_status(StatusCode::BAD_REQUEST);
+
+    Ok(())
+}
+
 #[tokio::test]
 async fn test_update_item_empty_value() -> color_eyre::Result<()> {
     let mut ctx = TestContext::new()?;
@@ -474,6 +745,9 @@ async fn test_update_item_empty_value() -> color_eyre::Result<()> {
         name: None,
         kind: None,
         value: Some(String::new()), // Empty value
+        tags: None,
+        folder: None,
+        fields: None,
     };
 
     let response = ctx
@@ -498,6 +772,9 @@ async fn test_update_item_not_found() -> color_eyre::Result<()> {
         name: None,
         kind: None,
         value: Some("new_value".to_string()),
+        tags: None,
+        folder: None,
+        fields: None,
     };
 
     let response = ctx
@@ -524,6 +801,9 @@ async fn test_update_item_no_fields() -> color_eyre::Result<()> {
         name: None,
         kind: None,
         value: None, // No fields to update
+        tags: None,
+        folder: None,
+        fields: None,
     };
 
     let response = ctx
@@ -570,6 +850,73 @@ async fn test_delete_item() -> color_eyre::Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn test_deleted_item_can_be_restored_from_trash() -> color_eyre::Result<()> {
+    let mut ctx = TestContext::new()?;
+    ctx.login().await?;
+    ctx.unlock_session().await?;
+
+    let item_id = ctx.create_test_item("Item to Restore", "password", "value").await?;
+    let token = ctx.auth_token.clone().unwrap();
+
+    ctx.server
+        .delete(&format!("/api/v1/items/{item_id}"))
+        .authorization_bearer(&token)
+        .await
+        .assert_status_ok();
+
+    let response = ctx.server.get("/api/v1/trash").authorization_bearer(&token).await;
+    response.assert_status_ok();
+    let body: ApiResponse<TrashResponse> = response.json();
+    assert_eq!(body.data.items.len(), 1);
+    assert_eq!(body.data.items[0].id, item_id);
+    assert_eq!(body.data.items[0].name, "Item to Restore");
+    assert!(body.data.items[0].purge_at.is_some());
+
+    let response = ctx
+        .server
+        .post(&format!("/api/v1/trash/{item_id}/restore"))
+        .authorization_bearer(&token)
+        .await;
+    response.assert_status_ok();
+    let body: ApiResponse<ItemResponse> = response.json();
+    assert_eq!(body.data.name, "Item to Restore");
+
+    ctx.server
+        .get(&format!("/api/v1/items/{item_id}"))
+        .authorization_bearer(&token)
+        .await
+        .assert_status_ok();
+
+    // Only items in the trash can be restored or purged
+    ctx.server
+        .post(&format!("/api/v1/trash/{item_id}/restore"))
+        .authorization_bearer(&token)
+        .await
+        .assert_status_not_found();
+    ctx.server
+        .delete(&format!("/api/v1/trash/{item_id}"))
+        .authorization_bearer(&token)
+        .await
+        .assert_status_not_found();
+
+    ctx.server
+        .delete(&format!("/api/v1/items/{item_id}"))
+        .authorization_bearer(&token)
+        .await
+        .assert_status_ok();
+    let response = ctx.server.delete("/api/v1/trash").authorization_bearer(&token).await;
+    response.assert_status_ok();
+    let body: ApiResponse<EmptyTrashResponse> = response.json();
+    assert_eq!(body.data.purged, 1);
+
+    let response = ctx.server.get("/api/v1/trash").authorization_bearer(&token).await;
+    let body: ApiResponse<TrashResponse> = response.json();
+    assert!(body.data.items.is_empty());
+
+    Ok(())
+}
+
 #[tokio::test]
 async fn test_delete_item_not_found() -> color_eyre::Result<()> {
     let mut ctx = TestContext::new()?;
@@ -589,6 +936,97 @@ async fn test_delete_item_not_found() -> color_eyre::Result<()> {
     Ok(())
 }
 
+// ============================================================================
+// Attachment Tests
+// ============================================================================
+
+#[tokio::test]
+async fn test_attachment_upload_download_and_delete() -> color_eyre::Result<()> {
+    let mut ctx = TestContext::new()?;
+    ctx.login().await?;
+    ctx.unlock_session().await?;
+
+    let item_id = ctx
+        .create_test_item("Client Cert", "certificate", "cert password")
+        .await?;
+    // Larger than the 1 MiB JSON body limit and not valid UTF-8
+    let data: Vec<u8> = (0..1_500_000u32).map(|i| i.to_le_bytes()[0]).collect();
+
+    let response = ctx
+        .server
+        .post(&format!("/api/v1/items/{item_id}/attachments"))
+        .add_query_param("file_name", "client.p12")
+        .authorization_bearer(ctx.auth_token.as_ref().unwrap())
+        .bytes(data.clone().into())
+        .await;
+    response.assert_status_ok();
+    let uploaded: ApiResponse<AttachmentResponse> = response.json();
+    assert_eq!(uploaded.data.file_name, "client.p12");
+    assert_eq!(uploaded.data.size, data.len() as u64);
+
+    let response = ctx
+        .server
+        .get(&format!("/api/v1/items/{item_id}/attachments"))
+        .authorization_bearer(ctx.auth_token.as_ref().unwrap())
+        .await;
+    response.assert_status_ok();
+    let listed: ApiResponse<ItemAttachmentsResponse> = response.json();
+    assert_eq!(listed.data.attachments.len(), 1);
+
+    let download_url = format!("/api/v1/items/{item_id}/attachments/{}", uploaded.data.id);
+    let response = ctx
+        .server
+        .get(&download_url)
+        .authorization_bearer(ctx.auth_token.as_ref().unwrap())
+        .await;
+    response.assert_status_ok();
+    assert_eq!(response.as_bytes().as_ref(), data.as_slice());
+
+    // Uploading the same file name twice is rejected
+    let response = ctx
+        .server
+        .post(&format!("/api/v1/items/{item_id}/attachments"))
+        .add_query_param("file_name", "client.p12")
+        .authorization_bearer(ctx.auth_token.as_ref().unwrap())
+        .bytes(vec![1, 2, 3].into())
+        .await;
+    response.assert_status(StatusCode::UNPROCESSABLE_ENTITY);
+
+    let response = ctx
+        .server
+        .delete(&download_url)
+        .authorization_bearer(ctx.auth_token.as_ref().unwrap())
+        .await;
+    response.assert_status_ok();
+
+    let response = ctx
+        .server
+        .get(&download_url)
+        .authorization_bearer(ctx.auth_token.as_ref().unwrap())
+        .await;
+    response.assert_status(StatusCode::NOT_FOUND);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_attachment_upload_unknown_item() -> color_eyre::Result<()> {
+    let mut ctx = TestContext::new()?;
+    ctx.login().await?;
+    ctx.unlock_session().await?;
+
+    let response = ctx
+        .server
+        .post("/api/v1/items/9999/attachments")
+        .add_query_param("file_name", "orphan.bin")
+        .authorization_bearer(ctx.auth_token.as_ref().unwrap())
+        .bytes(vec![0u8; 16].into())
+        .await;
+    response.assert_status(StatusCode::NOT_FOUND);
+
+    Ok(())
+}
+
 // ============================================================================
 // Search Tests
 // ============================================================================
@@ -670,6 +1108,57 @@ async fn test_search_items_by_kind() -> color_eyre::Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn test_search_items_does_not_match_values() -> color_eyre::Result<()> {
+    let mut ctx = TestContext::new()?;
+    ctx.login().await?;
+    ctx.unlock_session().await?;
+
+    ctx.create_test_item("Database", "password", "needle-in-value").await?;
+    ctx.create_test_item("needle service", "apikey", "key").await?;
+
+    let response = ctx
+        .server
+        .get("/api/v1/items/search?q=needle&limit=10")
+        .authorization_bearer(ctx.auth_token.as_ref().unwrap())
+        .await;
+
+    response.assert_status_ok();
+
+    let body: ApiResponse<SearchResponse> = response.json();
+    assert_eq!(body.data.items.len(), 1);
+    assert_eq!(body.data.items[0].name, "needle service");
+    assert_eq!(body.data.items[0].value_length, "key".len());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_search_items_sorted_by_value_length() -> color_eyre::Result<()> {
+    let mut ctx = TestContext::new()?;
+    ctx.login().await?;
+    ctx.unlock_session().await?;
+
+    ctx.create_test_item("medium", "password", "12345").await?;
+    ctx.create_test_item("long", "password", "1234567890").await?;
+    ctx.create_test_item("short", "password", "1").await?;
+
+    let response = ctx
+        .server
+        .get("/api/v1/items/search?sort=value_length&order=desc&limit=2")
+        .authorization_bearer(ctx.auth_token.as_ref().unwrap())
+        .await;
+
+    response.assert_status_ok();
+
+    let body: ApiResponse<SearchResponse> = response.json();
+    let names: Vec<_> = body.data.items.iter().map(|item| item.name.as_str()).collect();
+    assert_eq!(names, vec!["long", "medium"]);
+    assert_eq!(body.data.total_found, 3);
+
+    Ok(())
+}
+
 #[tokio::test]
 async fn test_search_items_pagination() -> color_eyre::Result<()> {
     let mut ctx = TestContext::new()?;
@@ -807,6 +1296,9 @@ async fn test_concurrent_item_creation() -> color_eyre::Result<()> {
                     name: format!("Concurrent Item {}", i),
                     kind: "password".to_string(),
                     value: format!("value_{}", i),
+                    tags: Vec::new(),
+                    folder: None,
+                    fields: Vec::new(),
                 };
 
                 server
@@ -858,6 +1350,9 @@ async fn test_large_item_handling() -> color_eyre::Result<()> {
         name: "Large Item".to_string(),
         kind: "note".to_string(),
         value: large_value.clone(),
+        tags: Vec::new(),
+        folder: None,
+        fields: Vec::new(),
     };
 
     let response = ctx
@@ -18,13 +18,6 @@ pub struct VaultResponse {
     pub is_active: bool,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-struct TestClaims {
-    sub: String,
-    scope: String,
-    exp: i64,
-}
-
 #[tokio::test]
 async fn test_create_vault_success() -> color_eyre::Result<()> {
     let mut ctx = TestContext::new()?;
@@ -1292,3 +1285,45 @@ async fn test_delete_vault_success() -> color_eyre::Result<()> {
 
     Ok(())
 }
+
+#[tokio::test]
+async fn test_vault_integrity_of_locked_vault() -> color_eyre::Result<()> {
+    let mut ctx = TestContext::new()?;
+    ctx.login().await?;
+    ctx.unlock_session().await?;
+
+    let vault_path = ctx.temp_dir.path().join("integrity_vault.db");
+    let create_request = json!({
+        "name": "Integrity Vault",
+        "master_password": "secure_password123",
+        "path": Some(vault_path)
+    });
+    let create_response = ctx
+        .server
+        .post("/api/v1/vaults")
+        .authorization_bearer(ctx.auth_token.as_ref().unwrap())
+        .json(&create_request)
+        .await;
+    create_response.assert_status_ok();
+    let vault_id = create_response.json::<ApiResponse<VaultResponse>>().data.id;
+
+    let response = ctx
+        .server
+        .get(&format!("/api/v1/vaults/{vault_id}/integrity"))
+        .authorization_bearer(ctx.auth_token.as_ref().unwrap())
+        .await;
+    response.assert_status_ok();
+    let body: serde_json::Value = response.json();
+    assert_eq!(body["data"]["healthy"], true);
+    // The vault is not open on the server, so its items cannot be decrypted
+    assert_eq!(body["data"]["decryption_checked"], false);
+
+    let missing = ctx
+        .server
+        .get("/api/v1/vaults/no-such-vault/integrity")
+        .authorization_bearer(ctx.auth_token.as_ref().unwrap())
+        .await;
+    missing.assert_status(StatusCode::NOT_FOUND);
+
+    Ok(())
+}
@@ -1,5 +1,5 @@
 use chamber_backup::{BackupManager, VaultOperations};
-use chamber_vault::{BackupConfig, Item, ItemKind};
+use chamber_vault::{BackupConfig, Item, ItemKind, KeyMaterial};
 use color_eyre::Result;
 use criterion::{BenchmarkId, Criterion, criterion_group, criterion_main};
 use std::time::Duration;
@@ -24,6 +24,9 @@ impl MockVault {
                 value: format!("benchmark_password_{i}_with_additional_content_to_simulate_real_world_data"),
                 created_at: OffsetDateTime::now_utc(),
                 updated_at: OffsetDateTime::now_utc(),
+                tags: Vec::new(),
+                folder: None,
+                fields: Vec::new(),
             })
             .collect();
 
@@ -35,6 +38,10 @@ impl VaultOperations for MockVault {
     fn list_items(&self) -> Result<Vec<Item>> {
         Ok(self.items.clone())
     }
+
+    fn backup_key(&self) -> Result<KeyMaterial> {
+        Ok(KeyMaterial([42u8; 32]))
+    }
 }
 
 fn create_test_config(temp_dir: &TempDir, format: &str, compress: bool) -> BackupConfig {
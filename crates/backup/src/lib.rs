@@ -3,4 +3,5 @@ mod service;
 
 pub use manager::BackupManager;
 pub use manager::VaultOperations;
+pub use manager::with_uncompressed_snapshot;
 pub use service::BackgroundService;
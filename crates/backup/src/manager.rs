@@ -1,7 +1,11 @@
-use chamber_import_export::{ExportFormat, export_items};
-use chamber_vault::{BackupConfig, Item, Vault};
+use chamber_import_export::{
+    AttachmentFile, BackupSecret, ExportFormat, export_chamber_backup_with_attachments, export_items,
+    read_chamber_backup,
+};
+use chamber_vault::{BackupConfig, Db, Item, KeyMaterial, Vault};
 use color_eyre::Result;
 use color_eyre::eyre::eyre;
+use std::collections::HashMap;
 use std::fs;
 use std::path::{Path, PathBuf};
 use time::OffsetDateTime;
@@ -18,12 +22,92 @@ pub trait VaultOperations {
     ///
     /// Note: Ensure to handle the `Result` properly to avoid runtime errors.
     fn list_items(&self) -> Result<Vec<Item>>;
+
+    /// Returns the key used to encrypt and verify `backup`-format backups.
+    ///
+    /// # Errors
+    /// This function will return an error if the key is unavailable (for example, the vault is locked).
+    fn backup_key(&self) -> Result<KeyMaterial>;
+
+    /// Writes a raw snapshot of the encrypted vault database to `dest`.
+    ///
+    /// Unlike the export formats, snapshots copy the stored ciphertext as is and therefore work
+    /// while the vault is locked. Implementations without an underlying database keep the
+    /// default, which reports that snapshots are unsupported.
+    ///
+    /// # Errors
+    /// This function will return an error if the snapshot cannot be written or is unsupported.
+    fn snapshot_to(&self, dest: &Path) -> Result<()> {
+        let _ = dest;
+        Err(eyre!("Snapshots are not supported by this vault"))
+    }
+
+    /// Returns the decrypted attachments of `items`, keyed by item id, for inclusion in
+    /// `backup`-format backups. Implementations without attachment support keep the default,
+    /// which returns none.
+    ///
+    /// # Errors
+    /// This function will return an error if an attachment cannot be read or decrypted.
+    fn attachment_files(&self, items: &[Item]) -> Result<HashMap<u64, Vec<AttachmentFile>>> {
+        let _ = items;
+        Ok(HashMap::new())
+    }
 }
 
 impl VaultOperations for Vault {
     fn list_items(&self) -> Result<Vec<Item>> {
         self.list_items()
     }
+
+    fn backup_key(&self) -> Result<KeyMaterial> {
+        self.backup_key()
+    }
+
+    fn snapshot_to(&self, dest: &Path) -> Result<()> {
+        self.snapshot_to(dest)
+    }
+
+    fn attachment_files(&self, items: &[Item]) -> Result<HashMap<u64, Vec<AttachmentFile>>> {
+        let mut files = HashMap::new();
+        for item in items {
+            let mut attached = Vec::new();
+            for attachment in self.list_attachments(item.id)? {
+                let mut data = Vec::with_capacity(usize::try_from(attachment.size).unwrap_or_default());
+                self.read_attachment(attachment.id, &mut data)?;
+                attached.push(AttachmentFile {
+                    file_name: attachment.file_name,
+                    data,
+                });
+            }
+            if !attached.is_empty() {
+                files.insert(item.id, attached);
+            }
+        }
+        Ok(files)
+    }
+}
+
+/// Runs `f` on an uncompressed copy of a snapshot backup.
+///
+/// Snapshots are `SQLite` files and can only be opened from disk, so a `.gz` snapshot is first
+/// inflated into a sibling temporary file that is removed once `f` returns. Uncompressed
+/// snapshots are passed through unchanged.
+///
+/// # Errors
+/// Returns an error if decompression fails or `f` returns an error.
+pub fn with_uncompressed_snapshot<T>(path: &Path, f: impl FnOnce(&Path) -> Result<T>) -> Result<T> {
+    if path.extension().and_then(|s| s.to_str()) != Some("gz") {
+        return f(path);
+    }
+
+    let temp_path = path.with_extension("inflating");
+    let mut decoder = flate2::read::GzDecoder::new(fs::File::open(path)?);
+    let result = fs::File::create(&temp_path)
+        .and_then(|mut out| std::io::copy(&mut decoder, &mut out))
+        .map_err(Into::into)
+        .and_then(|_| f(&temp_path));
+    let _ = fs::remove_file(&temp_path);
+    result
 }
 
 pub struct BackupManager<V: VaultOperations> {
@@ -123,20 +207,33 @@ impl<V: VaultOperations> BackupManager<V> {
         // Generate backup filename with timestamp
         let timestamp = OffsetDateTime::now_utc();
         let filename = self.generate_backup_filename(&timestamp)?;
-        let backup_path = self.config.backup_dir.join(&filename);
-
-        // Export the vault data
-        let items = self.vault.list_items()?;
+        // `filename` already ends in `.gz` when compressing; the export is written without it and
+        // `compress_backup` adds it back
+        let mut backup_path = self.config.backup_dir.join(&filename);
+        if self.config.compress {
+            backup_path.set_extension("");
+        }
 
-        let export_format = match self.config.format.as_str() {
-            "json" => ExportFormat::Json,
-            "csv" => ExportFormat::Csv,
-            "backup" => ExportFormat::ChamberBackup,
+        // Perform the export; `snapshot` copies the encrypted database and works on a locked vault,
+        // `backup` is always encrypted with the vault's backup key, while `json` and `csv` are
+        // explicit plaintext opt-ins
+        match self.config.format.as_str() {
+            "snapshot" => self.vault.snapshot_to(&backup_path)?,
+            "json" => export_items(&self.vault.list_items()?, &ExportFormat::Json, &backup_path)?,
+            "csv" => export_items(&self.vault.list_items()?, &ExportFormat::Csv, &backup_path)?,
+            "backup" => {
+                let items = self.vault.list_items()?;
+                let attachments = self.vault.attachment_files(&items)?;
+                let key = self.vault.backup_key()?;
+                export_chamber_backup_with_attachments(
+                    &items,
+                    &attachments,
+                    &backup_path,
+                    &BackupSecret::VaultKey(&key),
+                )?;
+            }
             _ => return Err(eyre!("Invalid backup format: {}", self.config.format)),
-        };
-
-        // Perform the export
-        export_items(&items, &export_format, &backup_path)?;
+        }
 
         // Compress if requested
         let final_path = if self.config.compress {
@@ -203,9 +300,21 @@ impl<V: VaultOperations> BackupManager<V> {
             return Err(eyre!("Backup file is empty: {}", path.display()));
         }
 
-        // For compressed files, try to decompress a small portion
-        if path.extension().and_then(|s| s.to_str()) == Some("gz") {
-            Self::verify_compressed_backup(path)?;
+        // Snapshots are checked by SQLite itself, whether compressed or not
+        if self.config.format == "snapshot" {
+            with_uncompressed_snapshot(path, Db::verify_snapshot)?;
+        } else if path.extension().and_then(|s| s.to_str()) == Some("gz") {
+            // For compressed files, try to decompress a small portion
+            if self.config.format == "backup" {
+                // Encrypted backups are only verified once they authenticate under the backup key
+                use std::io::Read;
+
+                let mut content = String::new();
+                flate2::read::GzDecoder::new(fs::File::open(path)?).read_to_string(&mut content)?;
+                self.verify_encrypted_backup(&content)?;
+            } else {
+                Self::verify_compressed_backup(path)?;
+            }
         } else {
             // For uncompressed files, try to parse the format
             self.verify_uncompressed_backup(path)?;
@@ -234,10 +343,7 @@ impl<V: VaultOperations> BackupManager<V> {
             "json" => {
                 serde_json::from_str::<serde_json::Value>(&content)?;
             }
-            "backup" => {
-                // Try to parse as ChamberBackup format
-                serde_json::from_str::<chamber_import_export::ChamberBackup>(&content)?;
-            }
+            "backup" => self.verify_encrypted_backup(&content)?,
             "csv" => {
                 // Basic CSV validation - check header exists
                 if !content.starts_with("name,kind,value") {
@@ -250,6 +356,19 @@ impl<V: VaultOperations> BackupManager<V> {
         Ok(())
     }
 
+    fn verify_encrypted_backup(&self, content: &str) -> Result<()> {
+        let key = self.vault.backup_key()?;
+        let backup = read_chamber_backup(content, Some(&BackupSecret::VaultKey(&key)))?;
+        if backup.items.len() != backup.item_count {
+            return Err(eyre!(
+                "Backup item count mismatch: expected {}, found {}",
+                backup.item_count,
+                backup.items.len()
+            ));
+        }
+        Ok(())
+    }
+
     fn cleanup_old_backups(&self) -> Result<()> {
         let mut backups = self.find_all_backups()?;
 
@@ -425,6 +544,10 @@ mod tests {
             }
             Ok(self.items.clone())
         }
+
+        fn backup_key(&self) -> Result<KeyMaterial> {
+            Ok(KeyMaterial([42u8; 32]))
+        }
     }
 
     fn create_test_item(id: u64, name: &str) -> Item {
@@ -435,6 +558,9 @@ mod tests {
             value: "test_value".to_string(),
             created_at: OffsetDateTime::now_utc(),
             updated_at: OffsetDateTime::now_utc(),
+            tags: Vec::new(),
+            folder: None,
+            fields: Vec::new(),
         }
     }
 
@@ -584,6 +710,9 @@ mod tests {
                 value: "secret123".to_string(),
                 created_at: OffsetDateTime::now_utc(),
                 updated_at: OffsetDateTime::now_utc(),
+                tags: Vec::new(),
+                folder: None,
+                fields: Vec::new(),
             },
             Item {
                 id: 2,
@@ -592,6 +721,9 @@ mod tests {
                 value: "api_key_abc".to_string(),
                 created_at: OffsetDateTime::now_utc(),
                 updated_at: OffsetDateTime::now_utc(),
+                tags: Vec::new(),
+                folder: None,
+                fields: Vec::new(),
             },
         ];
 
@@ -636,6 +768,10 @@ mod tests {
         fn list_items(&self) -> Result<Vec<Item>> {
             Ok(self.data.clone())
         }
+
+        fn backup_key(&self) -> Result<KeyMaterial> {
+            Err(eyre!("Locked"))
+        }
     }
 
     #[test]
@@ -1126,6 +1262,98 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_backup_format_is_encrypted_and_verifiable() {
+        let temp_dir = TempDir::new().unwrap();
+
+        for compress in [false, true] {
+            let config = create_test_config_with_options(&temp_dir, "backup", compress, true, 5, 24);
+            let vault = MockVault::new(vec![create_test_item(1, "github")]);
+            let mut manager = BackupManager::new(vault, config);
+
+            let path = manager.force_backup().unwrap();
+            let name = path.file_name().unwrap().to_string_lossy().into_owned();
+            assert!(name.ends_with(if compress { ".backup.gz" } else { ".backup" }));
+            assert!(!name.ends_with(".gz.gz"));
+            let content = chamber_import_export::import_chamber_backup(
+                &path,
+                Some(&BackupSecret::VaultKey(&KeyMaterial([42u8; 32]))),
+            )
+            .unwrap();
+            assert_eq!(content.len(), 1);
+            assert_eq!(content[0].name, "github");
+
+            if !compress {
+                let raw = fs::read_to_string(&path).unwrap();
+                assert!(!raw.contains("test_value"));
+                assert!(!raw.contains("github"));
+            }
+            fs::remove_file(&path).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_verify_encrypted_backup_rejects_tampering() {
+        let temp_dir = TempDir::new().unwrap();
+        let config = create_test_config_with_options(&temp_dir, "backup", false, false, 5, 24);
+        let vault = MockVault::new(vec![create_test_item(1, "github")]);
+        let mut manager = BackupManager::new(vault, config);
+
+        let path = manager.force_backup().unwrap();
+        manager.verify_backup(&path).unwrap();
+
+        let mut container: serde_json::Value = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        container["key_source"] = serde_json::Value::String("passphrase".to_string());
+        fs::write(&path, container.to_string()).unwrap();
+
+        assert!(manager.verify_backup(&path).is_err());
+    }
+
+    #[test]
+    fn test_snapshot_format_backs_up_locked_vault() {
+        let temp_dir = TempDir::new().unwrap();
+        let vault_path = temp_dir.path().join("vault.sqlite3");
+        let mut vault = Vault::open_or_create(Some(&vault_path)).unwrap();
+        vault.initialize("master").unwrap();
+        vault.unlock("master").unwrap();
+        vault
+            .create_item(&chamber_vault::NewItem {
+                name: "github".to_string(),
+                kind: ItemKind::Password,
+                value: "test_value".to_string(),
+                tags: Vec::new(),
+                folder: None,
+                fields: Vec::new(),
+            })
+            .unwrap();
+
+        for compress in [false, true] {
+            let config = create_test_config_with_options(&temp_dir, "snapshot", compress, true, 5, 24);
+            let locked = Vault::open_or_create(Some(&vault_path)).unwrap();
+            let mut manager = BackupManager::new(locked, config);
+
+            let path = manager.force_backup().unwrap();
+            let count = with_uncompressed_snapshot(&path, Db::verify_snapshot).unwrap();
+            assert_eq!(count, 1);
+
+            let mut restored = Vault::open_or_create(Some(&temp_dir.path().join("restored.sqlite3"))).unwrap();
+            with_uncompressed_snapshot(&path, |p| restored.restore_snapshot(p)).unwrap();
+            restored.unlock("master").unwrap();
+            assert_eq!(restored.list_items().unwrap()[0].value, "test_value");
+            fs::remove_file(&path).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_snapshot_format_unsupported_by_mock_vault() {
+        let temp_dir = TempDir::new().unwrap();
+        let config = create_test_config_with_options(&temp_dir, "snapshot", false, false, 5, 24);
+        let mut manager = BackupManager::new(MockVault::new(vec![]), config);
+
+        let err = manager.force_backup().unwrap_err();
+        assert!(err.to_string().contains("not supported"));
+    }
+
     #[test]
     fn test_different_intervals() {
         let temp_dir = TempDir::new().unwrap();
@@ -0,0 +1,10 @@
+//! `docker-credential-chamber`: docker's credential helper, serving registry logins from a vault.
+//!
+//! Docker runs `docker-credential-<credsStore>` with the operation as the only argument, so the
+//! vault is chosen with `CHAMBER_DOCKER_VAULT` rather than a flag.
+use chamber_secret_manager_cli::handle_docker_credential_command;
+
+fn main() {
+    let operation = std::env::args().nth(1).unwrap_or_default();
+    std::process::exit(handle_docker_credential_command(None, &operation));
+}
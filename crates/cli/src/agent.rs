@@ -0,0 +1,713 @@
+use crate::prompt_secret;
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use chamber_vault::{KeyMaterial, Vault};
+use clap::Subcommand;
+use color_eyre::eyre::eyre;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+/// Overrides the location of the agent socket.
+const SOCKET_ENV: &str = "CHAMBER_AGENT_SOCK";
+const DEFAULT_IDLE_TIMEOUT_MINUTES: u64 = 15;
+
+#[derive(Subcommand, Debug)]
+pub enum AgentCommand {
+    /// Start the agent in the background
+    Start {
+        #[arg(
+            long,
+            default_value_t = DEFAULT_IDLE_TIMEOUT_MINUTES,
+            help = "Lock all vaults after this many minutes without use (0 never locks)"
+        )]
+        idle_timeout: u64,
+        #[arg(long, help = "Run the agent in the foreground instead of detaching it")]
+        foreground: bool,
+    },
+    /// Lock every vault held by the agent, leaving it running
+    Lock,
+    /// Show whether the agent is running and which vaults it holds
+    Status,
+    /// Lock every vault and stop the agent
+    Stop,
+}
+
+/// A request sent to the agent, one JSON object per line.
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "request", rename_all = "snake_case")]
+enum Request {
+    Status,
+    /// Asks for the key of the vault stored at `path`
+    GetKey {
+        path: PathBuf,
+    },
+    /// Hands the key of the vault stored at `path` to the agent
+    AddKey {
+        path: PathBuf,
+        key: String,
+    },
+    Lock,
+    Stop,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "response", rename_all = "snake_case")]
+enum Response {
+    Ok,
+    Key {
+        key: String,
+    },
+    Status {
+        pid: u32,
+        vaults: Vec<String>,
+        idle_timeout_minutes: u64,
+        locks_in_seconds: Option<i64>,
+        ssh_auth_sock: PathBuf,
+        ssh_keys: usize,
+    },
+    Error {
+        message: String,
+    },
+}
+
+/// Runs an `agent` subcommand.
+///
+/// # Errors
+/// Returns an error if the agent cannot be started or reached, or on platforms without Unix
+/// domain sockets.
+pub async fn handle_agent_command(cmd: AgentCommand) -> color_eyre::Result<()> {
+    #[cfg(unix)]
+    {
+        match cmd {
+            AgentCommand::Start {
+                idle_timeout,
+                foreground: true,
+            } => server::serve(&socket_path(), idle_timeout).await,
+            AgentCommand::Start { idle_timeout, .. } => server::spawn(idle_timeout).await,
+            AgentCommand::Lock => {
+                match request(&Request::Lock)? {
+                    Some(Response::Ok) => println!("🔒 Locked all vaults held by the agent"),
+                    Some(response) => return Err(unexpected(response)),
+                    None => println!("⚪ The agent is not running"),
+                }
+                Ok(())
+            }
+            AgentCommand::Status => {
+                match request(&Request::Status)? {
+                    Some(Response::Status {
+                        pid,
+                        vaults,
+                        idle_timeout_minutes,
+                        locks_in_seconds,
+                        ssh_auth_sock,
+                        ssh_keys,
+                    }) => {
+                        println!("🟢 Agent running (pid {pid}) at {}", socket_path().display());
+                        if vaults.is_empty() {
+                            println!("   No unlocked vaults");
+                        } else {
+                            println!("   Unlocked vaults: {}", vaults.join(", "));
+                        }
+                        println!(
+                            "   SSH agent: {ssh_keys} key(s) at SSH_AUTH_SOCK={}",
+                            ssh_auth_sock.display()
+                        );
+                        match locks_in_seconds {
+                            Some(seconds) => println!(
+                                "   Locks after {idle_timeout_minutes} idle minute(s), in {}m {}s",
+                                seconds / 60,
+                                seconds % 60
+                            ),
+                            None => println!("   Idle locking is disabled"),
+                        }
+                    }
+                    Some(response) => return Err(unexpected(response)),
+                    None => println!("⚪ The agent is not running"),
+                }
+                Ok(())
+            }
+            AgentCommand::Stop => {
+                match request(&Request::Stop)? {
+                    Some(Response::Ok) => println!("🛑 Agent stopped"),
+                    Some(response) => return Err(unexpected(response)),
+                    None => println!("⚪ The agent is not running"),
+                }
+                Ok(())
+            }
+        }
+    }
+
+    #[cfg(not(unix))]
+    {
+        let _ = cmd;
+        Err(eyre!(
+            "chamber agent needs Unix domain sockets and is not supported on this platform"
+        ))
+    }
+}
+
+/// Unlocks `vault` with the key held by a running agent, or prompts for the master key when no
+/// agent holds it.
+///
+/// A vault unlocked with the master key is handed to the agent, if one is running, so later
+/// commands do not prompt again.
+///
+/// # Errors
+/// Returns an error if the master key cannot be read or does not unlock the vault.
+pub(crate) fn unlock_vault(vault: &mut Vault, prompt: &str) -> color_eyre::Result<()> {
+    if let Some(key) = fetch_key(vault) {
+        if vault.unlock_with_key(key).is_ok() {
+            warn_if_tampered(vault);
+            return Ok(());
+        }
+    }
+    let master = prompt_secret(prompt)?;
+    vault.unlock(&master)?;
+    warn_if_tampered(vault);
+    share_key(vault);
+    Ok(())
+}
+
+/// Tells the user when unlocking found the vault out of step with its signed manifest.
+pub(crate) fn warn_if_tampered(vault: &Vault) {
+    if let Some(warning) = vault.tamper_warning() {
+        eprintln!("⚠️  Vault tamper warning: {warning}.");
+        eprintln!(
+            "   Restore a backup if this was not you, or accept the current state with 'chamber doctor --accept-manifest'."
+        );
+    }
+}
+
+fn fetch_key(vault: &Vault) -> Option<KeyMaterial> {
+    let path = vault.db_path().to_path_buf();
+    match request(&Request::GetKey { path }) {
+        Ok(Some(Response::Key { key })) => decode_key(&key).ok(),
+        _ => None,
+    }
+}
+
+/// Hands an unlocked vault to the agent. Failures are ignored: the agent is only a cache.
+pub(crate) fn share_key(vault: &Vault) {
+    if let Ok(key) = vault.vault_key() {
+        let path = vault.db_path().to_path_buf();
+        let _ = request(&Request::AddKey {
+            path,
+            key: encode_key(&key),
+        });
+    }
+}
+
+fn encode_key(key: &KeyMaterial) -> String {
+    BASE64.encode(key.0)
+}
+
+fn decode_key(encoded: &str) -> color_eyre::Result<KeyMaterial> {
+    let bytes = BASE64.decode(encoded)?;
+    let key = <[u8; 32]>::try_from(bytes.as_slice()).map_err(|_| eyre!("Invalid vault key"))?;
+    Ok(KeyMaterial(key))
+}
+
+#[cfg(unix)]
+fn unexpected(response: Response) -> color_eyre::Report {
+    match response {
+        Response::Error { message } => eyre!("Agent error: {}", message),
+        other => eyre!("Unexpected response from the agent: {:?}", other),
+    }
+}
+
+/// Returns the agent socket: `$CHAMBER_AGENT_SOCK`, or `agent.sock` in a `chamber` directory
+/// under `$XDG_RUNTIME_DIR`, falling back to a per-user directory in the temp dir.
+fn socket_path() -> PathBuf {
+    if let Some(path) = std::env::var_os(SOCKET_ENV) {
+        return PathBuf::from(path);
+    }
+    let dir = match std::env::var_os("XDG_RUNTIME_DIR") {
+        Some(runtime) => PathBuf::from(runtime).join("chamber"),
+        None => std::env::temp_dir().join(format!("chamber-{}", current_uid())),
+    };
+    dir.join("agent.sock")
+}
+
+/// Returns the socket speaking the OpenSSH agent protocol, next to the agent socket.
+fn ssh_socket_path(agent_socket: &std::path::Path) -> PathBuf {
+    agent_socket.with_file_name("ssh-agent.sock")
+}
+
+#[cfg(unix)]
+fn current_uid() -> u32 {
+    // SAFETY: `geteuid` has no preconditions and cannot fail
+    unsafe { libc::geteuid() }
+}
+
+#[cfg(not(unix))]
+const fn current_uid() -> u32 {
+    0
+}
+
+/// Sends `request` to the running agent. Returns `None` when no agent is listening.
+#[cfg(unix)]
+fn request(request: &Request) -> color_eyre::Result<Option<Response>> {
+    request_at(&socket_path(), request)
+}
+
+#[cfg(not(unix))]
+#[allow(clippy::unnecessary_wraps)]
+const fn request(_request: &Request) -> color_eyre::Result<Option<Response>> {
+    Ok(None)
+}
+
+#[cfg(unix)]
+fn request_at(path: &std::path::Path, request: &Request) -> color_eyre::Result<Option<Response>> {
+    use std::io::{BufRead, BufReader, ErrorKind, Write};
+    use std::os::unix::fs::MetadataExt;
+    use std::os::unix::net::UnixStream;
+    use std::time::Duration;
+
+    let meta = match std::fs::symlink_metadata(path) {
+        Ok(meta) => meta,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e.into()),
+    };
+    if meta.uid() != current_uid() {
+        return Err(eyre!(
+            "Refusing to use agent socket {}: it is owned by another user",
+            path.display()
+        ));
+    }
+    let mut stream = match UnixStream::connect(path) {
+        Ok(stream) => stream,
+        // A socket left behind by an agent that did not shut down cleanly
+        Err(e) if e.kind() == ErrorKind::ConnectionRefused => return Ok(None),
+        Err(e) => return Err(e.into()),
+    };
+    stream.set_read_timeout(Some(Duration::from_secs(10)))?;
+    stream.set_write_timeout(Some(Duration::from_secs(10)))?;
+
+    let mut line = serde_json::to_string(request)?;
+    line.push('\n');
+    stream.write_all(line.as_bytes())?;
+
+    let mut response = String::new();
+    BufReader::new(stream).read_line(&mut response)?;
+    Ok(Some(serde_json::from_str(&response)?))
+}
+
+#[cfg(unix)]
+mod server {
+    use super::{Request, Response, current_uid, decode_key, encode_key, request, socket_path, ssh_socket_path};
+    use crate::ssh_agent::{self, SshKeyring};
+    use async_trait::async_trait;
+    use chamber_vault::{AutoLockCallback, AutoLockConfig, AutoLockService, Vault, VaultManager, VaultRegistry};
+    use color_eyre::eyre::eyre;
+    use std::os::unix::fs::{MetadataExt, PermissionsExt};
+    use std::path::{Path, PathBuf};
+    use std::sync::Arc;
+    use std::time::Duration;
+    use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+    use tokio::net::{UnixListener, UnixStream};
+    use tokio::sync::{Mutex, Notify};
+
+    struct AgentState {
+        /// Whoever needs both locks takes `manager` first, then `keyring`.
+        manager: Arc<Mutex<VaultManager>>,
+        keyring: Arc<Mutex<SshKeyring>>,
+        ssh_socket: PathBuf,
+        autolock: AutoLockService,
+        idle_timeout_minutes: u64,
+        shutdown: Notify,
+    }
+
+    /// Locks every vault once the agent has been idle for the configured time.
+    struct LockAllVaults {
+        manager: Arc<Mutex<VaultManager>>,
+        keyring: Arc<Mutex<SshKeyring>>,
+    }
+
+    #[async_trait]
+    impl AutoLockCallback for LockAllVaults {
+        async fn on_auto_lock(&self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+            self.manager.lock().await.close_all_vaults();
+            self.keyring.lock().await.clear();
+            Ok(())
+        }
+    }
+
+    /// Starts the agent as a detached background process and waits until it accepts requests.
+    pub(super) async fn spawn(idle_timeout: u64) -> color_eyre::Result<()> {
+        use std::os::unix::process::CommandExt;
+        use std::process::{Command, Stdio};
+
+        if let Some(Response::Status { pid, .. }) = request(&Request::Status)? {
+            println!("🟢 Agent already running (pid {pid})");
+            return Ok(());
+        }
+
+        let mut child = Command::new(std::env::current_exe()?)
+            .args(["agent", "start", "--foreground", "--idle-timeout"])
+            .arg(idle_timeout.to_string())
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            // Keep the agent alive when the terminal's process group is interrupted
+            .process_group(0)
+            .spawn()?;
+
+        for _ in 0..50 {
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            if let Some(Response::Status { pid, .. }) = request(&Request::Status)? {
+                let socket = socket_path();
+                println!("🟢 Agent started (pid {pid}) at {}", socket.display());
+                println!(
+                    "   For SSH, run: export SSH_AUTH_SOCK={}",
+                    ssh_socket_path(&socket).display()
+                );
+                if idle_timeout > 0 {
+                    println!("   Vaults are locked after {idle_timeout} idle minute(s)");
+                }
+                return Ok(());
+            }
+            if let Some(status) = child.try_wait()? {
+                return Err(eyre!(
+                    "The agent exited ({}); run 'chamber agent start --foreground' to see why",
+                    status
+                ));
+            }
+        }
+        Err(eyre!("Timed out waiting for the agent to start"))
+    }
+
+    /// Runs the agent in the foreground until it is stopped or receives SIGINT/SIGTERM.
+    ///
+    /// # Errors
+    /// Returns an error if another agent is already listening at `path` or the socket cannot be
+    /// created securely.
+    pub(super) async fn serve(path: &Path, idle_timeout_minutes: u64) -> color_eyre::Result<()> {
+        use tokio::signal::unix::{SignalKind, signal};
+
+        let listener = bind(path)?;
+        let ssh_socket = ssh_socket_path(path);
+        let ssh_listener = bind(&ssh_socket)?;
+        let manager = Arc::new(Mutex::new(VaultManager::new()?));
+        let keyring = Arc::new(Mutex::new(SshKeyring::default()));
+        let config = AutoLockConfig {
+            enabled: idle_timeout_minutes > 0,
+            inactivity_timeout_minutes: idle_timeout_minutes,
+            ..AutoLockConfig::default()
+        };
+        let callback = Arc::new(LockAllVaults {
+            manager: Arc::clone(&manager),
+            keyring: Arc::clone(&keyring),
+        });
+        let state = Arc::new(AgentState {
+            manager,
+            keyring,
+            ssh_socket,
+            autolock: AutoLockService::new(config, callback),
+            idle_timeout_minutes,
+            shutdown: Notify::new(),
+        });
+        let autolock = state.autolock.start().await;
+        let mut terminate = signal(SignalKind::terminate())?;
+
+        loop {
+            tokio::select! {
+                accepted = listener.accept() => match accepted {
+                    Ok((stream, _)) => {
+                        let state = Arc::clone(&state);
+                        tokio::spawn(async move {
+                            if let Err(e) = handle_connection(&state, stream).await {
+                                tracing::warn!("Agent connection failed: {}", e);
+                            }
+                        });
+                    }
+                    Err(e) => tracing::warn!("Failed to accept agent connection: {}", e),
+                },
+                accepted = ssh_listener.accept() => match accepted {
+                    Ok((stream, _)) => {
+                        let state = Arc::clone(&state);
+                        tokio::spawn(async move {
+                            state.autolock.update_activity().await;
+                            let served =
+                                ssh_agent::handle_connection(&state.manager, &state.keyring, stream, current_uid()).await;
+                            if let Err(e) = served {
+                                tracing::warn!("SSH agent connection failed: {}", e);
+                            }
+                        });
+                    }
+                    Err(e) => tracing::warn!("Failed to accept SSH agent connection: {}", e),
+                },
+                () = state.shutdown.notified() => break,
+                _ = tokio::signal::ctrl_c() => break,
+                _ = terminate.recv() => break,
+            }
+        }
+
+        state.autolock.stop().await;
+        autolock.abort();
+        state.manager.lock().await.close_all_vaults();
+        state.keyring.lock().await.clear();
+        std::fs::remove_file(path).ok();
+        std::fs::remove_file(&state.ssh_socket).ok();
+        Ok(())
+    }
+
+    /// Creates the socket readable and writable by the owner only, inside a directory that
+    /// only the owner can enter.
+    fn bind(path: &Path) -> color_eyre::Result<UnixListener> {
+        let dir = path
+            .parent()
+            .filter(|dir| !dir.as_os_str().is_empty())
+            .ok_or_else(|| eyre!("Invalid agent socket path {}", path.display()))?;
+        if !dir.exists() {
+            std::fs::create_dir_all(dir)?;
+            std::fs::set_permissions(dir, std::fs::Permissions::from_mode(0o700))?;
+        }
+        let meta = std::fs::metadata(dir)?;
+        if meta.uid() != current_uid() {
+            return Err(eyre!("{} is owned by another user", dir.display()));
+        }
+
+        if std::fs::symlink_metadata(path).is_ok() {
+            if std::os::unix::net::UnixStream::connect(path).is_ok() {
+                return Err(eyre!("An agent is already running at {}", path.display()));
+            }
+            // Left behind by an agent that did not shut down cleanly
+            std::fs::remove_file(path)?;
+        }
+
+        // Created with no permissions for group and others, so the socket is never reachable
+        // by another user, not even between `bind` and `set_permissions`
+        // SAFETY: `umask` only changes the file mode creation mask of this process
+        let previous = unsafe { libc::umask(0o077) };
+        let listener = UnixListener::bind(path);
+        // SAFETY: as above, restoring the previous mask
+        unsafe { libc::umask(previous) };
+        let listener = listener?;
+        std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600))?;
+        Ok(listener)
+    }
+
+    async fn handle_connection(state: &AgentState, stream: UnixStream) -> color_eyre::Result<()> {
+        let peer = stream.peer_cred()?;
+        if peer.uid() != current_uid() {
+            return Err(eyre!("Rejected connection from uid {}", peer.uid()));
+        }
+
+        let (reader, mut writer) = stream.into_split();
+        let mut lines = BufReader::new(reader).lines();
+        while let Some(line) = lines.next_line().await? {
+            let (response, stop) = match serde_json::from_str::<Request>(&line) {
+                Ok(request) => {
+                    let stop = request == Request::Stop;
+                    let response = handle_request(state, request)
+                        .await
+                        .unwrap_or_else(|e| Response::Error { message: e.to_string() });
+                    (response, stop)
+                }
+                Err(e) => (
+                    Response::Error {
+                        message: format!("Invalid request: {e}"),
+                    },
+                    false,
+                ),
+            };
+            let mut out = serde_json::to_string(&response)?;
+            out.push('\n');
+            writer.write_all(out.as_bytes()).await?;
+            if stop {
+                // Only after the reply is sent, so the client sees the agent stop cleanly
+                state.shutdown.notify_one();
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    async fn handle_request(state: &AgentState, request: Request) -> color_eyre::Result<Response> {
+        match request {
+            Request::Status => {
+                let manager = state.manager.lock().await;
+                let mut vaults: Vec<String> = manager
+                    .open_vaults
+                    .keys()
+                    .map(|id| {
+                        manager
+                            .list_vaults()
+                            .into_iter()
+                            .find(|v| &v.id == id)
+                            .map_or_else(|| id.clone(), |v| v.name.clone())
+                    })
+                    .collect();
+                let ssh_keys = {
+                    let mut keyring = state.keyring.lock().await;
+                    keyring.refresh(&manager.open_vaults);
+                    keyring.len()
+                };
+                drop(manager);
+                vaults.sort();
+                Ok(Response::Status {
+                    pid: std::process::id(),
+                    vaults,
+                    idle_timeout_minutes: state.idle_timeout_minutes,
+                    locks_in_seconds: state
+                        .autolock
+                        .get_time_until_lock()
+                        .await
+                        .map(|remaining| remaining.num_seconds()),
+                    ssh_auth_sock: state.ssh_socket.clone(),
+                    ssh_keys,
+                })
+            }
+            Request::GetKey { path } => {
+                state.autolock.update_activity().await;
+                let key = {
+                    let mut manager = state.manager.lock().await;
+                    let id = registered_vault_id(&mut manager, &path)?;
+                    manager
+                        .get_vault(&id)
+                        .ok_or_else(|| eyre!("Vault is not unlocked in the agent"))?
+                        .vault_key()?
+                };
+                Ok(Response::Key { key: encode_key(&key) })
+            }
+            Request::AddKey { path, key } => {
+                state.autolock.update_activity().await;
+                let id = registered_vault_id(&mut *state.manager.lock().await, &path)?;
+                let mut vault = Vault::open_by_id(&id)?;
+                vault.unlock_with_key(decode_key(&key)?)?;
+                state.manager.lock().await.open_vaults.insert(id, vault);
+                Ok(Response::Ok)
+            }
+            Request::Lock | Request::Stop => {
+                state.manager.lock().await.close_all_vaults();
+                state.keyring.lock().await.clear();
+                Ok(Response::Ok)
+            }
+        }
+    }
+
+    /// Returns the registry ID of the vault stored at `path`. Only registered vaults are held.
+    fn registered_vault_id(manager: &mut VaultManager, path: &Path) -> color_eyre::Result<String> {
+        let path = canonical(path);
+        let find = |manager: &VaultManager| {
+            manager
+                .list_vaults()
+                .into_iter()
+                .find(|v| canonical(&v.path) == path)
+                .map(|v| v.id.clone())
+        };
+        if let Some(id) = find(manager) {
+            return Ok(id);
+        }
+        // The vault may have been registered after the agent started
+        manager.registry = VaultRegistry::load()?;
+        find(manager).ok_or_else(|| eyre!("{} is not a registered vault", path.display()))
+    }
+
+    fn canonical(path: &Path) -> PathBuf {
+        std::fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+    }
+
+    #[cfg(test)]
+    mod tests {
+        #![allow(clippy::panic)]
+        use super::super::request_at;
+        use super::*;
+
+        #[tokio::test(flavor = "multi_thread")]
+        async fn test_agent_serves_status_and_stops() -> color_eyre::Result<()> {
+            let now = time::OffsetDateTime::now_utc().unix_timestamp_nanos();
+            let dir = std::env::temp_dir().join(format!("chamber_agent_{}_{now}", std::process::id()));
+            let path = dir.join("agent.sock");
+            let agent = tokio::spawn({
+                let path = path.clone();
+                async move { serve(&path, 5).await }
+            });
+
+            let ask = |request: Request| {
+                let path = path.clone();
+                tokio::task::spawn_blocking(move || request_at(&path, &request))
+            };
+            let mut status = None;
+            for _ in 0..50 {
+                if path.exists() {
+                    status = ask(Request::Status).await??;
+                    break;
+                }
+                tokio::time::sleep(Duration::from_millis(50)).await;
+            }
+            match status {
+                Some(Response::Status {
+                    pid,
+                    vaults,
+                    idle_timeout_minutes,
+                    locks_in_seconds,
+                    ssh_auth_sock,
+                    ssh_keys,
+                }) => {
+                    assert_eq!(ssh_auth_sock, dir.join("ssh-agent.sock"));
+                    assert_eq!(ssh_keys, 0);
+                    assert_eq!(pid, std::process::id());
+                    assert!(vaults.is_empty());
+                    assert_eq!(idle_timeout_minutes, 5);
+                    assert!(locks_in_seconds.is_some_and(|s| s > 0 && s <= 300));
+                }
+                other => panic!("unexpected status: {other:?}"),
+            }
+            assert_eq!(std::fs::metadata(&path)?.permissions().mode() & 0o777, 0o600);
+            assert_eq!(std::fs::metadata(&dir)?.permissions().mode() & 0o777, 0o700);
+
+            // Only registered vaults are held by the agent
+            let key = encode_key(&chamber_vault::KeyMaterial::random());
+            let unregistered = dir.join("unregistered.db");
+            match ask(Request::AddKey {
+                path: unregistered.clone(),
+                key,
+            })
+            .await??
+            {
+                Some(Response::Error { message }) => assert!(message.contains("not a registered vault")),
+                other => panic!("unexpected response: {other:?}"),
+            }
+            assert!(matches!(
+                ask(Request::GetKey { path: unregistered }).await??,
+                Some(Response::Error { .. })
+            ));
+
+            assert!(matches!(ask(Request::Stop).await??, Some(Response::Ok)));
+            agent.await??;
+            assert!(!path.exists());
+            assert!(ask(Request::Status).await??.is_none());
+
+            std::fs::remove_dir_all(dir).ok();
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_request_wire_format() -> color_eyre::Result<()> {
+        let request = Request::GetKey {
+            path: PathBuf::from("/tmp/vault.sqlite3"),
+        };
+        let line = serde_json::to_string(&request)?;
+        assert_eq!(line, r#"{"request":"get_key","path":"/tmp/vault.sqlite3"}"#);
+        assert_eq!(serde_json::from_str::<Request>(&line)?, request);
+        assert_eq!(serde_json::from_str::<Request>(r#"{"request":"stop"}"#)?, Request::Stop);
+        assert!(serde_json::from_str::<Request>(r#"{"request":"unlock_everything"}"#).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_key_encoding_round_trip() -> color_eyre::Result<()> {
+        let key = KeyMaterial::random();
+        assert_eq!(decode_key(&encode_key(&key))?.0, key.0);
+        assert!(decode_key(&BASE64.encode([0u8; 16])).is_err());
+        assert!(decode_key("not base64!").is_err());
+        Ok(())
+    }
+}
@@ -1,9 +1,10 @@
 use chamber_vault::{Vault, VaultManager};
 use color_eyre::Result;
+use std::path::PathBuf;
 use std::sync::Once;
 static TRACING_INIT: Once = Once::new();
 
-pub async fn handle_api_command(bind: String, port: Option<u16>) -> Result<()> {
+pub async fn handle_api_command(bind: String, port: Option<u16>, keyfile_dir: Option<PathBuf>) -> Result<()> {
     use chamber_api::ApiServer;
     use tracing::{info, warn};
 
@@ -33,7 +34,7 @@ pub async fn handle_api_command(bind: String, port: Option<u16>) -> Result<()> {
     let vault_manager = VaultManager::new()?;
 
     // Create and start the API server
-    let api_server = ApiServer::new(vault, vault_manager, &bind_address).await?;
+    let api_server = ApiServer::new(vault, vault_manager, &bind_address, keyfile_dir).await?;
     let addr = api_server.local_addr()?;
 
     println!("✅ Chamber API server running on http://{addr}");
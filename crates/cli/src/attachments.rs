@@ -0,0 +1,183 @@
+use crate::history::unlock_and_find;
+use crate::utils::format_relative_time;
+use chamber_import_export::AttachmentFile;
+use chamber_vault::Vault;
+use clap::Subcommand;
+use color_eyre::eyre::eyre;
+use std::fs::File;
+use std::io::{BufReader, BufWriter};
+use std::path::{Path, PathBuf};
+
+#[derive(Subcommand, Debug)]
+pub enum AttachmentCommand {
+    /// List the files attached to an item
+    List {
+        #[arg(short, long, help = "Name of the item")]
+        name: String,
+    },
+    /// Decrypt an attachment and write it to a file
+    Get {
+        #[arg(short, long, help = "Name of the item")]
+        name: String,
+        #[arg(short, long, help = "File name of the attachment")]
+        file: String,
+        #[arg(
+            short,
+            long,
+            help = "Where to write the attachment (defaults to its file name in the current directory, '-' for stdout)"
+        )]
+        output: Option<PathBuf>,
+    },
+    /// Remove an attachment from an item
+    Remove {
+        #[arg(short, long, help = "Name of the item")]
+        name: String,
+        #[arg(short, long, help = "File name of the attachment")]
+        file: String,
+    },
+}
+
+/// Encrypts a file and attaches it to an item.
+///
+/// The attachment is stored under the file's own name unless `file_name` is given.
+///
+/// # Errors
+/// Returns an error if the vault cannot be unlocked, the item does not exist, the file cannot
+/// be read, or the vault rejects the attachment (duplicate name or too large).
+pub fn handle_attach_command(
+    vault: &mut Vault,
+    name: &str,
+    file: &Path,
+    file_name: Option<&str>,
+) -> color_eyre::Result<()> {
+    let file_name = match file_name {
+        Some(file_name) => file_name.to_string(),
+        None => file
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .ok_or_else(|| eyre!("Cannot determine a file name for {}", file.display()))?,
+    };
+    let item = unlock_and_find(vault, name)?;
+    let mut reader = BufReader::new(File::open(file).map_err(|e| eyre!("Cannot read {}: {}", file.display(), e))?);
+    let attachment = vault.add_attachment(item.id, &file_name, &mut reader)?;
+    println!(
+        "📎 Attached '{}' ({}) to '{}'",
+        attachment.file_name,
+        format_size(attachment.size),
+        item.name
+    );
+    Ok(())
+}
+
+/// Runs an `attachment` subcommand.
+///
+/// # Errors
+/// Returns an error if the vault cannot be unlocked, the item or attachment does not exist, or
+/// reading, writing or decrypting the attachment fails.
+pub fn handle_attachment_command(vault: &mut Vault, cmd: AttachmentCommand) -> color_eyre::Result<()> {
+    match cmd {
+        AttachmentCommand::List { name } => {
+            let item = unlock_and_find(vault, &name)?;
+            let attachments = vault.list_attachments(item.id)?;
+            if attachments.is_empty() {
+                println!("'{}' has no attachments", item.name);
+                return Ok(());
+            }
+            println!("📎 Attachments of '{}':", item.name);
+            for attachment in attachments {
+                println!(
+                    "   {:<32} {:>10}  added {}",
+                    attachment.file_name,
+                    format_size(attachment.size),
+                    format_relative_time(attachment.created_at)
+                );
+            }
+        }
+        AttachmentCommand::Get { name, file, output } => {
+            let item = unlock_and_find(vault, &name)?;
+            let attachment = find_attachment(vault, item.id, &file)?;
+            let output = output.unwrap_or_else(|| PathBuf::from(&attachment.file_name));
+            if output.as_os_str() == "-" {
+                vault.read_attachment(attachment.id, &mut std::io::stdout().lock())?;
+                return Ok(());
+            }
+            if output.exists() {
+                return Err(eyre!(
+                    "{} already exists; choose another path with --output",
+                    output.display()
+                ));
+            }
+            let mut writer = BufWriter::new(File::create(&output)?);
+            if let Err(e) = vault.read_attachment(attachment.id, &mut writer) {
+                drop(writer);
+                std::fs::remove_file(&output).ok();
+                return Err(e);
+            }
+            println!("✅ Wrote '{}' to {}", attachment.file_name, output.display());
+        }
+        AttachmentCommand::Remove { name, file } => {
+            let item = unlock_and_find(vault, &name)?;
+            let attachment = find_attachment(vault, item.id, &file)?;
+            vault.delete_attachment(attachment.id)?;
+            println!("🗑️  Removed '{}' from '{}'", attachment.file_name, item.name);
+        }
+    }
+    Ok(())
+}
+
+/// Attaches files read from a backup to the item called `item_name`.
+///
+/// # Errors
+/// Returns an error if the item does not exist or an attachment cannot be stored.
+pub(crate) fn restore_attachments(
+    vault: &mut Vault,
+    item_name: &str,
+    files: &[AttachmentFile],
+) -> color_eyre::Result<()> {
+    if files.is_empty() {
+        return Ok(());
+    }
+    let item = vault
+        .get_item_by_name(item_name)?
+        .ok_or_else(|| eyre!("Item '{}' not found", item_name))?;
+    for file in files {
+        vault.add_attachment(item.id, &file.file_name, &mut file.data.as_slice())?;
+    }
+    Ok(())
+}
+
+fn find_attachment(vault: &Vault, item_id: u64, file: &str) -> color_eyre::Result<chamber_vault::Attachment> {
+    vault
+        .list_attachments(item_id)?
+        .into_iter()
+        .find(|a| a.file_name == file)
+        .ok_or_else(|| eyre!("Attachment '{}' not found", file))
+}
+
+#[allow(clippy::cast_precision_loss)]
+fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KiB", "MiB", "GiB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{bytes} B")
+    } else {
+        format!("{size:.1} {}", UNITS[unit])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_size() {
+        assert_eq!(format_size(512), "512 B");
+        assert_eq!(format_size(1536), "1.5 KiB");
+        assert_eq!(format_size(5 * 1024 * 1024), "5.0 MiB");
+    }
+}
@@ -1,6 +1,11 @@
-use chamber_backup::BackupManager;
-use chamber_import_export::{detect_format_from_extension, import_items};
-use chamber_vault::{BackupConfig, Vault};
+use crate::agent::unlock_vault;
+use crate::attachments::restore_attachments;
+use chamber_backup::{BackupManager, with_uncompressed_snapshot};
+use chamber_import_export::{
+    AttachmentFile, BackupKeySource, BackupSecret, ExportFormat, chamber_backup_key_source,
+    detect_format_from_extension, import_chamber_backup_with_attachments, import_items,
+};
+use chamber_vault::{BackupConfig, Db, NewItem, Vault};
 use clap::Subcommand;
 use color_eyre::eyre::eyre;
 use std::path::{Path, PathBuf};
@@ -21,7 +26,7 @@ pub enum BackupCommand {
         /// Backup directory path
         #[arg(long)]
         backup_dir: Option<PathBuf>,
-        /// Backup format (json, csv, backup)
+        /// Backup format: backup (encrypted), snapshot (raw encrypted vault file), or json/csv (plaintext, opt-in)
         #[arg(long)]
         format: Option<String>,
         /// Enable/disable compression
@@ -166,7 +171,7 @@ pub fn handle_backup_command(vault: Vault, cmd: BackupCommand) -> color_eyre::Re
 
         BackupCommand::Restore { backup_path, yes } => handle_restore_backup(vault, &backup_path, yes),
 
-        BackupCommand::Verify { backup_path } => handle_verify_backup(&backup_path),
+        BackupCommand::Verify { backup_path } => handle_verify_backup(vault, &backup_path),
 
         BackupCommand::Status => handle_backup_status(vault),
 
@@ -220,12 +225,15 @@ fn handle_configure(
 
     if let Some(fmt) = format {
         match fmt.as_str() {
-            "json" | "csv" | "backup" => {
+            "json" | "csv" | "backup" | "snapshot" => {
                 config.format.clone_from(&fmt);
                 changed = true;
                 println!("Backup format set to: {fmt}");
+                if matches!(fmt.as_str(), "json" | "csv") {
+                    println!("⚠️  {fmt} backups store your secrets unencrypted on disk");
+                }
             }
-            _ => return Err(eyre!("Invalid format '{}'. Use: json, csv, or backup", fmt)),
+            _ => return Err(eyre!("Invalid format '{}'. Use: json, csv, backup, or snapshot", fmt)),
         }
     }
 
@@ -255,8 +263,7 @@ fn handle_configure(
 fn handle_backup_now(mut vault: Vault, output: Option<PathBuf>, force: bool) -> color_eyre::Result<()> {
     // Unlock vault if needed
     if !vault.is_unlocked() {
-        let password = rpassword::prompt_password("Enter master password: ")?;
-        vault.unlock(&password)?;
+        unlock_vault(&mut vault, "Enter master password: ")?;
     }
 
     let config = if let Some(custom_path) = output {
@@ -348,9 +355,13 @@ fn handle_restore_backup(mut vault: Vault, backup_path: &Path, skip_confirmation
         return Err(eyre!("Backup file not found: {}", backup_path.display()));
     }
 
-    // Verify backup first
+    if is_snapshot_file(backup_path) {
+        return handle_restore_snapshot(vault, backup_path, skip_confirmation);
+    }
+
+    // Verify backup first; encrypted backups are decrypted and authenticated here
     println!("Verifying backup integrity...");
-    verify_backup_file(backup_path)?;
+    let items = verify_backup_file(&mut vault, backup_path)?;
     println!("✅ Backup verification passed");
 
     if !skip_confirmation {
@@ -367,30 +378,30 @@ fn handle_restore_backup(mut vault: Vault, backup_path: &Path, skip_confirmation
         }
     }
 
-    // Unlock vault
-    let password = rpassword::prompt_password("Enter master password: ")?;
-    vault.unlock(&password)?;
-
-    // Detect backup format
-    let format = chamber_import_export::detect_format_from_extension(backup_path)
-        .unwrap_or(chamber_import_export::ExportFormat::Json);
+    // Unlock vault (already unlocked if the backup was sealed with the vault key)
+    if !vault.is_unlocked() {
+        unlock_vault(&mut vault, "Enter master password: ")?;
+    }
 
     println!("Importing backup data...");
-    let items = chamber_import_export::import_items(backup_path, &format)?;
-
     println!("Found {} items in backup", items.len());
 
     // Clear existing items (if user confirmed)
-    let existing_items = vault.list_items()?;
+    let existing_items = vault.list_item_headers()?;
     for item in existing_items {
         vault.delete_item(item.id)?;
     }
 
     // Import new items
     let mut imported_count = 0;
-    for item in items {
+    for (item, attachments) in items {
         match vault.create_item(&item) {
-            Ok(()) => imported_count += 1,
+            Ok(()) => {
+                imported_count += 1;
+                if let Err(e) = restore_attachments(&mut vault, &item.name, &attachments) {
+                    println!("⚠️  Failed to restore attachments of '{}': {}", item.name, e);
+                }
+            }
             Err(e) => println!("⚠️  Failed to import '{}': {}", item.name, e),
         }
     }
@@ -399,27 +410,60 @@ fn handle_restore_backup(mut vault: Vault, backup_path: &Path, skip_confirmation
     Ok(())
 }
 
-fn handle_verify_backup(backup_path: &PathBuf) -> color_eyre::Result<()> {
+fn handle_verify_backup(mut vault: Vault, backup_path: &PathBuf) -> color_eyre::Result<()> {
     println!("Verifying backup: {}", backup_path.display());
 
-    verify_backup_file(backup_path)?;
+    let item_count = if is_snapshot_file(backup_path) {
+        with_uncompressed_snapshot(backup_path, Db::verify_snapshot)?
+    } else {
+        verify_backup_file(&mut vault, backup_path)?.len()
+    };
 
     println!("✅ Backup verification passed");
 
     // Show additional info
     let metadata = std::fs::metadata(backup_path)?;
     println!("   Size: {} bytes", metadata.len());
+    println!("   Items: {item_count}");
+
+    Ok(())
+}
+
+/// Restores a raw vault snapshot, replacing the vault database page for page.
+///
+/// Snapshots carry their own key material, so no password is needed to restore one; afterwards
+/// the vault unlocks with the master password that was in effect when the snapshot was taken.
+fn handle_restore_snapshot(mut vault: Vault, backup_path: &Path, skip_confirmation: bool) -> color_eyre::Result<()> {
+    println!("Verifying snapshot integrity...");
+    let item_count = with_uncompressed_snapshot(backup_path, Db::verify_snapshot)?;
+    println!("✅ Snapshot verification passed ({item_count} items)");
+
+    if !skip_confirmation {
+        println!("⚠️  WARNING: This will replace the entire vault, including its master password!");
+        print!("Are you sure you want to restore from this snapshot? (y/N): ");
+        std::io::Write::flush(&mut std::io::stdout())?;
 
-    // Try to get item count
-    if let Some(format) = chamber_import_export::detect_format_from_extension(backup_path) {
-        if let Ok(items) = chamber_import_export::import_items(backup_path, &format) {
-            println!("   Items: {}", items.len());
+        let mut input = String::new();
+        std::io::stdin().read_line(&mut input)?;
+
+        if !matches!(input.trim().to_lowercase().as_str(), "y" | "yes") {
+            println!("Restore cancelled");
+            return Ok(());
         }
     }
 
+    with_uncompressed_snapshot(backup_path, |path| vault.restore_snapshot(path))?;
+    println!("✅ Vault restored from snapshot");
+    println!("   Unlock it with the master password that was in use when the snapshot was taken");
     Ok(())
 }
 
+fn is_snapshot_file(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|n| n.to_str())
+        .is_some_and(|n| n.ends_with(".snapshot") || n.ends_with(".snapshot.gz"))
+}
+
 fn handle_backup_status(vault: Vault) -> color_eyre::Result<()> {
     let config = vault.get_backup_config().unwrap_or_default();
 
@@ -530,7 +574,7 @@ fn print_backup_config(config: &BackupConfig) {
     );
 }
 
-fn verify_backup_file(backup_path: &std::path::Path) -> color_eyre::Result<()> {
+fn verify_backup_file(vault: &mut Vault, backup_path: &std::path::Path) -> color_eyre::Result<Vec<BackupItem>> {
     if !backup_path.exists() {
         return Err(eyre!("Backup file does not exist"));
     }
@@ -540,12 +584,56 @@ fn verify_backup_file(backup_path: &std::path::Path) -> color_eyre::Result<()> {
         return Err(eyre!("Backup file is empty"));
     }
 
-    // Try to parse the backup based on format
-    if let Some(format) = detect_format_from_extension(backup_path) {
-        import_items(backup_path, &format)?;
-    } else {
-        return Err(eyre!("Unable to detect backup format"));
+    read_backup_items(vault, backup_path)
+}
+
+/// An item read from a backup, together with its attachments.
+pub(crate) type BackupItem = (NewItem, Vec<AttachmentFile>);
+
+/// Reads the items stored in a backup or export file.
+///
+/// Plaintext formats are parsed directly and never carry attachments. Encrypted Chamber backups prompt for the backup
+/// passphrase, or unlock `vault` with the master password when the backup was sealed with
+/// the vault key.
+///
+/// # Errors
+///
+/// Returns an error if the format cannot be detected, a prompt fails, the vault cannot be
+/// unlocked, or the backup cannot be parsed or decrypted.
+pub(crate) fn read_backup_items(vault: &mut Vault, backup_path: &Path) -> color_eyre::Result<Vec<BackupItem>> {
+    let format = detect_format_from_extension(backup_path).ok_or_else(|| eyre!("Unable to detect backup format"))?;
+    read_items_with_format(vault, backup_path, &format)
+}
+
+/// Like [`read_backup_items`], but with an explicitly chosen format.
+///
+/// # Errors
+///
+/// See [`read_backup_items`].
+pub(crate) fn read_items_with_format(
+    vault: &mut Vault,
+    path: &Path,
+    format: &ExportFormat,
+) -> color_eyre::Result<Vec<BackupItem>> {
+    if !matches!(format, ExportFormat::ChamberBackup) {
+        return Ok(import_items(path, format)?
+            .into_iter()
+            .map(|item| (item, Vec::new()))
+            .collect());
     }
 
-    Ok(())
+    match chamber_backup_key_source(path)? {
+        None => import_chamber_backup_with_attachments(path, None),
+        Some(BackupKeySource::Passphrase) => {
+            let passphrase = rpassword::prompt_password("Enter backup passphrase: ")?;
+            import_chamber_backup_with_attachments(path, Some(&BackupSecret::Passphrase(&passphrase)))
+        }
+        Some(BackupKeySource::VaultKey) => {
+            if !vault.is_unlocked() {
+                unlock_vault(vault, "Enter master password: ")?;
+            }
+            let key = vault.backup_key()?;
+            import_chamber_backup_with_attachments(path, Some(&BackupSecret::VaultKey(&key)))
+        }
+    }
 }
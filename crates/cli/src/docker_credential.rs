@@ -0,0 +1,280 @@
+use crate::run::open_vault;
+use chamber_vault::{Item, ItemField, ItemHeader, ItemKind, NewItem, Vault};
+use color_eyre::eyre::eyre;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::io::{Read, Write};
+
+/// Prefix of item names that hold registry credentials, e.g. `docker:ghcr.io`.
+const NAME_PREFIX: &str = "docker:";
+/// Tag that offers an `ApiKey` or `Password` item with a `url` field to docker.
+const TAG: &str = "docker";
+/// Selects the vault used by `docker-credential-chamber`, which docker runs without arguments.
+const VAULT_ENV: &str = "CHAMBER_DOCKER_VAULT";
+/// The message docker recognises as "no credentials for this registry".
+const NOT_FOUND: &str = "credentials not found in native keychain";
+
+/// The credential exchanged with docker, using docker's field names.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct Credentials {
+    #[serde(rename = "ServerURL")]
+    server_url: String,
+    #[serde(rename = "Username")]
+    username: String,
+    #[serde(rename = "Secret")]
+    secret: String,
+}
+
+/// Runs one operation of docker's credential helper protocol on stdin and stdout and returns the
+/// exit code.
+///
+/// `get` and `erase` read a registry URL, `store` reads a JSON credential and `list` prints a JSON
+/// map of registry URLs to usernames. Credentials are kept as items named `docker:<registry>`, and
+/// `ApiKey` or `Password` items tagged `docker` are offered for the registry in their `url` field.
+/// `erase` only deletes `docker:` items; tagged items the user keeps themselves are left alone.
+/// As docker expects, errors are reported on stdout with exit code 1.
+pub fn handle_docker_credential_command(vault_id: Option<&str>, operation: &str) -> i32 {
+    let vault_id = vault_id
+        .map(str::to_string)
+        .or_else(|| std::env::var(VAULT_ENV).ok().filter(|v| !v.is_empty()));
+    let mut stdout = std::io::stdout().lock();
+    let result = run(vault_id.as_deref(), operation, std::io::stdin().lock(), &mut stdout);
+    match result {
+        Ok(()) => 0,
+        Err(e) => {
+            let _ = writeln!(stdout, "{e}");
+            1
+        }
+    }
+}
+
+fn run(vault_id: Option<&str>, operation: &str, mut input: impl Read, out: &mut impl Write) -> color_eyre::Result<()> {
+    if operation == "version" {
+        writeln!(out, "docker-credential-chamber {}", env!("CARGO_PKG_VERSION"))?;
+        return Ok(());
+    }
+    if !matches!(operation, "get" | "store" | "erase" | "list") {
+        return Err(eyre!("Usage: docker-credential-chamber <store|get|erase|list|version>"));
+    }
+    let mut request = String::new();
+    input.read_to_string(&mut request)?;
+
+    let mut vault = open_vault(vault_id)?;
+    match operation {
+        "get" => {
+            let credentials = get(&vault, request.trim())?.ok_or_else(|| eyre!(NOT_FOUND))?;
+            serde_json::to_writer(&mut *out, &credentials)?;
+            writeln!(out)?;
+        }
+        "store" => {
+            let credentials: Credentials =
+                serde_json::from_str(&request).map_err(|e| eyre!("Invalid credentials: {e}"))?;
+            store(&mut vault, &credentials)?;
+        }
+        "erase" => {
+            if !erase(&mut vault, request.trim())? {
+                return Err(eyre!(NOT_FOUND));
+            }
+        }
+        _ => {
+            serde_json::to_writer(&mut *out, &list(&vault)?)?;
+            writeln!(out)?;
+        }
+    }
+    Ok(())
+}
+
+/// Reduces a registry URL to `host[/path]` so `https://ghcr.io/` and `ghcr.io` are the same registry.
+fn registry_key(server_url: &str) -> String {
+    let rest = server_url.split_once("://").map_or(server_url, |(_, rest)| rest);
+    rest.trim().trim_end_matches('/').to_ascii_lowercase()
+}
+
+/// Returns the registry URL an item holds credentials for, if it is a registry credential.
+fn registry_of(item: &Item) -> Option<&str> {
+    if !matches!(item.kind, ItemKind::ApiKey | ItemKind::Password) {
+        return None;
+    }
+    if item.has_tag(TAG) {
+        if let Some(url) = item.field("url").filter(|u| !u.trim().is_empty()) {
+            return Some(url);
+        }
+    }
+    item.name.strip_prefix(NAME_PREFIX).filter(|r| !r.trim().is_empty())
+}
+
+/// Decrypts the items that may hold registry credentials, picked from the item headers: tagged
+/// items, whose registry is in a field, and `docker:` items whose name `keep` accepts.
+fn candidates(vault: &Vault, keep: impl Fn(&str) -> bool) -> color_eyre::Result<Vec<Item>> {
+    let mut items = Vec::new();
+    for header in vault.list_item_headers()? {
+        if is_candidate(&header, &keep) {
+            items.extend(vault.get_item_by_id(header.id)?);
+        }
+    }
+    Ok(items)
+}
+
+fn is_candidate(header: &ItemHeader, keep: impl Fn(&str) -> bool) -> bool {
+    matches!(header.kind, ItemKind::ApiKey | ItemKind::Password)
+        && (header.has_tag(TAG) || header.name.strip_prefix(NAME_PREFIX).is_some_and(keep))
+}
+
+fn find(vault: &Vault, server_url: &str) -> color_eyre::Result<Vec<Item>> {
+    let key = registry_key(server_url);
+    Ok(candidates(vault, |registry| registry_key(registry) == key)?
+        .into_iter()
+        .filter(|item| registry_of(item).is_some_and(|r| registry_key(r) == key))
+        .collect())
+}
+
+fn get(vault: &Vault, server_url: &str) -> color_eyre::Result<Option<Credentials>> {
+    Ok(find(vault, server_url)?.into_iter().next().map(|item| Credentials {
+        server_url: server_url.to_string(),
+        username: item.field("username").unwrap_or_default().to_string(),
+        secret: item.value,
+    }))
+}
+
+fn store(vault: &mut Vault, credentials: &Credentials) -> color_eyre::Result<()> {
+    if credentials.server_url.trim().is_empty() {
+        return Err(eyre!("Missing server URL"));
+    }
+    let username = ItemField::new("username", &credentials.username, false);
+    if let Some(item) = find(vault, &credentials.server_url)?.into_iter().next() {
+        let mut fields: Vec<ItemField> = item
+            .fields
+            .into_iter()
+            .filter(|f| !f.name.eq_ignore_ascii_case("username"))
+            .collect();
+        fields.push(username);
+        return vault.update_item_with_fields(item.id, &credentials.secret, &fields);
+    }
+    vault.create_item(&NewItem {
+        name: format!("{NAME_PREFIX}{}", registry_key(&credentials.server_url)),
+        kind: ItemKind::Password,
+        value: credentials.secret.clone(),
+        tags: vec![TAG.to_string()],
+        folder: None,
+        fields: vec![username, ItemField::new("url", &credentials.server_url, false)],
+    })
+}
+
+/// Deletes the `docker:` item holding the credentials for `server_url`; returns `false` when
+/// there was none.
+fn erase(vault: &mut Vault, server_url: &str) -> color_eyre::Result<bool> {
+    let Some(item) = find(vault, server_url)?
+        .into_iter()
+        .find(|item| item.name.starts_with(NAME_PREFIX))
+    else {
+        return Ok(false);
+    };
+    vault.delete_item(item.id)?;
+    Ok(true)
+}
+
+fn list(vault: &Vault) -> color_eyre::Result<BTreeMap<String, String>> {
+    Ok(candidates(vault, |_| true)?
+        .iter()
+        .filter_map(|item| {
+            let registry = registry_of(item)?;
+            Some((
+                registry.to_string(),
+                item.field("username").unwrap_or_default().to_string(),
+            ))
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+    use super::*;
+
+    #[test]
+    fn test_registry_key() {
+        assert_eq!(registry_key("https://index.docker.io/v1/"), "index.docker.io/v1");
+        assert_eq!(registry_key("GHCR.io"), "ghcr.io");
+        assert_eq!(registry_key("https://ghcr.io/"), registry_key("ghcr.io"));
+    }
+
+    #[test]
+    fn test_store_get_list_and_erase() -> color_eyre::Result<()> {
+        let now = time::OffsetDateTime::now_utc().unix_timestamp_nanos();
+        let path = std::env::temp_dir().join(format!("chamber_cli_docker_{}_{now}.db", std::process::id()));
+        let mut vault = Vault::open_or_create(Some(&path))?;
+        vault.initialize("m")?;
+        vault.unlock("m")?;
+        vault.create_item(&NewItem {
+            name: "Registry token".into(),
+            kind: ItemKind::ApiKey,
+            value: "glpat".into(),
+            tags: vec![TAG.into()],
+            folder: None,
+            fields: vec![
+                ItemField::new("url", "registry.gitlab.com", false),
+                ItemField::new("username", "ci", false),
+            ],
+        })?;
+        // Untagged items with a url are not registry credentials
+        vault.create_item(&NewItem {
+            name: "Website".into(),
+            kind: ItemKind::Password,
+            value: "hunter2".into(),
+            tags: Vec::new(),
+            folder: None,
+            fields: vec![ItemField::new("url", "https://ghcr.io", false)],
+        })?;
+
+        assert!(get(&vault, "https://ghcr.io")?.is_none());
+        let gitlab = get(&vault, "https://registry.gitlab.com/")?.unwrap();
+        assert_eq!((gitlab.username.as_str(), gitlab.secret.as_str()), ("ci", "glpat"));
+
+        let ghcr = Credentials {
+            server_url: "https://ghcr.io".into(),
+            username: "me".into(),
+            secret: "ghp_1".into(),
+        };
+        store(&mut vault, &ghcr)?;
+        store(
+            &mut vault,
+            &Credentials {
+                secret: "ghp_2".into(),
+                ..ghcr.clone()
+            },
+        )?;
+        assert_eq!(get(&vault, "ghcr.io")?.unwrap().secret, "ghp_2");
+        assert_eq!(
+            vault.get_item_by_name("docker:ghcr.io")?.unwrap().kind,
+            ItemKind::Password
+        );
+
+        let listed = list(&vault)?;
+        assert_eq!(listed.len(), 2);
+        assert_eq!(listed["https://ghcr.io"], "me");
+        assert_eq!(listed["registry.gitlab.com"], "ci");
+
+        assert!(erase(&mut vault, "ghcr.io")?);
+        assert!(!erase(&mut vault, "ghcr.io")?);
+        assert!(get(&vault, "ghcr.io")?.is_none());
+
+        // Tagged items belong to the user and are never erased
+        assert!(!erase(&mut vault, "registry.gitlab.com")?);
+        assert!(get(&vault, "registry.gitlab.com")?.is_some());
+
+        let _ = std::fs::remove_file(&path);
+        Ok(())
+    }
+
+    #[test]
+    fn test_unknown_operation_and_version() {
+        let mut out = Vec::new();
+        assert!(run(None, "frobnicate", std::io::empty(), &mut out).is_err());
+        run(None, "version", std::io::empty(), &mut out).unwrap();
+        assert!(
+            String::from_utf8(out)
+                .unwrap()
+                .starts_with("docker-credential-chamber ")
+        );
+    }
+}
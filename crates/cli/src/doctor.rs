@@ -0,0 +1,88 @@
+use crate::agent::unlock_vault;
+use crate::run::open_locked_vault;
+use chamber_vault::{IntegrityIssue, IntegrityReport};
+use color_eyre::Result;
+
+/// Checks a vault for corruption and tampering. With `accept_manifest`, the current state of a
+/// vault that does not match its signed manifest is accepted first; with `quarantine`, the rows
+/// that fail to decrypt or lost their parent are moved into the quarantine table.
+///
+/// If the vault cannot be unlocked, the checks that need no key still run.
+///
+/// # Errors
+/// Returns an error if the vault cannot be opened, `quarantine` or `accept_manifest` is set and
+/// the vault cannot be unlocked, or the database cannot be queried.
+pub fn handle_doctor_command(vault: Option<&str>, quarantine: bool, accept_manifest: bool) -> Result<()> {
+    let (mut vault, label) = open_locked_vault(vault)?;
+    println!("🩺 Checking vault{label}");
+    if let Err(e) = unlock_vault(&mut vault, &format!("Enter master key{label}: ")) {
+        if quarantine || accept_manifest {
+            return Err(e);
+        }
+        println!("⚠️  Could not unlock the vault ({e}); item contents are not checked.");
+    }
+
+    if vault.tamper_warning().is_some() {
+        if accept_manifest {
+            vault.accept_manifest()?;
+            println!("✅ Accepted the current state of the vault; it can be changed again.");
+        } else {
+            println!("💡 The vault refuses changes until you restore a backup or accept its current state");
+            println!("   with 'chamber doctor --accept-manifest'.");
+        }
+    } else if accept_manifest {
+        println!("✅ The vault matches its signed manifest; there is nothing to accept.");
+    }
+
+    let report = if quarantine {
+        vault.quarantine_corrupt_rows()?
+    } else {
+        vault.check_integrity()?
+    };
+    print_report(&report);
+
+    let repairable = report.undecryptable.len() + report.orphans.len();
+    if quarantine && repairable > 0 {
+        println!("📦 Quarantined {repairable} problem(s); the rest of the vault is usable again.");
+    } else if repairable > 0 {
+        println!("💡 Run 'chamber doctor --quarantine' to move these rows out of the way.");
+    }
+    if report.quarantined > 0 {
+        println!("   {} row(s) are kept in the quarantine table.", report.quarantined);
+    }
+    if !report.database.is_empty() {
+        println!("💡 Restore a backup or snapshot to recover from damage to the database file.");
+    }
+    Ok(())
+}
+
+fn print_report(report: &IntegrityReport) {
+    print_section("Database file", &report.database);
+    print_section("Key material", &report.key_material);
+    if report.decryption_checked {
+        let issues: Vec<_> = report.undecryptable.iter().map(describe).collect();
+        print_section(&format!("Decryption of {} item(s)", report.items_checked), &issues);
+    } else {
+        println!("⚪ Decryption: not checked");
+    }
+    let orphans: Vec<_> = report.orphans.iter().map(describe).collect();
+    print_section("Orphaned rows", &orphans);
+    if report.is_healthy() {
+        println!("✅ No problems found");
+    }
+}
+
+fn print_section(title: &str, problems: &[String]) {
+    if problems.is_empty() {
+        println!("✅ {title}: ok");
+    } else {
+        println!("❌ {title}: {} problem(s)", problems.len());
+        for problem in problems {
+            println!("   - {problem}");
+        }
+    }
+}
+
+fn describe(issue: &IntegrityIssue) -> String {
+    format!("{}: {}", issue.row, issue.problem)
+}
@@ -0,0 +1,455 @@
+use crate::run::open_vault;
+use chamber_vault::{Item, ItemField, ItemHeader, ItemKind, NewItem, Vault};
+use color_eyre::eyre::eyre;
+use std::io::{BufRead, Write};
+
+/// Prefix of item names that hold git credentials, e.g. `git:github.com` or `git:me@github.com/org`.
+const NAME_PREFIX: &str = "git:";
+
+/// Tag that lets an item named otherwise be used for git, through its `url` or `host` field.
+const TAG: &str = "git";
+
+/// Implements git's credential helper protocol on stdin and stdout.
+///
+/// `get` prints the username and password of the best matching item, `store` saves the
+/// credential git reports as working in a `git:` item, and `erase` moves the best matching
+/// `git:` item to the trash if it still holds the password git reports as rejected; items the
+/// user named otherwise are never changed. Unknown operations are ignored, as git expects of
+/// helpers.
+///
+/// Items match when they are `Password` or `ApiKey` items whose name is `git:[user@]host[/path]`,
+/// or, if no such item matches, items tagged `git` whose `url` or `host` field points at the
+/// requested host; a `username` field must agree with the username git asks for. Configure git with
+/// `git config --global credential.helper '!chamber git-credential --vault <name>'`.
+///
+/// # Errors
+/// Returns an error if the input is malformed or the vault cannot be opened, unlocked or updated.
+pub fn handle_git_credential_command(vault_id: Option<&str>, operation: &str) -> color_eyre::Result<()> {
+    if !matches!(operation, "get" | "store" | "erase") {
+        return Ok(());
+    }
+    let request = Credential::read(std::io::stdin().lock())?;
+    let Some(host) = request.host.as_deref() else {
+        return Ok(());
+    };
+    if host.is_empty() {
+        return Ok(());
+    }
+
+    let mut vault = open_vault(vault_id)?;
+    match operation {
+        "get" => {
+            if let Some(credential) = get(&vault, &request)? {
+                credential.write(&mut std::io::stdout().lock())?;
+            }
+        }
+        "store" => store(&mut vault, &request)?,
+        _ => erase(&mut vault, &request)?,
+    }
+    Ok(())
+}
+
+/// The attributes git exchanges with credential helpers.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+struct Credential {
+    protocol: Option<String>,
+    host: Option<String>,
+    path: Option<String>,
+    username: Option<String>,
+    password: Option<String>,
+}
+
+impl Credential {
+    /// Reads `key=value` lines up to a blank line or the end of input.
+    fn read(reader: impl BufRead) -> color_eyre::Result<Self> {
+        let mut credential = Self::default();
+        for line in reader.lines() {
+            let line = line?;
+            if line.is_empty() {
+                break;
+            }
+            let (key, raw) = line
+                .split_once('=')
+                .ok_or_else(|| eyre!("Invalid credential line '{}'", line))?;
+            let value = Some(raw.to_string());
+            match key {
+                "protocol" => credential.protocol = value,
+                "host" => credential.host = value,
+                "path" => credential.path = value,
+                "username" => credential.username = value,
+                "password" => credential.password = value,
+                "url" => {
+                    let target = Target::parse(raw);
+                    credential.protocol = target.protocol.or(credential.protocol);
+                    credential.host = Some(target.host);
+                    credential.path = target.path.or(credential.path);
+                    credential.username = target.username.or(credential.username);
+                }
+                // Attributes such as capability[] or wwwauth[] are not needed to find a password
+                _ => {}
+            }
+        }
+        Ok(credential)
+    }
+
+    fn write(&self, out: &mut impl Write) -> color_eyre::Result<()> {
+        if let Some(username) = &self.username {
+            writeln!(out, "username={username}")?;
+        }
+        if let Some(password) = &self.password {
+            writeln!(out, "password={password}")?;
+        }
+        out.flush()?;
+        Ok(())
+    }
+}
+
+/// Where an item's credential may be used, parsed from `[protocol://][user@]host[/path]`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Target {
+    protocol: Option<String>,
+    username: Option<String>,
+    host: String,
+    path: Option<String>,
+}
+
+impl Target {
+    fn parse(spec: &str) -> Self {
+        let (protocol, rest) = match spec.split_once("://") {
+            Some((protocol, rest)) => (Some(protocol.to_ascii_lowercase()), rest),
+            None => (None, spec),
+        };
+        let (authority, path) = match rest.split_once('/') {
+            Some((authority, path)) => (authority, Some(path)),
+            None => (rest, None),
+        };
+        let (username, host) = match authority.rsplit_once('@') {
+            Some((userinfo, host)) => {
+                // A password in the URL is never used; the item value is the secret
+                let user = userinfo.split_once(':').map_or(userinfo, |(user, _)| user);
+                (Some(user.to_string()), host)
+            }
+            None => (None, authority),
+        };
+        Self {
+            protocol,
+            username: username.filter(|u| !u.is_empty()),
+            host: host.to_ascii_lowercase(),
+            path: path.map(normalize_path).filter(|p| !p.is_empty()),
+        }
+    }
+
+    /// Returns the target of an item that can hold git credentials.
+    fn of_item(item: &Item) -> Option<Self> {
+        if !matches!(item.kind, ItemKind::Password | ItemKind::ApiKey) {
+            return None;
+        }
+        let spec = match item.name.strip_prefix(NAME_PREFIX) {
+            Some(spec) => spec,
+            None if item.has_tag(TAG) => item.field("url").or_else(|| item.field("host"))?,
+            None => return None,
+        };
+        let mut target = Self::parse(spec.trim());
+        if target.host.is_empty() {
+            return None;
+        }
+        if let Some(username) = item.field("username").filter(|u| !u.is_empty()) {
+            target.username = Some(username.to_string());
+        }
+        Some(target)
+    }
+
+    /// Returns how specifically this target matches `request`, or `None` when it does not.
+    fn score(&self, request: &Credential) -> Option<u8> {
+        let host = request.host.as_deref()?.to_ascii_lowercase();
+        if self.host != host {
+            return None;
+        }
+        let mut score = 0;
+        if let Some(protocol) = &self.protocol {
+            if request
+                .protocol
+                .as_deref()
+                .is_some_and(|p| !p.eq_ignore_ascii_case(protocol))
+            {
+                return None;
+            }
+            score += 1;
+        }
+        if let (Some(wanted), Some(username)) = (&request.username, &self.username) {
+            if wanted != username {
+                return None;
+            }
+            score += 1;
+        }
+        if let Some(path) = &self.path {
+            let requested = normalize_path(request.path.as_deref()?);
+            if requested != *path && !requested.starts_with(&format!("{path}/")) {
+                return None;
+            }
+            score += 2;
+        }
+        Some(score)
+    }
+}
+
+/// Drops surrounding slashes and a trailing `.git` so `org/repo.git` matches `org/repo`.
+fn normalize_path(path: &str) -> String {
+    let path = path.trim_matches('/');
+    path.strip_suffix(".git").unwrap_or(path).to_string()
+}
+
+/// Returns the items matching `request`, most specific first.
+///
+/// Items named `git:...` are picked from the item headers and only decrypted when their name
+/// points at the requested host. Items tagged `git` keep their location in fields, so they are
+/// only decrypted when no item named by the convention matches. Other items are never read.
+fn matching_items(vault: &Vault, request: &Credential) -> color_eyre::Result<Vec<(Item, Target)>> {
+    let host = request.host.as_deref().unwrap_or_default().to_ascii_lowercase();
+    let (named, others): (Vec<ItemHeader>, Vec<ItemHeader>) = vault
+        .list_item_headers()?
+        .into_iter()
+        .filter(|h| matches!(h.kind, ItemKind::Password | ItemKind::ApiKey))
+        .filter(|h| h.name.starts_with(NAME_PREFIX) || h.has_tag(TAG))
+        .partition(|h| h.name.starts_with(NAME_PREFIX));
+    let named = named
+        .into_iter()
+        .filter(|h| Target::parse(h.name[NAME_PREFIX.len()..].trim()).host == host);
+    let mut matches = score_items(vault, request, named)?;
+    if matches.is_empty() {
+        matches = score_items(vault, request, others)?;
+    }
+    Ok(matches)
+}
+
+/// Decrypts the items of `headers` and keeps those matching `request`, most specific first.
+fn score_items(
+    vault: &Vault,
+    request: &Credential,
+    headers: impl IntoIterator<Item = ItemHeader>,
+) -> color_eyre::Result<Vec<(Item, Target)>> {
+    let mut matches = Vec::new();
+    for header in headers {
+        let Some(item) = vault.get_item_by_id(header.id)? else {
+            continue;
+        };
+        if let Some(target) = Target::of_item(&item) {
+            if let Some(score) = target.score(request) {
+                matches.push((score, item, target));
+            }
+        }
+    }
+    matches.sort_by(|a, b| b.0.cmp(&a.0).then_with(|| a.1.name.cmp(&b.1.name)));
+    Ok(matches.into_iter().map(|(_, item, target)| (item, target)).collect())
+}
+
+fn get(vault: &Vault, request: &Credential) -> color_eyre::Result<Option<Credential>> {
+    let Some((item, target)) = matching_items(vault, request)?.into_iter().next() else {
+        return Ok(None);
+    };
+    Ok(Some(Credential {
+        username: target.username.or_else(|| request.username.clone()),
+        password: Some(item.value),
+        ..Credential::default()
+    }))
+}
+
+fn store(vault: &mut Vault, request: &Credential) -> color_eyre::Result<()> {
+    let (Some(host), Some(password)) = (&request.host, &request.password) else {
+        return Ok(());
+    };
+    // Only items kept by this helper are updated; the user's own items are never overwritten
+    if let Some((item, _)) = matching_items(vault, request)?.into_iter().next() {
+        if item.name.starts_with(NAME_PREFIX) {
+            if item.value != *password {
+                vault.update_item(item.id, password)?;
+            }
+            return Ok(());
+        }
+        if item.value == *password {
+            return Ok(());
+        }
+    }
+
+    let path = request.path.as_deref().map(normalize_path).filter(|p| !p.is_empty());
+    let location = path
+        .as_ref()
+        .map_or_else(|| host.clone(), |path| format!("{host}/{path}"));
+    let mut name = format!("{NAME_PREFIX}{location}");
+    if let Some(username) = &request.username {
+        if vault.get_item_by_name(&name)?.is_some() {
+            name = format!("{NAME_PREFIX}{username}@{location}");
+        }
+    }
+    let mut fields = Vec::new();
+    if let Some(username) = &request.username {
+        fields.push(ItemField::new("username", username, false));
+    }
+    if let Some(protocol) = &request.protocol {
+        fields.push(ItemField::new("url", format!("{protocol}://{location}"), false));
+    }
+    vault.create_item(&NewItem {
+        name,
+        kind: ItemKind::Password,
+        value: password.clone(),
+        tags: vec![TAG.to_string()],
+        folder: None,
+        fields,
+    })
+}
+
+fn erase(vault: &mut Vault, request: &Credential) -> color_eyre::Result<()> {
+    let Some(password) = &request.password else {
+        return Ok(());
+    };
+    if let Some((item, _)) = matching_items(vault, request)?.into_iter().next() {
+        // Only forget the secret git was rejected with, never a newer one stored since, and
+        // never an item the user keeps for other purposes
+        if item.name.starts_with(NAME_PREFIX) && item.value == *password {
+            vault.delete_item(item.id)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+    use super::*;
+
+    fn request(input: &str) -> Credential {
+        Credential::read(input.as_bytes()).unwrap()
+    }
+
+    #[test]
+    fn test_read_and_write_credential() {
+        let credential = request("protocol=https\nhost=github.com\npath=org/repo.git\nwwwauth[]=Basic\n\nignored=1\n");
+        assert_eq!(credential.protocol.as_deref(), Some("https"));
+        assert_eq!(credential.host.as_deref(), Some("github.com"));
+        assert_eq!(credential.path.as_deref(), Some("org/repo.git"));
+        assert_eq!(credential.username, None);
+
+        let from_url = request("url=https://me@git.example.com:8443/team/app\n");
+        assert_eq!(from_url.host.as_deref(), Some("git.example.com:8443"));
+        assert_eq!(from_url.username.as_deref(), Some("me"));
+        assert_eq!(from_url.path.as_deref(), Some("team/app"));
+        assert!(Credential::read("not a credential\n".as_bytes()).is_err());
+
+        let mut out = Vec::new();
+        Credential {
+            username: Some("me".into()),
+            password: Some("token=with=equals".into()),
+            ..Credential::default()
+        }
+        .write(&mut out)
+        .unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "username=me\npassword=token=with=equals\n"
+        );
+    }
+
+    #[test]
+    fn test_target_matching() {
+        let github = request("protocol=https\nhost=GitHub.com\npath=org/repo.git\n");
+        assert_eq!(Target::parse("github.com").score(&github), Some(0));
+        assert_eq!(Target::parse("https://github.com/org").score(&github), Some(3));
+        assert_eq!(Target::parse("github.com/org/repo").score(&github), Some(2));
+        assert_eq!(Target::parse("github.com/other").score(&github), None);
+        assert_eq!(Target::parse("ssh://github.com").score(&github), None);
+        assert_eq!(Target::parse("gitlab.com").score(&github), None);
+
+        let as_me = request("protocol=https\nhost=github.com\nusername=me\n");
+        assert_eq!(Target::parse("me@github.com").score(&as_me), Some(1));
+        assert_eq!(Target::parse("you@github.com").score(&as_me), None);
+        assert_eq!(Target::parse("github.com/org").score(&as_me), None);
+    }
+
+    #[test]
+    fn test_get_store_and_erase() -> color_eyre::Result<()> {
+        let now = time::OffsetDateTime::now_utc().unix_timestamp_nanos();
+        let path = std::env::temp_dir().join(format!("chamber_cli_git_{}_{now}.db", std::process::id()));
+        let mut vault = Vault::open_or_create(Some(&path))?;
+        vault.initialize("m")?;
+        vault.unlock("m")?;
+        vault.create_item(&NewItem {
+            name: "GitHub PAT".into(),
+            kind: ItemKind::ApiKey,
+            value: "ghp_org".into(),
+            tags: vec!["git".into()],
+            folder: None,
+            fields: vec![ItemField::new("url", "https://github.com/org", false)],
+        })?;
+        // A website login is not handed to git unless it is tagged for it
+        vault.create_item(&NewItem {
+            name: "GitLab".into(),
+            kind: ItemKind::Password,
+            value: "website".into(),
+            tags: Vec::new(),
+            folder: None,
+            fields: vec![ItemField::new("url", "https://gitlab.com", false)],
+        })?;
+
+        let repo = request("protocol=https\nhost=github.com\npath=org/repo.git\n");
+        let found = get(&vault, &repo)?.unwrap();
+        assert_eq!(found.password.as_deref(), Some("ghp_org"));
+        assert_eq!(found.username, None);
+        assert!(get(&vault, &request("protocol=https\nhost=gitlab.com\n"))?.is_none());
+
+        // New credentials are stored under the naming convention, the user's own items are kept
+        store(
+            &mut vault,
+            &request("protocol=https\nhost=gitlab.com\nusername=me\npassword=glpat\n"),
+        )?;
+        let stored = vault.get_item_by_name("git:gitlab.com")?.unwrap();
+        assert_eq!(stored.field("username"), Some("me"));
+        let gitlab = request("protocol=https\nhost=gitlab.com\n");
+        assert_eq!(get(&vault, &gitlab)?.unwrap().username.as_deref(), Some("me"));
+        store(
+            &mut vault,
+            &request("protocol=https\nhost=github.com\npath=org/repo\npassword=ghp_new\n"),
+        )?;
+        assert_eq!(get(&vault, &repo)?.unwrap().password.as_deref(), Some("ghp_new"));
+        assert_eq!(vault.get_item_by_name("GitHub PAT")?.unwrap().value, "ghp_org");
+        assert_eq!(vault.get_item_by_name("GitLab")?.unwrap().value, "website");
+        assert_eq!(vault.list_items()?.len(), 4);
+
+        // A `git:` item is updated in place
+        store(
+            &mut vault,
+            &request("protocol=https\nhost=github.com\npath=org/repo\npassword=ghp_newer\n"),
+        )?;
+        assert_eq!(get(&vault, &repo)?.unwrap().password.as_deref(), Some("ghp_newer"));
+        assert_eq!(vault.list_items()?.len(), 4);
+
+        // Erasing a stale password keeps the current one
+        erase(
+            &mut vault,
+            &request("protocol=https\nhost=github.com\npath=org/repo\npassword=old\n"),
+        )?;
+        assert!(get(&vault, &repo)?.is_some());
+        // Items the user named themselves are never erased, nor copied when git approves them
+        let other_repo = request("protocol=https\nhost=github.com\npath=org/other\npassword=ghp_org\n");
+        store(&mut vault, &other_repo)?;
+        erase(&mut vault, &other_repo)?;
+        assert_eq!(vault.get_item_by_name("GitHub PAT")?.unwrap().value, "ghp_org");
+        assert_eq!(vault.list_items()?.len(), 4);
+
+        // Without the rejected password nothing is erased, with it only the best match
+        store(
+            &mut vault,
+            &request("protocol=https\nhost=gitlab.com\nusername=you\npassword=glpat_you\n"),
+        )?;
+        erase(&mut vault, &gitlab)?;
+        assert_eq!(vault.list_items()?.len(), 5);
+        erase(
+            &mut vault,
+            &request("protocol=https\nhost=gitlab.com\nusername=me\npassword=glpat\n"),
+        )?;
+        assert!(vault.get_item_by_name("git:gitlab.com")?.is_none());
+        assert!(vault.get_item_by_name("git:you@gitlab.com")?.is_some());
+
+        let _ = std::fs::remove_file(&path);
+        Ok(())
+    }
+}
@@ -86,6 +86,13 @@ pub fn handle_health_command(vault: &Vault, detailed: bool) -> Result<()> {
     );
     println!();
 
+    let kdf = vault.kdf_params()?;
+    if !kdf.meets_recommendations() {
+        println!("⚠️  Master key derivation is below current recommendations ({kdf})");
+        println!("   Strengthen it with 'chamber registry rekdf'");
+        println!();
+    }
+
     // Summary
     let total_issues = report.weak_passwords.len()
         + report.reused_passwords.len()
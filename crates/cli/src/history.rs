@@ -0,0 +1,95 @@
+use crate::agent::unlock_vault;
+use crate::utils::format_relative_time;
+use chamber_vault::{Item, Vault};
+use color_eyre::eyre::eyre;
+
+/// Prints the archived values of an item, newest first.
+///
+/// Values are masked unless `show_values` is set, mirroring how `chamber update` shows the
+/// current value of a secret.
+///
+/// # Errors
+/// Returns an error if the vault cannot be unlocked, the item does not exist, or its history
+/// cannot be decrypted.
+pub fn handle_history_command(vault: &mut Vault, name: &str, show_values: bool) -> color_eyre::Result<()> {
+    let item = unlock_and_find(vault, name)?;
+    let history = vault.item_history(item.id)?;
+
+    println!("🕘 History for '{}' [{}]", item.name, item.kind.display_name());
+    println!(
+        "   current      updated {}  {}",
+        format_relative_time(item.updated_at),
+        display_value(&item.value, show_values)
+    );
+
+    if history.is_empty() {
+        println!("No previous revisions.");
+        return Ok(());
+    }
+
+    for revision in history.iter().rev() {
+        println!(
+            "   rev {:<8} replaced {}  {}",
+            revision.revision,
+            format_relative_time(revision.archived_at),
+            display_value(&revision.value, show_values)
+        );
+    }
+
+    println!();
+    println!("💡 Use 'chamber rollback -n {name} -r <rev>' to restore a revision");
+    Ok(())
+}
+
+/// Restores an item to one of its archived revisions.
+///
+/// Without an explicit `revision`, the most recent archived value is restored, which undoes
+/// the last update. The value being replaced is archived, so the rollback itself can be undone.
+///
+/// # Errors
+/// Returns an error if the vault cannot be unlocked, the item or revision does not exist, or
+/// the update fails.
+pub fn handle_rollback_command(vault: &mut Vault, name: &str, revision: Option<u64>) -> color_eyre::Result<()> {
+    let item = unlock_and_find(vault, name)?;
+    let revision = match revision {
+        Some(rev) => rev,
+        None => vault
+            .item_history(item.id)?
+            .last()
+            .map(|r| r.revision)
+            .ok_or_else(|| eyre!("'{}' has no previous revisions", name))?,
+    };
+
+    vault.restore_revision(item.id, revision)?;
+    println!("✅ Restored '{name}' to revision {revision}");
+    Ok(())
+}
+
+pub(crate) fn unlock_and_find(vault: &mut Vault, name: &str) -> color_eyre::Result<Item> {
+    if !vault.is_unlocked() {
+        unlock_vault(vault, "Enter master key: ")?;
+    }
+    vault
+        .get_item_by_name(name)?
+        .ok_or_else(|| eyre!("Item '{}' not found", name))
+}
+
+fn display_value(value: &str, show_values: bool) -> String {
+    if show_values {
+        value.to_string()
+    } else {
+        format!("{}***", value.chars().take(3).collect::<String>())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_display_value_masks_by_default() {
+        assert_eq!(display_value("hunter2", false), "hun***");
+        assert_eq!(display_value("ab", false), "ab***");
+        assert_eq!(display_value("hunter2", true), "hunter2");
+    }
+}
@@ -0,0 +1,323 @@
+use crate::run::open_vault;
+use chamber_vault::Vault;
+use color_eyre::eyre::eyre;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fmt;
+use std::ops::Range;
+use std::path::Path;
+
+const REFERENCE_SCHEME: &str = "chamber://";
+
+/// A reference to a secret, written as `chamber://<vault>/<item>[/<field>]`.
+///
+/// The vault is matched by name or ID in the vault registry. The path is the item name,
+/// optionally followed by `/<field>` to select one of the item's fields instead of its value.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+struct SecretRef {
+    vault: String,
+    path: String,
+}
+
+impl SecretRef {
+    fn parse(reference: &str) -> color_eyre::Result<Self> {
+        let rest = reference
+            .strip_prefix(REFERENCE_SCHEME)
+            .ok_or_else(|| eyre!("'{}' does not start with {}", reference, REFERENCE_SCHEME))?;
+        match rest.split_once('/') {
+            Some((vault, path)) if !vault.trim().is_empty() && !path.trim().is_empty() => Ok(Self {
+                vault: vault.trim().to_string(),
+                path: path.trim().to_string(),
+            }),
+            _ => Err(eyre!(
+                "'{}' is not a valid reference; expected {}<vault>/<item>[/<field>]",
+                reference,
+                REFERENCE_SCHEME
+            )),
+        }
+    }
+}
+
+impl fmt::Display for SecretRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{REFERENCE_SCHEME}{}/{}", self.vault, self.path)
+    }
+}
+
+/// A `{{ chamber://... }}` placeholder: where it sits in the template and what it refers to.
+#[derive(Debug)]
+struct Placeholder {
+    range: Range<usize>,
+    reference: SecretRef,
+}
+
+/// Renders a template, replacing `{{ chamber://<vault>/<item>[/<field>] }}` references with the
+/// decrypted secrets.
+///
+/// Each referenced vault is unlocked once. Other `{{ ... }}` expressions are left untouched,
+/// so templates for other tools keep working. The output is written to `output`, or stdout when
+/// no output is given. With `check`, references are only resolved and nothing is written.
+///
+/// Output files are created readable by the owner only, and an existing world-readable file is
+/// never overwritten with secrets.
+///
+/// # Errors
+/// Returns an error if the template cannot be read, contains a malformed reference, a vault
+/// cannot be unlocked, a reference does not resolve, or the output cannot be written safely.
+pub fn handle_inject_command(input: &Path, output: Option<&Path>, check: bool) -> color_eyre::Result<()> {
+    let template =
+        std::fs::read_to_string(input).map_err(|e| eyre!("Cannot read template {}: {}", input.display(), e))?;
+    let placeholders = find_placeholders(&template)?;
+
+    if !check {
+        // Fail before prompting for master keys if the output cannot be written safely
+        if let Some(output) = output {
+            ensure_not_world_readable(output)?;
+        }
+    }
+
+    let mut vaults: BTreeMap<&str, BTreeSet<&SecretRef>> = BTreeMap::new();
+    for placeholder in &placeholders {
+        vaults
+            .entry(placeholder.reference.vault.as_str())
+            .or_default()
+            .insert(&placeholder.reference);
+    }
+
+    let mut values = HashMap::new();
+    let mut unresolved = Vec::new();
+    for (vault_name, references) in vaults {
+        let vault = open_vault(Some(vault_name))?;
+        for reference in references {
+            match resolve(&vault, &reference.path) {
+                Ok(value) => {
+                    values.insert(reference.clone(), value);
+                }
+                Err(e) => unresolved.push(format!("{reference}: {e}")),
+            }
+        }
+    }
+
+    if !unresolved.is_empty() {
+        for line in &unresolved {
+            eprintln!("❌ {line}");
+        }
+        return Err(eyre!(
+            "{} of {} reference(s) in {} could not be resolved",
+            unresolved.len(),
+            values.len() + unresolved.len(),
+            input.display()
+        ));
+    }
+
+    if check {
+        println!(
+            "✅ All {} reference(s) in {} resolve ({} placeholder(s))",
+            values.len(),
+            input.display(),
+            placeholders.len()
+        );
+        return Ok(());
+    }
+
+    let rendered = render(&template, &placeholders, &values);
+    match output {
+        Some(output) => {
+            write_private(output, &rendered)?;
+            eprintln!(
+                "✅ Rendered {} placeholder(s) into {}",
+                placeholders.len(),
+                output.display()
+            );
+        }
+        None => print!("{rendered}"),
+    }
+    Ok(())
+}
+
+fn find_placeholders(template: &str) -> color_eyre::Result<Vec<Placeholder>> {
+    let mut placeholders = Vec::new();
+    let mut pos = 0;
+    while let Some(offset) = template[pos..].find("{{") {
+        let start = pos + offset;
+        let Some(len) = template[start + 2..].find("}}") else {
+            break;
+        };
+        let end = start + 2 + len + 2;
+        let inner = template[start + 2..end - 2].trim();
+        if inner.starts_with(REFERENCE_SCHEME) {
+            let line = template[..start].matches('\n').count() + 1;
+            let reference = SecretRef::parse(inner).map_err(|e| eyre!("line {}: {}", line, e))?;
+            placeholders.push(Placeholder {
+                range: start..end,
+                reference,
+            });
+            pos = end;
+        } else {
+            pos = start + 2;
+        }
+    }
+    Ok(placeholders)
+}
+
+/// Resolves `path` (`<item>` or `<item>/<field>`) in an unlocked vault.
+///
+/// An item whose name contains `/` takes precedence over a field of a shorter item name.
+fn resolve(vault: &Vault, path: &str) -> color_eyre::Result<String> {
+    if let Some(item) = vault.get_item_by_name(path)? {
+        return Ok(item.value);
+    }
+    if let Some((name, field)) = path.rsplit_once('/') {
+        if let Some(item) = vault.get_item_by_name(name)? {
+            return item
+                .field(field)
+                .map(str::to_string)
+                .ok_or_else(|| eyre!("item '{}' has no field '{}'", name, field));
+        }
+    }
+    Err(eyre!("item '{}' not found", path))
+}
+
+fn render(template: &str, placeholders: &[Placeholder], values: &HashMap<SecretRef, String>) -> String {
+    let mut out = String::with_capacity(template.len());
+    let mut pos = 0;
+    for placeholder in placeholders {
+        out.push_str(&template[pos..placeholder.range.start]);
+        if let Some(value) = values.get(&placeholder.reference) {
+            out.push_str(value);
+        }
+        pos = placeholder.range.end;
+    }
+    out.push_str(&template[pos..]);
+    out
+}
+
+#[cfg(unix)]
+fn ensure_not_world_readable(path: &Path) -> color_eyre::Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+
+    match std::fs::metadata(path) {
+        Ok(meta) if meta.permissions().mode() & 0o004 != 0 => Err(eyre!(
+            "Refusing to write secrets to {}: the file is world-readable (run 'chmod o-r {}' first)",
+            path.display(),
+            path.display()
+        )),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(not(unix))]
+fn ensure_not_world_readable(_path: &Path) -> color_eyre::Result<()> {
+    Ok(())
+}
+
+/// Writes `contents` to `path`, creating the file readable by the owner only.
+fn write_private(path: &Path, contents: &str) -> color_eyre::Result<()> {
+    use std::io::Write;
+
+    ensure_not_world_readable(path)?;
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut file = options
+        .open(path)
+        .map_err(|e| eyre!("Cannot write {}: {}", path.display(), e))?;
+    file.write_all(contents.as_bytes())?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_secret_ref_parse() {
+        let reference = SecretRef::parse("chamber://work/db-password").unwrap();
+        assert_eq!(reference.vault, "work");
+        assert_eq!(reference.path, "db-password");
+        assert_eq!(reference.to_string(), "chamber://work/db-password");
+
+        let field = SecretRef::parse("chamber://work/orders-db/host").unwrap();
+        assert_eq!(field.path, "orders-db/host");
+
+        assert!(SecretRef::parse("chamber://work").is_err());
+        assert!(SecretRef::parse("chamber:///item").is_err());
+        assert!(SecretRef::parse("vault://work/item").is_err());
+    }
+
+    #[test]
+    fn test_find_placeholders_and_render() {
+        let template = "user: app\npassword: {{ chamber://work/db-password }}\nhost: {{chamber://work/db/host}}\nother: {{ .Values.name }}\n";
+        let placeholders = find_placeholders(template).unwrap();
+        assert_eq!(placeholders.len(), 2);
+        assert_eq!(
+            &template[placeholders[0].range.clone()],
+            "{{ chamber://work/db-password }}"
+        );
+        assert_eq!(placeholders[1].reference.path, "db/host");
+
+        let values = HashMap::from([
+            (placeholders[0].reference.clone(), "s3cret".to_string()),
+            (placeholders[1].reference.clone(), "db.internal".to_string()),
+        ]);
+        assert_eq!(
+            render(template, &placeholders, &values),
+            "user: app\npassword: s3cret\nhost: db.internal\nother: {{ .Values.name }}\n"
+        );
+    }
+
+    #[test]
+    fn test_find_placeholders_reports_malformed_reference_line() {
+        let err = find_placeholders("a: 1\nb: {{ chamber://missing-item }}\n").unwrap_err();
+        assert!(err.to_string().starts_with("line 2:"));
+    }
+
+    #[test]
+    fn test_resolve_items_and_fields() -> color_eyre::Result<()> {
+        use chamber_vault::{ItemField, ItemKind, NewItem};
+
+        let now = time::OffsetDateTime::now_utc().unix_timestamp_nanos();
+        let path = std::env::temp_dir().join(format!("chamber_cli_inject_{}_{now}.db", std::process::id()));
+        let mut vault = Vault::open_or_create(Some(&path))?;
+        vault.initialize("m")?;
+        vault.unlock("m")?;
+        vault.create_item(&NewItem {
+            name: "orders-db".into(),
+            kind: ItemKind::Database,
+            value: "db-password".into(),
+            tags: Vec::new(),
+            folder: None,
+            fields: vec![ItemField::new("host", "db.internal", false)],
+        })?;
+
+        assert_eq!(resolve(&vault, "orders-db")?, "db-password");
+        assert_eq!(resolve(&vault, "orders-db/host")?, "db.internal");
+        assert!(resolve(&vault, "orders-db/port").is_err());
+        assert!(resolve(&vault, "missing").is_err());
+
+        std::fs::remove_file(path).ok();
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_write_private_refuses_world_readable_files() -> color_eyre::Result<()> {
+        use std::os::unix::fs::PermissionsExt;
+
+        let path = std::env::temp_dir().join(format!("chamber_cli_inject_out_{}", std::process::id()));
+        std::fs::remove_file(&path).ok();
+
+        write_private(&path, "secret")?;
+        assert_eq!(std::fs::metadata(&path)?.permissions().mode() & 0o777, 0o600);
+
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o644))?;
+        assert!(write_private(&path, "secret").is_err());
+        assert_eq!(std::fs::read_to_string(&path)?, "secret");
+
+        std::fs::remove_file(path).ok();
+        Ok(())
+    }
+}
@@ -0,0 +1,75 @@
+use chamber_vault::{DEFAULT_KDF_MEMORY_KIB, DEFAULT_KDF_PARALLELISM, DEFAULT_KDF_TIME, KdfParams};
+use clap::Args;
+use color_eyre::Result;
+use std::time::Duration;
+
+/// Argon2id costs for deriving the master key; unset costs fall back to the defaults.
+#[derive(Args, Debug, Default)]
+pub struct KdfArgs {
+    /// Argon2id memory cost, e.g. 64MiB, 1GiB or 65536KiB (a bare number is MiB) [default: 19MiB]
+    #[arg(long, value_name = "SIZE", value_parser = parse_memory)]
+    pub kdf_memory: Option<u32>,
+    /// Argon2id passes over the memory [default: 3]
+    #[arg(long, value_name = "PASSES")]
+    pub kdf_time: Option<u32>,
+    /// Argon2id lanes [default: 1]
+    #[arg(long, value_name = "LANES")]
+    pub kdf_parallelism: Option<u32>,
+    /// Pick the passes so that unlocking takes about this many milliseconds on this machine
+    #[arg(long, value_name = "MS", conflicts_with = "kdf_time")]
+    pub target_ms: Option<u64>,
+}
+
+impl KdfArgs {
+    /// Builds the KDF parameters, benchmarking this machine when `--target-ms` is given.
+    ///
+    /// # Errors
+    /// Returns an error if Argon2id does not accept the costs or the benchmark fails.
+    pub fn params(&self) -> Result<KdfParams> {
+        let m_cost = self.kdf_memory.unwrap_or(DEFAULT_KDF_MEMORY_KIB);
+        let p_cost = self.kdf_parallelism.unwrap_or(DEFAULT_KDF_PARALLELISM);
+        match self.target_ms {
+            Some(ms) => {
+                let kdf = KdfParams::tune(Duration::from_millis(ms), m_cost, p_cost)?;
+                println!("⏱️  Tuned for about {ms} ms: {kdf}");
+                Ok(kdf)
+            }
+            None => KdfParams::new(m_cost, self.kdf_time.unwrap_or(DEFAULT_KDF_TIME), p_cost),
+        }
+    }
+}
+
+/// Parses a memory size such as `256MiB`, `1G` or `65536KiB` into KiB; a bare number is MiB.
+fn parse_memory(input: &str) -> Result<u32, String> {
+    let input = input.trim();
+    let split = input.find(|c: char| !c.is_ascii_digit()).unwrap_or(input.len());
+    let (number, unit) = input.split_at(split);
+    let number: u64 = number.parse().map_err(|_| format!("Invalid memory size '{input}'"))?;
+    let multiplier = match unit.trim().to_ascii_lowercase().as_str() {
+        "k" | "kib" | "kb" => 1,
+        "" | "m" | "mib" | "mb" => 1024,
+        "g" | "gib" | "gb" => 1024 * 1024,
+        _ => return Err(format!("Unknown memory unit in '{input}'; use KiB, MiB or GiB")),
+    };
+    number
+        .checked_mul(multiplier)
+        .and_then(|kib| u32::try_from(kib).ok())
+        .ok_or_else(|| format!("Memory size '{input}' is too large"))
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+    use super::*;
+
+    #[test]
+    fn test_parse_memory() {
+        assert_eq!(parse_memory("256MiB").unwrap(), 256 * 1024);
+        assert_eq!(parse_memory("64").unwrap(), 64 * 1024);
+        assert_eq!(parse_memory("1 GiB").unwrap(), 1024 * 1024);
+        assert_eq!(parse_memory("65536k").unwrap(), 65536);
+        assert!(parse_memory("MiB").is_err());
+        assert!(parse_memory("12TB").is_err());
+        assert!(parse_memory("8192GiB").is_err());
+    }
+}
@@ -1,21 +1,54 @@
+mod agent;
 mod api;
+mod attachments;
 mod backup;
+mod docker_credential;
+mod doctor;
+mod git_credential;
 mod health;
+mod history;
+mod inject;
+mod kdf;
+mod keygen;
+mod otp;
+mod recovery;
+mod rotate;
+mod run;
+#[cfg(unix)]
+mod ssh_agent;
 mod stats;
+mod trash;
 mod utils;
 mod vault;
 
+use crate::agent::{AgentCommand, handle_agent_command, unlock_vault};
 use crate::api::handle_api_command;
-use crate::backup::{BackupCommand, handle_backup_command};
+use crate::attachments::{AttachmentCommand, handle_attach_command, handle_attachment_command, restore_attachments};
+use crate::backup::{BackupCommand, handle_backup_command, read_items_with_format};
+pub use crate::docker_credential::handle_docker_credential_command;
+use crate::doctor::handle_doctor_command;
+use crate::git_credential::handle_git_credential_command;
 use crate::health::{analyze_password_strength, handle_health_command};
+use crate::history::{handle_history_command, handle_rollback_command};
+use crate::inject::handle_inject_command;
+use crate::kdf::KdfArgs;
+use crate::keygen::{GenerateCommand, handle_generate_command};
+use crate::otp::handle_otp_command;
+use crate::recovery::{RecoveryCommand, handle_recovery_command};
+use crate::rotate::handle_rotate_key_command;
+use crate::run::handle_run_command;
 use crate::stats::handle_stats_command;
-use crate::utils::{filter_and_sort_items, format_relative_time};
+use crate::trash::{TrashCommand, handle_trash_command};
+use crate::utils::{filter_and_sort_items, format_relative_time, parse_field_arg, resolve_keyfile};
 use crate::vault::{VaultCommand, handle_vault_command};
-use chamber_import_export::{ExportFormat, detect_format_from_extension, export_items, import_items};
+use chamber_backup::VaultOperations;
+use chamber_import_export::{
+    BackupSecret, ExportFormat, detect_format_from_extension, export_chamber_backup_with_attachments, export_items,
+};
 use chamber_password_gen::{
     PasswordConfig, generate_complex_password, generate_memorable_password, generate_simple_password,
 };
-use chamber_vault::{Item, ItemKind, NewItem, Vault, VaultManager};
+use chamber_vault::{Item, ItemKind, NewItem, Vault, VaultManager, VaultRegistry, validate_fields};
 use clap::{Parser, Subcommand};
 use color_eyre::Result;
 use color_eyre::eyre::eyre;
@@ -62,10 +95,24 @@ pub enum Commands {
         bind: String,
         #[arg(short, long, help = "Port to bind the API server to")]
         port: Option<u16>,
+        #[arg(long, help = "Directory whose keyfiles clients may name when logging in")]
+        keyfile_dir: Option<PathBuf>,
     },
 
     /// Initialize a new Chamber vault with master password encryption
-    Init,
+    Init {
+        /// Also require this keyfile (any file) to unlock the vault
+        #[arg(long)]
+        keyfile: Option<PathBuf>,
+        /// Create the keyfile with 64 random bytes
+        #[arg(long, requires = "keyfile")]
+        generate_keyfile: bool,
+        /// Encrypt item names and kinds too, so the vault file does not reveal what it holds
+        #[arg(long)]
+        private_metadata: bool,
+        #[command(flatten)]
+        kdf: KdfArgs,
+    },
 
     /// Add a new secret item to the vault
     Add {
@@ -88,6 +135,16 @@ pub enum Commands {
         complex: bool,
         #[arg(long, help = "Generate memorable password")]
         memorable: bool,
+        #[arg(long = "tag", help = "Tag to attach to the item (repeatable)")]
+        tags: Vec<String>,
+        #[arg(long, help = "Folder path for the item (e.g. work/prod)")]
+        folder: Option<String>,
+        #[arg(
+            long = "field",
+            value_name = "NAME=VALUE",
+            help = "Typed or custom field, e.g. host=db.internal (repeatable)"
+        )]
+        fields: Vec<String>,
     },
 
     /// Show vault health report
@@ -106,6 +163,10 @@ pub enum Commands {
         recent: Option<usize>,
         #[arg(long, help = "Filter by name pattern (supports wildcards like GitHub*)")]
         name: Option<String>,
+        #[arg(long, help = "Filter by tag")]
+        tag: Option<String>,
+        #[arg(long, help = "Filter by folder, including sub-folders")]
+        folder: Option<String>,
     },
 
     /// Retrieve and display a specific secret by name
@@ -115,10 +176,15 @@ pub enum Commands {
         name: String,
         #[arg(long, help = "Copy value to clipboard instead of displaying it")]
         copy_value: bool,
+        #[arg(long, help = "Print the given field instead of the main value")]
+        field: Option<String>,
     },
 
-    /// Generate secure passwords with customizable options
+    /// Generate secure passwords with customizable options, or SSH key pairs (generate ssh)
+    #[command(args_conflicts_with_subcommands = true)]
     Generate {
+        #[command(subcommand)]
+        target: Option<GenerateCommand>,
         /// Password length (default: 16 characters)
         #[arg(short, long, default_value = "16")]
         length: usize,
@@ -156,7 +222,7 @@ pub enum Commands {
         /// Output file path (e.g., backup.json, secrets.csv)
         #[arg(short, long)]
         output: PathBuf,
-        /// Export format: json, csv, backup (auto-detected from file extension)
+        /// Export format: backup (encrypted), json or csv (plaintext); auto-detected from file extension
         #[arg(short, long)]
         format: Option<String>,
         /// Include creation/modification timestamps in export
@@ -204,6 +270,111 @@ pub enum Commands {
         vault: Option<String>,
     },
 
+    /// Show the previous values of a secret
+    History {
+        #[arg(short, long, help = "Name of the item")]
+        name: String,
+        #[arg(long, help = "Show archived values instead of masking them")]
+        show_values: bool,
+    },
+
+    /// Restore a secret to one of its previous values
+    Rollback {
+        #[arg(short, long, help = "Name of the item to roll back")]
+        name: String,
+        #[arg(short, long, help = "Revision to restore (defaults to the most recent one)")]
+        revision: Option<u64>,
+    },
+
+    /// Show the current one-time (TOTP) code of an item
+    Otp {
+        #[arg(short, long, help = "Name of the TOTP item, or of an item with a totp field")]
+        name: String,
+        #[arg(long, help = "Copy the code to the clipboard instead of displaying it")]
+        copy: bool,
+    },
+
+    /// Run a command with secrets injected as environment variables
+    #[command(
+        after_help = "Examples:\n  chamber run --item DB_URL --item API_KEY -- cargo test\n  chamber run --vault work --tag ci -- ./deploy.sh\n  chamber run --item DATABASE_URL=prod-db -- psql"
+    )]
+    Run {
+        #[arg(
+            long,
+            help = "Vault to read the secrets from, by name or ID (defaults to the default vault)"
+        )]
+        vault: Option<String>,
+        #[arg(
+            short,
+            long = "item",
+            value_name = "[VAR=]NAME",
+            help = "Item to inject; exported under its name unless VAR= is given (repeatable)"
+        )]
+        items: Vec<String>,
+        #[arg(short, long, help = "Inject every item with this tag")]
+        tag: Option<String>,
+        #[arg(short, long, help = "Inject every item of this kind (e.g. env)")]
+        kind: Option<String>,
+        #[arg(
+            last = true,
+            required = true,
+            value_name = "COMMAND",
+            help = "Command to run, after --"
+        )]
+        command: Vec<String>,
+    },
+
+    /// Render a template, replacing {{ chamber://<vault>/<item>[/<field>] }} references with secrets
+    #[command(
+        after_help = "Examples:\n  chamber inject -i config.tpl -o config.yaml\n  chamber inject -i .env.tpl --check\n\nTemplate:\n  DATABASE_URL={{ chamber://work/db-url }}\n  DB_HOST={{ chamber://work/orders-db/host }}"
+    )]
+    Inject {
+        #[arg(short, long, help = "Template file to render")]
+        input: PathBuf,
+        #[arg(short, long, help = "File to write the result to (defaults to stdout)")]
+        output: Option<PathBuf>,
+        #[arg(long, help = "Only check that every reference resolves; nothing is written")]
+        check: bool,
+    },
+
+    /// Git credential helper: serve git credentials from the vault
+    #[command(
+        after_help = "Setup:\n  git config --global credential.helper '!chamber git-credential --vault work'\n\nItems named git:<host>[/<path>], or Password and ApiKey items with a url or host field,\nare offered to git; a username field is used as the login name."
+    )]
+    GitCredential {
+        /// Vault holding the credentials (name or ID); defaults to the active vault
+        #[arg(long)]
+        vault: Option<String>,
+        /// Operation requested by git: get, store or erase
+        operation: String,
+    },
+
+    /// Docker credential helper: keep registry logins in the vault (also installed as docker-credential-chamber)
+    #[command(
+        after_help = "Setup:\n  Set \"credsStore\": \"chamber\" in ~/.docker/config.json and pick the vault with\n  CHAMBER_DOCKER_VAULT=<name> (default: the active vault).\n  Registry logins are stored as docker:<registry> items.\n\nTest:\n  echo ghcr.io | chamber docker-credential get"
+    )]
+    DockerCredential {
+        /// Vault holding the credentials (name or ID); see below for the default
+        #[arg(long)]
+        vault: Option<String>,
+        /// Operation requested by docker: get, store, erase, list or version
+        operation: String,
+    },
+
+    /// Encrypt a file and attach it to an item
+    Attach {
+        #[arg(short, long, help = "Name of the item")]
+        name: String,
+        #[arg(short, long, help = "File to attach")]
+        file: PathBuf,
+        #[arg(long, help = "Store the attachment under this name instead of the file's own name")]
+        file_name: Option<String>,
+    },
+
+    /// List, extract and remove item attachments
+    #[command(subcommand)]
+    Attachment(AttachmentCommand),
+
     /// Backup management commands for automatic data protection
     #[command(subcommand)]
     Backup(BackupCommand),
@@ -211,6 +382,41 @@ pub enum Commands {
     /// Multiple vault management commands for organizing secrets
     #[command(subcommand)]
     Registry(VaultCommand),
+
+    /// Keep vaults unlocked in a background agent so commands stop prompting for the master key
+    #[command(subcommand)]
+    Agent(AgentCommand),
+
+    /// Split a recovery key into Shamir shares and use them to reset a forgotten master key
+    #[command(subcommand)]
+    Recovery(RecoveryCommand),
+
+    /// Replace the vault key with a new one and re-encrypt every item, e.g. after it may have leaked
+    RotateKey {
+        /// Vault to rotate (name or ID); defaults to the active vault
+        #[arg(long)]
+        vault: Option<String>,
+        /// Skip the confirmation prompt
+        #[arg(long, short)]
+        yes: bool,
+    },
+
+    /// Check a vault for corruption and tampering
+    Doctor {
+        /// Vault to check (name or ID); defaults to the active vault
+        #[arg(long)]
+        vault: Option<String>,
+        /// Move rows that fail to decrypt or lost their parent into a quarantine table
+        #[arg(long)]
+        quarantine: bool,
+        /// Accept the current state of a vault that does not match its signed manifest
+        #[arg(long)]
+        accept_manifest: bool,
+    },
+
+    /// List, restore and permanently delete deleted items
+    #[command(subcommand)]
+    Trash(TrashCommand),
 }
 
 /// Handles various commands related to a vault system, including initialization,
@@ -250,6 +456,7 @@ pub enum Commands {
 ///   - `simple`, `complex`, `memorable`: Password style preferences.
 ///   - `no_uppercase`, `no_lowercase`, `no_digits`, `no_symbols`: Exclusion flags for character sets.
 ///   - `include_ambiguous`: Includes or excludes ambiguous characters.
+///   - `target`: `generate ssh` creates an SSH key pair and stores it as an `SshKey` item instead.
 ///   - `count`: Number of passwords to generate (if greater than 1, passwords are numbered in output).
 ///   - Outputs the generated password(s).
 ///
@@ -282,7 +489,12 @@ pub enum Commands {
 ///
 /// # Examples
 /// ```ignore
-/// handle_command(Commands::Init);
+/// handle_command(Commands::Init {
+///     keyfile: None,
+///     generate_keyfile: false,
+///     private_metadata: false,
+///     kdf: KdfArgs::default(),
+/// });
 /// handle_command(Commands::Add {
 ///     name: "example_credential".to_string(),
 ///     kind: "password".to_string(),
@@ -291,6 +503,7 @@ pub enum Commands {
 /// handle_command(Commands::List);
 /// handle_command(Commands::Get { name: "example_credential".to_string() });
 /// handle_command(Commands::Generate {
+///     target: None,
 ///     length: 16,
 ///     complex: true,
 ///     simple: false,
@@ -307,20 +520,56 @@ pub enum Commands {
 #[allow(clippy::too_many_lines)]
 pub async fn handle_command(cmd: Commands) -> Result<()> {
     match cmd {
-        Commands::Api { bind, port } => handle_api_command(bind, port).await?,
-
-        Commands::Init => {
+        Commands::Api {
+            bind,
+            port,
+            keyfile_dir,
+        } => handle_api_command(bind, port, keyfile_dir).await?,
+
+        Commands::Init {
+            keyfile,
+            generate_keyfile,
+            private_metadata,
+            kdf,
+        } => {
             let mut vault = Vault::open_or_create(None)?;
             if vault.is_initialized() {
                 println!("Vault already initialized at {}", vault.db_path().display());
             } else {
+                let kdf = kdf.params()?;
+                if !kdf.meets_recommendations() {
+                    println!("⚠️  {kdf} is below current recommendations for Argon2id");
+                }
+                let keyfile = resolve_keyfile(keyfile, generate_keyfile)?;
                 let master = prompt_secret("Create master key: ")?;
                 let confirm = prompt_secret("Confirm master key: ")?;
                 if master != confirm {
                     return Err(eyre!("Master keys do not match"));
                 }
-                vault.initialize(&master)?;
-                println!("Initialized vault at {}", vault.db_path().display());
+                if let Some(keyfile) = &keyfile {
+                    // Record the keyfile so every client unlocking this vault picks it up
+                    let mut registry = VaultRegistry::load()?;
+                    let vault_id = registry
+                        .find_by_path(vault.db_path())
+                        .ok_or_else(|| eyre!("The default vault is missing from the vault registry"))?
+                        .id
+                        .clone();
+                    vault.set_keyfile(Some(keyfile.clone()));
+                    vault.initialize_with_kdf(&master, &kdf)?;
+                    registry.set_keyfile(&vault_id, Some(keyfile.clone()))?;
+                } else {
+                    vault.initialize_with_kdf(&master, &kdf)?;
+                }
+                if private_metadata {
+                    vault.unlock(&master)?;
+                    vault.set_private_metadata(true)?;
+                }
+                println!(
+                    "Initialized vault at {}{}{}",
+                    vault.db_path().display(),
+                    if keyfile.is_some() { " (keyfile required)" } else { "" },
+                    if private_metadata { " with private metadata" } else { "" }
+                );
             }
         }
         Commands::Add {
@@ -332,10 +581,14 @@ pub async fn handle_command(cmd: Commands) -> Result<()> {
             simple,
             complex,
             memorable,
+            tags,
+            folder,
+            fields,
         } => {
+            let fields = fields.iter().map(|f| parse_field_arg(f)).collect::<Result<Vec<_>>>()?;
+
             let mut vault = Vault::open_or_create(None)?;
-            let master = prompt_secret("Enter master key: ")?;
-            vault.unlock(&master)?;
+            unlock_vault(&mut vault, "Enter master key: ")?;
 
             // Determine the value to use
             let item_value = if generate {
@@ -375,19 +628,18 @@ pub async fn handle_command(cmd: Commands) -> Result<()> {
             };
 
             let kind = match kind.as_str() {
-                "pass" | "password" => ItemKind::Password,
-                "env" | "envvar" => ItemKind::EnvVar,
-                "ssh" | "sshkey" => ItemKind::SshKey,
-                "apikey" | "api_key" | "api-key" | "token" => ItemKind::ApiKey,
-                "certificate" | "cert" | "ssl" | "tls" => ItemKind::Certificate,
-                "database" | "db" | "connection" => ItemKind::Database,
-                _ => ItemKind::Note,
+                "connection" => ItemKind::Database,
+                other => ItemKind::from_str(other).unwrap_or(ItemKind::Note),
             };
+            validate_fields(kind, &fields)?;
 
             let item = NewItem {
                 name: name.clone(),
                 kind,
                 value: item_value,
+                tags,
+                folder,
+                fields,
             };
 
             match vault.create_item(&item) {
@@ -415,8 +667,7 @@ pub async fn handle_command(cmd: Commands) -> Result<()> {
                 }
             };
 
-            let master = prompt_secret("Enter master key: ")?;
-            vault.unlock(&master)?;
+            unlock_vault(&mut vault, "Enter master key: ")?;
             if !vault.is_unlocked() {
                 eprintln!("❌ Vault is locked. Please unlock it first.");
                 std::process::exit(1);
@@ -429,19 +680,27 @@ pub async fn handle_command(cmd: Commands) -> Result<()> {
             since,
             recent,
             name,
+            tag,
+            folder,
         } => {
             let mut vault = Vault::open_or_create(None)?;
-            let master = prompt_secret("Enter master key: ")?;
-            vault.unlock(&master)?;
-
-            let all_items = vault.list_items()?;
-            let filtered_items =
-                match filter_and_sort_items(all_items, r#type.as_deref(), since.as_deref(), recent, name.as_deref()) {
-                    Ok(items) => items,
-                    Err(e) => {
-                        return Err(eyre!("Error while filtering items: {e}"));
-                    }
-                };
+            unlock_vault(&mut vault, "Enter master key: ")?;
+
+            let all_items = vault.list_item_headers()?;
+            let filtered_items = match filter_and_sort_items(
+                all_items,
+                r#type.as_deref(),
+                since.as_deref(),
+                recent,
+                name.as_deref(),
+                tag.as_deref(),
+                folder.as_deref(),
+            ) {
+                Ok(items) => items,
+                Err(e) => {
+                    return Err(eyre!("Error while filtering items: {e}"));
+                }
+            };
 
             if filtered_items.is_empty() {
                 println!("No items found matching the criteria.");
@@ -463,24 +722,39 @@ pub async fn handle_command(cmd: Commands) -> Result<()> {
                         .format(&time::format_description::well_known::Rfc3339)
                         .unwrap_or_else(|_| "unknown".to_string())
                 );
+                if let Some(folder) = &item.folder {
+                    println!("    📁 {folder}");
+                }
+                if !item.tags.is_empty() {
+                    println!("    🏷️  {}", item.tags.join(", "));
+                }
             }
         }
 
-        Commands::Get { name, copy_value } => {
+        Commands::Get {
+            name,
+            copy_value,
+            field,
+        } => {
             let mut vault = Vault::open_or_create(None)?;
-            let master = prompt_secret("Enter master key: ")?;
-            vault.unlock(&master)?;
+            unlock_vault(&mut vault, "Enter master key: ")?;
             if let Some(item) = vault.get_item_by_name(&name)? {
+                let value = match &field {
+                    Some(field) => item
+                        .field(field)
+                        .ok_or_else(|| eyre!("Item '{}' has no field '{}'", name, field))?,
+                    None => item.value.as_str(),
+                };
                 if copy_value {
                     // Copy to clipboard instead of displaying
                     let mut clipboard =
                         arboard::Clipboard::new().map_err(|e| eyre!("Failed to access clipboard: {}", e))?;
                     clipboard
-                        .set_text(&item.value)
+                        .set_text(value)
                         .map_err(|e| eyre!("Failed to copy to clipboard: {}", e))?;
                     println!("✅ Value for '{name}' copied to clipboard");
                 } else {
-                    println!("{}", item.value);
+                    println!("{value}");
                 }
             } else {
                 return Err(eyre!("Item not found"));
@@ -488,6 +762,12 @@ pub async fn handle_command(cmd: Commands) -> Result<()> {
         }
 
         Commands::Generate {
+            target: Some(target), ..
+        } => {
+            handle_generate_command(target)?;
+        }
+        Commands::Generate {
+            target: None,
             length,
             simple,
             complex,
@@ -533,8 +813,7 @@ pub async fn handle_command(cmd: Commands) -> Result<()> {
             include_metadata: _,
         } => {
             let mut vault = Vault::open_or_create(None)?;
-            let master = prompt_secret("Enter master key: ")?;
-            vault.unlock(&master)?;
+            unlock_vault(&mut vault, "Enter master key: ")?;
 
             let items = vault.list_items()?;
             if items.is_empty() {
@@ -542,15 +821,42 @@ pub async fn handle_command(cmd: Commands) -> Result<()> {
                 return Ok(());
             }
 
-            // Determine format
+            // Determine format; unknown extensions default to the encrypted backup format
             let export_format = if let Some(format_str) = format {
                 ExportFormat::from_str(&format_str)?
             } else {
                 // Try to detect from file extension
-                detect_format_from_extension(&output).unwrap_or(ExportFormat::Json)
+                detect_format_from_extension(&output).unwrap_or(ExportFormat::ChamberBackup)
             };
 
-            export_items(&items, &export_format, &output)?;
+            if matches!(export_format, ExportFormat::ChamberBackup) {
+                // Only the encrypted backup format carries attachments
+                let attachments = vault.attachment_files(&items)?;
+                let passphrase = prompt_secret("Enter backup passphrase (leave empty to use the vault key): ")?;
+                if passphrase.is_empty() {
+                    let key = vault.backup_key()?;
+                    export_chamber_backup_with_attachments(
+                        &items,
+                        &attachments,
+                        &output,
+                        &BackupSecret::VaultKey(&key),
+                    )?;
+                } else {
+                    let confirm = prompt_secret("Confirm backup passphrase: ")?;
+                    if passphrase != confirm {
+                        return Err(eyre!("Backup passphrases do not match"));
+                    }
+                    export_chamber_backup_with_attachments(
+                        &items,
+                        &attachments,
+                        &output,
+                        &BackupSecret::Passphrase(&passphrase),
+                    )?;
+                }
+            } else {
+                println!("⚠️  {export_format:?} exports contain your secrets unencrypted. Store the file securely.");
+                export_items(&items, &export_format, &output)?;
+            }
             println!(
                 "Exported {} items to {} (format: {:?})",
                 items.len(),
@@ -577,7 +883,8 @@ pub async fn handle_command(cmd: Commands) -> Result<()> {
                     .ok_or_else(|| eyre!("Could not detect file format. Please specify with --format"))?
             };
 
-            let new_items = import_items(&input, &import_format)?;
+            let mut vault = Vault::open_or_create(None)?;
+            let new_items = read_items_with_format(&mut vault, &input, &import_format)?;
             if new_items.is_empty() {
                 println!("No items found in import file.");
                 return Ok(());
@@ -587,24 +894,24 @@ pub async fn handle_command(cmd: Commands) -> Result<()> {
 
             if dry_run {
                 println!("DRY RUN - Items that would be imported:");
-                for item in &new_items {
+                for (item, _) in &new_items {
                     println!("  - {} [{}]", item.name, item.kind.as_str());
                 }
                 return Ok(());
             }
 
-            let master = prompt_secret("Enter master key: ")?;
-            let mut vault = Vault::open_or_create(None)?;
-            vault.unlock(&master)?;
+            if !vault.is_unlocked() {
+                unlock_vault(&mut vault, "Enter master key: ")?;
+            }
 
-            let existing_items = vault.list_items()?;
+            let existing_items = vault.list_item_headers()?;
             let existing_names: std::collections::HashSet<String> =
                 existing_items.iter().map(|item| item.name.clone()).collect();
 
             let mut imported_count = 0;
             let mut skipped_count = 0;
 
-            for item in new_items {
+            for (item, attachments) in new_items {
                 if existing_names.contains(&item.name) {
                     if skip_duplicates {
                         println!("Skipping duplicate: {}", item.name);
@@ -622,6 +929,9 @@ pub async fn handle_command(cmd: Commands) -> Result<()> {
                 match vault.create_item(&item) {
                     Ok(()) => {
                         imported_count += 1;
+                        if let Err(e) = restore_attachments(&mut vault, &item.name, &attachments) {
+                            println!("Failed to import attachments of '{}': {e}", item.name);
+                        }
                     }
                     Err(e) => {
                         println!("Failed to import item: {e}");
@@ -640,8 +950,7 @@ pub async fn handle_command(cmd: Commands) -> Result<()> {
                 }
             };
 
-            let master = prompt_secret("Enter master key: ")?;
-            vault.unlock(&master)?;
+            unlock_vault(&mut vault, "Enter master key: ")?;
             if !vault.is_unlocked() {
                 eprintln!("❌ Vault is locked. Please unlock it first.");
                 std::process::exit(1);
@@ -767,11 +1076,10 @@ pub async fn handle_command(cmd: Commands) -> Result<()> {
                 println!(
                     "❌ Item '{}' not found{}.",
                     name,
-                    if vault_name.is_some() {
-                        format!(" in vault '{}'", vault_name.unwrap())
-                    } else {
-                        " in any vault".to_string()
-                    }
+                    vault_name.map_or_else(
+                        || " in any vault".to_string(),
+                        |vault_name| format!(" in vault '{vault_name}'")
+                    )
                 );
 
                 // Show suggestions from all vaults
@@ -784,6 +1092,54 @@ pub async fn handle_command(cmd: Commands) -> Result<()> {
             }
         }
 
+        Commands::History { name, show_values } => {
+            let mut vault = Vault::open_or_create(None)?;
+            handle_history_command(&mut vault, &name, show_values)?;
+        }
+        Commands::Rollback { name, revision } => {
+            let mut vault = Vault::open_or_create(None)?;
+            handle_rollback_command(&mut vault, &name, revision)?;
+        }
+        Commands::Otp { name, copy } => {
+            let mut vault = Vault::open_or_create(None)?;
+            handle_otp_command(&mut vault, &name, copy)?;
+        }
+
+        Commands::Run {
+            vault,
+            items,
+            tag,
+            kind,
+            command,
+        } => {
+            let code = handle_run_command(vault.as_deref(), &items, tag.as_deref(), kind.as_deref(), &command).await?;
+            if code != 0 {
+                std::process::exit(code);
+            }
+        }
+
+        Commands::Inject { input, output, check } => {
+            handle_inject_command(&input, output.as_deref(), check)?;
+        }
+        Commands::GitCredential { vault, operation } => {
+            handle_git_credential_command(vault.as_deref(), &operation)?;
+        }
+        Commands::DockerCredential { vault, operation } => {
+            let code = handle_docker_credential_command(vault.as_deref(), &operation);
+            if code != 0 {
+                std::process::exit(code);
+            }
+        }
+
+        Commands::Attach { name, file, file_name } => {
+            let mut vault = Vault::open_or_create(None)?;
+            handle_attach_command(&mut vault, &name, &file, file_name.as_deref())?;
+        }
+        Commands::Attachment(attachment_cmd) => {
+            let mut vault = Vault::open_or_create(None)?;
+            handle_attachment_command(&mut vault, attachment_cmd)?;
+        }
+
         Commands::Backup(backup_cmd) => {
             let vault = Vault::open_or_create(None)?;
             let _ = handle_backup_command(vault, backup_cmd);
@@ -792,6 +1148,15 @@ pub async fn handle_command(cmd: Commands) -> Result<()> {
             let mut manager = VaultManager::new()?;
             handle_vault_command(&mut manager, registry_cmd)?;
         }
+        Commands::Agent(agent_cmd) => handle_agent_command(agent_cmd).await?,
+        Commands::Recovery(recovery_cmd) => handle_recovery_command(recovery_cmd)?,
+        Commands::RotateKey { vault, yes } => handle_rotate_key_command(vault.as_deref(), yes)?,
+        Commands::Doctor {
+            vault,
+            quarantine,
+            accept_manifest,
+        } => handle_doctor_command(vault.as_deref(), quarantine, accept_manifest)?,
+        Commands::Trash(trash_cmd) => handle_trash_command(trash_cmd)?,
     }
     Ok(())
 }
@@ -834,13 +1199,15 @@ const fn get_type_emoji(item_type: ItemKind) -> &'static str {
         ItemKind::Document => "📋",
         ItemKind::Recovery => "🔄",
         ItemKind::OAuth => "🎫",
+        ItemKind::Totp => "⏱️",
     }
 }
 
 fn prompt_secret(prompt: &str) -> Result<String> {
-    use std::io::{Write, stdout};
-    print!("{prompt}");
-    stdout().flush()?;
+    use std::io::{Write, stderr};
+    // Prompts go to stderr so that stdout stays clean for piped output and helper protocols
+    eprint!("{prompt}");
+    stderr().flush()?;
     // Read without echo on Windows/Linux/macOS
     let pass = rpassword::prompt_password("")?;
     Ok(pass)
@@ -855,12 +1222,12 @@ fn find_item_across_vaults(item_name: &str) -> Result<(Vault, Option<Item>, Stri
     // First try the active vault
     if let Ok(mut vault) = Vault::open_active() {
         if let Ok(Some(vault_id)) = vault.get_vault_id() {
-            let master = prompt_secret(&format!(
+            let prompt = format!(
                 "Enter master key for active vault ({}): ",
                 get_vault_display_name(&manager, &vault_id)
-            ))?;
+            );
 
-            if vault.unlock(&master).is_ok() {
+            if unlock_vault(&mut vault, &prompt).is_ok() {
                 if let Ok(Some(item)) = vault.get_item_by_name(item_name) {
                     let vault_name = get_vault_display_name(&manager, &vault_id);
                     return Ok((vault, Some(item), vault_name));
@@ -884,10 +1251,8 @@ fn find_item_across_vaults(item_name: &str) -> Result<(Vault, Option<Item>, Stri
 
         println!("🔍 Searching in vault: {} ({})", vault_info.name, vault_info.category);
 
-        let master = prompt_secret(&format!("Enter master key for '{}': ", vault_info.name))?;
-
         match Vault::open_by_id(&vault_info.id) {
-            Ok(mut vault) => match vault.unlock(&master) {
+            Ok(mut vault) => match unlock_vault(&mut vault, &format!("Enter master key for '{}': ", vault_info.name)) {
                 Ok(()) => {
                     tried_vaults.push(vault_info.name.clone());
                     if let Ok(Some(item)) = vault.get_item_by_name(item_name) {
@@ -936,7 +1301,7 @@ fn suggest_similar_items_across_vaults(item_name: &str) -> Result<()> {
         if let Ok(vault) = Vault::open_by_id(&vault_info.id) {
             // Only suggest from already unlocked vaults to avoid password prompts
             if vault.is_unlocked() {
-                if let Ok(items) = vault.list_items() {
+                if let Ok(items) = vault.list_item_headers() {
                     for item in items {
                         if is_similar_name(&item.name, item_name) {
                             suggestions.push((item.name, item.kind, vault_info.name.clone()));
@@ -989,8 +1354,7 @@ fn find_item_in_specific_vault(item_name: &str, vault_identifier: &str) -> Resul
         .ok_or_else(|| eyre!("Vault '{}' not found", vault_identifier))?;
 
     let mut vault = Vault::open_by_id(&vault_info.id)?;
-    let master = prompt_secret(&format!("Enter master key for '{}': ", vault_info.name))?;
-    vault.unlock(&master)?;
+    unlock_vault(&mut vault, &format!("Enter master key for '{}': ", vault_info.name))?;
 
     let item = vault.get_item_by_name(item_name)?;
     Ok((vault, item, vault_info.name.clone()))
@@ -1012,6 +1376,7 @@ mod handle_command_tests {
     async fn test_generate_simple_ok() {
         // Runs the simple generator branch (no prompts involved)
         let cmd = Commands::Generate {
+            target: None,
             length: 12,
             simple: true,
             complex: false,
@@ -1031,6 +1396,7 @@ mod handle_command_tests {
     async fn test_generate_complex_ok() {
         // Runs the complex generator branch (no prompts involved)
         let cmd = Commands::Generate {
+            target: None,
             length: 24,
             simple: false,
             complex: true,
@@ -1050,6 +1416,7 @@ mod handle_command_tests {
     async fn test_generate_memorable_ok() {
         // Runs the memorable generator branch (no prompts involved)
         let cmd = Commands::Generate {
+            target: None,
             length: 16, // ignored by memorable branch but required by struct
             simple: false,
             complex: false,
@@ -1069,6 +1436,7 @@ mod handle_command_tests {
     async fn test_generate_count_multiple_ok() {
         // Ensure the loop over count works (no prompts involved)
         let cmd = Commands::Generate {
+            target: None,
             length: 10,
             simple: true,
             complex: false,
@@ -1089,6 +1457,7 @@ mod handle_command_tests {
         // Triggers the custom branch and makes the generator return an error
         // by disabling all sets. The error should propagate out of handle_command.
         let cmd = Commands::Generate {
+            target: None,
             length: 10,
             simple: false,
             complex: false,
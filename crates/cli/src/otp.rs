@@ -0,0 +1,32 @@
+use crate::history::unlock_and_find;
+use chamber_vault::Vault;
+use color_eyre::eyre::eyre;
+
+/// Prints the current TOTP code of an item and how long it remains valid.
+///
+/// The item is either a TOTP item, whose value is the `otpauth://` URI or base32 secret, or any
+/// other item carrying a `totp` field. With `copy`, the code goes to the clipboard instead.
+///
+/// # Errors
+/// Returns an error if the vault cannot be unlocked, the item does not exist or has no TOTP
+/// secret, the secret cannot be parsed, or the clipboard is unavailable.
+pub fn handle_otp_command(vault: &mut Vault, name: &str, copy: bool) -> color_eyre::Result<()> {
+    let item = unlock_and_find(vault, name)?;
+    let totp = item.totp()?.ok_or_else(|| eyre!("'{}' has no TOTP secret", name))?;
+    let current = totp.now()?;
+
+    if copy {
+        let mut clipboard = arboard::Clipboard::new().map_err(|e| eyre!("Failed to access clipboard: {}", e))?;
+        clipboard
+            .set_text(&current.code)
+            .map_err(|e| eyre!("Failed to copy to clipboard: {}", e))?;
+        println!(
+            "✅ Code for '{name}' copied to clipboard (valid for {}s)",
+            current.remaining_seconds
+        );
+    } else {
+        println!("{}", current.code);
+        eprintln!("⏱️  valid for {}s", current.remaining_seconds);
+    }
+    Ok(())
+}
@@ -0,0 +1,165 @@
+use crate::agent::warn_if_tampered;
+use crate::prompt_secret;
+use crate::run::{open_locked_vault, open_vault};
+use chamber_vault::RecoveryShare;
+use clap::Subcommand;
+use color_eyre::Result;
+use color_eyre::eyre::eyre;
+
+#[derive(Subcommand, Debug)]
+pub enum RecoveryCommand {
+    /// Split a new recovery key into shares; any threshold of them can reset the master key
+    Create {
+        /// Number of shares to create
+        #[arg(long, default_value = "5")]
+        shares: u8,
+        /// Number of shares needed to recover the vault
+        #[arg(long, default_value = "3")]
+        threshold: u8,
+        /// Vault to protect (name or ID); defaults to the active vault
+        #[arg(long)]
+        vault: Option<String>,
+    },
+    /// Combine recovery shares and set a new master key
+    Unlock {
+        /// Vault to recover (name or ID); defaults to the active vault
+        #[arg(long)]
+        vault: Option<String>,
+    },
+    /// Show whether recovery shares have been created
+    Status {
+        /// Vault to check (name or ID); defaults to the active vault
+        #[arg(long)]
+        vault: Option<String>,
+    },
+    /// Remove the recovery key so existing shares stop working
+    Remove {
+        /// Vault to update (name or ID); defaults to the active vault
+        #[arg(long)]
+        vault: Option<String>,
+    },
+}
+
+/// Runs a `recovery` subcommand.
+///
+/// # Errors
+/// Returns an error if the vault cannot be opened or unlocked, the share counts are invalid, the
+/// shares do not recover the vault or the new master keys do not match.
+pub fn handle_recovery_command(cmd: RecoveryCommand) -> Result<()> {
+    match cmd {
+        RecoveryCommand::Create {
+            shares,
+            threshold,
+            vault,
+        } => {
+            let mut vault = open_vault(vault.as_deref())?;
+            let replaced = vault.has_recovery()?;
+            let shares = vault.create_recovery_shares(shares, threshold)?;
+            println!(
+                "🔑 Created {} recovery shares; any {threshold} of them can reset the master key.",
+                shares.len()
+            );
+            if replaced {
+                println!("   Shares created earlier no longer work.");
+            }
+            println!("   Give each share to a different person or place and keep them offline.\n");
+            for share in &shares {
+                println!("Share {}/{}: {share}", share.index(), shares.len());
+            }
+        }
+        RecoveryCommand::Unlock { vault } => {
+            let (mut vault, label) = open_locked_vault(vault.as_deref())?;
+            if !vault.has_recovery()? {
+                return Err(eyre!("Recovery is not set up{label}"));
+            }
+            let shares = read_shares(|n| prompt_secret(&format!("Enter recovery share {n}: ")))?;
+
+            let master = prompt_secret("Create new master key: ")?;
+            let confirm = prompt_secret("Confirm new master key: ")?;
+            if master != confirm {
+                return Err(eyre!("Master keys do not match"));
+            }
+            if master.is_empty() {
+                return Err(eyre!("Master key cannot be empty"));
+            }
+            vault.recover_master_key(&shares, &master)?;
+            println!("✅ Vault recovered; unlock it with the new master key from now on.");
+            warn_if_tampered(&vault);
+        }
+        RecoveryCommand::Status { vault } => {
+            let (vault, label) = open_locked_vault(vault.as_deref())?;
+            if vault.has_recovery()? {
+                println!("🟢 Recovery shares have been created{label}");
+            } else {
+                println!("⚪ No recovery shares{label}; create them with 'chamber recovery create'");
+            }
+        }
+        RecoveryCommand::Remove { vault } => {
+            let mut vault = open_vault(vault.as_deref())?;
+            vault.remove_recovery()?;
+            println!("🗑️  Removed the recovery key; existing shares no longer work.");
+        }
+    }
+    Ok(())
+}
+
+/// Reads shares with `prompt` until as many as the first share's threshold have been entered.
+/// Mistyped and repeated shares are reported and asked for again.
+fn read_shares(mut prompt: impl FnMut(usize) -> Result<String>) -> Result<Vec<RecoveryShare>> {
+    let mut shares: Vec<RecoveryShare> = Vec::new();
+    while shares
+        .first()
+        .is_none_or(|first| shares.len() < usize::from(first.threshold()))
+    {
+        let input = prompt(shares.len() + 1)?;
+        if input.trim().is_empty() {
+            return Err(eyre!("Recovery cancelled"));
+        }
+        match input.parse::<RecoveryShare>() {
+            Ok(share) if shares.iter().any(|s| s.index() == share.index()) => {
+                eprintln!("Share {} was already entered", share.index());
+            }
+            Ok(share)
+                if shares
+                    .first()
+                    .is_some_and(|first| first.threshold() != share.threshold()) =>
+            {
+                eprintln!("That share belongs to a different set of recovery shares");
+            }
+            Ok(share) => shares.push(share),
+            Err(e) => eprintln!("{e}"),
+        }
+    }
+    Ok(shares)
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+    use super::*;
+    use chamber_vault::Vault;
+
+    #[test]
+    fn test_read_shares_until_threshold() {
+        let path = std::env::temp_dir().join(format!("chamber_cli_recovery_{}.db", std::process::id()));
+        let mut vault = Vault::open_or_create(Some(&path)).unwrap();
+        vault.initialize("m").unwrap();
+        vault.unlock("m").unwrap();
+        let created = vault.create_recovery_shares(4, 2).unwrap();
+
+        let mut inputs = vec![
+            created[2].to_string(),
+            "not-a-share".to_string(),
+            created[2].to_string(),
+            created[0].to_string().to_lowercase(),
+            created[1].to_string(),
+        ]
+        .into_iter();
+        let shares = read_shares(|_| Ok(inputs.next().unwrap())).unwrap();
+        assert_eq!(shares.iter().map(RecoveryShare::index).collect::<Vec<_>>(), [3, 1]);
+        assert_eq!(inputs.len(), 1);
+
+        assert!(read_shares(|_| Ok(String::new())).is_err());
+        let _ = std::fs::remove_file(&path);
+    }
+}
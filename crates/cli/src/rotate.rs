@@ -0,0 +1,141 @@
+use crate::agent::share_key;
+use crate::prompt_secret;
+use crate::run::open_locked_vault;
+use chamber_backup::BackupManager;
+use chamber_vault::{BackupConfig, Vault};
+use color_eyre::Result;
+use color_eyre::eyre::eyre;
+use std::io::Write;
+use std::path::PathBuf;
+
+/// Replaces the vault key of a vault and re-encrypts all of its data.
+///
+/// Backups in the `backup` format are sealed with a key derived from the vault key, so when the
+/// vault keeps such backups a new one is taken right after the rotation.
+///
+/// # Errors
+/// Returns an error if the vault cannot be opened, the master key is wrong or the rotation
+/// fails; a failed rotation leaves the vault unchanged. An error is also returned if the new
+/// backup fails, although the key was rotated.
+pub fn handle_rotate_key_command(vault: Option<&str>, yes: bool) -> Result<()> {
+    let (mut vault, label) = open_locked_vault(vault)?;
+    println!("🔄 Rotating the vault key{label}");
+    println!("   Every item, revision and attachment is re-encrypted with a new random key.");
+    let slots = vault.list_key_slots()?;
+    if !slots.is_empty() {
+        let names: Vec<_> = slots.iter().map(|s| s.name.as_str()).collect();
+        println!(
+            "⚠️  Key slots are revoked and must be added again: {}",
+            names.join(", ")
+        );
+    }
+    if vault.has_recovery()? {
+        println!("⚠️  Existing recovery shares stop working; create new ones afterwards.");
+    }
+    let backup_config = vault.get_backup_config().unwrap_or_default();
+    if keeps_vault_key_backups(&backup_config) {
+        println!("⚠️  Backups encrypted with the vault key can no longer be restored; a new backup is taken");
+        println!("   in {} right after the rotation.", backup_config.backup_dir.display());
+    }
+    println!("   Passphrase-protected backups and snapshots are not affected.");
+
+    if !yes {
+        print!("Rotate the vault key? (y/N): ");
+        std::io::stdout().flush()?;
+        let mut input = String::new();
+        std::io::stdin().read_line(&mut input)?;
+        if !matches!(input.trim().to_lowercase().as_str(), "y" | "yes") {
+            println!("Key rotation cancelled");
+            return Ok(());
+        }
+    }
+
+    let master = prompt_secret("Enter master key: ")?;
+    let rotation = vault.rotate_key(&master, |done, total| {
+        eprint!("\r   Re-encrypting items: {done}/{total}");
+        let _ = std::io::stderr().flush();
+    })?;
+    eprintln!();
+    // Replace the old key held by the agent, if one is running
+    share_key(&vault);
+
+    println!(
+        "✅ Vault key rotated: {} item(s), {} revision(s) and {} attachment(s) re-encrypted",
+        rotation.items, rotation.revisions, rotation.attachments
+    );
+    if !rotation.revoked_key_slots.is_empty() {
+        println!("   Revoked key slots: {}", rotation.revoked_key_slots.join(", "));
+    }
+    if rotation.recovery_removed {
+        println!("   Recovery shares were removed; run 'chamber recovery create' to make new ones.");
+    }
+
+    let backup = backup_rotated_vault(vault, backup_config).map_err(|e| {
+        eyre!("The vault key was rotated, but the new backup failed ({e}); run 'chamber backup now --force'")
+    })?;
+    if let Some(path) = backup {
+        println!("💾 New backup with the rotated key: {}", path.display());
+    }
+    Ok(())
+}
+
+/// Returns whether the vault's backups are sealed with its vault key: the format is `backup`
+/// and backups are taken automatically or have been taken before.
+fn keeps_vault_key_backups(config: &BackupConfig) -> bool {
+    config.format == "backup"
+        && (config.enabled || std::fs::read_dir(&config.backup_dir).is_ok_and(|mut entries| entries.next().is_some()))
+}
+
+/// Takes a new backup of a vault whose key was just rotated, if its backups are sealed with the
+/// vault key, and returns its path.
+fn backup_rotated_vault(vault: Vault, config: BackupConfig) -> Result<Option<PathBuf>> {
+    if !keeps_vault_key_backups(&config) {
+        return Ok(None);
+    }
+    BackupManager::new(vault, config).force_backup().map(Some)
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+    use super::*;
+    use chamber_vault::{ItemKind, NewItem};
+
+    #[test]
+    fn test_backup_after_rotation_uses_the_new_key() -> Result<()> {
+        let now = time::OffsetDateTime::now_utc().unix_timestamp_nanos();
+        let dir = std::env::temp_dir().join(format!("chamber_cli_rotate_{}_{now}", std::process::id()));
+        std::fs::create_dir_all(&dir)?;
+        let path = dir.join("vault.db");
+        let mut vault = Vault::open_or_create(Some(&path))?;
+        vault.initialize("m")?;
+        vault.unlock("m")?;
+        vault.create_item(&NewItem {
+            name: "db".into(),
+            kind: ItemKind::Password,
+            value: "secret".into(),
+            tags: Vec::new(),
+            folder: None,
+            fields: Vec::new(),
+        })?;
+        let config = BackupConfig {
+            enabled: true,
+            backup_dir: dir.join("backups"),
+            format: "backup".into(),
+            compress: false,
+            verify_after_backup: true,
+            ..BackupConfig::default()
+        };
+        assert!(keeps_vault_key_backups(&config));
+        assert!(!keeps_vault_key_backups(&BackupConfig {
+            format: "snapshot".into(),
+            ..config.clone()
+        }));
+
+        vault.rotate_key("m", |_, _| {})?;
+        let backup = backup_rotated_vault(vault, config)?.unwrap();
+        assert!(backup.exists());
+        let _ = std::fs::remove_dir_all(&dir);
+        Ok(())
+    }
+}
@@ -0,0 +1,269 @@
+use crate::agent::unlock_vault;
+use chamber_vault::{Item, ItemKind, Vault, VaultManager};
+use color_eyre::eyre::eyre;
+use std::collections::BTreeMap;
+use std::process::ExitStatus;
+use std::str::FromStr;
+
+/// Runs `command` with secrets from the vault injected as environment variables.
+///
+/// Secrets are selected by name with `items` (`NAME` or `ENV_VAR=NAME`) and/or by `tag` and
+/// `kind`; when both a tag and a kind are given, items must match both. Each value is exported
+/// under the item's name, converted to a valid variable name when needed (`github-token`
+/// becomes `GITHUB_TOKEN`). Values only ever live in the child's environment: they are not
+/// written to disk and never appear on the command line.
+///
+/// Returns the exit code of the command. Signals received while the command runs are forwarded
+/// to it.
+///
+/// # Errors
+/// Returns an error if the vault cannot be opened or unlocked, no item is selected, an item
+/// does not exist, two items map to the same variable, or the command cannot be started.
+pub async fn handle_run_command(
+    vault_id: Option<&str>,
+    items: &[String],
+    tag: Option<&str>,
+    kind: Option<&str>,
+    command: &[String],
+) -> color_eyre::Result<i32> {
+    if items.is_empty() && tag.is_none() && kind.is_none() {
+        return Err(eyre!("Select the secrets to inject with --item, --tag or --kind"));
+    }
+    let kind = kind.map(ItemKind::from_str).transpose()?;
+
+    let vault = open_vault(vault_id)?;
+    let env = collect_env(&vault, items, tag, kind)?;
+    // Close the vault, and drop its key, before handing control to the command
+    drop(vault);
+
+    let status = run_child(command, &env).await?;
+    Ok(exit_code(status))
+}
+
+/// Opens the vault called `vault_id` (by name or ID), or the default vault, and unlocks it through
+/// the agent or by prompting for its master key.
+pub(crate) fn open_vault(vault_id: Option<&str>) -> color_eyre::Result<Vault> {
+    let (mut vault, label) = open_locked_vault(vault_id)?;
+    unlock_vault(&mut vault, &format!("Enter master key{label}: "))?;
+    Ok(vault)
+}
+
+/// Opens the vault called `vault_id` (by name or ID), or the default vault, without unlocking it.
+/// Also returns a label naming the vault for prompts.
+pub(crate) fn open_locked_vault(vault_id: Option<&str>) -> color_eyre::Result<(Vault, String)> {
+    Ok(match vault_id {
+        Some(identifier) => {
+            let manager = VaultManager::new()?;
+            let info = manager
+                .list_vaults()
+                .into_iter()
+                .find(|v| v.name.eq_ignore_ascii_case(identifier) || v.id == identifier)
+                .ok_or_else(|| eyre!("Vault '{}' not found", identifier))?;
+            (Vault::open_by_id(&info.id)?, format!(" for '{}'", info.name))
+        }
+        None => (Vault::open_or_create(None)?, String::new()),
+    })
+}
+
+/// Decrypts the selected items and maps them to environment variable names.
+fn collect_env(
+    vault: &Vault,
+    items: &[String],
+    tag: Option<&str>,
+    kind: Option<ItemKind>,
+) -> color_eyre::Result<BTreeMap<String, String>> {
+    let mut selected: Vec<(String, Item)> = Vec::new();
+    for arg in items {
+        let (var, name) = parse_item_arg(arg);
+        let item = vault
+            .get_item_by_name(name)?
+            .ok_or_else(|| eyre!("Item '{}' not found", name))?;
+        selected.push((var.map_or_else(|| env_var_name(&item.name), str::to_string), item));
+    }
+
+    if tag.is_some() || kind.is_some() {
+        for header in vault.list_item_headers()? {
+            if tag.is_some_and(|t| !header.has_tag(t)) || kind.is_some_and(|k| header.kind != k) {
+                continue;
+            }
+            if selected.iter().any(|(_, item)| item.id == header.id) {
+                continue;
+            }
+            if let Some(item) = vault.get_item_by_id(header.id)? {
+                selected.push((env_var_name(&item.name), item));
+            }
+        }
+    }
+
+    if selected.is_empty() {
+        return Err(eyre!("No items match the selection"));
+    }
+
+    let mut env = BTreeMap::new();
+    for (var, item) in selected {
+        if env.insert(var.clone(), item.value).is_some() {
+            return Err(eyre!(
+                "More than one item maps to ${}; use --item {}=<name> to rename one",
+                var,
+                var
+            ));
+        }
+    }
+    Ok(env)
+}
+
+/// Splits an `--item` argument into an optional variable name and the item name.
+///
+/// `ENV_VAR=NAME` exports item `NAME` as `ENV_VAR`. When the part before `=` is not a valid
+/// variable name, the whole argument is taken as the item name.
+fn parse_item_arg(arg: &str) -> (Option<&str>, &str) {
+    match arg.split_once('=') {
+        Some((var, name)) if is_valid_env_var(var) && !name.is_empty() => (Some(var), name),
+        _ => (None, arg),
+    }
+}
+
+fn is_valid_env_var(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars.next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Returns the variable an item is exported as: its name when that is already a valid variable
+/// name, otherwise the name upper-cased with every other character replaced by `_`.
+fn env_var_name(item_name: &str) -> String {
+    if is_valid_env_var(item_name) {
+        return item_name.to_string();
+    }
+    let mut var: String = item_name
+        .trim()
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_uppercase()
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    if !var.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_') {
+        var.insert(0, '_');
+    }
+    var
+}
+
+async fn run_child(command: &[String], env: &BTreeMap<String, String>) -> color_eyre::Result<ExitStatus> {
+    let (program, args) = command.split_first().ok_or_else(|| eyre!("No command given"))?;
+    let mut child = tokio::process::Command::new(program)
+        .args(args)
+        .envs(env)
+        .spawn()
+        .map_err(|e| eyre!("Failed to run '{}': {}", program, e))?;
+
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{SignalKind, signal};
+
+        let pid = child.id().and_then(|id| libc::pid_t::try_from(id).ok());
+        let mut signals = [
+            SignalKind::interrupt(),
+            SignalKind::terminate(),
+            SignalKind::hangup(),
+            SignalKind::quit(),
+            SignalKind::user_defined1(),
+            SignalKind::user_defined2(),
+        ]
+        .into_iter()
+        .map(|kind| Ok((kind.as_raw_value(), signal(kind)?)))
+        .collect::<std::io::Result<Vec<_>>>()?;
+
+        loop {
+            let forwarded = next_signal(&mut signals);
+            tokio::select! {
+                status = child.wait() => return Ok(status?),
+                raw = forwarded => {
+                    if let Some(pid) = pid {
+                        // SAFETY: `kill` has no memory-safety requirements; the pid is our own child
+                        unsafe {
+                            libc::kill(pid, raw);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    #[cfg(not(unix))]
+    {
+        loop {
+            tokio::select! {
+                status = child.wait() => return Ok(status?),
+                // The console delivers Ctrl+C to the child as well; keep waiting for it to exit
+                _ = tokio::signal::ctrl_c() => {}
+            }
+        }
+    }
+}
+
+/// Waits for the next signal on any of `signals` and returns its number.
+#[cfg(unix)]
+async fn next_signal(signals: &mut [(i32, tokio::signal::unix::Signal)]) -> i32 {
+    std::future::poll_fn(|cx| {
+        for (raw, signal) in signals.iter_mut() {
+            if signal.poll_recv(cx).is_ready() {
+                return std::task::Poll::Ready(*raw);
+            }
+        }
+        std::task::Poll::Pending
+    })
+    .await
+}
+
+/// Converts the child's status into the exit code of `chamber run`, following the shell
+/// convention of `128 + signal` for commands killed by a signal.
+fn exit_code(status: ExitStatus) -> i32 {
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::ExitStatusExt;
+        if let Some(signal) = status.signal() {
+            return 128 + signal;
+        }
+    }
+    status.code().unwrap_or(1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_env_var_name() {
+        assert_eq!(env_var_name("DB_URL"), "DB_URL");
+        assert_eq!(env_var_name("api_key"), "api_key");
+        assert_eq!(env_var_name("github-token"), "GITHUB_TOKEN");
+        assert_eq!(env_var_name("prod db.url"), "PROD_DB_URL");
+        assert_eq!(env_var_name("2fa-backup"), "_2FA_BACKUP");
+    }
+
+    #[test]
+    fn test_parse_item_arg() {
+        assert_eq!(parse_item_arg("DB_URL"), (None, "DB_URL"));
+        assert_eq!(
+            parse_item_arg("DATABASE_URL=prod db"),
+            (Some("DATABASE_URL"), "prod db")
+        );
+        assert_eq!(parse_item_arg("a=b=c"), (Some("a"), "b=c"));
+        assert_eq!(parse_item_arg("not valid=x"), (None, "not valid=x"));
+        assert_eq!(parse_item_arg("VAR="), (None, "VAR="));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_run_child_injects_env_and_propagates_exit_code() -> color_eyre::Result<()> {
+        let env = BTreeMap::from([("CHAMBER_RUN_TEST".to_string(), "s3cret".to_string())]);
+        let command = ["sh", "-c", r#"[ "$CHAMBER_RUN_TEST" = s3cret ] && exit 7"#].map(String::from);
+        let status = run_child(&command, &env).await?;
+        assert_eq!(exit_code(status), 7);
+        Ok(())
+    }
+}
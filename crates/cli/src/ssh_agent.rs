@@ -0,0 +1,565 @@
+//! The OpenSSH agent protocol, served by `chamber agent` on its own socket.
+//!
+//! Identities come from the `SshKey` items of the vaults held by the agent, plus keys added with
+//! `ssh-add`, which live in memory only. Locking the agent forgets both.
+
+use chamber_vault::{Item, ItemKind, SSH_AGENT_CONFIRM_FIELD, Vault, VaultManager, parse_flag};
+use color_eyre::eyre::eyre;
+use rsa::signature::{SignatureEncoding, Signer};
+use ssh_encoding::{Decode, Encode, Reader};
+use ssh_key::private::{KeypairData, RsaKeypair};
+use ssh_key::{Algorithm, HashAlg, PrivateKey, Signature};
+use std::collections::{HashMap, HashSet};
+use std::time::{Duration, Instant};
+use time::OffsetDateTime;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::UnixStream;
+use tokio::sync::Mutex;
+
+const SSH_AGENT_FAILURE: u8 = 5;
+const SSH_AGENT_SUCCESS: u8 = 6;
+const SSH_AGENTC_REQUEST_IDENTITIES: u8 = 11;
+const SSH_AGENT_IDENTITIES_ANSWER: u8 = 12;
+const SSH_AGENTC_SIGN_REQUEST: u8 = 13;
+const SSH_AGENT_SIGN_RESPONSE: u8 = 14;
+const SSH_AGENTC_ADD_IDENTITY: u8 = 17;
+const SSH_AGENTC_REMOVE_IDENTITY: u8 = 18;
+const SSH_AGENTC_REMOVE_ALL_IDENTITIES: u8 = 19;
+const SSH_AGENTC_ADD_ID_CONSTRAINED: u8 = 25;
+
+const SSH_AGENT_CONSTRAIN_LIFETIME: u8 = 1;
+const SSH_AGENT_CONSTRAIN_CONFIRM: u8 = 2;
+
+const SSH_AGENT_RSA_SHA2_256: u32 = 2;
+const SSH_AGENT_RSA_SHA2_512: u32 = 4;
+
+/// Messages are small; anything larger than this is not an agent client.
+const MAX_MESSAGE_LEN: u32 = 256 * 1024;
+
+/// A key the agent can sign with.
+#[derive(Clone)]
+struct Identity {
+    key: PrivateKey,
+    /// The public key in SSH wire format, which is how clients refer to the key
+    blob: Vec<u8>,
+    comment: String,
+    confirm: bool,
+    expires: Option<Instant>,
+}
+
+impl Identity {
+    fn new(key: PrivateKey, comment: String, confirm: bool, expires: Option<Instant>) -> color_eyre::Result<Self> {
+        let blob = key.public_key().to_bytes()?;
+        Ok(Self {
+            key,
+            blob,
+            comment,
+            confirm,
+            expires,
+        })
+    }
+
+    /// Loads the key stored in an `SshKey` item: an OpenSSH private key, decrypted with the
+    /// item's `passphrase` field when it is encrypted.
+    fn from_item(item: &Item) -> color_eyre::Result<Self> {
+        let mut key = PrivateKey::from_openssh(item.value.trim())?;
+        if key.is_encrypted() {
+            let passphrase = item
+                .field("passphrase")
+                .ok_or_else(|| eyre!("the key is encrypted and the item has no passphrase field"))?;
+            key = key.decrypt(passphrase)?;
+        }
+        let confirm = item
+            .field(SSH_AGENT_CONFIRM_FIELD)
+            .and_then(parse_flag)
+            .unwrap_or(false);
+        Self::new(key, item.name.clone(), confirm, None)
+    }
+}
+
+/// An identity loaded from a vault item, kept until the item changes.
+struct CachedIdentity {
+    updated_at: OffsetDateTime,
+    /// `None` when the item does not hold a usable key
+    identity: Option<Identity>,
+}
+
+/// The identities offered by the agent.
+#[derive(Default)]
+pub(crate) struct SshKeyring {
+    /// Keys of `SshKey` items, by vault ID and item ID
+    vault_keys: HashMap<(String, u64), CachedIdentity>,
+    /// Keys added with `ssh-add`
+    added: Vec<Identity>,
+    /// Vault keys removed with `ssh-add -d`, hidden until the agent is locked
+    removed: HashSet<Vec<u8>>,
+}
+
+impl SshKeyring {
+    /// Forgets every identity.
+    pub(crate) fn clear(&mut self) {
+        self.vault_keys.clear();
+        self.added.clear();
+        self.removed.clear();
+    }
+
+    /// Returns the number of identities currently offered.
+    pub(crate) fn len(&self) -> usize {
+        self.identities().len()
+    }
+
+    /// Syncs the vault keys with the `SshKey` items of `vaults` and drops expired keys.
+    ///
+    /// Items are only decrypted when they were added or updated since the last refresh.
+    pub(crate) fn refresh(&mut self, vaults: &HashMap<String, Vault>) {
+        let now = Instant::now();
+        self.added
+            .retain(|identity| identity.expires.is_none_or(|expires| expires > now));
+
+        let mut current = HashSet::new();
+        for (vault_id, vault) in vaults {
+            let headers = match vault.list_item_headers() {
+                Ok(headers) => headers,
+                Err(e) => {
+                    tracing::warn!("Cannot list SSH keys of vault {}: {}", vault_id, e);
+                    continue;
+                }
+            };
+            for header in headers.into_iter().filter(|h| h.kind == ItemKind::SshKey) {
+                let id = (vault_id.clone(), header.id);
+                let up_to_date = self
+                    .vault_keys
+                    .get(&id)
+                    .is_some_and(|cached| cached.updated_at == header.updated_at);
+                if !up_to_date {
+                    let identity = match vault.get_item_by_id(header.id) {
+                        Ok(Some(item)) => Identity::from_item(&item)
+                            .map_err(|e| tracing::warn!("Skipping SSH key '{}': {}", header.name, e))
+                            .ok(),
+                        Ok(None) => None,
+                        Err(e) => {
+                            tracing::warn!("Cannot read SSH key '{}': {}", header.name, e);
+                            None
+                        }
+                    };
+                    self.vault_keys.insert(
+                        id.clone(),
+                        CachedIdentity {
+                            updated_at: header.updated_at,
+                            identity,
+                        },
+                    );
+                }
+                current.insert(id);
+            }
+        }
+        self.vault_keys.retain(|id, _| current.contains(id));
+    }
+
+    /// Returns the identities on offer: keys added with `ssh-add` first, then vault keys that
+    /// were not removed, without duplicates.
+    fn identities(&self) -> Vec<&Identity> {
+        let mut seen = HashSet::new();
+        let mut vault_keys: Vec<&Identity> = self
+            .vault_keys
+            .values()
+            .filter_map(|cached| cached.identity.as_ref())
+            .filter(|identity| !self.removed.contains(&identity.blob))
+            .collect();
+        vault_keys.sort_by(|a, b| a.comment.cmp(&b.comment));
+        self.added
+            .iter()
+            .chain(vault_keys)
+            .filter(|identity| seen.insert(identity.blob.as_slice()))
+            .collect()
+    }
+
+    fn find(&self, blob: &[u8]) -> Option<&Identity> {
+        self.identities().into_iter().find(|identity| identity.blob == blob)
+    }
+
+    /// Handles every message except sign requests, which may have to wait for confirmation.
+    fn handle(&mut self, kind: u8, mut body: &[u8]) -> color_eyre::Result<Vec<u8>> {
+        match kind {
+            SSH_AGENTC_REQUEST_IDENTITIES => {
+                let identities = self.identities();
+                let mut reply = vec![SSH_AGENT_IDENTITIES_ANSWER];
+                u32::try_from(identities.len())?.encode(&mut reply)?;
+                for identity in identities {
+                    identity.blob.encode(&mut reply)?;
+                    identity.comment.encode(&mut reply)?;
+                }
+                Ok(reply)
+            }
+            SSH_AGENTC_ADD_IDENTITY | SSH_AGENTC_ADD_ID_CONSTRAINED => {
+                let keypair = KeypairData::decode(&mut body)?;
+                let comment = String::decode(&mut body)?;
+                let mut confirm = false;
+                let mut expires = None;
+                while kind == SSH_AGENTC_ADD_ID_CONSTRAINED && !body.is_finished() {
+                    match u8::decode(&mut body)? {
+                        SSH_AGENT_CONSTRAIN_LIFETIME => {
+                            let seconds = u32::decode(&mut body)?;
+                            expires = Some(Instant::now() + Duration::from_secs(seconds.into()));
+                        }
+                        SSH_AGENT_CONSTRAIN_CONFIRM => confirm = true,
+                        other => return Err(eyre!("Unsupported key constraint {}", other)),
+                    }
+                }
+                let identity = Identity::new(PrivateKey::new(keypair, comment.clone())?, comment, confirm, expires)?;
+                self.added.retain(|added| added.blob != identity.blob);
+                self.removed.remove(&identity.blob);
+                self.added.push(identity);
+                Ok(vec![SSH_AGENT_SUCCESS])
+            }
+            SSH_AGENTC_REMOVE_IDENTITY => {
+                let blob = Vec::<u8>::decode(&mut body)?;
+                if self.find(&blob).is_none() {
+                    return Err(eyre!("No such identity"));
+                }
+                self.added.retain(|added| added.blob != blob);
+                self.removed.insert(blob);
+                Ok(vec![SSH_AGENT_SUCCESS])
+            }
+            SSH_AGENTC_REMOVE_ALL_IDENTITIES => {
+                let blobs: Vec<Vec<u8>> = self.identities().into_iter().map(|i| i.blob.clone()).collect();
+                self.added.clear();
+                self.removed.extend(blobs);
+                Ok(vec![SSH_AGENT_SUCCESS])
+            }
+            // Agent locking (`ssh-add -x`), extensions and anything newer are not supported
+            other => Err(eyre!("Unsupported message type {}", other)),
+        }
+    }
+}
+
+/// Serves one SSH agent client until it disconnects.
+///
+/// # Errors
+/// Returns an error if the peer is another user, a message is malformed at the framing level,
+/// or the connection fails. Errors within a message are answered with `SSH_AGENT_FAILURE`.
+pub(crate) async fn handle_connection(
+    manager: &Mutex<VaultManager>,
+    keyring: &Mutex<SshKeyring>,
+    mut stream: UnixStream,
+    uid: u32,
+) -> color_eyre::Result<()> {
+    let peer = stream.peer_cred()?;
+    if peer.uid() != uid {
+        return Err(eyre!("Rejected SSH agent connection from uid {}", peer.uid()));
+    }
+
+    loop {
+        let len = match stream.read_u32().await {
+            Ok(len) => len,
+            Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => return Ok(()),
+            Err(e) => return Err(e.into()),
+        };
+        if len > MAX_MESSAGE_LEN {
+            return Err(eyre!("SSH agent message of {} bytes is too large", len));
+        }
+        let mut message = vec![0; len as usize];
+        stream.read_exact(&mut message).await?;
+
+        let reply = handle_message(manager, keyring, &message).await;
+        stream.write_u32(u32::try_from(reply.len())?).await?;
+        stream.write_all(&reply).await?;
+    }
+}
+
+/// Answers one SSH agent message. Like the agent's own requests, it locks `manager` before
+/// `keyring` so the two cannot deadlock.
+async fn handle_message(manager: &Mutex<VaultManager>, keyring: &Mutex<SshKeyring>, message: &[u8]) -> Vec<u8> {
+    let reply = match message.split_first() {
+        Some((&SSH_AGENTC_SIGN_REQUEST, body)) => sign_request(manager, keyring, body).await,
+        Some((&kind, body)) => {
+            let manager = manager.lock().await;
+            let mut keyring = keyring.lock().await;
+            keyring.refresh(&manager.open_vaults);
+            drop(manager);
+            keyring.handle(kind, body)
+        }
+        None => Err(eyre!("Empty message")),
+    };
+    reply.unwrap_or_else(|e| {
+        tracing::debug!("SSH agent request failed: {}", e);
+        vec![SSH_AGENT_FAILURE]
+    })
+}
+
+async fn sign_request(
+    manager: &Mutex<VaultManager>,
+    keyring: &Mutex<SshKeyring>,
+    mut body: &[u8],
+) -> color_eyre::Result<Vec<u8>> {
+    let blob = Vec::<u8>::decode(&mut body)?;
+    let data = Vec::<u8>::decode(&mut body)?;
+    let flags = u32::decode(&mut body)?;
+
+    let identity = {
+        let manager = manager.lock().await;
+        let mut keyring = keyring.lock().await;
+        keyring.refresh(&manager.open_vaults);
+        drop(manager);
+        keyring.find(&blob).cloned()
+    }
+    .ok_or_else(|| eyre!("No such identity"))?;
+
+    // Asked without holding any lock, so other clients are not blocked while the user decides
+    if identity.confirm && !confirm_use(&identity).await {
+        return Err(eyre!("Use of '{}' was not confirmed", identity.comment));
+    }
+    sign_response(&identity.key, &data, flags)
+}
+
+/// Signs `data` and encodes the `SSH_AGENT_SIGN_RESPONSE` message.
+///
+/// RSA keys sign with SHA-256 or SHA-512 as requested in `flags`; legacy SHA-1 `ssh-rsa`
+/// signatures are refused.
+fn sign_response(key: &PrivateKey, data: &[u8], flags: u32) -> color_eyre::Result<Vec<u8>> {
+    let signature = match key.key_data().rsa() {
+        Some(rsa) => {
+            let private = rsa_private_key(rsa)?;
+            if flags & SSH_AGENT_RSA_SHA2_512 != 0 {
+                let signature = rsa::pkcs1v15::SigningKey::<sha2::Sha512>::new(private).try_sign(data)?;
+                Signature::new(
+                    Algorithm::Rsa {
+                        hash: Some(HashAlg::Sha512),
+                    },
+                    signature.to_vec(),
+                )?
+            } else if flags & SSH_AGENT_RSA_SHA2_256 != 0 {
+                let signature = rsa::pkcs1v15::SigningKey::<sha2::Sha256>::new(private).try_sign(data)?;
+                Signature::new(
+                    Algorithm::Rsa {
+                        hash: Some(HashAlg::Sha256),
+                    },
+                    signature.to_vec(),
+                )?
+            } else {
+                return Err(eyre!("SHA-1 ssh-rsa signatures are not supported"));
+            }
+        }
+        None => Signer::<Signature>::try_sign(key, data)?,
+    };
+
+    let mut reply = vec![SSH_AGENT_SIGN_RESPONSE];
+    signature.encode_prefixed(&mut reply)?;
+    Ok(reply)
+}
+
+/// Converts an SSH RSA keypair for signing.
+///
+/// `ssh-key`'s own conversion passes the first prime twice, which newer `rsa` releases reject,
+/// so the key is assembled from its components here.
+fn rsa_private_key(keypair: &RsaKeypair) -> color_eyre::Result<rsa::RsaPrivateKey> {
+    let key = rsa::RsaPrivateKey::from_components(
+        rsa::BigUint::try_from(&keypair.public.n)?,
+        rsa::BigUint::try_from(&keypair.public.e)?,
+        rsa::BigUint::try_from(&keypair.private.d)?,
+        vec![
+            rsa::BigUint::try_from(&keypair.private.p)?,
+            rsa::BigUint::try_from(&keypair.private.q)?,
+        ],
+    )?;
+    Ok(key)
+}
+
+/// Asks the user to allow one use of a key through `$SSH_ASKPASS`, like `ssh-agent` does.
+/// Without an askpass program the use is refused.
+async fn confirm_use(identity: &Identity) -> bool {
+    let Some(askpass) = std::env::var_os("SSH_ASKPASS") else {
+        tracing::warn!(
+            "Refusing to use '{}': it needs confirmation but SSH_ASKPASS is not set",
+            identity.comment
+        );
+        return false;
+    };
+    let prompt = format!(
+        "Allow use of key {}?\nKey fingerprint {}.",
+        identity.comment,
+        identity.key.fingerprint(HashAlg::Sha256)
+    );
+    tokio::process::Command::new(askpass)
+        .arg(prompt)
+        .env("SSH_ASKPASS_PROMPT", "confirm")
+        .stdin(std::process::Stdio::null())
+        .status()
+        .await
+        .is_ok_and(|status| status.success())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chamber_vault::{ItemField, NewItem};
+    use ssh_key::PublicKey;
+
+    fn random_key(algorithm: Algorithm) -> PrivateKey {
+        let mut rng = ssh_key::rand_core::OsRng;
+        PrivateKey::random(&mut rng, algorithm).expect("key generation")
+    }
+
+    fn add_message(key: &PrivateKey, comment: &str, constraints: &[u8]) -> color_eyre::Result<Vec<u8>> {
+        let mut body = Vec::new();
+        key.key_data().encode(&mut body)?;
+        comment.encode(&mut body)?;
+        body.extend_from_slice(constraints);
+        Ok(body)
+    }
+
+    fn listed(keyring: &mut SshKeyring) -> color_eyre::Result<Vec<(Vec<u8>, String)>> {
+        let reply = keyring.handle(SSH_AGENTC_REQUEST_IDENTITIES, &[])?;
+        let (&kind, mut body) = reply.split_first().expect("reply");
+        assert_eq!(kind, SSH_AGENT_IDENTITIES_ANSWER);
+        let count = u32::decode(&mut body)?;
+        (0..count)
+            .map(|_| Ok((Vec::<u8>::decode(&mut body)?, String::decode(&mut body)?)))
+            .collect()
+    }
+
+    #[test]
+    fn test_add_list_and_remove_identities() -> color_eyre::Result<()> {
+        let mut keyring = SshKeyring::default();
+        let ed25519 = random_key(Algorithm::Ed25519);
+        let ecdsa = random_key(Algorithm::Ecdsa {
+            curve: ssh_key::EcdsaCurve::NistP256,
+        });
+
+        let reply = keyring.handle(SSH_AGENTC_ADD_IDENTITY, &add_message(&ed25519, "laptop", &[])?)?;
+        assert_eq!(reply, [SSH_AGENT_SUCCESS]);
+        let constraints = [SSH_AGENT_CONSTRAIN_CONFIRM];
+        keyring.handle(
+            SSH_AGENTC_ADD_ID_CONSTRAINED,
+            &add_message(&ecdsa, "deploy", &constraints)?,
+        )?;
+        assert!(
+            keyring
+                .handle(SSH_AGENTC_ADD_ID_CONSTRAINED, &add_message(&ecdsa, "x", &[9])?)
+                .is_err()
+        );
+
+        let identities = listed(&mut keyring)?;
+        assert_eq!(identities.len(), 2);
+        assert_eq!(identities[0].1, "laptop");
+        let public = PublicKey::from_bytes(&identities[0].0)?;
+        assert_eq!(public.key_data(), ed25519.public_key().key_data());
+        assert!(keyring.find(&identities[1].0).is_some_and(|i| i.confirm));
+
+        let mut remove = Vec::new();
+        identities[0].0.encode(&mut remove)?;
+        keyring.handle(SSH_AGENTC_REMOVE_IDENTITY, &remove)?;
+        assert!(keyring.handle(SSH_AGENTC_REMOVE_IDENTITY, &remove).is_err());
+        assert_eq!(keyring.len(), 1);
+        keyring.handle(SSH_AGENTC_REMOVE_ALL_IDENTITIES, &[])?;
+        assert_eq!(keyring.len(), 0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_lifetime_constraint_expires_keys() -> color_eyre::Result<()> {
+        let mut keyring = SshKeyring::default();
+        let mut constraints = vec![SSH_AGENT_CONSTRAIN_LIFETIME];
+        0u32.encode(&mut constraints)?;
+        keyring.handle(
+            SSH_AGENTC_ADD_ID_CONSTRAINED,
+            &add_message(&random_key(Algorithm::Ed25519), "short-lived", &constraints)?,
+        )?;
+        keyring.refresh(&HashMap::new());
+        assert_eq!(keyring.len(), 0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_sign_response_verifies() -> color_eyre::Result<()> {
+        use rsa::signature::Verifier;
+
+        let data = b"session-id and userauth request";
+        for algorithm in [
+            Algorithm::Ed25519,
+            Algorithm::Ecdsa {
+                curve: ssh_key::EcdsaCurve::NistP384,
+            },
+        ] {
+            let key = random_key(algorithm);
+            let reply = sign_response(&key, data, 0)?;
+            let (&kind, mut body) = reply.split_first().expect("reply");
+            assert_eq!(kind, SSH_AGENT_SIGN_RESPONSE);
+            let signature = Signature::decode(&mut Vec::<u8>::decode(&mut body)?.as_slice())?;
+            key.public_key().key_data().verify(data, &signature)?;
+        }
+
+        let rsa = PrivateKey::from(ssh_key::private::RsaKeypair::random(
+            &mut ssh_key::rand_core::OsRng,
+            2048,
+        )?);
+        assert!(sign_response(&rsa, data, 0).is_err(), "SHA-1 signatures are refused");
+        let reply = sign_response(&rsa, data, SSH_AGENT_RSA_SHA2_256)?;
+        let signature = Signature::decode(&mut Vec::<u8>::decode(&mut &reply[1..])?.as_slice())?;
+        assert_eq!(
+            signature.algorithm(),
+            Algorithm::Rsa {
+                hash: Some(HashAlg::Sha256)
+            }
+        );
+        rsa.public_key().key_data().verify(data, &signature)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_refresh_loads_ssh_key_items() -> color_eyre::Result<()> {
+        let now = OffsetDateTime::now_utc().unix_timestamp_nanos();
+        let path = std::env::temp_dir().join(format!("chamber_cli_ssh_agent_{}_{now}.db", std::process::id()));
+        let mut vault = Vault::open_or_create(Some(&path))?;
+        vault.initialize("m")?;
+        vault.unlock("m")?;
+
+        let key = random_key(Algorithm::Ed25519);
+        let encrypted = random_key(Algorithm::Ed25519).encrypt(&mut ssh_key::rand_core::OsRng, "hunter2")?;
+        for (name, pem, fields) in [
+            (
+                "github",
+                key.to_openssh(ssh_key::LineEnding::LF)?.to_string(),
+                vec![ItemField::new(SSH_AGENT_CONFIRM_FIELD, "yes", false)],
+            ),
+            (
+                "prod",
+                encrypted.to_openssh(ssh_key::LineEnding::LF)?.to_string(),
+                vec![ItemField::new("passphrase", "hunter2", true)],
+            ),
+            ("broken", "not a key".to_string(), Vec::new()),
+        ] {
+            vault.create_item(&NewItem {
+                name: name.into(),
+                kind: ItemKind::SshKey,
+                value: pem,
+                tags: Vec::new(),
+                folder: None,
+                fields,
+            })?;
+        }
+
+        let mut keyring = SshKeyring::default();
+        let mut vaults = HashMap::from([("main".to_string(), vault)]);
+        keyring.refresh(&vaults);
+        let identities = listed(&mut keyring)?;
+        let names: Vec<&str> = identities.iter().map(|(_, comment)| comment.as_str()).collect();
+        assert_eq!(names, ["github", "prod"]);
+        assert!(keyring.find(&identities[0].0).is_some_and(|i| i.confirm));
+        assert!(keyring.find(&identities[1].0).is_some_and(|i| !i.confirm));
+
+        // Removed vault keys stay hidden until the agent is locked
+        let mut remove = Vec::new();
+        identities[1].0.encode(&mut remove)?;
+        keyring.handle(SSH_AGENTC_REMOVE_IDENTITY, &remove)?;
+        keyring.refresh(&vaults);
+        assert_eq!(keyring.len(), 1);
+
+        vaults.clear();
+        keyring.refresh(&vaults);
+        assert_eq!(keyring.len(), 0);
+
+        std::fs::remove_file(path).ok();
+        Ok(())
+    }
+}
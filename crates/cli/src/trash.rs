@@ -0,0 +1,137 @@
+use crate::run::open_vault;
+use crate::utils::format_relative_time;
+use chamber_vault::{TrashedItem, Vault};
+use clap::Subcommand;
+use color_eyre::Result;
+use color_eyre::eyre::eyre;
+use std::io::Write;
+use time::OffsetDateTime;
+
+#[derive(Subcommand, Debug)]
+pub enum TrashCommand {
+    /// List deleted items and when they will be purged
+    List {
+        /// Vault to list (name or ID); defaults to the active vault
+        #[arg(long)]
+        vault: Option<String>,
+    },
+    /// Bring a deleted item back
+    Restore {
+        /// Name or ID of the deleted item
+        item: String,
+        /// Vault holding the item (name or ID); defaults to the active vault
+        #[arg(long)]
+        vault: Option<String>,
+    },
+    /// Permanently delete every item in the trash
+    Empty {
+        /// Skip the confirmation prompt
+        #[arg(long, short)]
+        yes: bool,
+        /// Vault to update (name or ID); defaults to the active vault
+        #[arg(long)]
+        vault: Option<String>,
+    },
+    /// Show or set how many days deleted items are kept; 0 keeps them until the trash is emptied
+    Retention {
+        /// New retention period in days
+        days: Option<u32>,
+        /// Vault to update (name or ID); defaults to the active vault
+        #[arg(long)]
+        vault: Option<String>,
+    },
+}
+
+/// Runs a `trash` subcommand.
+///
+/// # Errors
+/// Returns an error if the vault cannot be opened or unlocked, the item is not in the trash or
+/// its name is taken, or the vault cannot be updated.
+pub fn handle_trash_command(cmd: TrashCommand) -> Result<()> {
+    match cmd {
+        TrashCommand::List { vault } => {
+            let vault = open_vault(vault.as_deref())?;
+            let trash = vault.list_trash()?;
+            if trash.is_empty() {
+                println!("🗑️  The trash is empty");
+                return Ok(());
+            }
+            println!("🗑️  {} deleted item(s):", trash.len());
+            for item in &trash {
+                println!(
+                    "  [{}] {} ({}) - deleted {}{}",
+                    item.id,
+                    item.name,
+                    item.kind.as_str(),
+                    format_relative_time(item.deleted_at),
+                    describe_purge(item)
+                );
+            }
+            println!("\nRestore one with 'chamber trash restore <name or id>'.");
+        }
+        TrashCommand::Restore { item, vault } => {
+            let mut vault = open_vault(vault.as_deref())?;
+            let trashed = find_trashed(&vault, &item)?;
+            vault.restore_item(trashed.id)?;
+            println!("♻️  Restored '{}'", trashed.name);
+        }
+        TrashCommand::Empty { yes, vault } => {
+            let mut vault = open_vault(vault.as_deref())?;
+            let count = vault.list_trash()?.len();
+            if count == 0 {
+                println!("🗑️  The trash is already empty");
+                return Ok(());
+            }
+            if !yes {
+                print!("Permanently delete {count} item(s) with their history and attachments? (y/N): ");
+                std::io::stdout().flush()?;
+                let mut input = String::new();
+                std::io::stdin().read_line(&mut input)?;
+                if !matches!(input.trim().to_lowercase().as_str(), "y" | "yes") {
+                    println!("Emptying the trash cancelled");
+                    return Ok(());
+                }
+            }
+            let purged = vault.empty_trash()?;
+            println!("✅ Permanently deleted {purged} item(s)");
+        }
+        TrashCommand::Retention { days, vault } => {
+            let mut vault = open_vault(vault.as_deref())?;
+            if let Some(days) = days {
+                vault.set_trash_retention_days(days)?;
+            }
+            match vault.trash_retention_days()? {
+                0 => println!("Deleted items are kept until the trash is emptied"),
+                days => println!("Deleted items are purged after {days} day(s)"),
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Finds an item in the trash by ID or, failing that, by name.
+fn find_trashed(vault: &Vault, item: &str) -> Result<TrashedItem> {
+    let trash = vault.list_trash()?;
+    if let Some(found) = item
+        .parse::<u64>()
+        .ok()
+        .and_then(|id| trash.iter().find(|t| t.id == id))
+    {
+        return Ok(found.clone());
+    }
+    let mut matches = trash.into_iter().filter(|t| t.name == item);
+    match (matches.next(), matches.next()) {
+        (Some(found), None) => Ok(found),
+        (Some(_), Some(_)) => Err(eyre!(
+            "Several deleted items are named '{item}'; restore one by its ID from 'chamber trash list'"
+        )),
+        (None, _) => Err(eyre!("No item named '{item}' in the trash")),
+    }
+}
+
+fn describe_purge(item: &TrashedItem) -> String {
+    item.purge_at.map_or_else(String::new, |purge_at| {
+        let days = (purge_at - OffsetDateTime::now_utc()).whole_days().max(0);
+        format!(", purged in {days} day(s)")
+    })
+}
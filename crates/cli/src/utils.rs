@@ -1,7 +1,8 @@
-use chamber_vault::{Item, ItemKind};
+use chamber_vault::{ItemField, ItemHeader, ItemKind, folder_matches, generate_keyfile};
 use color_eyre::Result;
 use color_eyre::eyre::eyre;
 use regex::Regex;
+use std::path::PathBuf;
 use std::str::FromStr;
 use time::{Duration, OffsetDateTime};
 
@@ -94,12 +95,14 @@ pub fn matches_wildcard_pattern(name: &str, pattern: &str) -> bool {
 
 /// Filter and sort items based on list criteria
 pub fn filter_and_sort_items(
-    items: Vec<Item>,
+    items: Vec<ItemHeader>,
     item_type: Option<&str>,
     since: Option<&str>,
     recent: Option<usize>,
     name_pattern: Option<&str>,
-) -> Result<Vec<Item>> {
+    tag: Option<&str>,
+    folder: Option<&str>,
+) -> Result<Vec<ItemHeader>> {
     let mut filtered_items = items;
 
     // Filter by type
@@ -119,8 +122,18 @@ pub fn filter_and_sort_items(
         filtered_items.retain(|item| matches_wildcard_pattern(&item.name, pattern));
     }
 
+    // Filter by tag
+    if let Some(tag) = tag {
+        filtered_items.retain(|item| item.has_tag(tag));
+    }
+
+    // Filter by folder (includes sub-folders)
+    if let Some(folder) = folder {
+        filtered_items.retain(|item| folder_matches(item.folder.as_deref(), folder));
+    }
+
     // Sort by creation date (newest first)
-    filtered_items.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+    filtered_items.sort_by_key(|item| std::cmp::Reverse(item.created_at));
 
     // Limit to recent items if specified
     if let Some(limit) = recent {
@@ -130,6 +143,44 @@ pub fn filter_and_sort_items(
     Ok(filtered_items)
 }
 
+/// Parses a `--field name=value` argument into an item field.
+///
+/// Typed fields take their secret flag from the kind's schema when the item is stored, so
+/// fields parsed here start out as non-secret.
+pub fn parse_field_arg(arg: &str) -> Result<ItemField> {
+    let (name, value) = arg
+        .split_once('=')
+        .ok_or_else(|| eyre!("Invalid field '{}': expected name=value", arg))?;
+    if name.trim().is_empty() {
+        return Err(eyre!("Invalid field '{}': name cannot be empty", arg));
+    }
+    Ok(ItemField::new(name.trim(), value, false))
+}
+
+/// Resolves the `--keyfile` of a new vault to an absolute path, first creating a random keyfile
+/// there when `generate` is set.
+///
+/// # Errors
+/// Returns an error if the keyfile cannot be generated, or does not exist when not generated.
+pub fn resolve_keyfile(keyfile: Option<PathBuf>, generate: bool) -> Result<Option<PathBuf>> {
+    let Some(keyfile) = keyfile else {
+        return Ok(None);
+    };
+    if generate {
+        generate_keyfile(&keyfile)?;
+        println!(
+            "🔑 Generated keyfile {}; keep a copy, the vault cannot be opened without it",
+            keyfile.display()
+        );
+    } else if !keyfile.is_file() {
+        return Err(eyre!(
+            "Keyfile {} not found; add --generate-keyfile to create one",
+            keyfile.display()
+        ));
+    }
+    Ok(Some(std::fs::canonicalize(keyfile)?))
+}
+
 /// Format relative time like "2 hours ago", "3 days ago"
 pub fn format_relative_time(datetime: OffsetDateTime) -> String {
     let now = OffsetDateTime::now_utc();
@@ -163,15 +214,16 @@ mod tests {
     use time::macros::datetime;
 
     // Helper function to create test items
-    fn create_test_item(name: &str, kind: ItemKind, days_ago: i64) -> Item {
+    fn create_test_item(name: &str, kind: ItemKind, days_ago: i64) -> ItemHeader {
         let created_at = OffsetDateTime::now_utc() - Duration::days(days_ago);
-        Item {
+        ItemHeader {
             id: 1,
             name: name.to_string(),
             kind,
-            value: "test_value".to_string(),
             created_at,
             updated_at: created_at,
+            tags: Vec::new(),
+            folder: None,
         }
     }
 
@@ -401,7 +453,7 @@ mod tests {
     mod filter_and_sort_tests {
         use super::*;
 
-        fn create_sample_items() -> Vec<Item> {
+        fn create_sample_items() -> Vec<ItemHeader> {
             vec![
                 create_test_item("password1", ItemKind::Password, 1),
                 create_test_item("password2", ItemKind::Password, 3),
@@ -416,7 +468,7 @@ mod tests {
         #[test]
         fn test_no_filters() {
             let items = create_sample_items();
-            let result = filter_and_sort_items(items.clone(), None, None, None, None).unwrap();
+            let result = filter_and_sort_items(items.clone(), None, None, None, None, None, None).unwrap();
 
             // Should return all items, sorted by creation date (newest first)
             assert_eq!(result.len(), items.len());
@@ -432,19 +484,19 @@ mod tests {
             let items = create_sample_items();
 
             // Filter by password
-            let result = filter_and_sort_items(items.clone(), Some("password"), None, None, None).unwrap();
+            let result = filter_and_sort_items(items.clone(), Some("password"), None, None, None, None, None).unwrap();
 
             assert_eq!(result.len(), 3); // password1, password2, MY-PASSWORD
             assert!(result.iter().all(|item| item.kind == ItemKind::Password));
 
             // Filter by apikey
-            let result = filter_and_sort_items(items.clone(), Some("apikey"), None, None, None).unwrap();
+            let result = filter_and_sort_items(items.clone(), Some("apikey"), None, None, None, None, None).unwrap();
 
             assert_eq!(result.len(), 3); // api-key1, api-key2, github-token
             assert!(result.iter().all(|item| item.kind == ItemKind::ApiKey));
 
             // Filter by note
-            let result = filter_and_sort_items(items, Some("note"), None, None, None).unwrap();
+            let result = filter_and_sort_items(items, Some("note"), None, None, None, None, None).unwrap();
 
             assert_eq!(result.len(), 1);
             assert_eq!(result[0].name, "note1");
@@ -453,7 +505,7 @@ mod tests {
         #[test]
         fn test_filter_by_invalid_type() {
             let items = create_sample_items();
-            let result = filter_and_sort_items(items, Some("invalidtype"), None, None, None);
+            let result = filter_and_sort_items(items, Some("invalidtype"), None, None, None, None, None);
 
             assert!(result.is_err());
         }
@@ -463,17 +515,17 @@ mod tests {
             let items = create_sample_items();
 
             // Filter by pattern "password*"
-            let result = filter_and_sort_items(items.clone(), None, None, None, Some("password*")).unwrap();
+            let result = filter_and_sort_items(items.clone(), None, None, None, Some("password*"), None, None).unwrap();
 
             assert_eq!(result.len(), 2); // password1, password2
 
             // Filter by pattern "*api*"
-            let result = filter_and_sort_items(items.clone(), None, None, None, Some("*api*")).unwrap();
+            let result = filter_and_sort_items(items.clone(), None, None, None, Some("*api*"), None, None).unwrap();
 
             assert_eq!(result.len(), 2); // api-key1, api-key2
 
             // Filter by pattern "MY*"
-            let result = filter_and_sort_items(items, None, None, None, Some("MY*")).unwrap();
+            let result = filter_and_sort_items(items, None, None, None, Some("MY*"), None, None).unwrap();
 
             assert_eq!(result.len(), 1);
             assert_eq!(result[0].name, "MY-PASSWORD");
@@ -484,7 +536,7 @@ mod tests {
             let items = create_sample_items();
 
             // Get 3 most recent items
-            let result = filter_and_sort_items(items, None, None, Some(3), None).unwrap();
+            let result = filter_and_sort_items(items, None, None, Some(3), None, None, None).unwrap();
 
             assert_eq!(result.len(), 3);
 
@@ -499,7 +551,7 @@ mod tests {
             let items = create_sample_items();
 
             // Filter by "2 days ago"
-            let result = filter_and_sort_items(items, None, Some("2 days ago"), None, None).unwrap();
+            let result = filter_and_sort_items(items, None, Some("2 days ago"), None, None, None, None).unwrap();
 
             // Should include items created in the last 2 days (0, 1 days ago)
             let expected_items: Vec<_> = result
@@ -516,7 +568,7 @@ mod tests {
         #[test]
         fn test_filter_by_invalid_since() {
             let items = create_sample_items();
-            let result = filter_and_sort_items(items, None, Some("invalid time expression"), None, None);
+            let result = filter_and_sort_items(items, None, Some("invalid time expression"), None, None, None, None);
 
             assert!(result.is_err());
         }
@@ -526,7 +578,16 @@ mod tests {
             let items = create_sample_items();
 
             // Combine type and name pattern filters
-            let result = filter_and_sort_items(items.clone(), Some("password"), None, None, Some("password*")).unwrap();
+            let result = filter_and_sort_items(
+                items.clone(),
+                Some("password"),
+                None,
+                None,
+                Some("password*"),
+                None,
+                None,
+            )
+            .unwrap();
 
             assert_eq!(result.len(), 2); // password1, password2
             assert!(
@@ -536,8 +597,16 @@ mod tests {
             );
 
             // Combine all filters
-            let result =
-                filter_and_sort_items(items, Some("apikey"), Some("3 days ago"), Some(2), Some("*key*")).unwrap();
+            let result = filter_and_sort_items(
+                items,
+                Some("apikey"),
+                Some("3 days ago"),
+                Some(2),
+                Some("*key*"),
+                None,
+                None,
+            )
+            .unwrap();
 
             // Should filter by type=apikey, since=3 days ago, name=*key*, and limit to 2 items
             assert!(result.len() <= 2);
@@ -549,10 +618,26 @@ mod tests {
             let items = create_sample_items();
 
             // Filter that should return no results
-            let result = filter_and_sort_items(items, None, None, None, Some("nonexistent*")).unwrap();
+            let result = filter_and_sort_items(items, None, None, None, Some("nonexistent*"), None, None).unwrap();
 
             assert!(result.is_empty());
         }
+
+        #[test]
+        fn test_filter_by_tag_and_folder() {
+            let mut items = create_sample_items();
+            items[0].tags = vec!["Prod".to_string()];
+            items[0].folder = Some("work/infra".to_string());
+            items[2].tags = vec!["prod".to_string(), "ci".to_string()];
+            items[2].folder = Some("workshop".to_string());
+
+            let result = filter_and_sort_items(items.clone(), None, None, None, None, Some("prod"), None).unwrap();
+            assert_eq!(result.len(), 2);
+
+            let result = filter_and_sort_items(items, None, None, None, None, Some("prod"), Some("work")).unwrap();
+            assert_eq!(result.len(), 1);
+            assert_eq!(result[0].name, "password1");
+        }
     }
 
     // Tests for format_relative_time
@@ -673,4 +758,13 @@ mod tests {
             assert_eq!(format_relative_time(now - Duration::days(2)), "2 days ago");
         }
     }
+
+    #[test]
+    fn test_parse_field_arg() {
+        let field = parse_field_arg(" host =db.internal:5432=x").unwrap();
+        assert_eq!(field.name, "host");
+        assert_eq!(field.value, "db.internal:5432=x");
+        assert!(parse_field_arg("novalue").is_err());
+        assert!(parse_field_arg("=value").is_err());
+    }
 }
@@ -1,7 +1,11 @@
-use chamber_vault::{VaultCategory, VaultManager};
+use crate::kdf::KdfArgs;
+use crate::prompt_secret;
+use crate::run::{open_locked_vault, open_vault};
+use crate::utils::resolve_keyfile;
+use chamber_vault::{Db, MASTER_KEY_SLOT, SCHEMA_VERSION, VaultCategory, VaultManager};
 use clap::Subcommand;
 use color_eyre::eyre::eyre;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 #[derive(Subcommand, Debug)]
 pub enum VaultCommand {
@@ -20,6 +24,12 @@ pub enum VaultCommand {
         /// Optional description explaining the vault's purpose
         #[arg(long)]
         description: Option<String>,
+        /// Also require this keyfile (any file) to unlock the vault
+        #[arg(long)]
+        keyfile: Option<PathBuf>,
+        /// Create the keyfile with 64 random bytes
+        #[arg(long, requires = "keyfile")]
+        generate_keyfile: bool,
     },
     /// Switch to a different vault (makes it the active vault for operations)
     Switch {
@@ -62,6 +72,61 @@ pub enum VaultCommand {
         #[arg(long)]
         favorite: Option<bool>,
     },
+    /// Manage the additional passwords (key slots) that unlock a vault
+    #[command(subcommand)]
+    Keys(KeysCommand),
+    /// Re-wrap the vault key under new Argon2id costs; items are not re-encrypted
+    Rekdf {
+        /// Vault to update (name or ID); defaults to the active vault
+        #[arg(long)]
+        vault: Option<String>,
+        #[command(flatten)]
+        kdf: KdfArgs,
+    },
+    /// Encrypt item names and kinds in the vault file, or store them in plaintext again
+    PrivateMetadata {
+        /// Vault to convert (name or ID); defaults to the active vault
+        #[arg(long)]
+        vault: Option<String>,
+        /// Store names and kinds in plaintext again
+        #[arg(long)]
+        disable: bool,
+    },
+    /// Upgrade vault files to the database schema of this version of Chamber
+    Migrate {
+        /// Vault to migrate (name or ID); defaults to every registered vault
+        #[arg(long)]
+        vault: Option<String>,
+        /// Only report the pending migrations
+        #[arg(long)]
+        dry_run: bool,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum KeysCommand {
+    /// Add a password that also unlocks the vault, e.g. for a team member
+    Add {
+        /// Name of the key slot (e.g. "alice")
+        name: String,
+        /// Vault to update (name or ID); defaults to the active vault
+        #[arg(long)]
+        vault: Option<String>,
+    },
+    /// List the key slots of a vault
+    List {
+        /// Vault to inspect (name or ID); defaults to the active vault
+        #[arg(long)]
+        vault: Option<String>,
+    },
+    /// Revoke a key slot; its password stops unlocking the vault
+    Remove {
+        /// Name of the key slot to revoke
+        name: String,
+        /// Vault to update (name or ID); defaults to the active vault
+        #[arg(long)]
+        vault: Option<String>,
+    },
 }
 
 /// Handles various vault-related commands by interacting with a `VaultManager` instance.
@@ -174,10 +239,14 @@ pub fn handle_vault_command(manager: &mut VaultManager, cmd: crate::VaultCommand
             path,
             category,
             description,
+            keyfile,
+            generate_keyfile,
         } => {
             let category = parse_category(&category);
+            let keyfile = resolve_keyfile(keyfile, generate_keyfile)?;
             let password = rpassword::prompt_password("Enter master password for new vault: ")?;
-            let vault_id = manager.create_vault(name.clone(), path, category, description, &password)?;
+            let vault_id =
+                manager.create_vault_with_keyfile(name.clone(), path, category, description, &password, keyfile)?;
             println!("Created vault '{name}' with ID: {vault_id}");
         }
 
@@ -281,6 +350,9 @@ pub fn handle_vault_command(manager: &mut VaultManager, cmd: crate::VaultCommand
                 );
                 println!("  Active: {}", info.is_active);
                 println!("  Favorite: {}", info.is_favorite);
+                if let Some(keyfile) = &info.keyfile {
+                    println!("  Keyfile: {}", keyfile.display());
+                }
 
                 if let Some(desc) = &info.description {
                     println!("  Description: {desc}");
@@ -301,11 +373,128 @@ pub fn handle_vault_command(manager: &mut VaultManager, cmd: crate::VaultCommand
             manager.update_vault_info(&vault_id, name, description, category, favorite)?;
             println!("Updated vault: {vault_id}");
         }
+
+        crate::VaultCommand::Keys(keys_cmd) => handle_keys_command(keys_cmd)?,
+
+        crate::VaultCommand::Rekdf { vault, kdf } => {
+            let (mut vault, label) = open_locked_vault(vault.as_deref())?;
+            let old = vault.kdf_params()?;
+            let new = kdf.params()?;
+            if !new.meets_recommendations() {
+                println!("⚠️  {new} is below current recommendations for Argon2id");
+            }
+            let master = prompt_secret("Enter master key: ")?;
+            vault.rekdf(&master, &new)?;
+            println!("🔐 Master key derivation updated{label}");
+            println!("   {old} → {new}");
+            if !vault.list_key_slots()?.is_empty() {
+                println!("   Key slots keep their costs until they are added again.");
+            }
+        }
+
+        crate::VaultCommand::Migrate { vault, dry_run } => {
+            let ids = match vault {
+                Some(v) => vec![find_vault_id(manager, &v)?],
+                None => manager.list_vaults().into_iter().map(|v| v.id.clone()).collect(),
+            };
+            for id in ids {
+                let info = manager
+                    .registry
+                    .get_vault(&id)
+                    .ok_or_else(|| eyre!("Vault '{id}' not found"))?;
+                migrate_vault(&info.name, &info.path, dry_run)?;
+            }
+        }
+
+        crate::VaultCommand::PrivateMetadata { vault, disable } => {
+            let mut vault = open_vault(vault.as_deref())?;
+            let enable = !disable;
+            if vault.has_private_metadata()? == enable {
+                println!(
+                    "Item names and kinds are already stored {}",
+                    if enable { "encrypted" } else { "in plaintext" }
+                );
+                return Ok(());
+            }
+            let converted = vault.set_private_metadata(enable)?;
+            if enable {
+                println!("🕶️  Encrypted the names and kinds of {converted} item(s)");
+                println!("   Backups and copies of the vault file made earlier still contain them.");
+            } else {
+                println!("📝 Item names and kinds of {converted} item(s) are stored in plaintext again");
+            }
+        }
     }
 
     Ok(())
 }
 
+fn handle_keys_command(cmd: KeysCommand) -> color_eyre::Result<()> {
+    match cmd {
+        KeysCommand::Add { name, vault } => {
+            let mut vault = open_vault(vault.as_deref())?;
+            let password = prompt_secret(&format!("Enter password for key slot '{name}': "))?;
+            let confirm = prompt_secret("Confirm password: ")?;
+            if password != confirm {
+                return Err(eyre!("Passwords do not match"));
+            }
+            vault.add_key_slot(&name, &password)?;
+            println!("🔑 Added key slot '{name}'");
+        }
+        KeysCommand::List { vault } => {
+            let (vault, label) = open_locked_vault(vault.as_deref())?;
+            println!("🔑 Key slots{label}:");
+            println!("  {MASTER_KEY_SLOT:<20} master key");
+            for slot in vault.list_key_slots()? {
+                println!(
+                    "  {:<20} added {}",
+                    slot.name,
+                    slot.created_at.format(&time::format_description::well_known::Rfc3339)?
+                );
+            }
+            if vault.has_recovery()? {
+                println!("  {:<20} Shamir recovery shares", "recovery");
+            }
+        }
+        KeysCommand::Remove { name, vault } => {
+            let mut vault = open_vault(vault.as_deref())?;
+            vault.remove_key_slot(&name)?;
+            println!("🗑️  Removed key slot '{name}'");
+        }
+    }
+    Ok(())
+}
+
+/// Reports the pending schema migrations of one vault file and, unless `dry_run`, applies them.
+fn migrate_vault(name: &str, path: &Path, dry_run: bool) -> color_eyre::Result<()> {
+    if !path.exists() {
+        println!("⚪ {name}: no vault file at {}", path.display());
+        return Ok(());
+    }
+    let status = Db::schema_status(path)?;
+    if status.pending.is_empty() {
+        println!("✅ {name}: schema v{SCHEMA_VERSION}, up to date");
+        return Ok(());
+    }
+    println!("🔧 {name}: schema v{} → v{SCHEMA_VERSION}", status.version);
+    for (step, description) in (status.version + 1..).zip(&status.pending) {
+        println!("   v{step}: {description}");
+    }
+    if dry_run {
+        println!(
+            "   A copy of the vault will be saved as {}",
+            status.snapshot_path.display()
+        );
+    } else {
+        Db::open(path)?;
+        println!(
+            "   Migrated; the previous file is saved as {}",
+            status.snapshot_path.display()
+        );
+    }
+    Ok(())
+}
+
 fn parse_category(category: &str) -> VaultCategory {
     match category.to_lowercase().as_str() {
         "personal" => VaultCategory::Personal,
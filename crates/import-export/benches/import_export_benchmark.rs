@@ -1,7 +1,10 @@
-use chamber_import_export::{ExportFormat, export_items, import_items};
-use chamber_vault::{Item, ItemKind};
+use chamber_import_export::{
+    BackupSecret, ExportFormat, export_chamber_backup, export_items, import_chamber_backup, import_items,
+};
+use chamber_vault::{Item, ItemKind, NewItem};
 use criterion::{BenchmarkId, Criterion, criterion_group, criterion_main};
 use std::hint::black_box;
+use std::path::Path;
 use std::time::Duration;
 use tempfile::{NamedTempFile, TempDir};
 use time::OffsetDateTime;
@@ -21,10 +24,33 @@ fn create_test_items(count: usize) -> Vec<Item> {
             value: format!("test_value_{i}_with_some_longer_content_to_simulate_real_data"),
             created_at: OffsetDateTime::now_utc(),
             updated_at: OffsetDateTime::now_utc(),
+            tags: Vec::new(),
+            folder: None,
+            fields: Vec::new(),
         })
         .collect()
 }
 
+const BENCH_PASSPHRASE: &str = "benchmark-passphrase";
+
+fn export_any(items: &[Item], format: &ExportFormat, path: &Path) {
+    match format {
+        ExportFormat::ChamberBackup => {
+            export_chamber_backup(items, path, &BackupSecret::Passphrase(BENCH_PASSPHRASE)).unwrap();
+        }
+        _ => export_items(items, format, path).unwrap(),
+    }
+}
+
+fn import_any(path: &Path, format: &ExportFormat) -> Vec<NewItem> {
+    match format {
+        ExportFormat::ChamberBackup => {
+            import_chamber_backup(path, Some(&BackupSecret::Passphrase(BENCH_PASSPHRASE))).unwrap()
+        }
+        _ => import_items(path, format).unwrap(),
+    }
+}
+
 fn bench_export_operations(c: &mut Criterion) {
     let mut group = c.benchmark_group("export_operations");
     group.measurement_time(Duration::from_secs(15));
@@ -51,7 +77,7 @@ fn bench_export_operations(c: &mut Criterion) {
                             (temp_dir, file_path)
                         },
                         |(_temp_dir, file_path)| {
-                            export_items(items, format, &file_path).unwrap();
+                            export_any(items, format, &file_path);
                             black_box(());
                         },
                         criterion::BatchSize::SmallInput,
@@ -81,13 +107,13 @@ fn bench_import_operations(c: &mut Criterion) {
         for (format_name, format) in &formats {
             // Pre-create the test file
             let temp_file = NamedTempFile::new().unwrap();
-            export_items(&items, format, temp_file.path()).unwrap();
+            export_any(&items, format, temp_file.path());
 
             group.bench_with_input(
                 BenchmarkId::new(*format_name, count),
                 &(temp_file.path(), format),
                 |b, (path, format)| {
-                    b.iter(|| black_box(import_items(path, format).unwrap()));
+                    b.iter(|| black_box(import_any(path, format)));
                 },
             );
         }
@@ -123,8 +149,8 @@ fn bench_round_trip_operations(c: &mut Criterion) {
                         },
                         |(_temp_dir, file_path)| {
                             // Export then import
-                            export_items(items, format, &file_path).unwrap();
-                            let imported = import_items(&file_path, format).unwrap();
+                            export_any(items, format, &file_path);
+                            let imported = import_any(&file_path, format);
                             black_box(imported)
                         },
                         criterion::BatchSize::SmallInput,
@@ -1,13 +1,26 @@
-use chamber_vault::{Item, ItemKind, NewItem};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use chamber_vault::{
+    Item, ItemField, ItemKind, KdfParams, KeyMaterial, NewItem, TOTP_FIELD, aead_decrypt, aead_encrypt,
+    derive_key_from_secret, is_otpauth_uri, normalize_folder, normalize_tags,
+};
 use color_eyre::Result;
 use color_eyre::eyre::Error;
 use color_eyre::eyre::eyre;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::fs;
-use std::io::Write;
+use std::hash::BuildHasher;
+use std::io::{Read, Write};
 use std::path::Path;
 use std::str::FromStr;
 use time::OffsetDateTime;
+
+/// Identifier stored in every encrypted backup container.
+pub const ENCRYPTED_BACKUP_FORMAT: &str = "chamber-encrypted-backup";
+/// Current version of the encrypted backup container.
+pub const ENCRYPTED_BACKUP_VERSION: u32 = 2;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum ExportFormat {
     Json,
@@ -36,6 +49,51 @@ pub struct ExportedItem {
     pub created_at: String,
     pub updated_at: String,
     pub notes: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub folder: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub fields: Vec<ItemField>,
+    /// Files attached to the item. Only Chamber backups carry attachments.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub attachments: Vec<ExportedAttachment>,
+}
+
+/// An attachment inside a Chamber backup, with its contents base64 encoded.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExportedAttachment {
+    pub file_name: String,
+    pub data: String,
+}
+
+/// A decrypted attachment of an item, as written to or read from a Chamber backup.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AttachmentFile {
+    pub file_name: String,
+    pub data: Vec<u8>,
+}
+
+impl From<&AttachmentFile> for ExportedAttachment {
+    fn from(file: &AttachmentFile) -> Self {
+        Self {
+            file_name: file.file_name.clone(),
+            data: BASE64.encode(&file.data),
+        }
+    }
+}
+
+impl TryFrom<&ExportedAttachment> for AttachmentFile {
+    type Error = Error;
+
+    fn try_from(exported: &ExportedAttachment) -> Result<Self> {
+        Ok(Self {
+            file_name: exported.file_name.clone(),
+            data: BASE64
+                .decode(&exported.data)
+                .map_err(|e| eyre!("Invalid attachment '{}': {e}", exported.file_name))?,
+        })
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -46,6 +104,69 @@ pub struct ChamberBackup {
     pub items: Vec<ExportedItem>,
 }
 
+/// Describes which secret an encrypted backup was sealed with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BackupKeySource {
+    /// A backup passphrase chosen by the user at export time.
+    Passphrase,
+    /// The backup key of the vault the items came from (see `Vault::backup_key`).
+    VaultKey,
+}
+
+impl BackupKeySource {
+    #[must_use]
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            Self::Passphrase => "passphrase",
+            Self::VaultKey => "vault_key",
+        }
+    }
+}
+
+/// Secret used to seal or open an encrypted Chamber backup.
+pub enum BackupSecret<'a> {
+    Passphrase(&'a str),
+    VaultKey(&'a KeyMaterial),
+}
+
+impl BackupSecret<'_> {
+    #[must_use]
+    pub const fn source(&self) -> BackupKeySource {
+        match self {
+            Self::Passphrase(_) => BackupKeySource::Passphrase,
+            Self::VaultKey(_) => BackupKeySource::VaultKey,
+        }
+    }
+
+    const fn as_bytes(&self) -> &[u8] {
+        match self {
+            Self::Passphrase(passphrase) => passphrase.as_bytes(),
+            Self::VaultKey(key) => &key.0,
+        }
+    }
+}
+
+/// On-disk container of an encrypted backup.
+///
+/// The serialized `ChamberBackup` is encrypted with XChaCha20-Poly1305 under a key derived
+/// with Argon2id from the backup secret; the container header is bound as associated data.
+#[derive(Serialize, Deserialize)]
+pub struct EncryptedChamberBackup {
+    pub format: String,
+    pub version: u32,
+    pub key_source: BackupKeySource,
+    pub kdf: KdfParams,
+    pub nonce: String,
+    pub ciphertext: String,
+}
+
+impl EncryptedChamberBackup {
+    fn associated_data(format: &str, version: u32, key_source: BackupKeySource) -> Vec<u8> {
+        format!("{format}\x1f{version}\x1f{}", key_source.as_str()).into_bytes()
+    }
+}
+
 impl From<&Item> for ExportedItem {
     fn from(item: &Item) -> Self {
         Self {
@@ -61,6 +182,10 @@ impl From<&Item> for ExportedItem {
                 .format(&time::format_description::well_known::Rfc3339)
                 .unwrap_or_else(|_| "unknown".to_string()),
             notes: None,
+            tags: item.tags.clone(),
+            folder: item.folder.clone(),
+            fields: item.fields.clone(),
+            attachments: Vec::new(),
         }
     }
 }
@@ -71,9 +196,10 @@ impl From<&Item> for ExportedItem {
 ///
 /// * `items` - A slice of `Item` objects to be exported.
 /// * `format` - The export format, represented as an `ExportFormat` enum. Possible formats include:
-///     * `ExportFormat::Json` - Exports the data in JSON format.
-///     * `ExportFormat::Csv` - Exports the data in CSV format.
-///     * `ExportFormat::ChamberBackup` - Exports the data in a custom chamber backup format.
+///     * `ExportFormat::Json` - Exports the data in plaintext JSON format.
+///     * `ExportFormat::Csv` - Exports the data in plaintext CSV format.
+///     * `ExportFormat::ChamberBackup` - Not supported here; Chamber backups are always encrypted
+///       and must be written with [`export_chamber_backup`].
 /// * `output_path` - The file path where the exported data will be saved.
 ///
 /// # Returns
@@ -86,6 +212,7 @@ impl From<&Item> for ExportedItem {
 /// * The specified `output_path` is invalid or inaccessible.
 /// * An I/O error occurs while writing to the file.
 /// * Serialization to the chosen export format fails.
+/// * `format` is `ExportFormat::ChamberBackup`, which requires a backup secret.
 ///
 /// # Note
 ///
@@ -94,7 +221,9 @@ pub fn export_items(items: &[Item], format: &ExportFormat, output_path: &Path) -
     match format {
         ExportFormat::Json => export_json(items, output_path),
         ExportFormat::Csv => export_csv(items, output_path),
-        ExportFormat::ChamberBackup => export_chamber_backup(items, output_path),
+        ExportFormat::ChamberBackup => Err(eyre!(
+            "Chamber backups are encrypted; a backup passphrase or vault key is required"
+        )),
     }
 }
 
@@ -111,7 +240,7 @@ fn export_csv(items: &[Item], output_path: &Path) -> Result<()> {
     }
 
     let mut file = fs::File::create(output_path)?;
-    writeln!(file, "name,kind,value,created_at,updated_at")?;
+    writeln!(file, "name,kind,value,created_at,updated_at,tags,folder,fields")?;
 
     for item in items {
         let exported = ExportedItem::from(item);
@@ -120,27 +249,158 @@ fn export_csv(items: &[Item], output_path: &Path) -> Result<()> {
         let value = escape_csv_field(&exported.value);
         let created = escape_csv_field(&exported.created_at);
         let updated = escape_csv_field(&exported.updated_at);
+        let tags = escape_csv_field(&exported.tags.join(CSV_TAG_SEPARATOR));
+        let folder = escape_csv_field(exported.folder.as_deref().unwrap_or_default());
+        let fields = if exported.fields.is_empty() {
+            String::new()
+        } else {
+            escape_csv_field(&serde_json::to_string(&exported.fields)?)
+        };
 
-        writeln!(file, "{name},{kind},{value},{created},{updated}")?;
+        writeln!(
+            file,
+            "{name},{kind},{value},{created},{updated},{tags},{folder},{fields}"
+        )?;
     }
     Ok(())
 }
 
-fn export_chamber_backup(items: &[Item], output_path: &Path) -> Result<()> {
+/// Exports items into an encrypted Chamber backup.
+///
+/// The items are serialized as a `ChamberBackup`, then sealed with XChaCha20-Poly1305 under a
+/// key derived with Argon2id (fresh salt per backup) from `secret`. Nothing but the container
+/// header is written in cleartext.
+///
+/// # Errors
+///
+/// Returns an error if key derivation, encryption, serialization or writing the file fails.
+pub fn export_chamber_backup(items: &[Item], output_path: &Path, secret: &BackupSecret) -> Result<()> {
+    export_chamber_backup_with_attachments(items, &HashMap::new(), output_path, secret)
+}
+
+/// Exports items and their attachments as an encrypted Chamber backup.
+///
+/// `attachments` maps item ids to the decrypted files attached to that item; they are
+/// encrypted together with the items, see [`export_chamber_backup`].
+///
+/// # Errors
+///
+/// Returns an error if key derivation, encryption, serialization or writing the file fails.
+pub fn export_chamber_backup_with_attachments<S: BuildHasher>(
+    items: &[Item],
+    attachments: &HashMap<u64, Vec<AttachmentFile>, S>,
+    output_path: &Path,
+    secret: &BackupSecret,
+) -> Result<()> {
     let backup = ChamberBackup {
         version: "1.0".to_string(),
         exported_at: OffsetDateTime::now_utc()
             .format(&time::format_description::well_known::Rfc3339)
             .unwrap_or_else(|_| "unknown".to_string()),
         item_count: items.len(),
-        items: items.iter().map(ExportedItem::from).collect(),
+        items: items
+            .iter()
+            .map(|item| ExportedItem {
+                attachments: attachments
+                    .get(&item.id)
+                    .map(|files| files.iter().map(ExportedAttachment::from).collect())
+                    .unwrap_or_default(),
+                ..ExportedItem::from(item)
+            })
+            .collect(),
+    };
+    let plaintext = serde_json::to_vec(&backup)?;
+
+    let kdf = KdfParams::default_secure();
+    let key = derive_key_from_secret(secret.as_bytes(), &kdf)?;
+    let ad =
+        EncryptedChamberBackup::associated_data(ENCRYPTED_BACKUP_FORMAT, ENCRYPTED_BACKUP_VERSION, secret.source());
+    let (nonce, ciphertext) = aead_encrypt(&key, &plaintext, &ad)?;
+
+    let container = EncryptedChamberBackup {
+        format: ENCRYPTED_BACKUP_FORMAT.to_string(),
+        version: ENCRYPTED_BACKUP_VERSION,
+        key_source: secret.source(),
+        kdf,
+        nonce: BASE64.encode(nonce),
+        ciphertext: BASE64.encode(ciphertext),
     };
 
-    let json = serde_json::to_string_pretty(&backup)?;
+    let json = serde_json::to_string_pretty(&container)?;
     fs::write(output_path, json)?;
     Ok(())
 }
 
+/// Parses Chamber backup content, decrypting it when it is an encrypted container.
+///
+/// Legacy plaintext backups (version 1.0) are still accepted so existing backups remain
+/// restorable; they do not need a secret.
+///
+/// # Errors
+///
+/// Returns an error if the content is not a valid backup, if an encrypted backup is opened
+/// without a secret or with a secret of the wrong kind, or if decryption fails (wrong secret
+/// or tampered file).
+pub fn read_chamber_backup(content: &str, secret: Option<&BackupSecret>) -> Result<ChamberBackup> {
+    let value: serde_json::Value = serde_json::from_str(content).map_err(|e| eyre!("JSON parse error: {e}"))?;
+
+    if value.get("ciphertext").is_none() {
+        return serde_json::from_value(value).map_err(|e| eyre!("JSON parse error: {e}"));
+    }
+
+    let container: EncryptedChamberBackup =
+        serde_json::from_value(value).map_err(|e| eyre!("JSON parse error: {e}"))?;
+    if container.format != ENCRYPTED_BACKUP_FORMAT {
+        return Err(eyre!("Unrecognized backup container: {}", container.format));
+    }
+    if container.version > ENCRYPTED_BACKUP_VERSION {
+        return Err(eyre!(
+            "Backup version {} is newer than supported version {}",
+            container.version,
+            ENCRYPTED_BACKUP_VERSION
+        ));
+    }
+
+    let secret =
+        secret.ok_or_else(|| eyre!("This backup is encrypted; a backup passphrase or vault key is required"))?;
+    if secret.source() != container.key_source {
+        return Err(eyre!(
+            "This backup is encrypted with a {}, not a {}",
+            container.key_source.as_str().replace('_', " "),
+            secret.source().as_str().replace('_', " ")
+        ));
+    }
+
+    let nonce = BASE64
+        .decode(&container.nonce)
+        .map_err(|e| eyre!("Invalid backup nonce: {e}"))?;
+    let ciphertext = BASE64
+        .decode(&container.ciphertext)
+        .map_err(|e| eyre!("Invalid backup ciphertext: {e}"))?;
+    let key = derive_key_from_secret(secret.as_bytes(), &container.kdf)?;
+    let ad = EncryptedChamberBackup::associated_data(&container.format, container.version, container.key_source);
+    let plaintext = aead_decrypt(&key, &nonce, &ciphertext, &ad)
+        .map_err(|_| eyre!("Failed to decrypt backup: wrong passphrase/key or the file was modified"))?;
+
+    serde_json::from_slice(&plaintext).map_err(|e| eyre!("JSON parse error: {e}"))
+}
+
+/// Returns how a Chamber backup file is protected, or `None` for a legacy plaintext backup.
+///
+/// # Errors
+///
+/// Returns an error if the file cannot be read or is not valid JSON.
+pub fn chamber_backup_key_source(input_path: &Path) -> Result<Option<BackupKeySource>> {
+    let content = read_input(input_path)?;
+    let value: serde_json::Value = serde_json::from_str(&content).map_err(|e| eyre!("JSON parse error: {e}"))?;
+    if value.get("ciphertext").is_none() {
+        return Ok(None);
+    }
+    let container: EncryptedChamberBackup =
+        serde_json::from_value(value).map_err(|e| eyre!("JSON parse error: {e}"))?;
+    Ok(Some(container.key_source))
+}
+
 /// Imports items from a specified file path and format.
 ///
 /// # Arguments
@@ -161,8 +421,10 @@ fn export_chamber_backup(items: &[Item], output_path: &Path) -> Result<()> {
 /// format:
 /// * `ExportFormat::Json` - Calls the `import_json` function to handle JSON files.
 /// * `ExportFormat::Csv` - Calls the `import_csv` function to handle CSV files.
-/// * `ExportFormat::ChamberBackup` - Calls the `import_chamber_backup` function to handle Chamber
-///   Backup files.
+/// * `ExportFormat::ChamberBackup` - Calls the `import_chamber_backup` function without a secret,
+///   which only reads legacy plaintext backups; encrypted backups need [`import_chamber_backup`].
+///
+/// Files ending in `.gz` are transparently decompressed.
 ///
 /// # Errors
 ///
@@ -174,27 +436,53 @@ pub fn import_items(input_path: &Path, format: &ExportFormat) -> Result<Vec<NewI
     match format {
         ExportFormat::Json => import_json(input_path),
         ExportFormat::Csv => import_csv(input_path),
-        ExportFormat::ChamberBackup => import_chamber_backup(input_path),
+        ExportFormat::ChamberBackup => import_chamber_backup(input_path, None),
+    }
+}
+
+fn read_input(input_path: &Path) -> Result<String> {
+    if input_path.extension().and_then(|ext| ext.to_str()) == Some("gz") {
+        let file = fs::File::open(input_path)?;
+        let mut content = String::new();
+        flate2::read::GzDecoder::new(file).read_to_string(&mut content)?;
+        Ok(content)
+    } else {
+        Ok(fs::read_to_string(input_path)?)
     }
 }
 
 fn import_json(input_path: &Path) -> Result<Vec<NewItem>> {
-    let content = fs::read_to_string(input_path)?;
+    let content = read_input(input_path)?;
     let exported_items: Vec<ExportedItem> =
         serde_json::from_str(&content).map_err(|e| eyre!("JSON parse error: {e}"))?;
 
     let mut items = Vec::new();
     for exported in exported_items {
-        items.push(NewItem {
+        items.push(recognize_totp(NewItem {
             name: exported.name,
             kind: ItemKind::from_str(&exported.kind)?,
             value: exported.value,
-        });
+            tags: exported.tags,
+            folder: exported.folder,
+            fields: exported.fields,
+        }));
     }
 
     Ok(items)
 }
 
+/// Turns items whose value is an `otpauth://` URI into TOTP items.
+///
+/// Exports from other tools often store 2FA seeds as plain notes or passwords; recognizing
+/// them lets `chamber otp` generate codes right after the import.
+fn recognize_totp(mut item: NewItem) -> NewItem {
+    if item.kind != ItemKind::Totp && item.fields.is_empty() && is_otpauth_uri(&item.value) {
+        item.kind = ItemKind::Totp;
+        item.value = item.value.trim().to_string();
+    }
+    item
+}
+
 // Rust
 fn record_complete(line: &str) -> bool {
     // Returns true if the line ends outside of quotes (i.e., unescaped quotes are balanced)
@@ -221,7 +509,7 @@ fn record_complete(line: &str) -> bool {
 }
 
 fn import_csv(input_path: &Path) -> Result<Vec<NewItem>> {
-    let content = fs::read_to_string(input_path)?;
+    let content = read_input(input_path)?;
     let mut lines = content.lines();
 
     // Handle header
@@ -232,6 +520,8 @@ fn import_csv(input_path: &Path) -> Result<Vec<NewItem>> {
     if header.trim().is_empty() {
         return Ok(Vec::new());
     }
+    // Tags, folder and fields are optional columns, located by name so older exports still import
+    let columns = CsvOptionalColumns::from_header(header);
 
     let mut items = Vec::new();
     let mut buf = String::new();
@@ -268,11 +558,7 @@ fn import_csv(input_path: &Path) -> Result<Vec<NewItem>> {
             ));
         }
 
-        items.push(NewItem {
-            name: fields[0].clone(),
-            kind: ItemKind::from_str(&fields[1])?,
-            value: fields[2].clone(),
-        });
+        items.push(columns.new_item(&fields)?);
 
         buf.clear();
     }
@@ -292,32 +578,115 @@ fn import_csv(input_path: &Path) -> Result<Vec<NewItem>> {
                 current_record_start_line
             ));
         }
-        items.push(NewItem {
-            name: fields[0].clone(),
-            kind: ItemKind::from_str(&fields[1])?,
-            value: fields[2].clone(),
-        });
+        items.push(columns.new_item(&fields)?);
     }
 
     Ok(items)
 }
 
-fn import_chamber_backup(input_path: &Path) -> Result<Vec<NewItem>> {
-    let content = fs::read_to_string(input_path)?;
-    let backup: ChamberBackup = serde_json::from_str(&content).map_err(|e| eyre!("JSON parse error: {e}"))?;
+/// Imports items from a Chamber backup file, decrypting it with `secret` when it is encrypted.
+///
+/// # Errors
+///
+/// Returns an error if the file cannot be read, is not a valid backup, cannot be decrypted with
+/// the given secret, or contains an unknown item kind.
+pub fn import_chamber_backup(input_path: &Path, secret: Option<&BackupSecret>) -> Result<Vec<NewItem>> {
+    Ok(import_chamber_backup_with_attachments(input_path, secret)?
+        .into_iter()
+        .map(|(item, _)| item)
+        .collect())
+}
+
+/// Imports items together with their attachments from a Chamber backup file.
+///
+/// # Errors
+///
+/// Returns an error under the same conditions as [`import_chamber_backup`], or if an
+/// attachment is not valid base64.
+pub fn import_chamber_backup_with_attachments(
+    input_path: &Path,
+    secret: Option<&BackupSecret>,
+) -> Result<Vec<(NewItem, Vec<AttachmentFile>)>> {
+    let content = read_input(input_path)?;
+    let backup = read_chamber_backup(&content, secret)?;
 
     let mut items = Vec::new();
     for exported in backup.items {
-        items.push(NewItem {
-            name: exported.name,
-            kind: ItemKind::from_str(&exported.kind)?,
-            value: exported.value,
-        });
+        let attachments = exported
+            .attachments
+            .iter()
+            .map(AttachmentFile::try_from)
+            .collect::<Result<Vec<_>>>()?;
+        items.push((
+            NewItem {
+                name: exported.name,
+                kind: ItemKind::from_str(&exported.kind)?,
+                value: exported.value,
+                tags: exported.tags,
+                folder: exported.folder,
+                fields: exported.fields,
+            },
+            attachments,
+        ));
     }
 
     Ok(items)
 }
 
+/// Separator used to join an item's tags into a single CSV field.
+const CSV_TAG_SEPARATOR: &str = ";";
+
+/// Positions of the optional `tags`, `folder`, `fields` and `totp` columns in a CSV header.
+///
+/// The `fields` column holds the item's fields as a JSON array. The `totp` column, as written
+/// by other password managers, holds an `otpauth://` URI or base32 secret for the item.
+struct CsvOptionalColumns {
+    tags: Option<usize>,
+    folder: Option<usize>,
+    fields: Option<usize>,
+    totp: Option<usize>,
+}
+
+impl CsvOptionalColumns {
+    fn from_header(header: &str) -> Self {
+        let names = parse_csv_line(header);
+        let position = |column: &str| names.iter().position(|n| n.eq_ignore_ascii_case(column));
+        Self {
+            tags: position("tags"),
+            folder: position("folder"),
+            fields: position("fields"),
+            totp: position("totp")
+                .or_else(|| position("otp"))
+                .or_else(|| position("otpauth")),
+        }
+    }
+
+    fn new_item(&self, fields: &[String]) -> Result<NewItem> {
+        let field = |idx: Option<usize>| idx.and_then(|i| fields.get(i)).map(String::as_str);
+        let mut item = recognize_totp(NewItem {
+            name: fields[0].clone(),
+            kind: ItemKind::from_str(&fields[1])?,
+            value: fields[2].clone(),
+            tags: field(self.tags)
+                .map(|t| normalize_tags(&t.split(CSV_TAG_SEPARATOR).collect::<Vec<_>>()))
+                .unwrap_or_default(),
+            folder: field(self.folder).and_then(normalize_folder),
+            fields: match field(self.fields).map(str::trim) {
+                Some(json) if !json.is_empty() => {
+                    serde_json::from_str(json).map_err(|e| eyre!("Invalid fields column: {e}"))?
+                }
+                _ => Vec::new(),
+            },
+        });
+        if let Some(totp) = field(self.totp).map(str::trim).filter(|t| !t.is_empty()) {
+            if item.kind != ItemKind::Totp && item.fields.iter().all(|f| !f.name.eq_ignore_ascii_case(TOTP_FIELD)) {
+                item.fields.push(ItemField::new(TOTP_FIELD, totp, true));
+            }
+        }
+        Ok(item)
+    }
+}
+
 // Helper functions for CSV handling
 fn escape_csv_field(field: &str) -> String {
     if field.contains(',') || field.contains('"') || field.contains('\n') {
@@ -370,6 +739,9 @@ pub fn detect_format_from_extension(path: &Path) -> Option<ExportFormat> {
     path.extension()
         .and_then(|ext| ext.to_str())
         .and_then(|ext| match ext.to_lowercase().as_str() {
+            // Compressed backups carry the real format in the inner extension
+            "gz" => detect_format_from_extension(&path.with_extension("")),
+            "backup" => Some(ExportFormat::ChamberBackup),
             "json" => {
                 // Try to detect if it's a chamber backup by checking the filename
                 if path
@@ -412,6 +784,30 @@ mod tests {
             value: value.to_string(),
             created_at: now,
             updated_at: now,
+            tags: Vec::new(),
+            folder: None,
+            fields: Vec::new(),
+        }
+    }
+
+    const TEST_PASSPHRASE: &str = "correct horse battery staple";
+
+    // Chamber backups need a secret; plaintext formats go through the generic entry points
+    fn export_with_secret(items: &[Item], format: &ExportFormat, path: &Path) -> Result<()> {
+        match format {
+            ExportFormat::ChamberBackup => {
+                export_chamber_backup(items, path, &BackupSecret::Passphrase(TEST_PASSPHRASE))
+            }
+            _ => export_items(items, format, path),
+        }
+    }
+
+    fn import_with_secret(path: &Path, format: &ExportFormat) -> Result<Vec<NewItem>> {
+        match format {
+            ExportFormat::ChamberBackup => {
+                import_chamber_backup(path, Some(&BackupSecret::Passphrase(TEST_PASSPHRASE)))
+            }
+            _ => import_items(path, format),
         }
     }
 
@@ -508,14 +904,119 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_tags_and_folder_round_trip() {
+        let mut items = sample_items();
+        items[0].tags = vec!["prod".to_string(), "db,primary".to_string()];
+        items[0].folder = Some("work/infra".to_string());
+        items[2].tags = vec!["personal".to_string()];
+
+        for format in [ExportFormat::Json, ExportFormat::Csv, ExportFormat::ChamberBackup] {
+            let path = unique_path("labels");
+            export_with_secret(&items, &format, &path).unwrap();
+            let imported = import_with_secret(&path, &format).unwrap();
+            fs::remove_file(&path).ok();
+
+            for (ni, item) in imported.iter().zip(&items) {
+                assert_eq!(ni.tags, item.tags, "{format:?}");
+                assert_eq!(ni.folder, item.folder, "{format:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_fields_round_trip() {
+        let mut items = sample_items();
+        items[0].fields = vec![
+            ItemField::new("host", "db.internal", false),
+            ItemField::new("note", "line one\nline \"two\", three", true),
+        ];
+
+        for format in [ExportFormat::Json, ExportFormat::Csv, ExportFormat::ChamberBackup] {
+            let path = unique_path("fields");
+            export_with_secret(&items, &format, &path).unwrap();
+            let imported = import_with_secret(&path, &format).unwrap();
+            fs::remove_file(&path).ok();
+
+            for (ni, item) in imported.iter().zip(&items) {
+                assert_eq!(ni.fields, item.fields, "{format:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_import_recognizes_otpauth_values() {
+        let uri = "otpauth://totp/GitHub:octocat?secret=JBSWY3DPEHPK3PXP&issuer=GitHub";
+        let path = unique_path("csv");
+        let content =
+            format!("name,kind,value,totp\ngithub 2fa,note,{uri},\ngithub,password,hunter2,JBSWY3DPEHPK3PXP\n");
+        fs::write(&path, content).unwrap();
+        let imported = import_items(&path, &ExportFormat::Csv).unwrap();
+        fs::remove_file(&path).ok();
+
+        assert_eq!(imported[0].kind, ItemKind::Totp);
+        assert_eq!(imported[0].value, uri);
+        assert_eq!(imported[1].kind, ItemKind::Password);
+        assert_eq!(
+            imported[1].fields,
+            vec![ItemField::new(TOTP_FIELD, "JBSWY3DPEHPK3PXP", true)]
+        );
+
+        let path = unique_path("json");
+        let content = format!(
+            r#"[{{"name":"seed","kind":"password","value":"{uri}","created_at":"2024-01-01T00:00:00Z","updated_at":"2024-01-01T00:00:00Z"}}]"#
+        );
+        fs::write(&path, content).unwrap();
+        let imported = import_items(&path, &ExportFormat::Json).unwrap();
+        fs::remove_file(&path).ok();
+        assert_eq!(imported[0].kind, ItemKind::Totp);
+    }
+
+    #[test]
+    fn test_csv_without_label_columns_imports_unlabeled() {
+        let path = unique_path("csv");
+        fs::write(
+            &path,
+            "name,kind,value
+test,password,secret",
+        )
+        .unwrap();
+
+        let imported = import_items(&path, &ExportFormat::Csv).unwrap();
+        fs::remove_file(&path).ok();
+
+        assert!(imported[0].tags.is_empty());
+        assert!(imported[0].folder.is_none());
+    }
+
+    #[test]
+    fn test_chamber_backup_carries_attachments() {
+        let items = sample_items();
+        let p12 = AttachmentFile {
+            file_name: "client.p12".to_string(),
+            data: vec![0x30, 0x82, 0x00, 0xff, 0xfe],
+        };
+        let attachments = HashMap::from([(items[1].id, vec![p12.clone()])]);
+        let path = unique_path("json");
+        let key = KeyMaterial::random();
+        export_chamber_backup_with_attachments(&items, &attachments, &path, &BackupSecret::VaultKey(&key)).unwrap();
+        let imported = import_chamber_backup_with_attachments(&path, Some(&BackupSecret::VaultKey(&key))).unwrap();
+        fs::remove_file(&path).ok();
+
+        assert_eq!(imported.len(), items.len());
+        assert!(imported[0].1.is_empty());
+        assert_eq!(imported[1].0.name, items[1].name);
+        assert_eq!(imported[1].1, vec![p12]);
+    }
+
     #[test]
     fn test_chamber_backup_round_trip() {
         let items = sample_items();
         let path = unique_path("json"); // backup uses JSON extension
 
-        export_items(&items, &ExportFormat::ChamberBackup, &path).unwrap();
+        export_with_secret(&items, &ExportFormat::ChamberBackup, &path).unwrap();
 
-        let imported = import_items(&path, &ExportFormat::ChamberBackup).unwrap();
+        let imported = import_with_secret(&path, &ExportFormat::ChamberBackup).unwrap();
         fs::remove_file(&path).ok();
 
         assert_eq!(imported.len(), items.len());
@@ -597,8 +1098,8 @@ mod tests {
 
         // Backup
         let path_bak = unique_path("json");
-        export_items(&items, &ExportFormat::ChamberBackup, &path_bak).unwrap();
-        let b = import_items(&path_bak, &ExportFormat::ChamberBackup).unwrap();
+        export_with_secret(&items, &ExportFormat::ChamberBackup, &path_bak).unwrap();
+        let b = import_with_secret(&path_bak, &ExportFormat::ChamberBackup).unwrap();
         assert_eq!(b.len(), items.len());
         fs::remove_file(&path_bak).ok();
     }
@@ -733,17 +1234,17 @@ mod tests {
     #[test]
     fn test_chamber_backup_with_zero_items() {
         let path = unique_path("json");
-        export_items(&[], &ExportFormat::ChamberBackup, &path).unwrap();
+        export_with_secret(&[], &ExportFormat::ChamberBackup, &path).unwrap();
 
         // Verify the backup structure
         let content = fs::read_to_string(&path).unwrap();
-        let backup: ChamberBackup = serde_json::from_str(&content).unwrap();
+        let backup = read_chamber_backup(&content, Some(&BackupSecret::Passphrase(TEST_PASSPHRASE))).unwrap();
 
         assert_eq!(backup.version, "1.0");
         assert_eq!(backup.item_count, 0);
         assert!(backup.items.is_empty());
 
-        let imported = import_items(&path, &ExportFormat::ChamberBackup).unwrap();
+        let imported = import_with_secret(&path, &ExportFormat::ChamberBackup).unwrap();
         fs::remove_file(&path).ok();
 
         assert!(imported.is_empty());
@@ -806,6 +1307,7 @@ mod tests {
             ItemKind::SshKey,
             ItemKind::Certificate,
             ItemKind::Database,
+            ItemKind::Totp,
         ];
 
         let items: Vec<Item> = all_kinds
@@ -828,8 +1330,8 @@ mod tests {
 
         // Test Chamber backup round trip
         let backup_path = unique_path("json");
-        export_items(&items, &ExportFormat::ChamberBackup, &backup_path).unwrap();
-        let backup_imported = import_items(&backup_path, &ExportFormat::ChamberBackup).unwrap();
+        export_with_secret(&items, &ExportFormat::ChamberBackup, &backup_path).unwrap();
+        let backup_imported = import_with_secret(&backup_path, &ExportFormat::ChamberBackup).unwrap();
         fs::remove_file(&backup_path).ok();
 
         // Verify all formats preserved all kinds
@@ -866,11 +1368,16 @@ mod tests {
         let items = sample_items();
         let path = unique_path("json");
 
-        export_items(&items, &ExportFormat::ChamberBackup, &path).unwrap();
+        export_with_secret(&items, &ExportFormat::ChamberBackup, &path).unwrap();
 
         // Read and verify the backup structure
         let content = fs::read_to_string(&path).unwrap();
-        let backup: ChamberBackup = serde_json::from_str(&content).unwrap();
+        let container: EncryptedChamberBackup = serde_json::from_str(&content).unwrap();
+        assert_eq!(container.format, ENCRYPTED_BACKUP_FORMAT);
+        assert_eq!(container.version, ENCRYPTED_BACKUP_VERSION);
+        assert_eq!(container.key_source, BackupKeySource::Passphrase);
+
+        let backup = read_chamber_backup(&content, Some(&BackupSecret::Passphrase(TEST_PASSPHRASE))).unwrap();
 
         assert_eq!(backup.version, "1.0");
         assert_eq!(backup.item_count, items.len());
@@ -941,9 +1448,9 @@ mod tests {
             (ExportFormat::ChamberBackup, "json"),
         ] {
             let path = unique_path(ext);
-            export_items(&items, &format, &path).unwrap();
+            export_with_secret(&items, &format, &path).unwrap();
 
-            let imported = import_items(&path, &format).unwrap();
+            let imported = import_with_secret(&path, &format).unwrap();
             fs::remove_file(&path).ok();
 
             assert_eq!(imported.len(), 2);
@@ -966,6 +1473,9 @@ mod tests {
             value: "value".to_string(),
             created_at: far_future,
             updated_at: far_future,
+            tags: Vec::new(),
+            folder: None,
+            fields: Vec::new(),
         };
 
         let exported = ExportedItem::from(&item);
@@ -1056,10 +1566,10 @@ simple,note,no_quotes
                 fs::create_dir_all(parent).unwrap();
             }
 
-            export_items(&items, &format, &nested_path).unwrap();
+            export_with_secret(&items, &format, &nested_path).unwrap();
             assert!(nested_path.exists());
 
-            let imported = import_items(&nested_path, &format).unwrap();
+            let imported = import_with_secret(&nested_path, &format).unwrap();
             assert_eq!(imported.len(), 1);
         }
 
@@ -1104,11 +1614,11 @@ simple,note,no_quotes
             });
 
             let start = std::time::Instant::now();
-            export_items(&items, &format, &path).unwrap();
+            export_with_secret(&items, &format, &path).unwrap();
             let export_duration = start.elapsed();
 
             let start = std::time::Instant::now();
-            let imported = import_items(&path, &format).unwrap();
+            let imported = import_with_secret(&path, &format).unwrap();
             let import_duration = start.elapsed();
 
             fs::remove_file(&path).ok();
@@ -1126,4 +1636,125 @@ simple,note,no_quotes
             );
         }
     }
+
+    #[test]
+    fn test_chamber_backup_is_encrypted_on_disk() {
+        let items = sample_items();
+        let path = unique_path("backup");
+
+        export_with_secret(&items, &ExportFormat::ChamberBackup, &path).unwrap();
+        let content = fs::read_to_string(&path).unwrap();
+        fs::remove_file(&path).ok();
+
+        for item in &items {
+            assert!(!content.contains(&item.value), "secret value leaked into backup");
+        }
+        assert!(!content.contains("alpha"), "item name leaked into backup");
+    }
+
+    #[test]
+    fn test_chamber_backup_wrong_passphrase_fails() {
+        let path = unique_path("backup");
+        export_with_secret(&sample_items(), &ExportFormat::ChamberBackup, &path).unwrap();
+
+        let err = import_chamber_backup(&path, Some(&BackupSecret::Passphrase("not the passphrase")))
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("Failed to decrypt backup"));
+
+        // The generic entry point has no secret and must refuse encrypted backups
+        let err = import_items(&path, &ExportFormat::ChamberBackup)
+            .unwrap_err()
+            .to_string();
+        fs::remove_file(&path).ok();
+        assert!(err.contains("encrypted"));
+    }
+
+    #[test]
+    fn test_chamber_backup_with_vault_key() {
+        let key = KeyMaterial([7u8; 32]);
+        let path = unique_path("backup");
+
+        export_chamber_backup(&sample_items(), &path, &BackupSecret::VaultKey(&key)).unwrap();
+        assert_eq!(
+            chamber_backup_key_source(&path).unwrap(),
+            Some(BackupKeySource::VaultKey)
+        );
+
+        let imported = import_chamber_backup(&path, Some(&BackupSecret::VaultKey(&key))).unwrap();
+        assert_eq!(imported.len(), sample_items().len());
+
+        let other = KeyMaterial([8u8; 32]);
+        assert!(import_chamber_backup(&path, Some(&BackupSecret::VaultKey(&other))).is_err());
+
+        let err = import_chamber_backup(&path, Some(&BackupSecret::Passphrase(TEST_PASSPHRASE)))
+            .unwrap_err()
+            .to_string();
+        fs::remove_file(&path).ok();
+        assert!(err.contains("vault key"));
+    }
+
+    #[test]
+    fn test_chamber_backup_tampered_header_fails() {
+        let path = unique_path("backup");
+        export_with_secret(&sample_items(), &ExportFormat::ChamberBackup, &path).unwrap();
+
+        let content = fs::read_to_string(&path).unwrap();
+        let tampered = content.replace(&format!("\"version\": {ENCRYPTED_BACKUP_VERSION}"), "\"version\": 1");
+        assert_ne!(content, tampered);
+        fs::remove_file(&path).ok();
+
+        assert!(read_chamber_backup(&tampered, Some(&BackupSecret::Passphrase(TEST_PASSPHRASE))).is_err());
+    }
+
+    #[test]
+    fn test_legacy_plaintext_chamber_backup_still_imports() {
+        let legacy = ChamberBackup {
+            version: "1.0".to_string(),
+            exported_at: "2024-01-01T00:00:00Z".to_string(),
+            item_count: 1,
+            items: vec![ExportedItem::from(&mk_item(1, "legacy", ItemKind::Note, "old"))],
+        };
+        let path = unique_path("json");
+        fs::write(&path, serde_json::to_string(&legacy).unwrap()).unwrap();
+
+        assert_eq!(chamber_backup_key_source(&path).unwrap(), None);
+        let imported = import_items(&path, &ExportFormat::ChamberBackup).unwrap();
+        fs::remove_file(&path).ok();
+
+        assert_eq!(imported.len(), 1);
+        assert_eq!(imported[0].name, "legacy");
+    }
+
+    #[test]
+    fn test_export_items_rejects_chamber_backup_without_secret() {
+        let path = unique_path("backup");
+        let err = export_items(&sample_items(), &ExportFormat::ChamberBackup, &path)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("encrypted"));
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn test_import_gzip_compressed_backup() {
+        let items = sample_items();
+        let plain = unique_path("backup");
+        export_with_secret(&items, &ExportFormat::ChamberBackup, &plain).unwrap();
+
+        let compressed = plain.with_extension("backup.gz");
+        let mut encoder =
+            flate2::write::GzEncoder::new(fs::File::create(&compressed).unwrap(), flate2::Compression::default());
+        encoder.write_all(&fs::read(&plain).unwrap()).unwrap();
+        encoder.finish().unwrap();
+        fs::remove_file(&plain).ok();
+
+        assert!(matches!(
+            detect_format_from_extension(&compressed),
+            Some(ExportFormat::ChamberBackup)
+        ));
+        let imported = import_with_secret(&compressed, &ExportFormat::ChamberBackup).unwrap();
+        fs::remove_file(&compressed).ok();
+        assert_eq!(imported.len(), items.len());
+    }
 }
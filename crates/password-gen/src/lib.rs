@@ -1,3 +1,7 @@
+mod ssh;
+
+pub use ssh::{GeneratedSshKey, SshKeyConfig, SshKeyType};
+
 use color_eyre::Result;
 use color_eyre::eyre::eyre;
 use rand::seq::{IndexedRandom, SliceRandom};
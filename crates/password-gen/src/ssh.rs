@@ -0,0 +1,214 @@
+use color_eyre::Result;
+use color_eyre::eyre::eyre;
+use ssh_key::private::{Ed25519Keypair, RsaKeypair};
+use ssh_key::rand_core::OsRng;
+use ssh_key::{HashAlg, LineEnding, PrivateKey};
+use std::fmt;
+use std::str::FromStr;
+
+/// Accepted RSA modulus sizes; OpenSSH refuses anything below 2048 bits.
+const RSA_BITS: std::ops::RangeInclusive<usize> = 2048..=16384;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SshKeyType {
+    #[default]
+    Ed25519,
+    Rsa,
+}
+
+impl SshKeyType {
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Ed25519 => "ed25519",
+            Self::Rsa => "rsa",
+        }
+    }
+}
+
+impl fmt::Display for SshKeyType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for SshKeyType {
+    type Err = color_eyre::Report;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "ed25519" => Ok(Self::Ed25519),
+            "rsa" => Ok(Self::Rsa),
+            other => Err(eyre!("Unsupported SSH key type '{other}' (expected ed25519 or rsa)")),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SshKeyConfig {
+    pub key_type: SshKeyType,
+    /// Modulus size for RSA keys; ignored for Ed25519.
+    pub bits: usize,
+    pub comment: String,
+    pub passphrase: Option<String>,
+}
+
+impl Default for SshKeyConfig {
+    fn default() -> Self {
+        Self {
+            key_type: SshKeyType::Ed25519,
+            bits: 4096,
+            comment: String::new(),
+            passphrase: None,
+        }
+    }
+}
+
+/// A freshly generated key pair in OpenSSH formats.
+pub struct GeneratedSshKey {
+    /// The private key as an `OPENSSH PRIVATE KEY` PEM block, encrypted when a passphrase was given.
+    pub private_key: String,
+    /// The public key as an `authorized_keys` line, including the comment.
+    pub public_key: String,
+    /// The SHA-256 fingerprint, as printed by `ssh-keygen -l`.
+    pub fingerprint: String,
+}
+
+impl SshKeyConfig {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[must_use]
+    pub const fn with_key_type(mut self, key_type: SshKeyType) -> Self {
+        self.key_type = key_type;
+        self
+    }
+
+    #[must_use]
+    pub const fn with_bits(mut self, bits: usize) -> Self {
+        self.bits = bits;
+        self
+    }
+
+    #[must_use]
+    pub fn with_comment(mut self, comment: impl Into<String>) -> Self {
+        self.comment = comment.into();
+        self
+    }
+
+    /// Sets the passphrase protecting the private key; an empty passphrase leaves it unencrypted.
+    #[must_use]
+    pub fn with_passphrase(mut self, passphrase: Option<String>) -> Self {
+        self.passphrase = passphrase.filter(|p| !p.is_empty());
+        self
+    }
+
+    /// Generates a new key pair from the operating system's random number generator.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the RSA key size is outside 2048..=16384 bits, or if key
+    /// generation, encryption or encoding fails.
+    pub fn generate(&self) -> Result<GeneratedSshKey> {
+        let mut key = match self.key_type {
+            SshKeyType::Ed25519 => PrivateKey::from(Ed25519Keypair::random(&mut OsRng)),
+            SshKeyType::Rsa => {
+                if !RSA_BITS.contains(&self.bits) {
+                    return Err(eyre!(
+                        "RSA keys must be between {} and {} bits",
+                        RSA_BITS.start(),
+                        RSA_BITS.end()
+                    ));
+                }
+                let keypair =
+                    RsaKeypair::random(&mut OsRng, self.bits).map_err(|e| eyre!("Failed to generate RSA key: {e}"))?;
+                PrivateKey::from(keypair)
+            }
+        };
+        key.set_comment(self.comment.as_str());
+
+        let public_key = key
+            .public_key()
+            .to_openssh()
+            .map_err(|e| eyre!("Failed to encode public key: {e}"))?;
+        let fingerprint = key.fingerprint(HashAlg::Sha256).to_string();
+
+        if let Some(passphrase) = &self.passphrase {
+            key = key
+                .encrypt(&mut OsRng, passphrase)
+                .map_err(|e| eyre!("Failed to encrypt private key: {e}"))?;
+        }
+        let private_key = key
+            .to_openssh(LineEnding::LF)
+            .map_err(|e| eyre!("Failed to encode private key: {e}"))?;
+
+        Ok(GeneratedSshKey {
+            private_key: private_key.to_string(),
+            public_key,
+            fingerprint,
+        })
+    }
+}
+
+impl GeneratedSshKey {
+    /// The algorithm name as it appears at the start of the public key, e.g. `ssh-ed25519`.
+    #[must_use]
+    pub fn algorithm(&self) -> &str {
+        self.public_key.split_whitespace().next().unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+    use super::*;
+    use ssh_key::Algorithm;
+
+    #[test]
+    fn test_generate_ed25519_key() {
+        let key = SshKeyConfig::new().with_comment("me@host").generate().unwrap();
+        assert_eq!(key.algorithm(), "ssh-ed25519");
+        assert!(key.public_key.ends_with(" me@host"));
+        assert!(key.fingerprint.starts_with("SHA256:"));
+
+        let private = PrivateKey::from_openssh(&key.private_key).unwrap();
+        assert!(!private.is_encrypted());
+        assert_eq!(private.algorithm(), Algorithm::Ed25519);
+        assert_eq!(private.public_key().to_openssh().unwrap(), key.public_key);
+    }
+
+    #[test]
+    fn test_generate_encrypted_rsa_key() {
+        let key = SshKeyConfig::new()
+            .with_key_type(SshKeyType::Rsa)
+            .with_bits(2048)
+            .with_passphrase(Some("hunter2".to_string()))
+            .generate()
+            .unwrap();
+        assert_eq!(key.algorithm(), "ssh-rsa");
+
+        let private = PrivateKey::from_openssh(&key.private_key).unwrap();
+        assert!(private.is_encrypted());
+        let decrypted = private.decrypt("hunter2").unwrap();
+        assert_eq!(
+            decrypted.public_key().key_data(),
+            ssh_key::PublicKey::from_openssh(&key.public_key).unwrap().key_data()
+        );
+    }
+
+    #[test]
+    fn test_rejects_weak_rsa_and_unknown_types() {
+        let config = SshKeyConfig::new().with_key_type(SshKeyType::Rsa).with_bits(1024);
+        assert!(config.generate().is_err());
+        assert!("dsa".parse::<SshKeyType>().is_err());
+        assert_eq!("ED25519".parse::<SshKeyType>().unwrap(), SshKeyType::Ed25519);
+        assert!(
+            SshKeyConfig::new()
+                .with_passphrase(Some(String::new()))
+                .passphrase
+                .is_none()
+        );
+    }
+}
@@ -1,9 +1,15 @@
 use crate::app;
 use crate::vault_selector::{VaultAction, VaultSelector, VaultSelectorMode};
 use async_trait::async_trait;
-use chamber_import_export::{ExportFormat, export_items, import_items};
-use chamber_password_gen::PasswordConfig;
-use chamber_vault::{AutoLockCallback, AutoLockConfig, AutoLockService, Item, ItemKind, NewItem, Vault, VaultManager};
+use chamber_import_export::{
+    BackupKeySource, BackupSecret, ExportFormat, chamber_backup_key_source, export_chamber_backup, export_items,
+    import_chamber_backup, import_items,
+};
+use chamber_password_gen::{GeneratedSshKey, PasswordConfig, SshKeyConfig, SshKeyType};
+use chamber_vault::{
+    AutoLockCallback, AutoLockConfig, AutoLockService, Item, ItemField, ItemHeader, ItemKind, ItemRevision, NewItem,
+    TrashedItem, Vault, VaultManager, validate_fields,
+};
 use color_eyre::Result;
 use color_eyre::eyre::eyre;
 use ratatui::prelude::Style;
@@ -22,12 +28,21 @@ pub enum Screen {
     GeneratePassword,
     ImportExport,
     VaultSelector,
+    Trash,
+}
+
+/// A permanent deletion in the trash view that waits for the user to confirm it.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum TrashPurge {
+    Item(u64),
+    All,
 }
 
 #[derive(Clone, Copy, PartialEq, Eq)]
 pub enum UnlockField {
     Master,
     Confirm,
+    Keyfile,
 }
 
 #[derive(Clone, Copy, PartialEq, Eq)]
@@ -42,6 +57,13 @@ pub enum AddItemField {
     Name,
     Kind,
     Value,
+    /// A typed field of the selected kind, indexing into [`ItemKind::field_specs`].
+    Field(usize),
+    /// Custom fields entered as `name=value` pairs separated by `;`.
+    CustomFields,
+    /// Tags separated by commas.
+    Tags,
+    Folder,
 }
 
 #[derive(Clone, Copy, PartialEq, Eq)]
@@ -112,6 +134,7 @@ pub struct ItemCounts {
     pub documents: usize,
     pub recovery_codes: usize,
     pub oauth_tokens: usize,
+    pub totp_codes: usize,
 }
 
 #[derive(Debug, Clone)]
@@ -145,19 +168,29 @@ pub struct App {
     pub master_confirm_input: String,
     pub master_mode_is_setup: bool,
     pub unlock_focus: UnlockField,
+    /// Path of the keyfile to unlock with, prefilled with the vault's registered keyfile.
+    pub keyfile_input: String,
     pub error: Option<String>,
 
-    pub items: Vec<Item>,
+    /// Metadata of every item; values are only decrypted when an item is viewed, edited or copied.
+    pub items: Vec<ItemHeader>,
     pub selected: usize,
     pub view_mode: ViewMode,
-    pub filtered_items: Vec<Item>,
+    pub filtered_items: Vec<ItemHeader>,
     pub search_query: String,
+    pub tag_filter: Option<String>,
     pub search_mode: bool,
 
     pub add_name: String,
     pub add_kind_idx: usize,
     pub add_value: String,
     pub add_value_scroll: usize,
+    /// Values of the typed fields of the selected kind, in schema order.
+    pub add_fields: Vec<String>,
+    pub add_custom_fields: String,
+    /// Tags of the new item, separated by commas.
+    pub add_tags: String,
+    pub add_folder: String,
     pub status_message: Option<String>,
     pub status_type: StatusType,
     pub scroll_offset: usize,
@@ -174,14 +207,25 @@ pub struct App {
     pub add_focus: AddItemField,
     pub view_item: Option<Item>,
     pub view_show_value: bool,
+    pub view_history: Vec<ItemRevision>,
+    pub view_show_history: bool,
+    pub view_history_selected: usize,
     pub edit_item: Option<Item>,
     pub edit_value: String,
 
+    // Trash view fields
+    pub trash_items: Vec<TrashedItem>,
+    pub trash_selected: usize,
+    pub trash_pending_purge: Option<TrashPurge>,
+
     // Password generation fields
     pub gen_focus: PasswordGenField,
     pub gen_length_str: String,
     pub gen_config: PasswordConfig,
     pub generated_password: Option<String>,
+    /// The kind of SSH key the generator makes, or `None` when it generates passwords.
+    pub gen_ssh_key_type: Option<SshKeyType>,
+    pub generated_ssh_key: Option<GeneratedSshKey>,
 
     // Import/Export fields
     pub ie_focus: ImportExportField,
@@ -208,6 +252,7 @@ impl App {
     /// - `master_confirm_input`: Stores user input for confirming the master password during setup.
     /// - `master_mode_is_setup`: Indicates whether the master mode is set up (false if initialization is incomplete).
     /// - `unlock_focus`: Tracks which unlock field is currently focused (e.g., Master field).
+    /// - `keyfile_input`: Path of the keyfile to unlock with, prefilled from the vault registry.
     /// - `error`: Holds any error message or state, defaulted to `None`.
     /// - `items`: A vector holding all items stored in the vault.
     /// - `selected`: Tracks the index of the currently selected item in the items list.
@@ -255,6 +300,7 @@ impl App {
     /// # Panics
     pub fn new() -> Result<Self> {
         let vault = Vault::open_default()?;
+        let keyfile_input = vault.keyfile().map(|k| k.display().to_string()).unwrap_or_default();
         let vault_manager = VaultManager::new()?;
         let vault_selector = VaultSelector::new();
 
@@ -279,17 +325,23 @@ impl App {
             master_confirm_input: String::new(),
             master_mode_is_setup,
             unlock_focus: UnlockField::Master,
+            keyfile_input,
             error: None,
             items: vec![],
             selected: 0,
             view_mode: ViewMode::All,
             filtered_items: vec![],
             search_query: String::new(),
+            tag_filter: None,
             search_mode: false,
             add_name: String::new(),
             add_kind_idx: 0,
             add_value: String::new(),
             add_value_scroll: 0,
+            add_fields: vec![String::new(); ItemKind::all()[0].field_specs().len()],
+            add_custom_fields: String::new(),
+            add_tags: String::new(),
+            add_folder: String::new(),
             status_message: None,
             status_type: StatusType::Info,
             scroll_offset: 0,
@@ -313,14 +365,23 @@ impl App {
             add_focus: AddItemField::Name,
             view_item: None,
             view_show_value: false,
+            view_history: Vec::new(),
+            view_show_history: false,
+            view_history_selected: 0,
             edit_item: None,
             edit_value: String::new(),
 
+            trash_items: Vec::new(),
+            trash_selected: 0,
+            trash_pending_purge: None,
+
             // Initialize password generation fields
             gen_focus: PasswordGenField::Length,
             gen_length_str: "16".to_string(),
             gen_config: PasswordConfig::default(),
             generated_password: None,
+            gen_ssh_key_type: None,
+            generated_ssh_key: None,
 
             // Initialize import/export fields
             ie_focus: ImportExportField::Path,
@@ -384,6 +445,15 @@ impl App {
     /// - Updates the `error` field in the struct to reflect any issues encountered during execution.
     /// - Modifies the state of `screen`, `master_mode_is_setup`, and `vault` upon successful execution.
     pub fn unlock(&mut self) -> Result<()> {
+        let keyfile = Some(self.keyfile_input.trim())
+            .filter(|k| !k.is_empty())
+            .map(PathBuf::from);
+        if keyfile.as_ref().is_some_and(|k| !k.is_file()) {
+            self.error = Some("Keyfile not found".to_string());
+            return Ok(());
+        }
+        self.vault.set_keyfile(keyfile.clone());
+
         if self.master_mode_is_setup {
             // Setup mode: create new master password (needs confirmation)
             if self.master_input != self.master_confirm_input {
@@ -395,6 +465,18 @@ impl App {
 
             // Initialize the vault
             self.vault.initialize(&self.master_input)?;
+
+            // Record the keyfile so other clients know the vault requires it
+            if keyfile.is_some() {
+                let vault_id = self
+                    .vault_manager
+                    .registry
+                    .find_by_path(self.vault.db_path())
+                    .map(|v| v.id.clone());
+                if let Some(vault_id) = vault_id {
+                    self.vault_manager.registry.set_keyfile(&vault_id, keyfile.clone())?;
+                }
+            }
         }
 
         // Always try to unlock (works for both setup and normal mode)
@@ -404,8 +486,13 @@ impl App {
             self.error = None;
             self.master_input.clear();
             self.master_confirm_input.clear();
+            self.warn_if_tampered();
         } else {
-            self.error = Some("Invalid master password".to_string());
+            self.error = Some(if keyfile.is_some() {
+                "Invalid master password or keyfile".to_string()
+            } else {
+                "Invalid master password".to_string()
+            });
             self.master_input.clear();
             if self.master_mode_is_setup {
                 self.master_confirm_input.clear();
@@ -418,7 +505,8 @@ impl App {
     /// Refreshes the list of items and updates the filtered items.
     ///
     /// This method performs the following actions:
-    /// 1. Updates the `items` field by retrieving the latest list of items from the `vault`.
+    /// 1. Updates the `items` field by retrieving the latest item headers from the `vault`, without
+    ///    decrypting any values.
     /// 2. Applies filtering logic to update the `filtered_items` list.
     /// 3. Ensures that the current selection (`selected`) is within the bounds of the updated `filtered_items` list.
     ///    If the current selection is out of bounds but the `filtered_items` list is not empty,
@@ -431,7 +519,7 @@ impl App {
     /// - `Ok(())` if the operation is successful.
     /// - `Err` with the specific error encountered when listing items from the `vault`.
     pub fn refresh_items(&mut self) -> Result<()> {
-        self.items = self.vault.list_items()?;
+        self.items = self.vault.list_item_headers()?;
         self.update_filtered_items();
         if self.selected >= self.filtered_items.len() && !self.filtered_items.is_empty() {
             self.selected = self.filtered_items.len().saturating_sub(1);
@@ -452,11 +540,21 @@ impl App {
             });
         }
 
+        // Apply tag filter
+        if let Some(tag) = &self.tag_filter {
+            filtered.retain(|item| item.has_tag(tag));
+        }
+
         // Apply search filter
         if !self.search_query.is_empty() {
             let query_lower = self.search_query.to_lowercase();
             filtered.retain(|item| {
-                item.name.to_lowercase().contains(&query_lower) || item.value.to_lowercase().contains(&query_lower)
+                item.name.to_lowercase().contains(&query_lower)
+                    || item.tags.iter().any(|t| t.to_lowercase().contains(&query_lower))
+                    || item
+                        .folder
+                        .as_ref()
+                        .is_some_and(|f| f.to_lowercase().contains(&query_lower))
             });
         }
 
@@ -477,10 +575,69 @@ impl App {
         }
     }
 
-    pub fn get_selected_item(&self) -> Option<&Item> {
+    /// Returns every tag in the vault with the number of items carrying it, sorted by name.
+    pub fn tag_counts(&self) -> Vec<(String, usize)> {
+        Self::label_counts(self.items.iter().flat_map(|i| i.tags.iter()))
+    }
+
+    /// Returns every folder in the vault with the number of items directly in it, sorted by path.
+    pub fn folder_counts(&self) -> Vec<(String, usize)> {
+        Self::label_counts(self.items.iter().filter_map(|i| i.folder.as_ref()))
+    }
+
+    fn label_counts<'a>(labels: impl Iterator<Item = &'a String>) -> Vec<(String, usize)> {
+        let mut counts: Vec<(String, usize)> = Vec::new();
+        for label in labels {
+            match counts.iter_mut().find(|(l, _)| l.eq_ignore_ascii_case(label)) {
+                Some((_, n)) => *n += 1,
+                None => counts.push((label.clone(), 1)),
+            }
+        }
+        counts.sort_by_key(|(l, _)| l.to_lowercase());
+        counts
+    }
+
+    /// Cycles the tag filter through all tags in the vault, then back to no filter.
+    pub fn cycle_tag_filter(&mut self) {
+        let tags = self.tag_counts();
+        let next = match &self.tag_filter {
+            None => tags.first().map(|(t, _)| t.clone()),
+            Some(current) => tags
+                .iter()
+                .position(|(t, _)| t.eq_ignore_ascii_case(current))
+                .and_then(|idx| tags.get(idx + 1))
+                .map(|(t, _)| t.clone()),
+        };
+        self.tag_filter = next;
+        self.selected = 0;
+        self.scroll_offset = 0;
+        self.update_filtered_items();
+        match &self.tag_filter {
+            Some(tag) => self.set_status(format!("Filtering by tag '{tag}'"), StatusType::Info),
+            None => self.set_status("Tag filter cleared".to_string(), StatusType::Info),
+        }
+    }
+
+    pub fn get_selected_item(&self) -> Option<&ItemHeader> {
         self.filtered_items.get(self.selected)
     }
 
+    /// Decrypts the selected item. Failures are reported in the status bar.
+    fn load_selected_item(&mut self) -> Option<Item> {
+        let id = self.get_selected_item()?.id;
+        match self.vault.get_item_by_id(id) {
+            Ok(Some(item)) => Some(item),
+            Ok(None) => {
+                self.set_status("Item no longer exists".to_string(), StatusType::Error);
+                None
+            }
+            Err(e) => {
+                self.set_status(format!("Failed to decrypt item: {e}"), StatusType::Error);
+                None
+            }
+        }
+    }
+
     pub fn get_item_counts(&self) -> ItemCounts {
         let passwords = self
             .items
@@ -545,6 +702,7 @@ impl App {
             .filter(|i| matches!(i.kind, ItemKind::Recovery))
             .count();
         let oauth_tokens = self.items.iter().filter(|i| matches!(i.kind, ItemKind::OAuth)).count();
+        let totp_codes = self.items.iter().filter(|i| matches!(i.kind, ItemKind::Totp)).count();
 
         ItemCounts {
             total: self.items.len(),
@@ -565,6 +723,7 @@ impl App {
             documents,
             recovery_codes,
             oauth_tokens,
+            totp_codes,
         }
     }
 
@@ -614,21 +773,36 @@ impl App {
     /// - Resets both single-line (`add_value`) and multi-line (`add_value_textarea`) value fields upon successful addition.
     /// - Automatically trims leading and trailing whitespace from the item name.
     pub fn add_item(&mut self) -> Result<()> {
-        let kind = ItemKind::all()[self.add_kind_idx.min(ItemKind::all().len() - 1)];
+        let kind = self.add_kind();
 
         // Get the value from the textarea instead of add_value
         let value = self.add_value_textarea.lines().join("\n");
 
+        let fields = match self.collect_add_fields(kind) {
+            Ok(fields) => fields,
+            Err(e) => {
+                self.set_status(format!("Invalid field: {e}"), StatusType::Error);
+                return Ok(());
+            }
+        };
+
         let new_item = NewItem {
             name: self.add_name.trim().to_string(),
             kind,
             value, // Use the textarea content
+            tags: self.add_tags.split(',').map(str::to_string).collect(),
+            folder: Some(self.add_folder.clone()).filter(|f| !f.trim().is_empty()),
+            fields,
         };
 
         match self.vault.create_item(&new_item) {
             Ok(()) => {
                 self.add_name.clear();
                 self.add_value.clear();
+                self.add_fields = vec![String::new(); kind.field_specs().len()];
+                self.add_custom_fields.clear();
+                self.add_tags.clear();
+                self.add_folder.clear();
                 // Reset the textarea as well
                 self.add_value_textarea = TextArea::default();
                 self.add_value_scroll = 0;
@@ -657,6 +831,65 @@ impl App {
         Ok(())
     }
 
+    /// Returns the kind selected in the add item form.
+    #[must_use]
+    pub fn add_kind(&self) -> ItemKind {
+        ItemKind::all()[self.add_kind_idx.min(ItemKind::all().len() - 1)]
+    }
+
+    /// Selects the kind at `idx` in the add item form and resets its typed field inputs.
+    pub fn set_add_kind_idx(&mut self, idx: usize) {
+        self.add_kind_idx = idx % ItemKind::all().len();
+        self.add_fields = vec![String::new(); self.add_kind().field_specs().len()];
+    }
+
+    /// Moves the focus of the add item form to the next input.
+    ///
+    /// The typed fields of the selected kind follow the value, then the custom fields, tags and
+    /// folder inputs.
+    pub fn next_add_focus(&mut self) {
+        self.add_focus = match self.add_focus {
+            AddItemField::Name => AddItemField::Kind,
+            AddItemField::Kind => AddItemField::Value,
+            AddItemField::Value if self.add_fields.is_empty() => AddItemField::CustomFields,
+            AddItemField::Value => AddItemField::Field(0),
+            AddItemField::Field(i) if i + 1 < self.add_fields.len() => AddItemField::Field(i + 1),
+            AddItemField::Field(_) => AddItemField::CustomFields,
+            AddItemField::CustomFields => AddItemField::Tags,
+            AddItemField::Tags => AddItemField::Folder,
+            AddItemField::Folder => AddItemField::Name,
+        };
+    }
+
+    /// Returns the single-line text input that has focus in the add item form, if any.
+    pub fn add_field_input_mut(&mut self) -> Option<&mut String> {
+        match self.add_focus {
+            AddItemField::Field(i) => self.add_fields.get_mut(i),
+            AddItemField::CustomFields => Some(&mut self.add_custom_fields),
+            AddItemField::Tags => Some(&mut self.add_tags),
+            AddItemField::Folder => Some(&mut self.add_folder),
+            AddItemField::Name | AddItemField::Kind | AddItemField::Value => None,
+        }
+    }
+
+    fn collect_add_fields(&self, kind: ItemKind) -> Result<Vec<ItemField>> {
+        let mut fields: Vec<ItemField> = kind
+            .field_specs()
+            .iter()
+            .zip(&self.add_fields)
+            .filter(|(_, value)| !value.trim().is_empty())
+            .map(|(spec, value)| ItemField::new(spec.name, value.trim(), spec.secret))
+            .collect();
+        for pair in self.add_custom_fields.split(';').filter(|p| !p.trim().is_empty()) {
+            let (name, value) = pair
+                .split_once('=')
+                .ok_or_else(|| eyre!("'{}' is not a name=value pair", pair.trim()))?;
+            fields.push(ItemField::new(name.trim(), value.trim(), false));
+        }
+        validate_fields(kind, &fields)?;
+        Ok(fields)
+    }
+
     /// Deletes the currently selected item from the vault.
     ///
     /// This function retrieves the currently selected item, deletes it from the vault
@@ -672,9 +905,94 @@ impl App {
     pub fn delete_selected(&mut self) -> Result<()> {
         if let Some(item) = self.get_selected_item() {
             let item_id = item.id;
+            let name = item.name.clone();
             self.vault.delete_item(item_id)?;
             self.refresh_items()?;
+            self.set_status(
+                format!("Moved '{name}' to the trash; press b to restore it"),
+                StatusType::Info,
+            );
+        }
+        Ok(())
+    }
+
+    /// Opens the trash view with the vault's deleted items.
+    ///
+    /// # Errors
+    /// Returns an error if the trash cannot be listed.
+    pub fn open_trash(&mut self) -> Result<()> {
+        self.trash_items = self.vault.list_trash()?;
+        self.trash_selected = 0;
+        self.trash_pending_purge = None;
+        self.screen = Screen::Trash;
+        Ok(())
+    }
+
+    pub const fn select_prev_trashed(&mut self) {
+        self.trash_selected = self.trash_selected.saturating_sub(1);
+    }
+
+    pub fn select_next_trashed(&mut self) {
+        if self.trash_selected + 1 < self.trash_items.len() {
+            self.trash_selected += 1;
         }
+    }
+
+    /// Takes the item selected in the trash view out of the trash.
+    ///
+    /// # Errors
+    /// Returns an error if the item cannot be restored, e.g. because its name was taken again,
+    /// or the lists cannot be refreshed.
+    pub fn restore_selected_trashed(&mut self) -> Result<()> {
+        let Some(item) = self.trash_items.get(self.trash_selected) else {
+            return Ok(());
+        };
+        let name = item.name.clone();
+        self.vault.restore_item(item.id)?;
+        self.refresh_trash()?;
+        self.refresh_items()?;
+        self.set_status(format!("Restored '{name}'"), StatusType::Success);
+        Ok(())
+    }
+
+    /// Asks for confirmation before `purge` permanently deletes items from the trash.
+    pub fn request_trash_purge(&mut self, purge: TrashPurge) {
+        let message = match purge {
+            TrashPurge::Item(id) => match self.trash_items.iter().find(|t| t.id == id) {
+                Some(item) => format!("Permanently delete '{}'? Press y to confirm", item.name),
+                None => return,
+            },
+            TrashPurge::All if self.trash_items.is_empty() => return,
+            TrashPurge::All => format!(
+                "Permanently delete all {} item(s) in the trash? Press y to confirm",
+                self.trash_items.len()
+            ),
+        };
+        self.trash_pending_purge = Some(purge);
+        self.set_status(message, StatusType::Warning);
+    }
+
+    /// Carries out the permanent deletion the user was asked to confirm, if any.
+    ///
+    /// # Errors
+    /// Returns an error if the deletion fails or the trash cannot be listed again.
+    pub fn confirm_trash_purge(&mut self) -> Result<()> {
+        let message = match self.trash_pending_purge.take() {
+            Some(TrashPurge::Item(id)) => {
+                self.vault.purge_item(id)?;
+                "Item permanently deleted".to_string()
+            }
+            Some(TrashPurge::All) => format!("Permanently deleted {} item(s)", self.vault.empty_trash()?),
+            None => return Ok(()),
+        };
+        self.refresh_trash()?;
+        self.set_status(message, StatusType::Success);
+        Ok(())
+    }
+
+    fn refresh_trash(&mut self) -> Result<()> {
+        self.trash_items = self.vault.list_trash()?;
+        self.trash_selected = self.trash_selected.min(self.trash_items.len().saturating_sub(1));
         Ok(())
     }
 
@@ -732,7 +1050,8 @@ impl App {
     ///
     /// This function retrieves the currently selected item using the `get_selected_item` method.
     /// If an item is selected, it initializes the system clipboard, attempts to copy the selected
-    /// item's value to the clipboard, and updates the status message to indicate success.
+    /// item's value to the clipboard, and updates the status message to indicate success. Only the
+    /// selected item is decrypted.
     ///
     /// # Returns
     ///
@@ -763,7 +1082,10 @@ impl App {
     /// - `set_status`: Updates the application's status message and type.
     ///
     pub fn copy_selected(&mut self) -> Result<()> {
-        if let Some(item) = self.get_selected_item() {
+        let Some(id) = self.get_selected_item().map(|item| item.id) else {
+            return Ok(());
+        };
+        if let Some(item) = self.vault.get_item_by_id(id)? {
             let mut clipboard = arboard::Clipboard::new().map_err(|e| eyre!("Failed to access clipboard: {}", e))?;
             clipboard
                 .set_text(&item.value)
@@ -774,9 +1096,12 @@ impl App {
     }
 
     pub fn view_selected(&mut self) {
-        if let Some(item) = self.get_selected_item() {
-            self.view_item = Some(item.clone());
+        if let Some(item) = self.load_selected_item() {
+            self.view_history = self.vault.item_history(item.id).unwrap_or_default();
+            self.view_history_selected = self.view_history.len().saturating_sub(1);
+            self.view_item = Some(item);
             self.view_show_value = false;
+            self.view_show_history = false;
             self.screen = Screen::ViewItem;
         }
     }
@@ -785,11 +1110,51 @@ impl App {
         self.view_show_value = !self.view_show_value;
     }
 
+    pub const fn toggle_history(&mut self) {
+        self.view_show_history = !self.view_show_history;
+    }
+
+    /// Selects the next older revision.
+    pub const fn select_prev_revision(&mut self) {
+        self.view_history_selected = self.view_history_selected.saturating_sub(1);
+    }
+
+    /// Selects the next newer revision.
+    pub fn select_next_revision(&mut self) {
+        if self.view_history_selected + 1 < self.view_history.len() {
+            self.view_history_selected += 1;
+        }
+    }
+
+    /// Rolls the viewed item back to the revision selected in the history pane.
+    ///
+    /// The replaced value is archived by the vault, so the refreshed history gains a revision and
+    /// the rollback can itself be undone from the same pane.
+    ///
+    /// # Errors
+    /// Returns an error if the vault update or the subsequent refresh fails.
+    pub fn restore_selected_revision(&mut self) -> Result<()> {
+        let Some(item) = &self.view_item else {
+            return Ok(());
+        };
+        let Some(revision) = self.view_history.get(self.view_history_selected).map(|r| r.revision) else {
+            return Ok(());
+        };
+
+        let id = item.id;
+        self.vault.restore_revision(id, revision)?;
+        self.refresh_items()?;
+        self.view_item = self.vault.get_item_by_id(id)?;
+        self.view_history = self.vault.item_history(id)?;
+        self.view_history_selected = self.view_history.len().saturating_sub(1);
+        self.set_status(format!("Restored revision {revision}"), StatusType::Success);
+        Ok(())
+    }
+
     pub fn edit_selected(&mut self) {
-        let selected_item = self.get_selected_item().cloned();
-        if let Some(item) = selected_item {
-            self.edit_item = Some(item.clone());
+        if let Some(item) = self.load_selected_item() {
             self.edit_value.clone_from(&item.value);
+            self.edit_item = Some(item);
             self.screen = Screen::EditItem;
         }
     }
@@ -839,11 +1204,37 @@ impl App {
         self.gen_focus = PasswordGenField::Length;
         self.gen_length_str = self.gen_config.length.to_string();
         self.generated_password = None;
+        self.generated_ssh_key = None;
         self.error = None;
         self.screen = Screen::GeneratePassword;
     }
 
+    /// Switches the generator between passwords, Ed25519 keys and RSA keys.
+    pub fn cycle_generator_kind(&mut self) {
+        self.gen_ssh_key_type = match self.gen_ssh_key_type {
+            None => Some(SshKeyType::Ed25519),
+            Some(SshKeyType::Ed25519) => Some(SshKeyType::Rsa),
+            Some(SshKeyType::Rsa) => None,
+        };
+        self.generated_password = None;
+        self.generated_ssh_key = None;
+        self.error = None;
+    }
+
     pub fn generate_password(&mut self) {
+        if let Some(key_type) = self.gen_ssh_key_type {
+            match SshKeyConfig::new().with_key_type(key_type).generate() {
+                Ok(key) => {
+                    self.generated_ssh_key = Some(key);
+                    self.error = None;
+                }
+                Err(e) => {
+                    self.error = Some(format!("Generation failed: {e}"));
+                }
+            }
+            return;
+        }
+
         if let Ok(length) = self.gen_length_str.parse::<usize>() {
             self.gen_config.length = length.clamp(4, 128);
         } else {
@@ -888,7 +1279,13 @@ impl App {
     ///
     /// This function makes use of the `arboard` crate for clipboard access and text manipulation.
     pub fn copy_generated_password(&mut self) -> Result<()> {
-        if let Some(password) = &self.generated_password {
+        if let Some(key) = &self.generated_ssh_key {
+            let mut clipboard = arboard::Clipboard::new().map_err(|e| eyre!("Failed to access clipboard: {}", e))?;
+            clipboard
+                .set_text(&key.public_key)
+                .map_err(|e| eyre!("Failed to copy to clipboard: {}", e))?;
+            self.error = Some("Public key copied to clipboard".into());
+        } else if let Some(password) = &self.generated_password {
             let mut clipboard = arboard::Clipboard::new().map_err(|e| eyre!("Failed to access clipboard: {}", e))?;
             clipboard
                 .set_text(password)
@@ -898,8 +1295,27 @@ impl App {
         Ok(())
     }
 
+    /// Opens the add item form with the generated secret: a password becomes the value, an SSH
+    /// key pair becomes an `SshKey` item with the private key as the value and the public key field.
     pub fn use_generated_password(&mut self) {
-        if let Some(password) = &self.generated_password {
+        if let Some(key) = self.generated_ssh_key.take() {
+            let kind_idx = ItemKind::all().iter().position(|k| *k == ItemKind::SshKey).unwrap_or(0);
+            self.set_add_kind_idx(kind_idx);
+            if let Some(i) = self
+                .add_kind()
+                .field_specs()
+                .iter()
+                .position(|f| f.name == "public_key")
+            {
+                self.add_fields[i] = key.public_key;
+            }
+            self.add_value_textarea.select_all();
+            self.add_value_textarea.cut();
+            self.add_value_textarea.insert_str(&key.private_key);
+            self.add_value = key.private_key;
+            self.screen = Screen::AddItem;
+            self.add_focus = AddItemField::Name;
+        } else if let Some(password) = &self.generated_password {
             self.add_value = password.clone();
             self.screen = Screen::AddItem;
             self.add_focus = AddItemField::Name;
@@ -982,8 +1398,15 @@ impl App {
                     }
                 }
 
-                export_items(&self.items, &format, &path)?;
-                self.error = Some(format!("Exported {} items to {}", self.items.len(), path.display()));
+                let items = self.vault.list_items()?;
+                if matches!(format, ExportFormat::ChamberBackup) {
+                    // Backups exported from the TUI are sealed with the vault's backup key
+                    let key = self.vault.backup_key()?;
+                    export_chamber_backup(&items, &path, &BackupSecret::VaultKey(&key))?;
+                } else {
+                    export_items(&items, &format, &path)?;
+                }
+                self.error = Some(format!("Exported {} items to {}", items.len(), path.display()));
             }
             ImportExportMode::Import => {
                 if !path.exists() {
@@ -991,7 +1414,22 @@ impl App {
                     return Ok(());
                 }
 
-                let new_items = import_items(&path, &format)?;
+                let new_items = if matches!(format, ExportFormat::ChamberBackup) {
+                    match chamber_backup_key_source(&path)? {
+                        None => import_chamber_backup(&path, None)?,
+                        Some(BackupKeySource::VaultKey) => {
+                            let key = self.vault.backup_key()?;
+                            import_chamber_backup(&path, Some(&BackupSecret::VaultKey(&key)))?
+                        }
+                        Some(BackupKeySource::Passphrase) => {
+                            self.error =
+                                Some("Passphrase-protected backups must be imported with `chamber import`".into());
+                            return Ok(());
+                        }
+                    }
+                } else {
+                    import_items(&path, &format)?
+                };
                 if new_items.is_empty() {
                     self.error = Some("No items found in file".into());
                     return Ok(());
@@ -1216,6 +1654,18 @@ impl App {
         Ok(())
     }
 
+    /// Shows the tamper warning found when the vault was unlocked, if there is one.
+    fn warn_if_tampered(&mut self) {
+        if let Some(warning) = self.vault.tamper_warning() {
+            self.set_status(
+                format!(
+                    "Vault tamper warning: {warning}. Changes are refused until 'chamber doctor --accept-manifest'"
+                ),
+                StatusType::Warning,
+            );
+        }
+    }
+
     fn switch_to_vault(&mut self, vault_id: &str) -> Result<String> {
         // First, switch the active vault in the registry
         self.vault_manager.switch_active_vault(vault_id)?;
@@ -1237,6 +1687,7 @@ impl App {
                 self.vault = new_vault;
                 self.refresh_items()?;
                 self.set_status(format!("Switched to vault: {vault_id}"), StatusType::Success);
+                self.warn_if_tampered();
             }
             Err(_) => {
                 // This vault has a different master password
@@ -1,7 +1,8 @@
 use crate::app::{
     AddItemField, App, ChangeKeyField, ImportExportField, ImportExportMode, ItemCounts, PasswordGenField, Screen,
-    StatusType, UnlockField, ViewMode,
+    StatusType, TrashPurge, UnlockField, ViewMode,
 };
+use chamber_password_gen::SshKeyType;
 use chamber_vault::ItemKind;
 use color_eyre::Result;
 use ratatui::crossterm::event;
@@ -113,6 +114,10 @@ const fn c_badge_oauth() -> Color {
     Color::Rgb(30, 144, 255) // Dodger blue for OAuth
 }
 
+const fn c_badge_totp() -> Color {
+    Color::Rgb(0, 206, 209) // Dark turquoise for one-time codes
+}
+
 fn truncate_text(text: &str, max_width: usize) -> String {
     if text.chars().count() <= max_width {
         text.to_string()
@@ -237,6 +242,7 @@ fn handle_key(app: &mut App, key: KeyEvent) -> Result<bool> {
                                             app.master_confirm_input.push_str(&text);
                                         }
                                     }
+                                    UnlockField::Keyfile => app.keyfile_input.push_str(text.trim()),
                                 }
                             }
                         }
@@ -260,6 +266,18 @@ fn handle_key(app: &mut App, key: KeyEvent) -> Result<bool> {
                                     }
                                 }
                             }
+                            AddItemField::Field(_)
+                            | AddItemField::CustomFields
+                            | AddItemField::Tags
+                            | AddItemField::Folder => {
+                                if let Ok(mut clipboard) = arboard::Clipboard::new() {
+                                    if let Ok(text) = clipboard.get_text() {
+                                        if let Some(input) = app.add_field_input_mut() {
+                                            input.push_str(text.trim_end());
+                                        }
+                                    }
+                                }
+                            }
                             AddItemField::Kind => {}
                         }
                         return Ok(false); // Prevent further processing
@@ -342,12 +360,11 @@ fn handle_key(app: &mut App, key: KeyEvent) -> Result<bool> {
                 app.unlock()?;
             }
             KeyCode::Tab => {
-                if app.master_mode_is_setup {
-                    app.unlock_focus = match app.unlock_focus {
-                        UnlockField::Master => UnlockField::Confirm,
-                        UnlockField::Confirm => UnlockField::Master,
-                    };
-                }
+                app.unlock_focus = match app.unlock_focus {
+                    UnlockField::Master if app.master_mode_is_setup => UnlockField::Confirm,
+                    UnlockField::Master | UnlockField::Confirm => UnlockField::Keyfile,
+                    UnlockField::Keyfile => UnlockField::Master,
+                };
             }
             KeyCode::Backspace => match app.unlock_focus {
                 UnlockField::Master => {
@@ -358,6 +375,9 @@ fn handle_key(app: &mut App, key: KeyEvent) -> Result<bool> {
                         app.master_confirm_input.pop();
                     }
                 }
+                UnlockField::Keyfile => {
+                    app.keyfile_input.pop();
+                }
             },
             KeyCode::Char(c) => {
                 // Only process regular characters (Ctrl combinations handled above)
@@ -368,6 +388,7 @@ fn handle_key(app: &mut App, key: KeyEvent) -> Result<bool> {
                             app.master_confirm_input.push(c);
                         }
                     }
+                    UnlockField::Keyfile => app.keyfile_input.push(c),
                 }
             }
             _ => {}
@@ -378,11 +399,9 @@ fn handle_key(app: &mut App, key: KeyEvent) -> Result<bool> {
             KeyCode::Char('a') => {
                 app.screen = Screen::AddItem;
             }
-            KeyCode::Char('c') => {
-                // Only handle 'c' for copy if Ctrl is NOT pressed (Ctrl+C handled above)
-                if !key.modifiers.contains(KeyModifiers::CONTROL) {
-                    app.copy_selected()?;
-                }
+            // Only handle 'c' for copy if Ctrl is NOT pressed (Ctrl+C handled above)
+            KeyCode::Char('c') if !key.modifiers.contains(KeyModifiers::CONTROL) => {
+                app.copy_selected()?;
             }
             KeyCode::Char('v') => {
                 // Only handle 'v' for view if Ctrl is NOT pressed (Ctrl+V handled above)
@@ -411,6 +430,9 @@ fn handle_key(app: &mut App, key: KeyEvent) -> Result<bool> {
             KeyCode::Char('d') => {
                 app.delete_selected()?;
             }
+            KeyCode::Char('b') => {
+                app.open_trash()?;
+            }
             KeyCode::Down => {
                 if app.filtered_items.is_empty() {
                     return Ok(false);
@@ -446,17 +468,18 @@ fn handle_key(app: &mut App, key: KeyEvent) -> Result<bool> {
             KeyCode::Char('r') => {
                 app.refresh_items()?;
             }
+            KeyCode::Char('t') => {
+                app.cycle_tag_filter();
+            }
             KeyCode::F(2) => {
                 app.open_vault_selector();
             }
             KeyCode::Char('/' | 's') => {
                 app.search_mode = true;
             }
-            KeyCode::Esc => {
-                if !app.search_query.is_empty() {
-                    app.search_query.clear();
-                    app.update_filtered_items();
-                }
+            KeyCode::Esc if !app.search_query.is_empty() => {
+                app.search_query.clear();
+                app.update_filtered_items();
             }
             _ => {}
         },
@@ -467,22 +490,14 @@ fn handle_key(app: &mut App, key: KeyEvent) -> Result<bool> {
             }
             KeyCode::Tab => {
                 app.add_value = app.add_value_textarea.lines().join("\n");
-                app.add_focus = match app.add_focus {
-                    AddItemField::Name => AddItemField::Kind,
-                    AddItemField::Kind => AddItemField::Value,
-                    AddItemField::Value => AddItemField::Name,
-                };
+                app.next_add_focus();
             }
             KeyCode::Left | KeyCode::Right if matches!(app.add_focus, AddItemField::Kind) => {
-                let total_kinds = 17;
+                let total_kinds = ItemKind::all().len();
                 if key.code == KeyCode::Right {
-                    app.add_kind_idx = (app.add_kind_idx + 1) % total_kinds;
+                    app.set_add_kind_idx(app.add_kind_idx + 1);
                 } else {
-                    app.add_kind_idx = if app.add_kind_idx == 0 {
-                        total_kinds - 1
-                    } else {
-                        app.add_kind_idx - 1
-                    };
+                    app.set_add_kind_idx(app.add_kind_idx + total_kinds - 1);
                 }
             }
             KeyCode::Char(c) => {
@@ -492,6 +507,11 @@ fn handle_key(app: &mut App, key: KeyEvent) -> Result<bool> {
                     AddItemField::Value => {
                         app.add_value_textarea.input(key);
                     }
+                    AddItemField::Field(_) | AddItemField::CustomFields | AddItemField::Tags | AddItemField::Folder => {
+                        if let Some(input) = app.add_field_input_mut() {
+                            input.push(c);
+                        }
+                    }
                     AddItemField::Kind => {}
                 }
             }
@@ -521,6 +541,20 @@ fn handle_key(app: &mut App, key: KeyEvent) -> Result<bool> {
                             return app.add_item().map(|()| false);
                         }
                     }
+                    AddItemField::Field(_) | AddItemField::CustomFields | AddItemField::Tags | AddItemField::Folder => {
+                        match key.code {
+                            KeyCode::Enter => {
+                                app.add_value = app.add_value_textarea.lines().join("\n");
+                                return app.add_item().map(|()| false);
+                            }
+                            KeyCode::Backspace => {
+                                if let Some(input) = app.add_field_input_mut() {
+                                    input.pop();
+                                }
+                            }
+                            _ => {}
+                        }
+                    }
                     AddItemField::Value => {
                         // Let textarea handle ALL input for Value field
                         match key.code {
@@ -546,6 +580,21 @@ fn handle_key(app: &mut App, key: KeyEvent) -> Result<bool> {
             KeyCode::Char('t') | KeyCode::Enter => {
                 app.toggle_value_visibility();
             }
+            KeyCode::Char('h') => {
+                app.toggle_history();
+            }
+            // The history pane lists the newest revision first, so Up moves to a newer one
+            KeyCode::Up if app.view_show_history => {
+                app.select_next_revision();
+            }
+            KeyCode::Down if app.view_show_history => {
+                app.select_prev_revision();
+            }
+            KeyCode::Char('r') if app.view_show_history => {
+                if let Err(e) = app.restore_selected_revision() {
+                    app.set_status(format!("Rollback failed: {e}"), StatusType::Error);
+                }
+            }
             KeyCode::Char('c') => {
                 if let Some(item) = &app.view_item {
                     if let Ok(mut clipboard) = arboard::Clipboard::new() {
@@ -554,6 +603,23 @@ fn handle_key(app: &mut App, key: KeyEvent) -> Result<bool> {
                     }
                 }
             }
+            KeyCode::Char('o') => {
+                if let Some(item) = &app.view_item {
+                    match item.totp().and_then(|totp| totp.map(|t| t.now()).transpose()) {
+                        Ok(Some(code)) => {
+                            if let Ok(mut clipboard) = arboard::Clipboard::new() {
+                                let _ = clipboard.set_text(&code.code);
+                                app.set_status(
+                                    format!("Copied one-time code for '{}' to clipboard", item.name),
+                                    StatusType::Success,
+                                );
+                            }
+                        }
+                        Ok(None) => app.set_status("This item has no TOTP secret".to_string(), StatusType::Warning),
+                        Err(e) => app.set_status(format!("Failed to generate code: {e}"), StatusType::Error),
+                    }
+                }
+            }
             _ => {}
         },
 
@@ -632,10 +698,11 @@ fn handle_key(app: &mut App, key: KeyEvent) -> Result<bool> {
             KeyCode::Char('u') => {
                 app.use_generated_password();
             }
-            KeyCode::Char(c) if matches!(app.gen_focus, PasswordGenField::Length) => {
-                if c.is_ascii_digit() {
-                    app.gen_length_str.push(c);
-                }
+            KeyCode::Char('k') => {
+                app.cycle_generator_kind();
+            }
+            KeyCode::Char(c) if matches!(app.gen_focus, PasswordGenField::Length) && c.is_ascii_digit() => {
+                app.gen_length_str.push(c);
             }
             KeyCode::Backspace if matches!(app.gen_focus, PasswordGenField::Length) => {
                 app.gen_length_str.pop();
@@ -700,6 +767,36 @@ fn handle_key(app: &mut App, key: KeyEvent) -> Result<bool> {
             }
             return Ok(false);
         }
+
+        Screen::Trash if app.trash_pending_purge.is_some() => {
+            if key.code == KeyCode::Char('y') {
+                if let Err(e) = app.confirm_trash_purge() {
+                    app.set_status(format!("Permanent delete failed: {e}"), StatusType::Error);
+                }
+            } else {
+                app.trash_pending_purge = None;
+                app.set_status("Permanent delete cancelled".to_string(), StatusType::Info);
+            }
+        }
+        Screen::Trash => match key.code {
+            KeyCode::Esc | KeyCode::Char('b') => {
+                app.screen = Screen::Main;
+            }
+            KeyCode::Up => app.select_prev_trashed(),
+            KeyCode::Down => app.select_next_trashed(),
+            KeyCode::Enter | KeyCode::Char('r') => {
+                if let Err(e) = app.restore_selected_trashed() {
+                    app.set_status(format!("Restore failed: {e}"), StatusType::Error);
+                }
+            }
+            KeyCode::Char('p') => {
+                if let Some(item) = app.trash_items.get(app.trash_selected) {
+                    app.request_trash_purge(TrashPurge::Item(item.id));
+                }
+            }
+            KeyCode::Char('E') => app.request_trash_purge(TrashPurge::All),
+            _ => {}
+        },
     }
 
     Ok(false)
@@ -750,6 +847,10 @@ fn draw(f: &mut Frame, app: &mut App) {
             draw_import_export(f, app);
         }
         Screen::VaultSelector => draw_vault_selector(f, app, root[1]),
+        Screen::Trash => {
+            draw_main(f, app, root[1]);
+            draw_trash(f, app);
+        }
     }
 }
 
@@ -793,7 +894,7 @@ fn draw_header(f: &mut Frame, area: Rect) {
 }
 
 fn draw_unlock(f: &mut Frame, app: &App, body: Rect) {
-    let area = centered_rect(60, 40, body);
+    let area = centered_rect(60, 50, body);
     let title = if app.master_mode_is_setup {
         " Create Master Key "
     } else {
@@ -838,6 +939,19 @@ fn draw_unlock(f: &mut Frame, app: &App, body: Rect) {
             Style::default().fg(c_text_dim()),
         )));
         lines.push(Line::from(cf_value));
+    }
+
+    lines.push(Line::default());
+    lines.push(Line::from(Span::styled(
+        "Keyfile (optional)",
+        Style::default().fg(c_text_dim()),
+    )));
+    lines.push(Line::from(field_box(
+        &app.keyfile_input,
+        matches!(app.unlock_focus, UnlockField::Keyfile),
+    )));
+
+    if app.master_mode_is_setup {
         lines.push(Line::default());
         lines.push(Line::from(vec![
             Span::styled("[Tab]", highlight),
@@ -850,6 +964,8 @@ fn draw_unlock(f: &mut Frame, app: &App, body: Rect) {
     } else {
         lines.push(Line::default());
         lines.push(Line::from(vec![
+            Span::styled("[Tab]", highlight),
+            Span::styled(" switch  ", dim),
             Span::styled("[Enter]", highlight),
             Span::styled(" unlock  ", dim),
             Span::styled("[Esc]", highlight),
@@ -981,6 +1097,7 @@ fn draw_items_section(f: &mut Frame, app: &App, area: Rect) {
                 ItemKind::Document => (" DOCUMENTS", "📋", c_badge_document()),
                 ItemKind::Recovery => (" RECOVERY", "🔄", c_badge_recovery()),
                 ItemKind::OAuth => (" OAUTH TOKENS", "🎫", c_badge_oauth()),
+                ItemKind::Totp => (" ONE-TIME CODES", "⏱️", c_badge_totp()),
             };
 
             let header_line = Line::from(vec![
@@ -1017,6 +1134,7 @@ fn draw_items_section(f: &mut Frame, app: &App, area: Rect) {
             ItemKind::Document => ("📋", c_badge_document()),
             ItemKind::Recovery => ("🔄", c_badge_recovery()),
             ItemKind::OAuth => ("🎫", c_badge_oauth()),
+            ItemKind::Totp => ("⏱️", c_badge_totp()),
         };
 
         let created_date = match time::format_description::parse("[year]-[month]-[day]") {
@@ -1214,9 +1332,10 @@ fn draw_categories_section(f: &mut Frame, app: &App, area: Rect) {
         documents,
         recovery_codes,
         oauth_tokens,
+        totp_codes,
     } = app.get_item_counts();
 
-    let categories_content = vec![
+    let mut categories_content = vec![
         Line::from(vec![
             Span::styled("🔐 ", Style::default().fg(c_badge_pwd())),
             Span::styled(format!("Passwords ({passwords})"), Style::default().fg(c_text())),
@@ -1288,8 +1407,48 @@ fn draw_categories_section(f: &mut Frame, app: &App, area: Rect) {
             Span::styled("🎫 ", Style::default().fg(c_badge_oauth())),
             Span::styled(format!("OAuth ({oauth_tokens})"), Style::default().fg(c_text())),
         ]),
+        Line::from(vec![
+            Span::styled("⏱️ ", Style::default().fg(c_badge_totp())),
+            Span::styled(format!("TOTP ({totp_codes})"), Style::default().fg(c_text())),
+        ]),
     ];
 
+    let tags = app.tag_counts();
+    if !tags.is_empty() {
+        categories_content.push(Line::from(""));
+        categories_content.push(Line::from(Span::styled(
+            "Tags [t]",
+            Style::default().fg(c_accent()).add_modifier(Modifier::BOLD),
+        )));
+        for (tag, count) in tags {
+            let active = app.tag_filter.as_ref().is_some_and(|t| t.eq_ignore_ascii_case(&tag));
+            let style = if active {
+                Style::default().fg(c_accent2()).add_modifier(Modifier::BOLD)
+            } else {
+                Style::default().fg(c_text())
+            };
+            categories_content.push(Line::from(vec![
+                Span::styled(if active { "▶ " } else { "🏷️ " }, style),
+                Span::styled(format!("{tag} ({count})"), style),
+            ]));
+        }
+    }
+
+    let folders = app.folder_counts();
+    if !folders.is_empty() {
+        categories_content.push(Line::from(""));
+        categories_content.push(Line::from(Span::styled(
+            "Folders",
+            Style::default().fg(c_accent()).add_modifier(Modifier::BOLD),
+        )));
+        for (folder, count) in folders {
+            categories_content.push(Line::from(vec![
+                Span::styled("📁 ", Style::default().fg(c_text_dim())),
+                Span::styled(format!("{folder} ({count})"), Style::default().fg(c_text())),
+            ]));
+        }
+    }
+
     let categories_block = Block::default()
         .borders(Borders::ALL)
         .border_type(BorderType::Rounded)
@@ -1340,7 +1499,11 @@ fn draw_help_section(f: &mut Frame, area: Rect) {
         ]),
         Line::from(vec![
             Span::styled("d ", Style::default().fg(c_accent()).add_modifier(Modifier::BOLD)),
-            Span::raw("Delete selected"),
+            Span::raw("Move to trash"),
+        ]),
+        Line::from(vec![
+            Span::styled("b ", Style::default().fg(c_accent()).add_modifier(Modifier::BOLD)),
+            Span::raw("Trash"),
         ]),
         Line::from(vec![
             Span::styled("v ", Style::default().fg(c_accent()).add_modifier(Modifier::BOLD)),
@@ -1510,11 +1673,16 @@ fn get_status_message_and_style(app: &App) -> (String, Style) {
                 crate::app::ImportExportMode::Import => "Import items from file".to_string(),
             },
             Screen::VaultSelector => "Select a vault to open".to_string(),
+            Screen::Trash => match app.vault.trash_retention_days() {
+                Ok(0) | Err(_) => format!(" {} deleted items", app.trash_items.len()),
+                Ok(days) => format!(" {} deleted items, purged after {days} days", app.trash_items.len()),
+            },
         };
         (context_message, Style::default().fg(c_text_dim()))
     }
 }
 
+#[allow(clippy::too_many_lines)]
 fn get_key_hints_for_screen(app: &App) -> Vec<Span<'static>> {
     let mut spans = Vec::new();
 
@@ -1555,6 +1723,7 @@ fn get_key_hints_for_screen(app: &App) -> Vec<Span<'static>> {
 
                 add_hint(&mut spans, "a", "Add", false);
                 add_hint(&mut spans, "/", "Search", false);
+                add_hint(&mut spans, "t", "Tag Filter", false);
 
                 // Show different Esc behavior based on search state
                 if app.search_query.is_empty() {
@@ -1567,6 +1736,7 @@ fn get_key_hints_for_screen(app: &App) -> Vec<Span<'static>> {
                 add_hint(&mut spans, "i", "Import", false);
                 add_hint(&mut spans, "o", "Export", false);
                 add_hint(&mut spans, "v", "Vaults", false);
+                add_hint(&mut spans, "b", "Trash", false);
             }
         }
         Screen::AddItem => {
@@ -1579,6 +1749,17 @@ fn get_key_hints_for_screen(app: &App) -> Vec<Span<'static>> {
             add_hint(&mut spans, "v", "Toggle Value", true);
             add_hint(&mut spans, "e", "Edit", false);
             add_hint(&mut spans, "c", "Copy", false);
+            add_hint(&mut spans, "h", "History", false);
+            if app
+                .view_item
+                .as_ref()
+                .is_some_and(|item| item.totp().is_ok_and(|t| t.is_some()))
+            {
+                add_hint(&mut spans, "o", "Copy Code", false);
+            }
+            if app.view_show_history {
+                add_hint(&mut spans, "r", "Restore", false);
+            }
             add_hint(&mut spans, "Esc", "Back", false);
         }
         Screen::EditItem => {
@@ -1592,6 +1773,7 @@ fn get_key_hints_for_screen(app: &App) -> Vec<Span<'static>> {
         }
         Screen::GeneratePassword => {
             add_hint(&mut spans, "Space", "Generate", true);
+            add_hint(&mut spans, "k", "Password/SSH Key", false);
             add_hint(&mut spans, "c", "Copy", false);
             add_hint(&mut spans, "u", "Use", false);
             add_hint(&mut spans, "Esc", "Back", false);
@@ -1607,6 +1789,19 @@ fn get_key_hints_for_screen(app: &App) -> Vec<Span<'static>> {
             add_hint(&mut spans, "Enter", "Select", true);
             add_hint(&mut spans, "Esc", "Close", false);
         }
+        Screen::Trash if app.trash_pending_purge.is_some() => {
+            add_hint(&mut spans, "y", "Delete Permanently", true);
+            add_hint(&mut spans, "Any", "Cancel", false);
+        }
+        Screen::Trash => {
+            add_hint(&mut spans, "↑↓", "Navigate", false);
+            if !app.trash_items.is_empty() {
+                add_hint(&mut spans, "Enter", "Restore", true);
+                add_hint(&mut spans, "p", "Delete Permanently", false);
+                add_hint(&mut spans, "E", "Empty Trash", false);
+            }
+            add_hint(&mut spans, "Esc", "Back", false);
+        }
     }
 
     // Add a trailing space for padding
@@ -1631,14 +1826,17 @@ fn draw_add_item(f: &mut Frame, app: &App) {
     let inner = block.inner(modal_area);
     f.render_widget(block, modal_area);
 
+    // One line per typed field plus the custom fields, tags and folder lines, inside a bordered block
+    let fields_height = u16::try_from(app.add_fields.len() + 5).unwrap_or(u16::MAX);
     let chunks = Layout::default()
         .direction(Direction::Vertical)
         .margin(1)
         .constraints([
-            Constraint::Length(3), // Name input
-            Constraint::Length(3), // Kind selection
-            Constraint::Min(5),    // Value input (expanded for multi-line)
-            Constraint::Length(5), // Instructions
+            Constraint::Length(3),             // Name input
+            Constraint::Length(3),             // Kind selection
+            Constraint::Min(5),                // Value input (expanded for multi-line)
+            Constraint::Length(fields_height), // Typed and custom fields
+            Constraint::Length(5),             // Instructions
         ])
         .split(inner);
 
@@ -1706,6 +1904,8 @@ fn draw_add_item(f: &mut Frame, app: &App) {
 
     f.render_widget(&textarea, chunks[2]);
 
+    draw_add_item_fields(f, app, selected_kind, chunks[3]);
+
     // Instructions
     let instructions = match app.add_focus {
         AddItemField::Name => {
@@ -1724,6 +1924,27 @@ fn draw_add_item(f: &mut Frame, app: &App) {
                 Span::styled(" to continue", Style::default().fg(c_text_dim())),
             ])]
         }
+        AddItemField::Field(_) | AddItemField::CustomFields | AddItemField::Tags | AddItemField::Folder => {
+            vec![
+                Line::from(vec![
+                    Span::styled(
+                        "Optional details. Custom fields use ",
+                        Style::default().fg(c_text_dim()),
+                    ),
+                    Span::styled("name=value; name=value", Style::default().fg(c_accent())),
+                    Span::styled(", tags ", Style::default().fg(c_text_dim())),
+                    Span::styled("prod, db", Style::default().fg(c_accent())),
+                    Span::styled(", folders ", Style::default().fg(c_text_dim())),
+                    Span::styled("work/infra", Style::default().fg(c_accent())),
+                ]),
+                Line::from(vec![
+                    Span::styled("Tab", Style::default().fg(c_accent()).add_modifier(Modifier::BOLD)),
+                    Span::styled(" to continue, ", Style::default().fg(c_text_dim())),
+                    Span::styled("Enter", Style::default().fg(c_accent()).add_modifier(Modifier::BOLD)),
+                    Span::styled(" to save", Style::default().fg(c_text_dim())),
+                ]),
+            ]
+        }
         AddItemField::Value => {
             vec![
                 Line::from(vec![
@@ -1760,7 +1981,66 @@ fn draw_add_item(f: &mut Frame, app: &App) {
         .block(instr_block)
         .style(Style::default().fg(c_text_dim()))
         .wrap(Wrap { trim: false });
-    f.render_widget(instr_text, chunks[3]);
+    f.render_widget(instr_text, chunks[4]);
+}
+
+fn draw_add_item_fields(f: &mut Frame, app: &App, kind: ItemKind, area: Rect) {
+    let label_style = |focused: bool| {
+        if focused {
+            Style::default().fg(c_accent()).add_modifier(Modifier::BOLD)
+        } else {
+            Style::default().fg(c_text_dim())
+        }
+    };
+
+    let mut lines: Vec<Line> = kind
+        .field_specs()
+        .iter()
+        .zip(&app.add_fields)
+        .enumerate()
+        .map(|(i, (spec, value))| {
+            let focused = app.add_focus == AddItemField::Field(i);
+            let shown = if spec.secret && !focused && !value.is_empty() {
+                "•".repeat(8)
+            } else {
+                value.clone()
+            };
+            Line::from(vec![
+                Span::styled(format!("{:>14}: ", spec.label), label_style(focused)),
+                Span::styled(shown, Style::default().fg(c_text())),
+            ])
+        })
+        .collect();
+
+    let custom_focused = app.add_focus == AddItemField::CustomFields;
+    lines.push(Line::from(vec![
+        Span::styled(format!("{:>14}: ", "Custom"), label_style(custom_focused)),
+        Span::styled(app.add_custom_fields.as_str(), Style::default().fg(c_text())),
+    ]));
+    lines.push(Line::from(vec![
+        Span::styled(
+            format!("{:>14}: ", "Tags"),
+            label_style(app.add_focus == AddItemField::Tags),
+        ),
+        Span::styled(app.add_tags.as_str(), Style::default().fg(c_text())),
+    ]));
+    lines.push(Line::from(vec![
+        Span::styled(
+            format!("{:>14}: ", "Folder"),
+            label_style(app.add_focus == AddItemField::Folder),
+        ),
+        Span::styled(app.add_folder.as_str(), Style::default().fg(c_text())),
+    ]));
+
+    let focused = matches!(
+        app.add_focus,
+        AddItemField::Field(_) | AddItemField::CustomFields | AddItemField::Tags | AddItemField::Folder
+    );
+    let block = Block::default()
+        .title("Fields")
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(if focused { c_accent() } else { c_border() }));
+    f.render_widget(Paragraph::new(lines).block(block), area);
 }
 
 const fn get_value_title_for_kind(kind: ItemKind) -> &'static str {
@@ -1782,6 +2062,7 @@ const fn get_value_title_for_kind(kind: ItemKind) -> &'static str {
         ItemKind::Document => "Document Content",
         ItemKind::Recovery => "Recovery Codes",
         ItemKind::OAuth => "OAuth Token",
+        ItemKind::Totp => "otpauth:// URI or Base32 Secret",
     }
 }
 
@@ -1986,10 +2267,16 @@ fn draw_generate_password(f: &mut Frame, app: &App) {
         .split(pad(area, 2, 2));
 
     // Title
-    let title = Paragraph::new(Line::from(vec![Span::styled(
-        "Configure and generate secure passwords",
-        Style::default().fg(c_text_dim()),
-    )]));
+    let generating = match app.gen_ssh_key_type {
+        None => "Password",
+        Some(SshKeyType::Ed25519) => "SSH key pair (Ed25519)",
+        Some(SshKeyType::Rsa) => "SSH key pair (RSA 4096)",
+    };
+    let title = Paragraph::new(Line::from(vec![
+        Span::styled("Generating: ", Style::default().fg(c_text_dim())),
+        Span::styled(generating, Style::default().fg(c_accent())),
+        Span::styled("   [k] switch", Style::default().fg(c_text_dim())),
+    ]));
     f.render_widget(title, inner[0]);
 
     // Length field
@@ -2071,9 +2358,20 @@ fn draw_generate_password(f: &mut Frame, app: &App) {
         .border_type(BorderType::Rounded)
         .border_style(Style::default().fg(c_border()))
         .style(Style::default().bg(Color::Rgb(20, 25, 35)))
-        .title(Span::styled(" Generated Password ", Style::default().fg(c_warn())));
+        .title(Span::styled(
+            if app.gen_ssh_key_type.is_some() {
+                " Public Key "
+            } else {
+                " Generated Password "
+            },
+            Style::default().fg(c_warn()),
+        ));
 
-    let pwd_content = if let Some(password) = &app.generated_password {
+    let pwd_content = if let Some(key) = &app.generated_ssh_key {
+        Paragraph::new(format!("{}\n{}", key.fingerprint, key.public_key))
+            .style(Style::default().fg(c_accent()).add_modifier(Modifier::BOLD))
+            .wrap(Wrap { trim: true })
+    } else if let Some(password) = &app.generated_password {
         Paragraph::new(password.clone())
             .style(Style::default().fg(c_accent()).add_modifier(Modifier::BOLD))
             .wrap(Wrap { trim: true })
@@ -2217,7 +2515,17 @@ fn draw_change_master(f: &mut Frame, app: &App) {
 #[allow(clippy::too_many_lines)]
 fn draw_view_item(f: &mut Frame, app: &App) {
     if let Some(item) = &app.view_item {
-        let area = centered_rect(70, 60, f.area());
+        let height = if item.fields.is_empty() { 60 } else { 80 };
+        let mut area = centered_rect(70, height, f.area());
+        if app.view_show_history {
+            let full = centered_rect(90, height, f.area());
+            let split = Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints([Constraint::Percentage(60), Constraint::Percentage(40)])
+                .split(full);
+            area = split[0];
+            draw_item_history(f, app, split[1]);
+        }
         f.render_widget(Clear, area);
 
         let block = Block::default()
@@ -2231,12 +2539,21 @@ fn draw_view_item(f: &mut Frame, app: &App) {
             ));
         f.render_widget(block, area);
 
+        let totp = item.totp();
+        let totp_height = if matches!(totp, Ok(None)) { 0 } else { 3 };
+        let fields_height = if item.fields.is_empty() {
+            0
+        } else {
+            u16::try_from(item.fields.len() + 2).unwrap_or(u16::MAX)
+        };
         let inner = Layout::default()
             .direction(Direction::Vertical)
             .constraints([
                 Constraint::Length(3),
                 Constraint::Length(3),
                 Constraint::Length(6),
+                Constraint::Length(totp_height),
+                Constraint::Length(fields_height),
                 Constraint::Length(3),
                 Constraint::Length(3),
                 Constraint::Length(2),
@@ -2297,6 +2614,63 @@ fn draw_view_item(f: &mut Frame, app: &App) {
             .wrap(Wrap { trim: true });
         f.render_widget(value_content, inner[2]);
 
+        if totp_height > 0 {
+            let code_line = match totp.and_then(|totp| totp.map(|t| t.now()).transpose()) {
+                Ok(Some(code)) => Line::from(vec![
+                    Span::styled(code.code, Style::default().fg(c_ok()).add_modifier(Modifier::BOLD)),
+                    Span::styled(
+                        format!("   expires in {}s", code.remaining_seconds),
+                        Style::default().fg(if code.remaining_seconds <= 5 {
+                            c_warn()
+                        } else {
+                            c_text_dim()
+                        }),
+                    ),
+                ]),
+                Ok(None) => Line::default(),
+                Err(e) => Line::from(Span::styled(
+                    format!("Invalid TOTP secret: {e}"),
+                    Style::default().fg(c_err()),
+                )),
+            };
+            let totp_block = Block::default()
+                .borders(Borders::ALL)
+                .border_type(BorderType::Rounded)
+                .border_style(Style::default().fg(c_border()))
+                .style(Style::default().bg(Color::Rgb(30, 32, 40)))
+                .title(Span::styled(" One-Time Code ", Style::default().fg(c_text_dim())));
+            f.render_widget(Paragraph::new(code_line).block(totp_block), inner[3]);
+        }
+
+        if !item.fields.is_empty() {
+            let field_lines: Vec<Line> = item
+                .fields
+                .iter()
+                .map(|field| {
+                    let label = item
+                        .kind
+                        .field_spec(&field.name)
+                        .map_or(field.name.as_str(), |spec| spec.label);
+                    let value = if field.secret && !app.view_show_value {
+                        "••••••••".to_string()
+                    } else {
+                        field.value.clone()
+                    };
+                    Line::from(vec![
+                        Span::styled(format!("{label}: "), Style::default().fg(c_text_dim())),
+                        Span::styled(value, Style::default().fg(c_text())),
+                    ])
+                })
+                .collect();
+            let fields_block = Block::default()
+                .borders(Borders::ALL)
+                .border_type(BorderType::Rounded)
+                .border_style(Style::default().fg(c_border()))
+                .style(Style::default().bg(Color::Rgb(30, 32, 40)))
+                .title(Span::styled(" Fields ", Style::default().fg(c_text_dim())));
+            f.render_widget(Paragraph::new(field_lines).block(fields_block), inner[4]);
+        }
+
         let created_str = item
             .created_at
             .format(&time::format_description::well_known::Rfc3339)
@@ -2310,7 +2684,7 @@ fn draw_view_item(f: &mut Frame, app: &App) {
         let created_content = Paragraph::new(created_str)
             .block(created_block)
             .style(Style::default().fg(c_text()));
-        f.render_widget(created_content, inner[3]);
+        f.render_widget(created_content, inner[5]);
 
         let updated_str = item
             .updated_at
@@ -2325,20 +2699,144 @@ fn draw_view_item(f: &mut Frame, app: &App) {
         let updated_content = Paragraph::new(updated_str)
             .block(updated_block)
             .style(Style::default().fg(c_text()));
-        f.render_widget(updated_content, inner[4]);
+        f.render_widget(updated_content, inner[6]);
 
-        let actions = Paragraph::new(Line::from(vec![
+        let mut action_spans = vec![
             Span::styled("[t/Enter]", Style::default().fg(c_warn()).add_modifier(Modifier::BOLD)),
             Span::styled(" Toggle visibility   ", Style::default().fg(c_text_dim())),
             Span::styled("[c]", Style::default().fg(c_accent()).add_modifier(Modifier::BOLD)),
             Span::styled(" Copy   ", Style::default().fg(c_text_dim())),
-            Span::styled("[Esc]", Style::default().fg(c_err()).add_modifier(Modifier::BOLD)),
-            Span::styled(" Close", Style::default().fg(c_text_dim())),
-        ]));
-        f.render_widget(actions, inner[5]);
+            Span::styled("[h]", Style::default().fg(c_accent()).add_modifier(Modifier::BOLD)),
+            Span::styled(" History   ", Style::default().fg(c_text_dim())),
+        ];
+        if totp_height > 0 {
+            action_spans.push(Span::styled(
+                "[o]",
+                Style::default().fg(c_accent()).add_modifier(Modifier::BOLD),
+            ));
+            action_spans.push(Span::styled(" Copy code   ", Style::default().fg(c_text_dim())));
+        }
+        action_spans.push(Span::styled(
+            "[Esc]",
+            Style::default().fg(c_err()).add_modifier(Modifier::BOLD),
+        ));
+        action_spans.push(Span::styled(" Close", Style::default().fg(c_text_dim())));
+        let actions = Paragraph::new(Line::from(action_spans));
+        f.render_widget(actions, inner[7]);
     }
 }
 
+fn draw_item_history(f: &mut Frame, app: &App, area: Rect) {
+    f.render_widget(Clear, area);
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_type(BorderType::Rounded)
+        .border_style(Style::default().fg(c_border()))
+        .style(Style::default().bg(c_bg_panel()).fg(c_text()))
+        .title(Span::styled(
+            format!(" History ({}) ", app.view_history.len()),
+            Style::default().fg(c_accent2()).add_modifier(Modifier::BOLD),
+        ));
+
+    if app.view_history.is_empty() {
+        let empty = Paragraph::new("No previous revisions")
+            .block(block)
+            .style(Style::default().fg(c_text_dim()));
+        f.render_widget(empty, area);
+        return;
+    }
+
+    let items: Vec<ListItem> = app
+        .view_history
+        .iter()
+        .enumerate()
+        .rev()
+        .map(|(idx, rev)| {
+            let replaced = rev
+                .archived_at
+                .format(&time::format_description::well_known::Rfc3339)
+                .unwrap_or_else(|_| "Unknown".to_string());
+            let value = if app.view_show_value {
+                rev.value.clone()
+            } else {
+                "••••••••".to_string()
+            };
+            let style = if idx == app.view_history_selected {
+                Style::default()
+                    .bg(c_accent())
+                    .fg(Color::Black)
+                    .add_modifier(Modifier::BOLD)
+            } else {
+                Style::default().fg(c_text())
+            };
+            ListItem::new(vec![
+                Line::from(Span::styled(format!(" rev {}  {replaced}", rev.revision), style)),
+                Line::from(Span::styled(format!("   {value}"), Style::default().fg(c_text_dim()))),
+            ])
+        })
+        .collect();
+
+    f.render_widget(List::new(items).block(block), area);
+}
+
+fn draw_trash(f: &mut Frame, app: &App) {
+    let area = centered_rect(70, 60, f.area());
+    f.render_widget(Clear, area);
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_type(BorderType::Rounded)
+        .border_style(Style::default().fg(c_border()))
+        .style(Style::default().bg(c_bg_panel()).fg(c_text()))
+        .title(Span::styled(
+            format!(" Trash ({}) ", app.trash_items.len()),
+            Style::default().fg(c_accent2()).add_modifier(Modifier::BOLD),
+        ));
+
+    if app.trash_items.is_empty() {
+        let empty = Paragraph::new("The trash is empty")
+            .block(block)
+            .style(Style::default().fg(c_text_dim()));
+        f.render_widget(empty, area);
+        return;
+    }
+
+    let now = time::OffsetDateTime::now_utc();
+    let items: Vec<ListItem> = app
+        .trash_items
+        .iter()
+        .enumerate()
+        .map(|(idx, item)| {
+            let deleted = item
+                .deleted_at
+                .format(&time::format_description::well_known::Rfc3339)
+                .unwrap_or_else(|_| "Unknown".to_string());
+            let purge = item.purge_at.map_or_else(
+                || "kept until the trash is emptied".to_string(),
+                |purge_at| format!("purged in {} days", (purge_at - now).whole_days().max(0)),
+            );
+            let style = if idx == app.trash_selected {
+                Style::default()
+                    .bg(c_accent())
+                    .fg(Color::Black)
+                    .add_modifier(Modifier::BOLD)
+            } else {
+                Style::default().fg(c_text())
+            };
+            ListItem::new(vec![
+                Line::from(Span::styled(format!(" {} ({})", item.name, item.kind.as_str()), style)),
+                Line::from(Span::styled(
+                    format!("   deleted {deleted}, {purge}"),
+                    Style::default().fg(c_text_dim()),
+                )),
+            ])
+        })
+        .collect();
+
+    f.render_widget(List::new(items).block(block), area);
+}
+
 fn draw_edit_item(f: &mut Frame, app: &App) {
     if let Some(item) = &app.edit_item {
         let area = centered_rect(70, 50, f.area());
@@ -0,0 +1,446 @@
+use crate::db::AttachmentRow;
+use crate::{KeyMaterial, Vault, aead_decrypt, aead_encrypt};
+use color_eyre::Result;
+use color_eyre::eyre::eyre;
+use serde::{Deserialize, Serialize};
+use std::io::{Read, Write};
+use time::OffsetDateTime;
+use zeroize::Zeroizing;
+
+/// Size of the plaintext chunks an attachment is split into before encryption.
+pub const ATTACHMENT_CHUNK_SIZE: usize = 64 * 1024;
+
+/// Largest attachment the vault accepts, in bytes.
+pub const MAX_ATTACHMENT_SIZE: u64 = 100 * 1024 * 1024;
+
+/// A file attached to an item. The contents are read with [`Vault::read_attachment`].
+#[derive(Debug, Clone)]
+pub struct Attachment {
+    pub id: u64,
+    pub item_id: u64,
+    pub file_name: String,
+    /// Size of the decrypted contents in bytes.
+    pub size: u64,
+    /// Number of encrypted chunks the contents are stored in.
+    pub chunks: u64,
+    pub created_at: OffsetDateTime,
+}
+
+/// Encrypted metadata of an attachment.
+#[derive(Serialize, Deserialize)]
+struct AttachmentMeta {
+    file_name: String,
+    size: u64,
+    chunks: u64,
+}
+
+/// An attachment being written, created by [`Vault::begin_attachment`].
+///
+/// Data passed to [`Vault::write_attachment`] is encrypted and stored one chunk at a time, so
+/// large files never have to be held in memory. The attachment only becomes visible once
+/// [`Vault::finish_attachment`] is called.
+pub struct AttachmentUpload {
+    id: u64,
+    item_id: u64,
+    file_name: String,
+    size: u64,
+    chunks: u64,
+    buffer: Zeroizing<Vec<u8>>,
+}
+
+impl AttachmentUpload {
+    /// Number of bytes written so far.
+    #[must_use]
+    pub const fn size(&self) -> u64 {
+        self.size
+    }
+}
+
+/// Associated data of an attachment chunk or, with `None`, of its metadata.
+///
+/// Binding the item id, attachment id and chunk index means chunks cannot be reordered, moved
+/// to another attachment, or an attachment moved to another item without detection. The
+/// chunk count is part of the encrypted metadata, which detects truncation.
+fn attachment_ad(item_id: u64, attachment_id: u64, chunk: Option<u64>) -> Vec<u8> {
+    let mut ad = b"chamber-attachment".to_vec();
+    for part in [item_id, attachment_id] {
+        ad.push(0x1f);
+        ad.extend_from_slice(&part.to_be_bytes());
+    }
+    ad.push(0x1f);
+    match chunk {
+        Some(index) => ad.extend_from_slice(&index.to_be_bytes()),
+        None => ad.extend_from_slice(b"meta"),
+    }
+    ad
+}
+
+/// Checks that `file_name` is a plain file name without any directory components.
+fn validate_file_name(file_name: &str) -> Result<&str> {
+    let file_name = file_name.trim();
+    if file_name.is_empty() || file_name == "." || file_name == ".." {
+        return Err(eyre!("Attachment file name cannot be empty"));
+    }
+    if file_name.contains(['/', '\\']) || file_name.chars().any(char::is_control) {
+        return Err(eyre!("Attachment file name '{}' must not contain a path", file_name));
+    }
+    Ok(file_name)
+}
+
+fn open_attachment(vk: &KeyMaterial, row: &AttachmentRow) -> Result<Attachment> {
+    let plaintext = aead_decrypt(
+        vk,
+        &row.meta_nonce,
+        &row.meta_ciphertext,
+        &attachment_ad(row.item_id, row.id, None),
+    )?;
+    let meta: AttachmentMeta = serde_json::from_slice(&plaintext)?;
+    Ok(Attachment {
+        id: row.id,
+        item_id: row.item_id,
+        file_name: meta.file_name,
+        size: meta.size,
+        chunks: meta.chunks,
+        created_at: row.created_at,
+    })
+}
+
+impl Vault {
+    /// Lists the attachments of an item, oldest first.
+    ///
+    /// # Errors
+    /// Returns an error if the vault is locked or the attachment metadata cannot be decrypted.
+    pub fn list_attachments(&self, item_id: u64) -> Result<Vec<Attachment>> {
+        let vk = self.key.as_ref().ok_or_else(|| eyre!("Locked"))?;
+        self.db
+            .list_attachments(item_id)?
+            .iter()
+            .map(|row| open_attachment(vk, row))
+            .collect()
+    }
+
+    /// Returns the attachment with the given id, if it exists.
+    ///
+    /// # Errors
+    /// Returns an error if the vault is locked or the attachment metadata cannot be decrypted.
+    pub fn get_attachment(&self, attachment_id: u64) -> Result<Option<Attachment>> {
+        let vk = self.key.as_ref().ok_or_else(|| eyre!("Locked"))?;
+        self.db
+            .get_attachment(attachment_id)?
+            .map(|row| open_attachment(vk, &row))
+            .transpose()
+    }
+
+    /// Attaches the contents of `reader` to an item under `file_name`.
+    ///
+    /// The contents are encrypted in chunks of [`ATTACHMENT_CHUNK_SIZE`] bytes as they are read.
+    /// If reading or storing fails, the partial attachment is removed again.
+    ///
+    /// # Errors
+    /// Returns an error under the same conditions as [`Vault::begin_attachment`] and
+    /// [`Vault::write_attachment`], or if `reader` fails.
+    pub fn add_attachment(&mut self, item_id: u64, file_name: &str, reader: &mut impl Read) -> Result<Attachment> {
+        let mut upload = self.begin_attachment(item_id, file_name)?;
+        let mut buf = Zeroizing::new(vec![0u8; ATTACHMENT_CHUNK_SIZE]);
+        let written = loop {
+            match reader.read(&mut buf) {
+                Ok(0) => break Ok(()),
+                Ok(n) => {
+                    if let Err(e) = self.write_attachment(&mut upload, &buf[..n]) {
+                        break Err(e);
+                    }
+                }
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {}
+                Err(e) => break Err(e.into()),
+            }
+        };
+        match written {
+            Ok(()) => self.finish_attachment(upload),
+            Err(e) => {
+                self.abort_attachment(&upload)?;
+                Err(e)
+            }
+        }
+    }
+
+    /// Starts a new attachment of an item.
+    ///
+    /// # Errors
+    /// - If the vault is locked, an `eyre!("Locked")` error is returned.
+    /// - If the item does not exist, an `eyre!("Item not found")` error is returned.
+    /// - If `file_name` is empty, contains a path, or is already used by another attachment of
+    ///   the item.
+    /// - If the database insertion fails.
+    pub fn begin_attachment(&mut self, item_id: u64, file_name: &str) -> Result<AttachmentUpload> {
+        let file_name = validate_file_name(file_name)?;
+        if self.db.get_item(item_id)?.is_none() {
+            return Err(eyre!("Item not found"));
+        }
+        if self.list_attachments(item_id)?.iter().any(|a| a.file_name == file_name) {
+            return Err(eyre!("An attachment named '{}' already exists on this item", file_name));
+        }
+        let id = self.db.insert_attachment(item_id)?;
+        Ok(AttachmentUpload {
+            id,
+            item_id,
+            file_name: file_name.to_string(),
+            size: 0,
+            chunks: 0,
+            buffer: Zeroizing::new(Vec::with_capacity(ATTACHMENT_CHUNK_SIZE)),
+        })
+    }
+
+    /// Appends `data` to an attachment started with [`Vault::begin_attachment`].
+    ///
+    /// # Errors
+    /// Returns an error if the vault is locked, the attachment would exceed
+    /// [`MAX_ATTACHMENT_SIZE`], or encryption or storing a chunk fails.
+    pub fn write_attachment(&mut self, upload: &mut AttachmentUpload, mut data: &[u8]) -> Result<()> {
+        let size = upload.size + data.len() as u64;
+        if size > MAX_ATTACHMENT_SIZE {
+            return Err(eyre!(
+                "Attachment exceeds the maximum size of {} MiB",
+                MAX_ATTACHMENT_SIZE / (1024 * 1024)
+            ));
+        }
+        upload.size = size;
+        while !data.is_empty() {
+            let take = (ATTACHMENT_CHUNK_SIZE - upload.buffer.len()).min(data.len());
+            upload.buffer.extend_from_slice(&data[..take]);
+            data = &data[take..];
+            if upload.buffer.len() == ATTACHMENT_CHUNK_SIZE {
+                self.flush_attachment_chunk(upload)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Stores the remaining data and the encrypted metadata of an attachment, making it visible.
+    ///
+    /// # Errors
+    /// Returns an error if the vault is locked or encryption or the database update fails.
+    pub fn finish_attachment(&mut self, mut upload: AttachmentUpload) -> Result<Attachment> {
+        if !upload.buffer.is_empty() {
+            self.flush_attachment_chunk(&mut upload)?;
+        }
+        let vk = self.key.as_ref().ok_or_else(|| eyre!("Locked"))?;
+        let meta = serde_json::to_vec(&AttachmentMeta {
+            file_name: upload.file_name.clone(),
+            size: upload.size,
+            chunks: upload.chunks,
+        })?;
+        let (nonce, ciphertext) = aead_encrypt(vk, &meta, &attachment_ad(upload.item_id, upload.id, None))?;
+        self.db.complete_attachment(upload.id, &nonce, &ciphertext)?;
+        self.get_attachment(upload.id)?
+            .ok_or_else(|| eyre!("Attachment not found"))
+    }
+
+    /// Discards an attachment that has not been finished.
+    ///
+    /// # Errors
+    /// Returns an error if the database deletion fails.
+    pub fn abort_attachment(&mut self, upload: &AttachmentUpload) -> Result<()> {
+        self.db.delete_attachment(upload.id)
+    }
+
+    fn flush_attachment_chunk(&self, upload: &mut AttachmentUpload) -> Result<()> {
+        let vk = self.key.as_ref().ok_or_else(|| eyre!("Locked"))?;
+        let ad = attachment_ad(upload.item_id, upload.id, Some(upload.chunks));
+        let (nonce, ciphertext) = aead_encrypt(vk, &upload.buffer, &ad)?;
+        self.db
+            .insert_attachment_chunk(upload.id, upload.chunks, &nonce, &ciphertext)?;
+        upload.chunks += 1;
+        upload.buffer.clear();
+        Ok(())
+    }
+
+    /// Decrypts chunk `index` of an attachment.
+    ///
+    /// Reading chunk by chunk lets callers stream large attachments without decrypting them
+    /// into memory at once.
+    ///
+    /// # Errors
+    /// Returns an error if the vault is locked, the chunk is missing, or it fails to decrypt.
+    pub fn read_attachment_chunk(&self, attachment: &Attachment, index: u64) -> Result<Zeroizing<Vec<u8>>> {
+        let vk = self.key.as_ref().ok_or_else(|| eyre!("Locked"))?;
+        self.decrypt_attachment_chunk(vk, attachment, index)
+    }
+
+    fn decrypt_attachment_chunk(
+        &self,
+        vk: &KeyMaterial,
+        attachment: &Attachment,
+        index: u64,
+    ) -> Result<Zeroizing<Vec<u8>>> {
+        if index >= attachment.chunks {
+            return Err(eyre!("Attachment chunk {} out of range", index));
+        }
+        let (nonce, ciphertext) = self
+            .db
+            .get_attachment_chunk(attachment.id, index)?
+            .ok_or_else(|| eyre!("Attachment '{}' is missing chunk {}", attachment.file_name, index))?;
+        let plaintext = aead_decrypt(
+            vk,
+            &nonce,
+            &ciphertext,
+            &attachment_ad(attachment.item_id, attachment.id, Some(index)),
+        )?;
+        Ok(Zeroizing::new(plaintext))
+    }
+
+    /// Decrypts an attachment into `writer` and returns its metadata.
+    ///
+    /// # Errors
+    /// Returns an error if the vault is locked, the attachment does not exist or fails to
+    /// decrypt, or writing fails.
+    pub fn read_attachment(&self, attachment_id: u64, writer: &mut impl Write) -> Result<Attachment> {
+        let attachment = self
+            .get_attachment(attachment_id)?
+            .ok_or_else(|| eyre!("Attachment not found"))?;
+        for index in 0..attachment.chunks {
+            writer.write_all(&self.read_attachment_chunk(&attachment, index)?)?;
+        }
+        writer.flush()?;
+        Ok(attachment)
+    }
+
+    /// Re-encrypts the attachments of an item from key `old` to key `new` and returns how many
+    /// there are.
+    pub(crate) fn reencrypt_attachments(&self, item_id: u64, old: &KeyMaterial, new: &KeyMaterial) -> Result<usize> {
+        let rows = self.db.list_attachments(item_id)?;
+        for row in &rows {
+            let attachment = open_attachment(old, row)?;
+            for index in 0..attachment.chunks {
+                let plaintext = self.decrypt_attachment_chunk(old, &attachment, index)?;
+                let (nonce, ciphertext) = aead_encrypt(new, &plaintext, &attachment_ad(item_id, row.id, Some(index)))?;
+                self.db.reseal_attachment_chunk(row.id, index, &nonce, &ciphertext)?;
+            }
+            let ad = attachment_ad(item_id, row.id, None);
+            let meta = Zeroizing::new(aead_decrypt(old, &row.meta_nonce, &row.meta_ciphertext, &ad)?);
+            let (nonce, ciphertext) = aead_encrypt(new, &meta, &ad)?;
+            self.db.complete_attachment(row.id, &nonce, &ciphertext)?;
+        }
+        Ok(rows.len())
+    }
+
+    /// Checks that every attachment of an item, metadata and chunks, decrypts with `vk`.
+    pub(crate) fn verify_attachments(&self, item_id: u64, vk: &KeyMaterial) -> Result<()> {
+        for row in self.db.list_attachments(item_id)? {
+            self.verify_attachment(&row, vk)?;
+        }
+        Ok(())
+    }
+
+    /// Checks that one attachment, metadata and chunks, decrypts with `vk`.
+    pub(crate) fn verify_attachment(&self, row: &AttachmentRow, vk: &KeyMaterial) -> Result<()> {
+        let attachment = open_attachment(vk, row)?;
+        for index in 0..attachment.chunks {
+            self.decrypt_attachment_chunk(vk, &attachment, index)?;
+        }
+        Ok(())
+    }
+
+    /// Deletes an attachment.
+    ///
+    /// # Errors
+    /// Returns an error if the database deletion fails.
+    pub fn delete_attachment(&mut self, attachment_id: u64) -> Result<()> {
+        self.db.delete_attachment(attachment_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ItemKind, NewItem};
+
+    fn unlocked_vault_with_item() -> Result<(Vault, u64, std::path::PathBuf)> {
+        let path = std::env::temp_dir().join(format!(
+            "chamber_attachments_{}_{}.sqlite3",
+            std::process::id(),
+            OffsetDateTime::now_utc().unix_timestamp_nanos()
+        ));
+        let mut vault = Vault::open_or_create(Some(&path))?;
+        vault.initialize("master")?;
+        vault.unlock("master")?;
+        vault.create_item(&NewItem {
+            name: "cert".to_string(),
+            kind: ItemKind::Certificate,
+            value: "client certificate".to_string(),
+            tags: Vec::new(),
+            folder: None,
+            fields: Vec::new(),
+        })?;
+        let id = vault.list_items()?[0].id;
+        Ok((vault, id, path))
+    }
+
+    #[test]
+    fn test_attachment_round_trip_across_chunks() -> Result<()> {
+        let (mut vault, item_id, path) = unlocked_vault_with_item()?;
+        let data: Vec<u8> = (0..ATTACHMENT_CHUNK_SIZE * 2 + 17)
+            .map(|i| u8::try_from(i % 251).unwrap_or(0))
+            .collect();
+
+        let attachment = vault.add_attachment(item_id, "cert.p12", &mut data.as_slice())?;
+        assert_eq!((attachment.size, attachment.chunks), (data.len() as u64, 3));
+
+        let mut out = Vec::new();
+        vault.read_attachment(attachment.id, &mut out)?;
+        assert_eq!(out, data);
+
+        let listed = vault.list_attachments(item_id)?;
+        assert_eq!(listed.len(), 1);
+        assert_eq!(listed[0].file_name, "cert.p12");
+
+        vault.delete_item(item_id)?;
+        assert!(
+            vault.get_attachment(attachment.id)?.is_some(),
+            "items in the trash keep their attachments"
+        );
+        vault.empty_trash()?;
+        assert!(vault.get_attachment(attachment.id)?.is_none());
+        std::fs::remove_file(path).ok();
+        Ok(())
+    }
+
+    #[test]
+    fn test_attachment_rejects_bad_names_and_unfinished_uploads_stay_hidden() -> Result<()> {
+        let (mut vault, item_id, path) = unlocked_vault_with_item()?;
+        assert!(vault.add_attachment(item_id, "../id_rsa", &mut [].as_slice()).is_err());
+        assert!(vault.add_attachment(item_id + 1, "x.bin", &mut [].as_slice()).is_err());
+
+        let mut upload = vault.begin_attachment(item_id, "key.pem")?;
+        vault.write_attachment(&mut upload, b"partial")?;
+        assert!(vault.list_attachments(item_id)?.is_empty());
+        vault.abort_attachment(&upload)?;
+
+        let empty = vault.add_attachment(item_id, "empty.txt", &mut [].as_slice())?;
+        assert_eq!((empty.size, empty.chunks), (0, 0));
+        assert!(vault.add_attachment(item_id, "empty.txt", &mut [].as_slice()).is_err());
+        std::fs::remove_file(path).ok();
+        Ok(())
+    }
+
+    #[test]
+    fn test_attachment_chunks_are_bound_to_their_position() -> Result<()> {
+        let (mut vault, item_id, path) = unlocked_vault_with_item()?;
+        let data = vec![7u8; ATTACHMENT_CHUNK_SIZE + 1];
+        let attachment = vault.add_attachment(item_id, "blob.bin", &mut data.as_slice())?;
+
+        // Swap the two chunks in the database: decryption must fail
+        let (n0, c0) = vault.db.get_attachment_chunk(attachment.id, 0)?.unwrap_or_default();
+        let (n1, c1) = vault.db.get_attachment_chunk(attachment.id, 1)?.unwrap_or_default();
+        vault.db.delete_attachment(attachment.id)?;
+        let swapped = vault.db.insert_attachment(item_id)?;
+        vault.db.insert_attachment_chunk(swapped, 0, &n1, &c1)?;
+        vault.db.insert_attachment_chunk(swapped, 1, &n0, &c0)?;
+        let forged = Attachment {
+            id: swapped,
+            ..attachment
+        };
+        assert!(vault.read_attachment_chunk(&forged, 0).is_err());
+        std::fs::remove_file(path).ok();
+        Ok(())
+    }
+}
@@ -15,7 +15,7 @@ pub struct BackupConfig {
     /// Maximum number of backups to retain
     pub max_backups: usize,
 
-    /// Backup format (json, csv, backup)
+    /// Backup format (json, csv, backup, snapshot)
     pub format: String,
 
     /// Compress backups
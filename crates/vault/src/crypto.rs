@@ -45,6 +45,19 @@ impl Drop for KeyMaterial {
     }
 }
 
+/// Memory cost of [`KdfParams::default_secure`] in KiB (19 MiB).
+pub const DEFAULT_KDF_MEMORY_KIB: u32 = 19456;
+/// Number of passes of [`KdfParams::default_secure`].
+pub const DEFAULT_KDF_TIME: u32 = 3;
+/// Number of lanes of [`KdfParams::default_secure`].
+pub const DEFAULT_KDF_PARALLELISM: u32 = 1;
+/// Least memory, in KiB, that [`KdfParams::meets_recommendations`] accepts.
+const MIN_RECOMMENDED_KDF_MEMORY_KIB: u32 = 7 * 1024;
+/// Least product of memory in KiB and passes that [`KdfParams::meets_recommendations`] accepts.
+const RECOMMENDED_KDF_MEMORY_PASSES: u64 = 7 * 1024 * 5;
+/// Most passes [`KdfParams::tune`] picks.
+const MAX_TUNED_KDF_TIME: u32 = 64;
+
 #[derive(Clone, Serialize, Deserialize)]
 pub struct KdfParams {
     pub salt: Vec<u8>,
@@ -87,11 +100,81 @@ impl KdfParams {
         getrandom::fill(&mut salt).expect("Failed to get random bytes");
         Self {
             salt,
-            m_cost_kib: 19456,
-            t_cost: 3,
-            p_cost: 1,
+            m_cost_kib: DEFAULT_KDF_MEMORY_KIB,
+            t_cost: DEFAULT_KDF_TIME,
+            p_cost: DEFAULT_KDF_PARALLELISM,
         } // ~19MB memory
     }
+
+    /// Creates parameters with the given costs and a fresh random salt.
+    ///
+    /// # Errors
+    /// Returns an error if Argon2id does not accept the costs, e.g. a memory cost below eight
+    /// KiB per lane or zero passes.
+    pub fn new(m_cost_kib: u32, t_cost: u32, p_cost: u32) -> Result<Self> {
+        Params::new(m_cost_kib, t_cost, p_cost, Some(32)).map_err(|e| eyre!("Invalid KDF parameters: {e}"))?;
+        Ok(Self {
+            m_cost_kib,
+            t_cost,
+            p_cost,
+            ..Self::default_secure()
+        })
+    }
+
+    /// Returns the same costs with a fresh random salt, for wrapping a key under a new password.
+    #[must_use]
+    pub fn resalted(&self) -> Self {
+        Self {
+            m_cost_kib: self.m_cost_kib,
+            t_cost: self.t_cost,
+            p_cost: self.p_cost,
+            ..Self::default_secure()
+        }
+    }
+
+    /// Returns whether the costs meet the OWASP recommendations for Argon2id: at least 7 MiB of
+    /// memory, and more passes the less memory is used (19 MiB with two passes, 46 MiB with one).
+    #[must_use]
+    pub fn meets_recommendations(&self) -> bool {
+        self.m_cost_kib >= MIN_RECOMMENDED_KDF_MEMORY_KIB
+            && u64::from(self.m_cost_kib) * u64::from(self.t_cost) >= RECOMMENDED_KDF_MEMORY_PASSES
+    }
+
+    /// Picks the number of passes so that one derivation with `m_cost_kib` of memory and
+    /// `p_cost` lanes takes about `target` on this machine, without going below the
+    /// recommendations.
+    ///
+    /// # Errors
+    /// Returns an error if Argon2id does not accept the costs or the benchmark derivation fails.
+    pub fn tune(target: std::time::Duration, m_cost_kib: u32, p_cost: u32) -> Result<Self> {
+        let probe = Self::new(m_cost_kib, 1, p_cost)?;
+        let started = std::time::Instant::now();
+        derive_key_from_secret(b"chamber-kdf-benchmark", &probe)?;
+        let per_pass = started.elapsed().as_secs_f64().max(f64::EPSILON);
+
+        let recommended = RECOMMENDED_KDF_MEMORY_PASSES.div_ceil(u64::from(m_cost_kib.max(1)));
+        #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+        let passes = (target.as_secs_f64() / per_pass)
+            .round()
+            .clamp(1.0, f64::from(MAX_TUNED_KDF_TIME)) as u32;
+        let t_cost = passes.max(u32::try_from(recommended).unwrap_or(u32::MAX));
+        Self::new(m_cost_kib, t_cost, p_cost)
+    }
+}
+
+impl std::fmt::Display for KdfParams {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let memory = if self.m_cost_kib % 1024 == 0 {
+            format!("{} MiB", self.m_cost_kib / 1024)
+        } else {
+            format!("{} KiB", self.m_cost_kib)
+        };
+        write!(
+            f,
+            "Argon2id {memory}, {} pass(es), {} lane(s)",
+            self.t_cost, self.p_cost
+        )
+    }
 }
 
 /// Derives a key from the given master password and key derivation function (KDF) parameters.
@@ -122,6 +205,18 @@ impl KdfParams {
 /// * If the KDF parameters cannot be created (`Params::new` fails).
 /// * If the hash computation with Argon2 fails (`hash_password_into` fails).
 pub fn derive_key(master: &str, kdf: &KdfParams) -> Result<KeyMaterial> {
+    derive_key_from_secret(master.as_bytes(), kdf)
+}
+
+/// Derives a key from arbitrary secret bytes using Argon2id and the given KDF parameters.
+///
+/// This is the byte-oriented variant of [`derive_key`], used when the input secret is not a
+/// user-entered password (for example, a subkey of the vault key protecting a backup).
+///
+/// # Errors
+///
+/// Returns an error if the KDF parameters are invalid or the Argon2 hash computation fails.
+pub fn derive_key_from_secret(secret: &[u8], kdf: &KdfParams) -> Result<KeyMaterial> {
     let argon2 = Argon2::new(
         Algorithm::Argon2id,
         Version::V0x13,
@@ -129,11 +224,53 @@ pub fn derive_key(master: &str, kdf: &KdfParams) -> Result<KeyMaterial> {
     );
     let mut out = [0u8; 32];
     argon2
-        .hash_password_into(master.as_bytes(), &kdf.salt, &mut out)
+        .hash_password_into(secret, &kdf.salt, &mut out)
         .map_err(|e| eyre!("{e}"))?;
     Ok(KeyMaterial(out))
 }
 
+/// Derives a purpose-bound subkey from `key` using HMAC-SHA256 over a context label.
+///
+/// Subkeys let other components (such as backups) encrypt data with material tied to the vault
+/// key without ever handing out the key that protects the items themselves.
+///
+/// # Errors
+///
+/// Returns an error if the HMAC cannot be initialized with the given key.
+pub fn derive_subkey(key: &KeyMaterial, context: &[u8]) -> Result<KeyMaterial> {
+    let mut mac = <HmacSha256 as Mac>::new_from_slice(&key.0)?;
+    mac.update(context);
+    let mut out = [0u8; 32];
+    out.copy_from_slice(&mac.finalize().into_bytes());
+    Ok(KeyMaterial(out))
+}
+
+/// Computes the value stored with a vault that recognizes its vault key, so a key handed over
+/// without the master password can be checked even when the vault has no items yet.
+///
+/// # Errors
+///
+/// Returns an error if the subkey cannot be derived.
+pub(crate) fn key_check_value(vk: &KeyMaterial) -> Result<Vec<u8>> {
+    Ok(key_check_mac(vk)?.finalize().into_bytes().to_vec())
+}
+
+/// Returns whether `check` is the key check value of `vk`, compared in constant time.
+///
+/// # Errors
+///
+/// Returns an error if the subkey cannot be derived.
+pub(crate) fn verify_key_check_value(vk: &KeyMaterial, check: &[u8]) -> Result<bool> {
+    Ok(key_check_mac(vk)?.verify_slice(check).is_ok())
+}
+
+fn key_check_mac(vk: &KeyMaterial) -> Result<HmacSha256> {
+    let key = derive_subkey(vk, b"chamber-key-check")?;
+    let mut mac = <HmacSha256 as Mac>::new_from_slice(&key.0)?;
+    mac.update(b"vault key");
+    Ok(mac)
+}
+
 // We implement a simple key wrap: derive an AEAD from the master-derived key,
 // generate random nonce and encrypt the vault key; store nonce+ciphertext.
 // Add a verifier: HMAC(master_derived, "chamber-verifier")
@@ -491,6 +628,31 @@ mod tests {
         assert_eq!(km.0.len(), 32);
     }
 
+    #[test]
+    fn test_kdfparams_recommendations_and_resalt() {
+        assert!(KdfParams::default_secure().meets_recommendations());
+        assert!(KdfParams::new(46 * 1024, 1, 1).unwrap().meets_recommendations());
+        assert!(!KdfParams::new(19 * 1024, 1, 1).unwrap().meets_recommendations());
+        assert!(!KdfParams::new(4096, 20, 1).unwrap().meets_recommendations());
+        assert!(KdfParams::new(4, 1, 1).is_err());
+
+        let params = KdfParams::new(256 * 1024, 4, 4).unwrap();
+        let resalted = params.resalted();
+        assert_ne!(resalted.salt, params.salt);
+        assert_eq!(
+            (resalted.m_cost_kib, resalted.t_cost, resalted.p_cost),
+            (256 * 1024, 4, 4)
+        );
+        assert_eq!(params.to_string(), "Argon2id 256 MiB, 4 pass(es), 4 lane(s)");
+    }
+
+    #[test]
+    fn test_kdfparams_tune_respects_recommendations() {
+        let tuned = KdfParams::tune(std::time::Duration::from_millis(1), 8 * 1024, 1).unwrap();
+        assert!(tuned.meets_recommendations());
+        assert_eq!(tuned.m_cost_kib, 8 * 1024);
+    }
+
     #[test]
     fn test_hmac_verifier_stable_for_same_key() {
         let master = "verifier-master";
@@ -1,7 +1,11 @@
+use crate::integrity::IntegrityRow;
+use crate::migrations::{self, SCHEMA_VERSION, SchemaStatus};
+use crate::trash::DEFAULT_TRASH_RETENTION_DAYS;
 use crate::{KdfParams, WrappedVaultKey};
 use color_eyre::Result;
 use color_eyre::eyre::eyre;
-use rusqlite::{Connection, OptionalExtension, params};
+use rusqlite::backup::Progress;
+use rusqlite::{Connection, MAIN_DB, OpenFlags, OptionalExtension, params};
 use time::OffsetDateTime;
 
 #[derive(Debug)]
@@ -22,47 +26,45 @@ impl Db {
     /// # Behavior
     /// 1. Attempts to open a database connection using the given file path.
     /// 2. Initializes the caller struct with the established database connection.
-    /// 3. Triggers any necessary database migrations to ensure the schema is up-to-date.
+    /// 3. Triggers any necessary database migrations to ensure the schema is up-to-date. An
+    ///    existing vault is first copied next to itself (see [`SchemaStatus::snapshot_path`]).
     /// 4. Returns the initialized struct if all operations are successful.
     ///
     /// # Errors
     /// This function will return an error if:
     /// * The database connection cannot be established (e.g., invalid path, file issues).
-    /// * The migration process fails.
+    /// * The vault was written by a newer version of Chamber (see [`SCHEMA_VERSION`]).
+    /// * The pre-migration snapshot or the migration process fails.
     pub fn open(path: &std::path::Path) -> Result<Self> {
         let conn = Connection::open(path)?;
+        conn.execute_batch("PRAGMA journal_mode=WAL; PRAGMA foreign_keys=ON;")?;
         let db = Self { conn };
-        db.migrate()?;
+        let version = db.schema_version()?;
+        if version < SCHEMA_VERSION && migrations::has_tables(&db.conn)? {
+            // Keep a copy of the vault as it was, in case a migration goes wrong
+            let status = SchemaStatus::new(path, version);
+            db.snapshot_to(&status.snapshot_path)?;
+        }
+        migrations::migrate(&db.conn)?;
         Ok(db)
     }
 
-    fn migrate(&self) -> Result<()> {
-        self.conn.execute_batch(
-            r"
-        PRAGMA journal_mode=WAL;
-        PRAGMA foreign_keys=ON;
-
-        CREATE TABLE IF NOT EXISTS meta (
-            id INTEGER PRIMARY KEY CHECK (id = 1),
-            kdf_params TEXT NOT NULL,
-            wrapped_key BLOB NOT NULL,
-            verifier BLOB NOT NULL
-        );
-
-        CREATE TABLE IF NOT EXISTS items (
-            id INTEGER PRIMARY KEY AUTOINCREMENT,
-            name TEXT NOT NULL,
-            kind TEXT NOT NULL,
-            nonce BLOB NOT NULL,
-            ciphertext BLOB NOT NULL,
-            created_at TEXT NOT NULL,
-            updated_at TEXT NOT NULL
-        );
+    /// Returns the schema version stored in the database, see [`SCHEMA_VERSION`].
+    ///
+    /// # Errors
+    /// Returns an error if the version cannot be read.
+    pub fn schema_version(&self) -> Result<u32> {
+        migrations::schema_version(&self.conn)
+    }
 
-        CREATE UNIQUE INDEX IF NOT EXISTS idx_items_name ON items(name);
-        ",
-        )?;
-        Ok(())
+    /// Reports the schema version of the vault file at `path` and the migrations opening it
+    /// would apply, without changing the file.
+    ///
+    /// # Errors
+    /// Returns an error if the file cannot be opened or was written by a newer Chamber.
+    pub fn schema_status(path: &std::path::Path) -> Result<SchemaStatus> {
+        let conn = Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+        Ok(SchemaStatus::new(path, migrations::schema_version(&conn)?))
     }
 
     /// Checks if the `meta` table in the database is empty.
@@ -119,14 +121,235 @@ impl Db {
     pub fn write_meta(&self, kdf: &KdfParams, wrapped: &WrappedVaultKey, verifier: &[u8]) -> Result<()> {
         let kdf_json = serde_json::to_string(kdf)?;
         let wrapped_json = serde_json::to_vec(wrapped)?;
-        self.conn.execute("DELETE FROM meta", [])?;
+        // Upsert so the columns not owned by the master key (e.g. recovery) survive a master change
         self.conn.execute(
-            "INSERT INTO meta (id, kdf_params, wrapped_key, verifier) VALUES (1, ?, ?, ?)",
+            "INSERT INTO meta (id, kdf_params, wrapped_key, verifier) VALUES (1, ?, ?, ?)
+             ON CONFLICT(id) DO UPDATE SET kdf_params = excluded.kdf_params,
+                 wrapped_key = excluded.wrapped_key, verifier = excluded.verifier",
             params![kdf_json, wrapped_json, verifier],
         )?;
         Ok(())
     }
 
+    /// Reads the manifest and its authentication tag, if one has been written.
+    ///
+    /// # Errors
+    /// Returns an error if the query fails.
+    pub fn read_manifest(&self) -> Result<Option<(String, Vec<u8>)>> {
+        let row: Option<(Option<String>, Option<Vec<u8>>)> = self
+            .conn
+            .query_row("SELECT manifest, manifest_mac FROM meta WHERE id = 1", [], |r| {
+                Ok((r.get(0)?, r.get(1)?))
+            })
+            .optional()?;
+        Ok(match row {
+            Some((Some(manifest), Some(mac))) => Some((manifest, mac)),
+            _ => None,
+        })
+    }
+
+    /// Stores the manifest and its authentication tag.
+    ///
+    /// # Errors
+    /// Returns an error if the vault is not initialized or the update fails.
+    pub fn write_manifest(&self, manifest: &str, mac: &[u8]) -> Result<()> {
+        let updated = self.conn.execute(
+            "UPDATE meta SET manifest = ?, manifest_mac = ? WHERE id = 1",
+            params![manifest, mac],
+        )?;
+        if updated == 0 {
+            return Err(eyre!("Vault not initialized"));
+        }
+        Ok(())
+    }
+
+    /// Reads the value that recognizes the vault key, if the vault has one yet.
+    ///
+    /// # Errors
+    /// Returns an error if the query fails.
+    pub fn read_key_check(&self) -> Result<Option<Vec<u8>>> {
+        let check: Option<Option<Vec<u8>>> = self
+            .conn
+            .query_row("SELECT key_check FROM meta WHERE id = 1", [], |r| r.get(0))
+            .optional()?;
+        Ok(check.flatten())
+    }
+
+    /// Stores the value that recognizes the vault key.
+    ///
+    /// # Errors
+    /// Returns an error if the vault is not initialized or the update fails.
+    pub fn write_key_check(&self, check: &[u8]) -> Result<()> {
+        let updated = self
+            .conn
+            .execute("UPDATE meta SET key_check = ? WHERE id = 1", params![check])?;
+        if updated == 0 {
+            return Err(eyre!("Vault not initialized"));
+        }
+        Ok(())
+    }
+
+    /// Lists what the manifest records about every item, ordered by id.
+    ///
+    /// # Errors
+    /// Returns an error if the query fails.
+    pub fn item_states(&self) -> Result<Vec<ItemStateRow>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT i.id, COALESCE(MAX(h.revision), 0), i.nonce, i.labels_nonce, i.deleted_at FROM items i
+             LEFT JOIN item_history h ON h.item_id = i.id GROUP BY i.id ORDER BY i.id ASC",
+        )?;
+        let rows = stmt
+            .query_map([], |r| {
+                Ok(ItemStateRow {
+                    id: r.get(0)?,
+                    revision: r.get(1)?,
+                    nonce: r.get(2)?,
+                    labels_nonce: r.get(3)?,
+                    deleted_at: r.get(4)?,
+                })
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(rows)
+    }
+
+    /// Stores the vault key wrapped with the recovery key, or clears it with `None`.
+    ///
+    /// # Errors
+    /// Returns an error if the vault is not initialized or the update fails.
+    pub fn write_recovery(&self, recovery: Option<(&WrappedVaultKey, &[u8])>) -> Result<()> {
+        let (wrapped_json, verifier) = match recovery {
+            Some((wrapped, verifier)) => (Some(serde_json::to_vec(wrapped)?), Some(verifier)),
+            None => (None, None),
+        };
+        let updated = self.conn.execute(
+            "UPDATE meta SET recovery_key = ?, recovery_verifier = ? WHERE id = 1",
+            params![wrapped_json, verifier],
+        )?;
+        if updated == 0 {
+            return Err(eyre!("Vault not initialized"));
+        }
+        Ok(())
+    }
+
+    /// Returns whether item names and kinds are stored encrypted.
+    ///
+    /// # Errors
+    /// Returns an error if the query fails.
+    pub fn private_metadata(&self) -> Result<bool> {
+        Ok(self
+            .conn
+            .query_row("SELECT private_metadata FROM meta WHERE id = 1", [], |r| r.get(0))
+            .optional()?
+            .unwrap_or(false))
+    }
+
+    /// Records whether item names and kinds are stored encrypted.
+    ///
+    /// # Errors
+    /// Returns an error if the vault is not initialized or the update fails.
+    pub fn set_private_metadata(&self, enabled: bool) -> Result<()> {
+        if self
+            .conn
+            .execute("UPDATE meta SET private_metadata = ? WHERE id = 1", params![enabled])?
+            == 0
+        {
+            return Err(eyre!("Vault not initialized"));
+        }
+        Ok(())
+    }
+
+    /// Reads the vault key wrapped with the recovery key and its verifier, if recovery is set up.
+    ///
+    /// # Errors
+    /// Returns an error if the query fails or the stored key cannot be deserialized.
+    pub fn read_recovery(&self) -> Result<Option<(WrappedVaultKey, Vec<u8>)>> {
+        let row = self
+            .conn
+            .query_row(
+                "SELECT recovery_key, recovery_verifier FROM meta WHERE id = 1",
+                [],
+                |r| {
+                    let wrapped_blob: Option<Vec<u8>> = r.get(0)?;
+                    let verifier: Option<Vec<u8>> = r.get(1)?;
+                    Ok(wrapped_blob.zip(verifier))
+                },
+            )
+            .optional()?
+            .flatten();
+        row.map(|(wrapped_blob, verifier)| Ok((serde_json::from_slice(&wrapped_blob)?, verifier)))
+            .transpose()
+    }
+
+    /// Adds a key slot wrapping the vault key under `name` and returns its ID.
+    ///
+    /// # Errors
+    /// Returns an error if a slot with the same name exists or the SQL execution fails.
+    pub fn insert_key_slot(
+        &self,
+        name: &str,
+        kdf: &KdfParams,
+        wrapped: &WrappedVaultKey,
+        verifier: &[u8],
+    ) -> Result<u64> {
+        self.conn.execute(
+            "INSERT INTO key_slots (name, kdf_params, wrapped_key, verifier, created_at) VALUES (?, ?, ?, ?, ?)",
+            params![
+                name,
+                serde_json::to_string(kdf)?,
+                serde_json::to_vec(wrapped)?,
+                verifier,
+                OffsetDateTime::now_utc().format(&time::format_description::well_known::Rfc3339)?
+            ],
+        )?;
+        Ok(u64::try_from(self.conn.last_insert_rowid())?)
+    }
+
+    /// Lists the key slots in the order they were added.
+    ///
+    /// # Errors
+    /// Returns an error if the query fails or a slot's KDF parameters or wrapped key cannot be
+    /// deserialized.
+    pub fn list_key_slots(&self) -> Result<Vec<KeySlotRow>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT id, name, kdf_params, wrapped_key, verifier, created_at FROM key_slots ORDER BY id ASC")?;
+        let rows = stmt.query_map([], |r| {
+            Ok((
+                r.get::<_, u64>(0)?,
+                r.get::<_, String>(1)?,
+                r.get::<_, String>(2)?,
+                r.get::<_, Vec<u8>>(3)?,
+                r.get::<_, Vec<u8>>(4)?,
+                parse_timestamp(&r.get::<_, String>(5)?)?,
+            ))
+        })?;
+        let mut out = Vec::new();
+        for r in rows {
+            // Parse JSON outside the row-mapper so errors are reported as eyre errors
+            let (id, name, kdf_json, wrapped_blob, verifier, created_at) = r?;
+            out.push(KeySlotRow {
+                id,
+                name,
+                kdf: serde_json::from_str(&kdf_json)?,
+                wrapped: serde_json::from_slice(&wrapped_blob)?,
+                verifier,
+                created_at,
+            });
+        }
+        Ok(out)
+    }
+
+    /// Deletes the key slot called `name`; returns `false` when there was none.
+    ///
+    /// # Errors
+    /// Returns an error if the SQL execution fails.
+    pub fn delete_key_slot(&self, name: &str) -> Result<bool> {
+        Ok(self
+            .conn
+            .execute("DELETE FROM key_slots WHERE name = ?", params![name])?
+            > 0)
+    }
+
     /// Reads metadata from the database.
     ///
     /// This method retrieves metadata from the `meta` table in the database with the `id` of 1.
@@ -208,10 +431,46 @@ impl Db {
     ///   - The current timestamp could not be formatted as RFC 3339.
     ///   - There is a database insertion failure for any reason (e.g., constraint violation, I/O error).
     pub fn insert_item(&self, name: &str, kind: &str, nonce: &[u8], ciphertext: &[u8]) -> Result<()> {
+        self.insert_item_with_labels(name, kind, nonce, ciphertext, None, None)
+    }
+
+    /// Inserts an item together with its encrypted labels (tags and folder).
+    ///
+    /// Behaves like [`Db::insert_item`]; `labels` is the `(nonce, ciphertext)` pair of the
+    /// encrypted labels, which the vault seals even for an item without tags or folder. `header` is the
+    /// encrypted name and kind of an item in a vault with private metadata, where `name` is the
+    /// item's blind index.
+    ///
+    /// # Errors
+    /// Returns an error under the same conditions as [`Db::insert_item`].
+    pub fn insert_item_with_labels(
+        &self,
+        name: &str,
+        kind: &str,
+        nonce: &[u8],
+        ciphertext: &[u8],
+        labels: Option<(&[u8], &[u8])>,
+        header: Option<(&[u8], &[u8])>,
+    ) -> Result<()> {
         let now = OffsetDateTime::now_utc().format(&time::format_description::well_known::Rfc3339)?;
+        let (labels_nonce, labels_ciphertext) = labels.unzip();
+        let (header_nonce, header_ciphertext) = header.unzip();
         match self.conn.execute(
-            "INSERT INTO items (name, kind, nonce, ciphertext, created_at, updated_at) VALUES (?, ?, ?, ?, ?, ?)",
-            params![name, kind, nonce, ciphertext, now, now],
+            "INSERT INTO items (name, kind, nonce, ciphertext, created_at, updated_at, labels_nonce, labels_ciphertext,
+                 header_nonce, header_ciphertext)
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+            params![
+                name,
+                kind,
+                nonce,
+                ciphertext,
+                now,
+                now,
+                labels_nonce,
+                labels_ciphertext,
+                header_nonce,
+                header_ciphertext
+            ],
         ) {
             Ok(_) => Ok(()),
             Err(e) => {
@@ -261,28 +520,30 @@ impl Db {
     /// a vector before returning it. Ensure that the `items` table structure in the database
     /// matches the fields being queried (`id`, `name`, `kind`, `nonce`, `ciphertext`, `created_at`, `updated_at`).
     pub fn list_items(&self) -> Result<Vec<ItemRow>> {
-        let mut stmt = self
-            .conn
-            .prepare("SELECT id, name, kind, nonce, ciphertext, created_at, updated_at FROM items ORDER BY name ASC")?;
-        let rows = stmt.query_map([], |r| {
-            Ok(ItemRow {
-                id: r.get(0)?,
-                name: r.get(1)?,
-                kind: r.get(2)?,
-                nonce: r.get(3)?,
-                ciphertext: r.get(4)?,
-                created_at: OffsetDateTime::parse(
-                    &r.get::<_, String>(5)?,
-                    &time::format_description::well_known::Rfc3339,
-                )
-                .map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))?, // Add comma and error conversion
-                updated_at: OffsetDateTime::parse(
-                    &r.get::<_, String>(6)?,
-                    &time::format_description::well_known::Rfc3339,
-                )
-                .map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))?,
-            })
-        })?;
+        let mut stmt = self.conn.prepare(
+            "SELECT id, name, kind, nonce, ciphertext, created_at, updated_at, labels_nonce, labels_ciphertext,
+                        header_nonce, header_ciphertext, deleted_at
+                 FROM items ORDER BY name ASC",
+        )?;
+        let rows = stmt.query_map([], ItemRow::from_row)?;
+        let mut out = Vec::new();
+        for r in rows {
+            out.push(r?);
+        }
+        Ok(out)
+    }
+
+    /// Lists the metadata of all items, ordered by name, without reading their encrypted values.
+    ///
+    /// # Errors
+    /// Returns an error if the query fails or a stored timestamp cannot be parsed.
+    pub fn list_item_headers(&self) -> Result<Vec<ItemHeaderRow>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, name, kind, created_at, updated_at, labels_nonce, labels_ciphertext, header_nonce, header_ciphertext,
+                        deleted_at
+                 FROM items ORDER BY name ASC",
+        )?;
+        let rows = stmt.query_map([], ItemHeaderRow::from_row)?;
         let mut out = Vec::new();
         for r in rows {
             out.push(r?);
@@ -290,6 +551,43 @@ impl Db {
         Ok(out)
     }
 
+    /// Fetches a single item by its id, using the primary key.
+    ///
+    /// # Errors
+    /// Returns an error if the query fails or a stored timestamp cannot be parsed.
+    pub fn get_item(&self, id: u64) -> Result<Option<ItemRow>> {
+        let row = self
+            .conn
+            .query_row(
+                "SELECT id, name, kind, nonce, ciphertext, created_at, updated_at, labels_nonce, labels_ciphertext,
+                        header_nonce, header_ciphertext, deleted_at
+                 FROM items WHERE id = ?",
+                params![id],
+                ItemRow::from_row,
+            )
+            .optional()?;
+        Ok(row)
+    }
+
+    /// Fetches a single item by its exact name, using the unique name index. Items in the trash
+    /// are not matched, since their names may have been taken again.
+    ///
+    /// # Errors
+    /// Returns an error if the query fails or a stored timestamp cannot be parsed.
+    pub fn get_item_by_name(&self, name: &str) -> Result<Option<ItemRow>> {
+        let row = self
+            .conn
+            .query_row(
+                "SELECT id, name, kind, nonce, ciphertext, created_at, updated_at, labels_nonce, labels_ciphertext,
+                        header_nonce, header_ciphertext, deleted_at
+                 FROM items WHERE name = ? AND deleted_at IS NULL",
+                params![name],
+                ItemRow::from_row,
+            )
+            .optional()?;
+        Ok(row)
+    }
+
     /// Deletes an item from the database with the specified `id`.
     ///
     /// # Arguments
@@ -311,6 +609,48 @@ impl Db {
         Ok(())
     }
 
+    /// Moves an item to the trash, or takes it out again with `None`. Returns whether an item
+    /// with `id` exists.
+    ///
+    /// # Errors
+    /// Returns an error if the timestamp formatting or the SQL execution fails.
+    pub fn set_deleted_at(&self, id: u64, deleted_at: Option<OffsetDateTime>) -> Result<bool> {
+        let deleted_at = deleted_at
+            .map(|t| t.format(&time::format_description::well_known::Rfc3339))
+            .transpose()?;
+        let updated = self
+            .conn
+            .execute("UPDATE items SET deleted_at = ? WHERE id = ?", params![deleted_at, id])?;
+        Ok(updated > 0)
+    }
+
+    /// Returns how many days items stay in the trash before they are purged; 0 keeps them until
+    /// the trash is emptied.
+    ///
+    /// # Errors
+    /// Returns an error if the query fails.
+    pub fn trash_retention_days(&self) -> Result<u32> {
+        let days = self
+            .conn
+            .query_row("SELECT trash_retention_days FROM meta WHERE id = 1", [], |r| r.get(0))
+            .optional()?;
+        Ok(days.unwrap_or(DEFAULT_TRASH_RETENTION_DAYS))
+    }
+
+    /// Stores how many days items stay in the trash.
+    ///
+    /// # Errors
+    /// Returns an error if the vault is not initialized or the update fails.
+    pub fn set_trash_retention_days(&self, days: u32) -> Result<()> {
+        let updated = self
+            .conn
+            .execute("UPDATE meta SET trash_retention_days = ? WHERE id = 1", params![days])?;
+        if updated == 0 {
+            return Err(eyre!("Vault not initialized"));
+        }
+        Ok(())
+    }
+
     /// Updates an item in the database with new encrypted data.
     ///
     /// # Parameters
@@ -327,17 +667,476 @@ impl Db {
     /// the target item using the provided `id`. The database interaction is performed using a
     /// connection (`self.conn`) and an SQL `UPDATE` query.
     ///
+    /// The value being replaced is first copied into `item_history` as the item's next revision,
+    /// inside the same transaction, so an update can never lose the previous ciphertext.
+    ///
     /// # Errors
     /// - Returns an error if the timestamp formatting fails.
     /// - Returns an error if the SQL execution fails.
     pub fn update_item(&self, id: u64, nonce: &[u8], ciphertext: &[u8]) -> Result<()> {
         let now = OffsetDateTime::now_utc().format(&time::format_description::well_known::Rfc3339)?;
-        self.conn.execute(
+        let tx = self.conn.unchecked_transaction()?;
+        tx.execute(
+            "INSERT INTO item_history (item_id, revision, nonce, ciphertext, created_at, archived_at)
+             SELECT id,
+                    (SELECT COALESCE(MAX(revision), 0) + 1 FROM item_history WHERE item_id = items.id),
+                    nonce, ciphertext, updated_at, ?
+             FROM items WHERE id = ?",
+            params![now, id],
+        )?;
+        tx.execute(
             "UPDATE items SET nonce = ?, ciphertext = ?, updated_at = ? WHERE id = ?",
             params![nonce, ciphertext, now, id],
         )?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Replaces the encrypted labels (tags and folder) of an item.
+    ///
+    /// Labels are metadata, so the item's value, `updated_at` and history are left untouched.
+    /// Passing `None` clears the labels, which the vault treats as tampering.
+    ///
+    /// # Errors
+    /// Returns an error if the SQL execution fails.
+    pub fn update_labels(&self, id: u64, labels: Option<(&[u8], &[u8])>) -> Result<()> {
+        let (labels_nonce, labels_ciphertext) = labels.unzip();
+        self.conn.execute(
+            "UPDATE items SET labels_nonce = ?, labels_ciphertext = ? WHERE id = ?",
+            params![labels_nonce, labels_ciphertext, id],
+        )?;
+        Ok(())
+    }
+
+    /// Starts a transaction on the vault's connection; every `Db` call made before it is
+    /// committed is part of it, and dropping it without committing rolls them back.
+    pub(crate) fn transaction(&self) -> Result<rusqlite::Transaction<'_>> {
+        Ok(self.conn.unchecked_transaction()?)
+    }
+
+    /// Replaces the encrypted value of an item in place, without archiving the previous value
+    /// or touching `updated_at`. Used when the same value is re-encrypted under a new key.
+    ///
+    /// # Errors
+    /// Returns an error if the SQL execution fails.
+    pub fn reseal_item(&self, id: u64, nonce: &[u8], ciphertext: &[u8]) -> Result<()> {
+        self.conn.execute(
+            "UPDATE items SET nonce = ?, ciphertext = ? WHERE id = ?",
+            params![nonce, ciphertext, id],
+        )?;
+        Ok(())
+    }
+
+    /// Replaces the encrypted value of an archived revision in place.
+    ///
+    /// # Errors
+    /// Returns an error if the SQL execution fails.
+    pub fn reseal_history(&self, item_id: u64, revision: u64, nonce: &[u8], ciphertext: &[u8]) -> Result<()> {
+        self.conn.execute(
+            "UPDATE item_history SET nonce = ?, ciphertext = ? WHERE item_id = ? AND revision = ?",
+            params![nonce, ciphertext, item_id, revision],
+        )?;
+        Ok(())
+    }
+
+    /// Replaces the stored name, kind and encrypted header of an item, e.g. when a vault switches
+    /// to or from private metadata. `updated_at` is left untouched.
+    ///
+    /// # Errors
+    /// Returns an error if another item already uses `name` or the SQL execution fails.
+    pub fn update_item_header(&self, id: u64, name: &str, kind: &str, header: Option<(&[u8], &[u8])>) -> Result<()> {
+        let (header_nonce, header_ciphertext) = header.unzip();
+        self.conn.execute(
+            "UPDATE items SET name = ?, kind = ?, header_nonce = ?, header_ciphertext = ? WHERE id = ?",
+            params![name, kind, header_nonce, header_ciphertext, id],
+        )?;
+        Ok(())
+    }
+
+    /// Lists the archived revisions of an item, oldest first.
+    ///
+    /// Each row holds a value the item had before one of its updates, still encrypted with the
+    /// item's associated data.
+    ///
+    /// # Errors
+    /// Returns an error if the query fails or a stored timestamp cannot be parsed.
+    pub fn list_history(&self, item_id: u64) -> Result<Vec<HistoryRow>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT item_id, revision, nonce, ciphertext, created_at, archived_at
+             FROM item_history WHERE item_id = ? ORDER BY revision ASC",
+        )?;
+        let rows = stmt.query_map(params![item_id], HistoryRow::from_row)?;
+        let mut out = Vec::new();
+        for r in rows {
+            out.push(r?);
+        }
+        Ok(out)
+    }
+
+    /// Fetches a single archived revision of an item.
+    ///
+    /// # Errors
+    /// Returns an error if the query fails or a stored timestamp cannot be parsed.
+    pub fn get_history(&self, item_id: u64, revision: u64) -> Result<Option<HistoryRow>> {
+        let row = self
+            .conn
+            .query_row(
+                "SELECT item_id, revision, nonce, ciphertext, created_at, archived_at
+                 FROM item_history WHERE item_id = ? AND revision = ?",
+                params![item_id, revision],
+                HistoryRow::from_row,
+            )
+            .optional()?;
+        Ok(row)
+    }
+
+    /// Starts a new attachment of an item and returns its id.
+    ///
+    /// The attachment stays pending, and is left out of [`Db::list_attachments`], until
+    /// [`Db::complete_attachment`] stores its encrypted metadata. Pending attachments older than
+    /// a day are left over from interrupted uploads and are removed here.
+    ///
+    /// # Errors
+    /// Returns an error if the item does not exist or the SQL execution fails.
+    pub fn insert_attachment(&self, item_id: u64) -> Result<u64> {
+        let now = OffsetDateTime::now_utc();
+        let stale = (now - time::Duration::days(1)).format(&time::format_description::well_known::Rfc3339)?;
+        self.conn.execute(
+            "DELETE FROM attachments WHERE meta_nonce IS NULL AND created_at < ?",
+            params![stale],
+        )?;
+        self.conn.execute(
+            "INSERT INTO attachments (item_id, created_at) VALUES (?, ?)",
+            params![item_id, now.format(&time::format_description::well_known::Rfc3339)?],
+        )?;
+        Ok(u64::try_from(self.conn.last_insert_rowid())?)
+    }
+
+    /// Stores one encrypted chunk of a pending attachment.
+    ///
+    /// # Errors
+    /// Returns an error if the attachment does not exist, the chunk was already written, or the
+    /// SQL execution fails.
+    pub fn insert_attachment_chunk(
+        &self,
+        attachment_id: u64,
+        index: u64,
+        nonce: &[u8],
+        ciphertext: &[u8],
+    ) -> Result<()> {
+        self.conn.execute(
+            "INSERT INTO attachment_chunks (attachment_id, chunk_index, nonce, ciphertext) VALUES (?, ?, ?, ?)",
+            params![attachment_id, index, nonce, ciphertext],
+        )?;
+        Ok(())
+    }
+
+    /// Replaces one encrypted attachment chunk in place.
+    ///
+    /// # Errors
+    /// Returns an error if the SQL execution fails.
+    pub fn reseal_attachment_chunk(
+        &self,
+        attachment_id: u64,
+        index: u64,
+        nonce: &[u8],
+        ciphertext: &[u8],
+    ) -> Result<()> {
+        self.conn.execute(
+            "UPDATE attachment_chunks SET nonce = ?, ciphertext = ? WHERE attachment_id = ? AND chunk_index = ?",
+            params![nonce, ciphertext, attachment_id, index],
+        )?;
+        Ok(())
+    }
+
+    /// Deletes every attachment that was started but never finished, returning how many.
+    ///
+    /// # Errors
+    /// Returns an error if the SQL execution fails.
+    pub fn delete_pending_attachments(&self) -> Result<usize> {
+        Ok(self
+            .conn
+            .execute("DELETE FROM attachments WHERE meta_nonce IS NULL", [])?)
+    }
+
+    /// Stores the encrypted metadata of a pending attachment, which makes it visible.
+    ///
+    /// # Errors
+    /// Returns an error if the SQL execution fails.
+    pub fn complete_attachment(&self, attachment_id: u64, meta_nonce: &[u8], meta_ciphertext: &[u8]) -> Result<()> {
+        self.conn.execute(
+            "UPDATE attachments SET meta_nonce = ?, meta_ciphertext = ? WHERE id = ?",
+            params![meta_nonce, meta_ciphertext, attachment_id],
+        )?;
+        Ok(())
+    }
+
+    /// Lists the completed attachments of an item, oldest first.
+    ///
+    /// # Errors
+    /// Returns an error if the query fails or a stored timestamp cannot be parsed.
+    pub fn list_attachments(&self, item_id: u64) -> Result<Vec<AttachmentRow>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, item_id, meta_nonce, meta_ciphertext, created_at FROM attachments
+             WHERE item_id = ? AND meta_nonce IS NOT NULL ORDER BY id ASC",
+        )?;
+        let rows = stmt.query_map(params![item_id], AttachmentRow::from_row)?;
+        let mut out = Vec::new();
+        for r in rows {
+            out.push(r?);
+        }
+        Ok(out)
+    }
+
+    /// Fetches a single completed attachment.
+    ///
+    /// # Errors
+    /// Returns an error if the query fails or a stored timestamp cannot be parsed.
+    pub fn get_attachment(&self, attachment_id: u64) -> Result<Option<AttachmentRow>> {
+        let row = self
+            .conn
+            .query_row(
+                "SELECT id, item_id, meta_nonce, meta_ciphertext, created_at FROM attachments
+                 WHERE id = ? AND meta_nonce IS NOT NULL",
+                params![attachment_id],
+                AttachmentRow::from_row,
+            )
+            .optional()?;
+        Ok(row)
+    }
+
+    /// Fetches the `(nonce, ciphertext)` pair of one attachment chunk.
+    ///
+    /// # Errors
+    /// Returns an error if the query fails.
+    pub fn get_attachment_chunk(&self, attachment_id: u64, index: u64) -> Result<Option<(Vec<u8>, Vec<u8>)>> {
+        let chunk = self
+            .conn
+            .query_row(
+                "SELECT nonce, ciphertext FROM attachment_chunks WHERE attachment_id = ? AND chunk_index = ?",
+                params![attachment_id, index],
+                |r| Ok((r.get(0)?, r.get(1)?)),
+            )
+            .optional()?;
+        Ok(chunk)
+    }
+
+    /// Deletes an attachment together with its chunks.
+    ///
+    /// # Errors
+    /// Returns an error if the SQL execution fails.
+    pub fn delete_attachment(&self, attachment_id: u64) -> Result<()> {
+        self.conn
+            .execute("DELETE FROM attachments WHERE id = ?", params![attachment_id])?;
+        Ok(())
+    }
+
+    /// Runs `PRAGMA integrity_check` and returns the problems it reports, which is empty when the
+    /// file is sound.
+    ///
+    /// # Errors
+    /// Returns an error if the check cannot be run at all.
+    pub fn integrity_check(&self) -> Result<Vec<String>> {
+        let mut stmt = self.conn.prepare("PRAGMA integrity_check")?;
+        let messages = stmt
+            .query_map([], |r| r.get::<_, String>(0))?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(messages.into_iter().filter(|m| m != "ok").collect())
+    }
+
+    /// Finds revisions and attachments whose item no longer exists, and attachment chunks whose
+    /// attachment no longer exists.
+    ///
+    /// # Errors
+    /// Returns an error if a query fails.
+    pub fn orphan_rows(&self) -> Result<Vec<IntegrityRow>> {
+        let mut out = Vec::new();
+        let mut stmt = self
+            .conn
+            .prepare("SELECT item_id, revision FROM item_history WHERE item_id NOT IN (SELECT id FROM items)")?;
+        for row in stmt.query_map([], |r| {
+            Ok(IntegrityRow::Revision {
+                item_id: r.get(0)?,
+                revision: r.get(1)?,
+            })
+        })? {
+            out.push(row?);
+        }
+        let mut stmt = self
+            .conn
+            .prepare("SELECT id FROM attachments WHERE item_id NOT IN (SELECT id FROM items)")?;
+        for row in stmt.query_map([], |r| Ok(IntegrityRow::Attachment(r.get(0)?)))? {
+            out.push(row?);
+        }
+        let mut stmt = self.conn.prepare(
+            "SELECT attachment_id, chunk_index FROM attachment_chunks
+             WHERE attachment_id NOT IN (SELECT id FROM attachments)",
+        )?;
+        for row in stmt.query_map([], |r| {
+            Ok(IntegrityRow::AttachmentChunk {
+                attachment_id: r.get(0)?,
+                index: r.get(1)?,
+            })
+        })? {
+            out.push(row?);
+        }
+        Ok(out)
+    }
+
+    /// Moves `row`, and the rows that depend on it, into the `quarantine` table and returns how
+    /// many rows were moved.
+    ///
+    /// Each row is kept as a JSON object of its columns, with blobs hex-encoded, so nothing is
+    /// lost and the rest of the vault no longer trips over it.
+    ///
+    /// # Errors
+    /// Returns an error if the SQL execution fails.
+    pub fn quarantine(&self, row: IntegrityRow, problem: &str) -> Result<usize> {
+        let moved = match row {
+            IntegrityRow::Item(id) => {
+                self.quarantine_rows(
+                    "attachment_chunks",
+                    "attachment_id IN (SELECT id FROM attachments WHERE item_id = ?1)",
+                    &[&id],
+                    problem,
+                )? + self.quarantine_rows("attachments", "item_id = ?1", &[&id], problem)?
+                    + self.quarantine_rows("item_history", "item_id = ?1", &[&id], problem)?
+                    + self.quarantine_rows("items", "id = ?1", &[&id], problem)?
+            }
+            IntegrityRow::Revision { item_id, revision } => self.quarantine_rows(
+                "item_history",
+                "item_id = ?1 AND revision = ?2",
+                &[&item_id, &revision],
+                problem,
+            )?,
+            IntegrityRow::Attachment(id) => {
+                self.quarantine_rows("attachment_chunks", "attachment_id = ?1", &[&id], problem)?
+                    + self.quarantine_rows("attachments", "id = ?1", &[&id], problem)?
+            }
+            IntegrityRow::AttachmentChunk { attachment_id, index } => self.quarantine_rows(
+                "attachment_chunks",
+                "attachment_id = ?1 AND chunk_index = ?2",
+                &[&attachment_id, &index],
+                problem,
+            )?,
+        };
+        Ok(moved)
+    }
+
+    fn quarantine_rows(
+        &self,
+        table: &str,
+        condition: &str,
+        args: &[&dyn rusqlite::ToSql],
+        problem: &str,
+    ) -> Result<usize> {
+        let mut stmt = self.conn.prepare(&format!("PRAGMA table_info({table})"))?;
+        let columns = stmt
+            .query_map([], |r| r.get::<_, String>(1))?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        let fields = columns
+            .iter()
+            .map(|c| format!("'{c}', CASE WHEN typeof({c}) = 'blob' THEN hex({c}) ELSE {c} END"))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let now = OffsetDateTime::now_utc().format(&time::format_description::well_known::Rfc3339)?;
+        let mut insert_args = args.to_vec();
+        insert_args.push(&problem);
+        insert_args.push(&now);
+        self.conn.execute(
+            &format!(
+                "INSERT INTO quarantine (source_table, source_rowid, problem, row_data, quarantined_at)
+                 SELECT '{table}', rowid, ?{}, json_object({fields}), ?{} FROM {table} WHERE {condition}",
+                args.len() + 1,
+                args.len() + 2
+            ),
+            insert_args.as_slice(),
+        )?;
+        let moved = self
+            .conn
+            .execute(&format!("DELETE FROM {table} WHERE {condition}"), args)?;
+        Ok(moved)
+    }
+
+    /// Returns how many rows have been moved into quarantine.
+    ///
+    /// # Errors
+    /// Returns an error if the query fails.
+    pub fn quarantined_rows(&self) -> Result<usize> {
+        let count: i64 = self
+            .conn
+            .query_row("SELECT COUNT(*) FROM quarantine", [], |r| r.get(0))?;
+        Ok(usize::try_from(count)?)
+    }
+
+    /// Rewrites the database file without free pages and truncates the write-ahead log, so that
+    /// data which has been overwritten or deleted no longer lingers in either file.
+    ///
+    /// # Errors
+    /// Returns an error if the checkpoint or `VACUUM` fails.
+    pub fn vacuum(&self) -> Result<()> {
+        self.conn.execute_batch("VACUUM; PRAGMA wal_checkpoint(TRUNCATE);")?;
+        Ok(())
+    }
+
+    /// Copies the whole database into a standalone file using `SQLite`'s online backup API.
+    ///
+    /// The snapshot is taken page by page from the live connection, so it is consistent even
+    /// while the vault is in use. Its contents are exactly what is stored on disk: items stay
+    /// encrypted and the KDF parameters, wrapped key, ids and timestamps are preserved. No key
+    /// is needed, which means a locked vault can be snapshotted.
+    ///
+    /// # Arguments
+    /// * `dest` - Path of the snapshot file. An existing file at that path is overwritten.
+    ///
+    /// # Errors
+    /// Returns an error if the destination cannot be opened or the backup step fails.
+    pub fn snapshot_to(&self, dest: &std::path::Path) -> Result<()> {
+        self.conn.backup(MAIN_DB, dest, None)?;
         Ok(())
     }
+
+    /// Replaces the contents of this database with a snapshot created by [`Db::snapshot_to`].
+    ///
+    /// The snapshot is verified with [`Db::verify_snapshot`] before anything is overwritten.
+    /// After a successful restore the database is page-for-page identical to the snapshot,
+    /// including the key material, so it must be unlocked with the snapshot's master password.
+    ///
+    /// # Errors
+    /// Returns an error if the snapshot fails verification or the restore step fails.
+    pub fn restore_from(&mut self, src: &std::path::Path) -> Result<()> {
+        Self::verify_snapshot(src)?;
+        self.conn.restore(MAIN_DB, src, None::<fn(Progress)>)?;
+        // Snapshots taken by older versions are brought up to the current schema
+        migrations::migrate(&self.conn)
+    }
+
+    /// Checks that a file is an intact vault snapshot and returns the number of items it holds.
+    ///
+    /// The file is opened without running migrations, passed through `PRAGMA integrity_check`,
+    /// and must contain initialized vault metadata.
+    ///
+    /// # Errors
+    /// Returns an error if the file is not a readable `SQLite` database, fails the integrity
+    /// check, or does not contain an initialized Chamber vault.
+    pub fn verify_snapshot(path: &std::path::Path) -> Result<usize> {
+        let conn = Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+        migrations::schema_version(&conn)?;
+        let integrity: String = conn.query_row("PRAGMA integrity_check", [], |r| r.get(0))?;
+        if integrity != "ok" {
+            return Err(eyre!("Snapshot failed the integrity check: {integrity}"));
+        }
+
+        let meta_rows: i64 = conn
+            .query_row("SELECT COUNT(*) FROM meta", [], |r| r.get(0))
+            .map_err(|_| eyre!("Not a Chamber vault snapshot: {}", path.display()))?;
+        if meta_rows != 1 {
+            return Err(eyre!("Snapshot does not contain an initialized vault"));
+        }
+
+        let items: i64 = conn.query_row("SELECT COUNT(*) FROM items", [], |r| r.get(0))?;
+        Ok(usize::try_from(items)?)
+    }
 }
 
 pub struct ItemRow {
@@ -348,6 +1147,160 @@ pub struct ItemRow {
     pub ciphertext: Vec<u8>,
     pub created_at: OffsetDateTime,
     pub updated_at: OffsetDateTime,
+    pub labels_nonce: Option<Vec<u8>>,
+    pub labels_ciphertext: Option<Vec<u8>>,
+    /// Encrypted name and kind; set when the vault has private metadata, in which case `name`
+    /// holds the item's blind index and `kind` is empty.
+    pub header_nonce: Option<Vec<u8>>,
+    pub header_ciphertext: Option<Vec<u8>>,
+    /// When the item was moved to the trash, if it is there.
+    pub deleted_at: Option<OffsetDateTime>,
+}
+
+/// The metadata of an item, as stored in `items`, without its encrypted value.
+pub struct ItemHeaderRow {
+    pub id: u64,
+    pub name: String,
+    pub kind: String,
+    pub created_at: OffsetDateTime,
+    pub updated_at: OffsetDateTime,
+    pub labels_nonce: Option<Vec<u8>>,
+    pub labels_ciphertext: Option<Vec<u8>>,
+    /// Encrypted name and kind; set when the vault has private metadata, in which case `name`
+    /// holds the item's blind index and `kind` is empty.
+    pub header_nonce: Option<Vec<u8>>,
+    pub header_ciphertext: Option<Vec<u8>>,
+    /// When the item was moved to the trash, if it is there.
+    pub deleted_at: Option<OffsetDateTime>,
+}
+
+/// The parts of an item that change with every write, as listed by [`Db::item_states`].
+pub struct ItemStateRow {
+    pub id: u64,
+    /// Newest archived revision, or 0 if the item has no history.
+    pub revision: u64,
+    /// Nonce of the current value.
+    pub nonce: Vec<u8>,
+    /// Nonce of the encrypted labels; `None` only in vaults written before labels were always
+    /// sealed.
+    pub labels_nonce: Option<Vec<u8>>,
+    /// When the item was moved to the trash, exactly as stored.
+    pub deleted_at: Option<String>,
+}
+
+/// An archived value of an item, as stored in `item_history`.
+pub struct HistoryRow {
+    pub item_id: u64,
+    pub revision: u64,
+    pub nonce: Vec<u8>,
+    pub ciphertext: Vec<u8>,
+    /// When this value was originally written to the item.
+    pub created_at: OffsetDateTime,
+    /// When this value was replaced by a newer one.
+    pub archived_at: OffsetDateTime,
+}
+
+/// A key slot: the vault key wrapped with a key derived from one of several passwords.
+pub struct KeySlotRow {
+    pub id: u64,
+    pub name: String,
+    pub kdf: KdfParams,
+    pub wrapped: WrappedVaultKey,
+    pub verifier: Vec<u8>,
+    pub created_at: OffsetDateTime,
+}
+
+/// An attachment of an item, as stored in `attachments`.
+///
+/// The file name and size live in the encrypted metadata; the contents are stored separately
+/// in `attachment_chunks`.
+pub struct AttachmentRow {
+    pub id: u64,
+    pub item_id: u64,
+    pub meta_nonce: Vec<u8>,
+    pub meta_ciphertext: Vec<u8>,
+    pub created_at: OffsetDateTime,
+}
+
+impl ItemRow {
+    fn from_row(r: &rusqlite::Row<'_>) -> rusqlite::Result<Self> {
+        Ok(Self {
+            id: r.get(0)?,
+            name: r.get(1)?,
+            kind: r.get(2)?,
+            nonce: r.get(3)?,
+            ciphertext: r.get(4)?,
+            created_at: parse_timestamp(&r.get::<_, String>(5)?)?,
+            updated_at: parse_timestamp(&r.get::<_, String>(6)?)?,
+            labels_nonce: r.get(7)?,
+            labels_ciphertext: r.get(8)?,
+            header_nonce: r.get(9)?,
+            header_ciphertext: r.get(10)?,
+            deleted_at: parse_optional_timestamp(r.get_ref(11)?.as_str_or_null()?)?,
+        })
+    }
+}
+
+impl ItemHeaderRow {
+    fn from_row(r: &rusqlite::Row<'_>) -> rusqlite::Result<Self> {
+        Ok(Self {
+            id: r.get(0)?,
+            name: r.get(1)?,
+            kind: r.get(2)?,
+            created_at: parse_timestamp(&r.get::<_, String>(3)?)?,
+            updated_at: parse_timestamp(&r.get::<_, String>(4)?)?,
+            labels_nonce: r.get(5)?,
+            labels_ciphertext: r.get(6)?,
+            header_nonce: r.get(7)?,
+            header_ciphertext: r.get(8)?,
+            deleted_at: parse_optional_timestamp(r.get_ref(9)?.as_str_or_null()?)?,
+        })
+    }
+
+    /// Associated data for the encrypted labels; the same as [`ItemRow::labels_ad`].
+    #[must_use]
+    pub fn labels_ad(&self) -> Vec<u8> {
+        ItemRow::labels_ad_for_name_kind(&self.name, &self.kind)
+    }
+}
+
+fn parse_timestamp(s: &str) -> rusqlite::Result<OffsetDateTime> {
+    OffsetDateTime::parse(s, &time::format_description::well_known::Rfc3339)
+        .map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))
+}
+
+fn parse_optional_timestamp(s: Option<&str>) -> rusqlite::Result<Option<OffsetDateTime>> {
+    s.map(parse_timestamp).transpose()
+}
+
+impl AttachmentRow {
+    fn from_row(r: &rusqlite::Row<'_>) -> rusqlite::Result<Self> {
+        Ok(Self {
+            id: r.get(0)?,
+            item_id: r.get(1)?,
+            meta_nonce: r.get(2)?,
+            meta_ciphertext: r.get(3)?,
+            created_at: OffsetDateTime::parse(&r.get::<_, String>(4)?, &time::format_description::well_known::Rfc3339)
+                .map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))?,
+        })
+    }
+}
+
+impl HistoryRow {
+    fn from_row(r: &rusqlite::Row<'_>) -> rusqlite::Result<Self> {
+        let parse = |s: String| {
+            OffsetDateTime::parse(&s, &time::format_description::well_known::Rfc3339)
+                .map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))
+        };
+        Ok(Self {
+            item_id: r.get(0)?,
+            revision: r.get(1)?,
+            nonce: r.get(2)?,
+            ciphertext: r.get(3)?,
+            created_at: parse(r.get(4)?)?,
+            archived_at: parse(r.get(5)?)?,
+        })
+    }
 }
 
 impl ItemRow {
@@ -355,6 +1308,18 @@ impl ItemRow {
     pub fn ad(&self) -> Vec<u8> {
         Self::ad_for_name_kind(&self.name, &self.kind)
     }
+    /// Associated data for the encrypted labels, domain-separated from the value's.
+    #[must_use]
+    pub fn labels_ad(&self) -> Vec<u8> {
+        Self::labels_ad_for_name_kind(&self.name, &self.kind)
+    }
+    #[must_use]
+    pub fn labels_ad_for_name_kind(name: &str, kind: &str) -> Vec<u8> {
+        let mut v = Self::ad_for_name_kind(name, kind);
+        v.push(0x1f);
+        v.extend_from_slice(b"labels");
+        v
+    }
     #[must_use]
     pub fn ad_for_name_kind(name: &str, kind: &str) -> Vec<u8> {
         // Associated data binds the ciphertext to immutable fields.
@@ -536,6 +1501,71 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_update_item_archives_previous_value() -> Result<()> {
+        let path = tmp_path("update_history");
+        let db = Db::open(&path)?;
+
+        db.insert_item("item", "note", b"n0", b"c0")?;
+        let id = db.list_items()?[0].id;
+        assert!(db.list_history(id)?.is_empty());
+
+        db.update_item(id, b"n1", b"c1")?;
+        db.update_item(id, b"n2", b"c2")?;
+
+        let history = db.list_history(id)?;
+        assert_eq!(history.len(), 2);
+        assert_eq!((history[0].revision, history[0].ciphertext.as_slice()), (1, &b"c0"[..]));
+        assert_eq!((history[1].revision, history[1].ciphertext.as_slice()), (2, &b"c1"[..]));
+        assert_eq!(db.get_history(id, 2)?.map(|r| r.nonce), Some(b"n1".to_vec()));
+        assert!(db.get_history(id, 3)?.is_none());
+
+        // History goes away with the item
+        db.delete_item(id)?;
+        assert!(db.list_history(id)?.is_empty());
+
+        fs::remove_file(path).ok();
+        Ok(())
+    }
+
+    #[test]
+    fn test_labels_columns_added_to_existing_vaults() -> Result<()> {
+        let path = tmp_path("labels_migration");
+        {
+            // Schema as created before labels existed
+            let conn = Connection::open(&path)?;
+            conn.execute_batch(
+                "CREATE TABLE items (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    name TEXT NOT NULL,
+                    kind TEXT NOT NULL,
+                    nonce BLOB NOT NULL,
+                    ciphertext BLOB NOT NULL,
+                    created_at TEXT NOT NULL,
+                    updated_at TEXT NOT NULL
+                );
+                INSERT INTO items (name, kind, nonce, ciphertext, created_at, updated_at)
+                VALUES ('old', 'note', x'00', x'01', '2024-01-01T00:00:00Z', '2024-01-01T00:00:00Z');",
+            )?;
+        }
+
+        let db = Db::open(&path)?;
+        let rows = db.list_items()?;
+        assert_eq!(rows.len(), 1);
+        assert!(rows[0].labels_nonce.is_none());
+
+        db.update_labels(rows[0].id, Some((b"ln", b"lc")))?;
+        let rows = db.list_items()?;
+        assert_eq!(rows[0].labels_ciphertext.as_deref(), Some(&b"lc"[..]));
+        assert_eq!(
+            rows[0].updated_at,
+            OffsetDateTime::parse("2024-01-01T00:00:00Z", &time::format_description::well_known::Rfc3339)?
+        );
+
+        fs::remove_file(path).ok();
+        Ok(())
+    }
+
     #[test]
     fn test_delete_item_removes_row() -> Result<()> {
         let path = tmp_path("delete_item");
@@ -556,6 +1586,33 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_get_item_and_headers_lookups() -> Result<()> {
+        let path = tmp_path("get_item");
+        let db = Db::open(&path)?;
+
+        db.insert_item("b-name", "password", b"nonce1", b"ct1")?;
+        db.insert_item("a-name", "env", b"nonce2", b"ct2")?;
+
+        let headers = db.list_item_headers()?;
+        let names: Vec<_> = headers.iter().map(|h| h.name.as_str()).collect();
+        assert_eq!(names, vec!["a-name", "b-name"]);
+
+        let row = db.get_item(headers[1].id)?.expect("item exists");
+        assert_eq!(row.name, "b-name");
+        assert_eq!(row.ciphertext, b"ct1");
+        assert_eq!(row.created_at, headers[1].created_at);
+
+        let by_name = db.get_item_by_name("a-name")?.expect("item exists");
+        assert_eq!(by_name.id, headers[0].id);
+
+        assert!(db.get_item(9999)?.is_none());
+        assert!(db.get_item_by_name("missing")?.is_none());
+
+        fs::remove_file(path).ok();
+        Ok(())
+    }
+
     #[test]
     fn test_itemrow_ad_helpers() {
         let name = "example";
@@ -580,7 +1637,72 @@ mod tests {
             ciphertext: vec![],
             created_at: OffsetDateTime::now_utc(),
             updated_at: OffsetDateTime::now_utc(),
+            labels_nonce: None,
+            labels_ciphertext: None,
+            header_nonce: None,
+            header_ciphertext: None,
+            deleted_at: None,
         };
         assert_eq!(row.ad(), expected);
+        assert_ne!(row.labels_ad(), expected);
+        assert!(row.labels_ad().starts_with(&expected));
+    }
+
+    #[test]
+    fn test_snapshot_and_restore_round_trip() -> Result<()> {
+        let path = tmp_path("snapshot_src");
+        let snapshot = tmp_path("snapshot_file");
+        let db = Db::open(&path)?;
+        db.write_meta(&small_kdf(), &mk_wrapped(7), &[1, 2, 3])?;
+        db.insert_item("a", "password", &[1; 24], &[2, 3, 4])?;
+        db.insert_item("b", "note", &[5; 24], &[6, 7])?;
+
+        db.snapshot_to(&snapshot)?;
+        assert_eq!(Db::verify_snapshot(&snapshot)?, 2);
+
+        // Diverge the live database, then restore it from the snapshot
+        let original = db.list_items()?;
+        let mut db = db;
+        db.delete_item(original[0].id)?;
+        db.insert_item("c", "apikey", &[9; 24], &[9])?;
+        db.restore_from(&snapshot)?;
+
+        let restored = db.list_items()?;
+        assert_eq!(restored.len(), original.len());
+        for (a, b) in original.iter().zip(&restored) {
+            assert_eq!(a.id, b.id);
+            assert_eq!(a.name, b.name);
+            assert_eq!(a.nonce, b.nonce);
+            assert_eq!(a.ciphertext, b.ciphertext);
+            assert_eq!(a.created_at, b.created_at);
+        }
+        let (_, wrapped, verifier) = db.read_meta()?.unwrap();
+        assert_eq!(wrapped.ciphertext, mk_wrapped(7).ciphertext);
+        assert_eq!(verifier, vec![1, 2, 3]);
+
+        fs::remove_file(path).ok();
+        fs::remove_file(snapshot).ok();
+        Ok(())
+    }
+
+    #[test]
+    fn test_verify_snapshot_rejects_non_vault_files() -> Result<()> {
+        let empty_vault = tmp_path("snapshot_empty");
+        let db = Db::open(&empty_vault)?;
+        let snapshot = tmp_path("snapshot_empty_copy");
+        db.snapshot_to(&snapshot)?;
+        assert!(Db::verify_snapshot(&snapshot).is_err());
+
+        let garbage = tmp_path("snapshot_garbage");
+        fs::write(&garbage, b"definitely not a database")?;
+        assert!(Db::verify_snapshot(&garbage).is_err());
+
+        let mut db = db;
+        assert!(db.restore_from(&garbage).is_err());
+
+        for p in [empty_vault, snapshot, garbage] {
+            fs::remove_file(p).ok();
+        }
+        Ok(())
     }
 }
@@ -0,0 +1,368 @@
+use crate::ItemKind;
+use color_eyre::Result;
+use color_eyre::eyre::eyre;
+use serde::{Deserialize, Serialize};
+
+/// Prefix that marks an encrypted payload holding structured fields.
+///
+/// Items without fields keep storing their raw value, so payloads written by earlier versions
+/// decode unchanged. The NUL bytes keep the marker from colliding with a typed-in secret.
+const FIELDS_PAYLOAD_MARKER: &[u8] = b"\0chamber-fields\0";
+
+/// Field of SSH key items that makes the SSH agent ask for confirmation before each use of the key.
+pub const SSH_AGENT_CONFIRM_FIELD: &str = "agent_confirm";
+
+/// A named field of an item, stored inside the item's encrypted payload.
+///
+/// Fields either belong to the schema of the item's kind (see [`ItemKind::field_specs`]) or are
+/// custom fields added by the user.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ItemField {
+    pub name: String,
+    pub value: String,
+    /// Secret fields are masked by the user interfaces unless explicitly revealed.
+    #[serde(default)]
+    pub secret: bool,
+}
+
+impl ItemField {
+    #[must_use]
+    pub fn new(name: impl Into<String>, value: impl Into<String>, secret: bool) -> Self {
+        Self {
+            name: name.into(),
+            value: value.into(),
+            secret,
+        }
+    }
+
+    /// Returns `true` if the field is not part of the schema of `kind`.
+    #[must_use]
+    pub fn is_custom(&self, kind: ItemKind) -> bool {
+        kind.field_spec(&self.name).is_none()
+    }
+}
+
+/// The expected format of a typed field value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FieldFormat {
+    Text,
+    /// A TCP/UDP port number between 1 and 65535.
+    Port,
+    /// Digits only, e.g. a card verification code.
+    Digits,
+    /// A card expiry date written as `MM/YY` or `MM/YYYY`.
+    Expiry,
+    /// An `otpauth://totp/` URI or a base32 TOTP secret.
+    Totp,
+    /// A yes/no setting, see [`parse_flag`].
+    Flag,
+}
+
+/// Describes one typed field of an item kind.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FieldSpec {
+    pub name: &'static str,
+    pub label: &'static str,
+    pub secret: bool,
+    pub format: FieldFormat,
+}
+
+impl FieldSpec {
+    const fn text(name: &'static str, label: &'static str) -> Self {
+        Self {
+            name,
+            label,
+            secret: false,
+            format: FieldFormat::Text,
+        }
+    }
+
+    const fn secret(name: &'static str, label: &'static str) -> Self {
+        Self {
+            name,
+            label,
+            secret: true,
+            format: FieldFormat::Text,
+        }
+    }
+
+    const fn with_format(self, format: FieldFormat) -> Self {
+        Self { format, ..self }
+    }
+
+    /// Checks that `value` matches the format of this field. Empty values are always accepted.
+    ///
+    /// # Errors
+    /// Returns an error describing the expected format if `value` does not match it.
+    pub fn validate(&self, value: &str) -> Result<()> {
+        let value = value.trim();
+        if value.is_empty() {
+            return Ok(());
+        }
+        let valid = match self.format {
+            FieldFormat::Text => true,
+            FieldFormat::Port => value.parse::<u16>().is_ok_and(|p| p > 0),
+            FieldFormat::Digits => value.chars().all(|c| c.is_ascii_digit()),
+            FieldFormat::Expiry => is_valid_expiry(value),
+            FieldFormat::Totp => crate::Totp::parse(value).is_ok(),
+            FieldFormat::Flag => parse_flag(value).is_some(),
+        };
+        if valid {
+            Ok(())
+        } else {
+            let expected = match self.format {
+                FieldFormat::Text => "text",
+                FieldFormat::Port => "a port number between 1 and 65535",
+                FieldFormat::Digits => "digits only",
+                FieldFormat::Expiry => "an expiry date like MM/YY",
+                FieldFormat::Totp => "an otpauth://totp/ URI or a base32 secret",
+                FieldFormat::Flag => "yes or no",
+            };
+            Err(eyre!("{} must be {}", self.label, expected))
+        }
+    }
+}
+
+/// Parses the value of a [`FieldFormat::Flag`] field: `yes`/`no`, `true`/`false`, `on`/`off` or
+/// `1`/`0`, in any case.
+#[must_use]
+pub fn parse_flag(value: &str) -> Option<bool> {
+    match value.trim().to_ascii_lowercase().as_str() {
+        "yes" | "true" | "on" | "1" => Some(true),
+        "no" | "false" | "off" | "0" => Some(false),
+        _ => None,
+    }
+}
+
+fn is_valid_expiry(value: &str) -> bool {
+    let Some((month, year)) = value.split_once('/') else {
+        return false;
+    };
+    let month_ok = month.len() == 2 && month.parse::<u8>().is_ok_and(|m| (1..=12).contains(&m));
+    let year_ok = matches!(year.len(), 2 | 4) && year.chars().all(|c| c.is_ascii_digit());
+    month_ok && year_ok
+}
+
+const PASSWORD_FIELDS: &[FieldSpec] = &[
+    FieldSpec::text("username", "Username"),
+    FieldSpec::text("url", "URL"),
+    FieldSpec::secret(crate::TOTP_FIELD, "TOTP Secret").with_format(FieldFormat::Totp),
+];
+const API_KEY_FIELDS: &[FieldSpec] = &[FieldSpec::text("service", "Service"), FieldSpec::text("url", "URL")];
+const SSH_KEY_FIELDS: &[FieldSpec] = &[
+    FieldSpec::text("public_key", "Public Key"),
+    FieldSpec::secret("passphrase", "Passphrase"),
+    FieldSpec::text(SSH_AGENT_CONFIRM_FIELD, "Confirm Agent Use").with_format(FieldFormat::Flag),
+];
+const DATABASE_FIELDS: &[FieldSpec] = &[
+    FieldSpec::text("host", "Host"),
+    FieldSpec::text("port", "Port").with_format(FieldFormat::Port),
+    FieldSpec::text("username", "Username"),
+    FieldSpec::text("database", "Database"),
+];
+const CREDIT_CARD_FIELDS: &[FieldSpec] = &[
+    FieldSpec::text("holder", "Cardholder"),
+    FieldSpec::text("expiry", "Expiry").with_format(FieldFormat::Expiry),
+    FieldSpec::secret("cvv", "CVV").with_format(FieldFormat::Digits),
+];
+const IDENTITY_FIELDS: &[FieldSpec] = &[
+    FieldSpec::text("full_name", "Full Name"),
+    FieldSpec::text("date_of_birth", "Date of Birth"),
+    FieldSpec::text("email", "Email"),
+    FieldSpec::text("phone", "Phone"),
+    FieldSpec::text("address", "Address"),
+];
+const SERVER_FIELDS: &[FieldSpec] = &[
+    FieldSpec::text("host", "Host"),
+    FieldSpec::text("port", "Port").with_format(FieldFormat::Port),
+    FieldSpec::text("username", "Username"),
+];
+const WIFI_FIELDS: &[FieldSpec] = &[FieldSpec::text("ssid", "SSID"), FieldSpec::text("security", "Security")];
+const LICENSE_FIELDS: &[FieldSpec] = &[
+    FieldSpec::text("product", "Product"),
+    FieldSpec::text("licensee", "Licensee"),
+];
+const BANK_ACCOUNT_FIELDS: &[FieldSpec] = &[
+    FieldSpec::text("bank", "Bank"),
+    FieldSpec::text("holder", "Account Holder"),
+    FieldSpec::text("iban", "IBAN"),
+    FieldSpec::text("swift", "SWIFT/BIC"),
+];
+const OAUTH_FIELDS: &[FieldSpec] = &[
+    FieldSpec::text("client_id", "Client ID"),
+    FieldSpec::text("token_url", "Token URL"),
+    FieldSpec::text("scopes", "Scopes"),
+    FieldSpec::secret("refresh_token", "Refresh Token"),
+];
+
+impl ItemKind {
+    /// Returns the typed fields stored alongside the main value of this kind.
+    ///
+    /// The main value keeps holding the primary secret, e.g. the password of a database
+    /// connection or the number of a credit card.
+    #[must_use]
+    pub const fn field_specs(self) -> &'static [FieldSpec] {
+        match self {
+            Self::Password => PASSWORD_FIELDS,
+            Self::ApiKey => API_KEY_FIELDS,
+            Self::SshKey => SSH_KEY_FIELDS,
+            Self::Database => DATABASE_FIELDS,
+            Self::CreditCard => CREDIT_CARD_FIELDS,
+            Self::Identity => IDENTITY_FIELDS,
+            Self::Server => SERVER_FIELDS,
+            Self::WifiPassword => WIFI_FIELDS,
+            Self::License => LICENSE_FIELDS,
+            Self::BankAccount => BANK_ACCOUNT_FIELDS,
+            Self::OAuth => OAUTH_FIELDS,
+            Self::EnvVar
+            | Self::Note
+            | Self::Certificate
+            | Self::SecureNote
+            | Self::Document
+            | Self::Recovery
+            | Self::Totp => &[],
+        }
+    }
+
+    /// Returns the typed field called `name`, if this kind has one.
+    #[must_use]
+    pub fn field_spec(self, name: &str) -> Option<&'static FieldSpec> {
+        self.field_specs()
+            .iter()
+            .find(|spec| spec.name.eq_ignore_ascii_case(name))
+    }
+}
+
+/// Validates the fields of an item of the given kind.
+///
+/// Field names must be non-empty and unique (compared case-insensitively), and typed fields
+/// must match the format of their [`FieldSpec`]. Custom fields accept any value.
+///
+/// # Errors
+/// Returns an error describing the first invalid field.
+pub fn validate_fields(kind: ItemKind, fields: &[ItemField]) -> Result<()> {
+    for (i, field) in fields.iter().enumerate() {
+        let name = field.name.trim();
+        if name.is_empty() {
+            return Err(eyre!("Field names cannot be empty"));
+        }
+        if fields[..i].iter().any(|f| f.name.trim().eq_ignore_ascii_case(name)) {
+            return Err(eyre!("Duplicate field '{}'", name));
+        }
+        if let Some(spec) = kind.field_spec(name) {
+            spec.validate(&field.value)?;
+        }
+    }
+    Ok(())
+}
+
+/// Normalizes fields before they are stored: trims names, drops typed fields left empty, and
+/// applies the secret flag of typed fields from the kind's schema.
+#[must_use]
+pub fn normalize_fields(kind: ItemKind, fields: &[ItemField]) -> Vec<ItemField> {
+    fields
+        .iter()
+        .filter_map(|field| {
+            let name = field.name.trim();
+            match kind.field_spec(name) {
+                Some(_) if field.value.trim().is_empty() => None,
+                Some(spec) => Some(ItemField::new(spec.name, field.value.trim(), spec.secret)),
+                None => Some(ItemField::new(name, field.value.clone(), field.secret)),
+            }
+        })
+        .collect()
+}
+
+#[derive(Serialize, Deserialize)]
+struct FieldsPayload {
+    value: String,
+    fields: Vec<ItemField>,
+}
+
+/// Serializes an item value and its fields into the plaintext that gets encrypted.
+pub(crate) fn encode_payload(value: &str, fields: &[ItemField]) -> Result<Vec<u8>> {
+    if fields.is_empty() {
+        return Ok(value.as_bytes().to_vec());
+    }
+    let payload = FieldsPayload {
+        value: value.to_string(),
+        fields: fields.to_vec(),
+    };
+    let mut out = FIELDS_PAYLOAD_MARKER.to_vec();
+    out.extend_from_slice(&serde_json::to_vec(&payload)?);
+    Ok(out)
+}
+
+/// Splits a decrypted payload back into the item value and its fields.
+pub(crate) fn decode_payload(plaintext: Vec<u8>) -> Result<(String, Vec<ItemField>)> {
+    if let Some(json) = plaintext.strip_prefix(FIELDS_PAYLOAD_MARKER) {
+        let payload: FieldsPayload = serde_json::from_slice(json)?;
+        return Ok((payload.value, payload.fields));
+    }
+    Ok((String::from_utf8(plaintext)?, Vec::new()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_payload_without_fields_is_raw_value() -> Result<()> {
+        let encoded = encode_payload("hunter2", &[])?;
+        assert_eq!(encoded, b"hunter2");
+        assert_eq!(decode_payload(encoded)?, ("hunter2".to_string(), Vec::new()));
+        Ok(())
+    }
+
+    #[test]
+    fn test_payload_round_trips_fields() -> Result<()> {
+        let fields = vec![
+            ItemField::new("host", "db.internal", false),
+            ItemField::new("pin", "1234", true),
+        ];
+        let encoded = encode_payload("s3cret", &fields)?;
+        assert_eq!(decode_payload(encoded)?, ("s3cret".to_string(), fields));
+        Ok(())
+    }
+
+    #[test]
+    fn test_validate_fields() {
+        let kind = ItemKind::Database;
+        assert!(validate_fields(kind, &[ItemField::new("port", "5432", false)]).is_ok());
+        assert!(validate_fields(kind, &[ItemField::new("port", "99999", false)]).is_err());
+        assert!(validate_fields(kind, &[ItemField::new("", "x", false)]).is_err());
+        assert!(
+            validate_fields(
+                kind,
+                &[ItemField::new("Notes", "a", false), ItemField::new("notes", "b", false)]
+            )
+            .is_err()
+        );
+
+        let card = ItemKind::CreditCard;
+        assert!(validate_fields(card, &[ItemField::new("expiry", "09/29", false)]).is_ok());
+        assert!(validate_fields(card, &[ItemField::new("expiry", "13/29", false)]).is_err());
+        assert!(validate_fields(card, &[ItemField::new("cvv", "12a", false)]).is_err());
+
+        let ssh = ItemKind::SshKey;
+        assert!(validate_fields(ssh, &[ItemField::new(SSH_AGENT_CONFIRM_FIELD, "Yes", false)]).is_ok());
+        assert!(validate_fields(ssh, &[ItemField::new(SSH_AGENT_CONFIRM_FIELD, "maybe", false)]).is_err());
+        assert_eq!(parse_flag(" off "), Some(false));
+    }
+
+    #[test]
+    fn test_normalize_fields_applies_schema() {
+        let fields = normalize_fields(
+            ItemKind::CreditCard,
+            &[
+                ItemField::new(" CVV ", "123", false),
+                ItemField::new("holder", " ", false),
+                ItemField::new("notes", "", false),
+            ],
+        );
+        assert_eq!(
+            fields,
+            vec![ItemField::new("cvv", "123", true), ItemField::new("notes", "", false)]
+        );
+    }
+}
@@ -0,0 +1,284 @@
+use crate::{KdfParams, KeyMaterial, Vault, WrappedVaultKey, decrypt_revision, open_item};
+use color_eyre::Result;
+use color_eyre::eyre::eyre;
+use std::fmt;
+
+/// Length of a wrapped vault key: the 32-byte key plus the Poly1305 tag.
+const WRAPPED_KEY_LEN: usize = 32 + 16;
+const XNONCE_LEN: usize = 24;
+/// Length of an HMAC-SHA256 verifier.
+const VERIFIER_LEN: usize = 32;
+
+/// A row of the vault database that an integrity check can point at.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IntegrityRow {
+    Item(u64),
+    Revision { item_id: u64, revision: u64 },
+    Attachment(u64),
+    AttachmentChunk { attachment_id: u64, index: u64 },
+}
+
+impl fmt::Display for IntegrityRow {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Item(id) => write!(f, "item {id}"),
+            Self::Revision { item_id, revision } => write!(f, "revision {revision} of item {item_id}"),
+            Self::Attachment(id) => write!(f, "attachment {id}"),
+            Self::AttachmentChunk { attachment_id, index } => write!(f, "chunk {index} of attachment {attachment_id}"),
+        }
+    }
+}
+
+/// A row that failed the integrity check.
+#[derive(Debug, Clone)]
+pub struct IntegrityIssue {
+    pub row: IntegrityRow,
+    pub problem: String,
+}
+
+/// Outcome of [`Vault::check_integrity`].
+#[derive(Debug, Clone, Default)]
+pub struct IntegrityReport {
+    /// Problems reported by `SQLite`'s `integrity_check`.
+    pub database: Vec<String>,
+    /// Problems with the stored master key, key slots or recovery key.
+    pub key_material: Vec<String>,
+    /// Whether item contents were decrypted, which needs an unlocked vault.
+    pub decryption_checked: bool,
+    pub items_checked: usize,
+    /// Items, revisions and attachments that fail to decrypt with the vault key.
+    pub undecryptable: Vec<IntegrityIssue>,
+    /// Revisions, attachments and chunks whose item or attachment no longer exists.
+    pub orphans: Vec<IntegrityIssue>,
+    /// Rows moved into quarantine, by this or earlier repairs.
+    pub quarantined: usize,
+}
+
+impl IntegrityReport {
+    /// Returns whether no problem was found.
+    #[must_use]
+    pub fn is_healthy(&self) -> bool {
+        self.database.is_empty()
+            && self.key_material.is_empty()
+            && self.undecryptable.is_empty()
+            && self.orphans.is_empty()
+    }
+}
+
+impl Vault {
+    /// Checks the vault file for corruption and tampering.
+    ///
+    /// Runs `SQLite`'s integrity check, checks that the master key, key slots and recovery key
+    /// are well formed, and looks for revisions, attachments and chunks that lost their parent
+    /// row. While the vault is unlocked, every item, revision and attachment is also decrypted,
+    /// which verifies its authentication tag against the associated data it is bound to.
+    ///
+    /// # Errors
+    /// Returns an error if the database cannot be queried at all.
+    pub fn check_integrity(&self) -> Result<IntegrityReport> {
+        let mut report = IntegrityReport {
+            database: self.db.integrity_check()?,
+            key_material: self.check_key_material(),
+            quarantined: self.db.quarantined_rows()?,
+            ..IntegrityReport::default()
+        };
+        report.orphans = self
+            .db
+            .orphan_rows()?
+            .into_iter()
+            .map(|row| IntegrityIssue {
+                row,
+                problem: "parent row is missing".to_string(),
+            })
+            .collect();
+        if let Some(vk) = &self.key {
+            report.decryption_checked = true;
+            self.check_decryption(vk, &mut report)?;
+        }
+        Ok(report)
+    }
+
+    /// Moves every row that fails the integrity check into a separate quarantine table, so the
+    /// rest of the vault stays usable, and returns the report of what was moved.
+    ///
+    /// An undecryptable item is quarantined with its revisions and attachments. Problems found
+    /// by `SQLite`'s own check or with the key material cannot be repaired this way and are only
+    /// reported.
+    ///
+    /// # Errors
+    /// Returns an error if the vault is locked, there is a tamper warning that was not accepted
+    /// (see [`Vault::accept_manifest`]) or the database update fails; in that case nothing is
+    /// moved.
+    pub fn quarantine_corrupt_rows(&mut self) -> Result<IntegrityReport> {
+        if self.key.is_none() {
+            return Err(eyre!("Vault is locked"));
+        }
+        self.ensure_manifest_accepted()?;
+        let mut report = self.check_integrity()?;
+        let tx = self.db.transaction()?;
+        for issue in report.undecryptable.iter().chain(&report.orphans) {
+            self.db.quarantine(issue.row, &issue.problem)?;
+        }
+        tx.commit()?;
+        self.seal_manifest()?;
+        report.quarantined = self.db.quarantined_rows()?;
+        Ok(report)
+    }
+
+    fn check_decryption(&self, vk: &KeyMaterial, report: &mut IntegrityReport) -> Result<()> {
+        for row in self.db.list_items()? {
+            report.items_checked += 1;
+            let id = row.id;
+            let undecryptable = |problem: String| IntegrityIssue {
+                row: IntegrityRow::Item(id),
+                problem,
+            };
+            let row = match row.reveal(vk) {
+                Ok(row) => row,
+                Err(e) => {
+                    report.undecryptable.push(undecryptable(format!("name and kind: {e}")));
+                    continue;
+                }
+            };
+            let ad = row.ad();
+            if let Err(e) = open_item(vk, row) {
+                report.undecryptable.push(undecryptable(format!("value: {e}")));
+                continue;
+            }
+
+            for revision in self.db.list_history(id)? {
+                if let Err(e) = decrypt_revision(vk, &ad, &revision) {
+                    report.undecryptable.push(IntegrityIssue {
+                        row: IntegrityRow::Revision {
+                            item_id: id,
+                            revision: revision.revision,
+                        },
+                        problem: e.to_string(),
+                    });
+                }
+            }
+            for attachment in self.db.list_attachments(id)? {
+                if let Err(e) = self.verify_attachment(&attachment, vk) {
+                    report.undecryptable.push(IntegrityIssue {
+                        row: IntegrityRow::Attachment(attachment.id),
+                        problem: e.to_string(),
+                    });
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks that the master key, key slots and recovery key are stored in the expected shape.
+    ///
+    /// Without the passwords the verifiers cannot be recomputed, but a truncated or mangled
+    /// entry is caught before it makes unlocking fail.
+    fn check_key_material(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        match self.db.read_meta() {
+            Ok(Some((kdf, wrapped, verifier))) => {
+                check_wrapped_key("Master key", Some(&kdf), &wrapped, &verifier, &mut problems);
+            }
+            Ok(None) => problems.push("Vault is not initialized".to_string()),
+            Err(e) => problems.push(format!("Master key metadata cannot be read: {e}")),
+        }
+        match self.db.list_key_slots() {
+            Ok(slots) => {
+                for slot in slots {
+                    let label = format!("Key slot '{}'", slot.name);
+                    check_wrapped_key(&label, Some(&slot.kdf), &slot.wrapped, &slot.verifier, &mut problems);
+                }
+            }
+            Err(e) => problems.push(format!("Key slots cannot be read: {e}")),
+        }
+        match self.db.read_recovery() {
+            Ok(Some((wrapped, verifier))) => {
+                check_wrapped_key("Recovery key", None, &wrapped, &verifier, &mut problems);
+            }
+            Ok(None) => {}
+            Err(e) => problems.push(format!("Recovery key cannot be read: {e}")),
+        }
+        problems
+    }
+}
+
+fn check_wrapped_key(
+    label: &str,
+    kdf: Option<&KdfParams>,
+    wrapped: &WrappedVaultKey,
+    verifier: &[u8],
+    problems: &mut Vec<String>,
+) {
+    if let Some(kdf) = kdf {
+        if let Err(e) = KdfParams::new(kdf.m_cost_kib, kdf.t_cost, kdf.p_cost) {
+            problems.push(format!("{label} has invalid KDF parameters: {e}"));
+        }
+        if kdf.salt.len() < argon2::RECOMMENDED_SALT_LEN {
+            problems.push(format!("{label} has a {}-byte KDF salt", kdf.salt.len()));
+        }
+    }
+    if wrapped.nonce.len() != XNONCE_LEN || wrapped.ciphertext.len() != WRAPPED_KEY_LEN {
+        problems.push(format!("{label} is not a wrapped 32-byte key"));
+    }
+    if verifier.len() != VERIFIER_LEN {
+        problems.push(format!(
+            "{label} verifier has {} bytes instead of {VERIFIER_LEN}",
+            verifier.len()
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+    use super::*;
+    use crate::test_support::{new_item, unlocked_vault};
+    use rusqlite::Connection;
+
+    #[test]
+    fn test_tampered_item_is_found_and_quarantined() {
+        let (mut vault, path) = unlocked_vault("integrity");
+        vault.create_item(&new_item("good")).unwrap();
+        vault.create_item(&new_item("bad")).unwrap();
+        let bad = vault.get_item_by_name("bad").unwrap().unwrap().id;
+        vault.update_item(bad, "newer").unwrap();
+        assert!(vault.check_integrity().unwrap().is_healthy());
+
+        {
+            let conn = Connection::open(&path).unwrap();
+            // Renaming breaks the associated data the value is bound to
+            conn.execute("UPDATE items SET name = 'renamed' WHERE id = ?", [bad])
+                .unwrap();
+            conn.execute_batch(
+                "PRAGMA foreign_keys = OFF;
+                 INSERT INTO item_history (item_id, revision, nonce, ciphertext, created_at, archived_at)
+                 VALUES (999, 1, x'00', x'00', '2024-01-01T00:00:00Z', '2024-01-01T00:00:00Z');",
+            )
+            .unwrap();
+        }
+        assert!(vault.list_items().is_err());
+
+        let report = vault.check_integrity().unwrap();
+        assert!(report.decryption_checked);
+        assert!(report.database.is_empty() && report.key_material.is_empty());
+        assert_eq!(report.items_checked, 2);
+        assert_eq!(report.undecryptable[0].row, IntegrityRow::Item(bad));
+        assert_eq!(
+            report.orphans[0].row,
+            IntegrityRow::Revision {
+                item_id: 999,
+                revision: 1
+            }
+        );
+
+        let repaired = vault.quarantine_corrupt_rows().unwrap();
+        // The item, its one revision and the orphaned revision
+        assert_eq!(repaired.quarantined, 3);
+        let names: Vec<_> = vault.list_items().unwrap().into_iter().map(|i| i.name).collect();
+        assert_eq!(names, ["good"]);
+        let after = vault.check_integrity().unwrap();
+        assert!(after.is_healthy());
+        assert_eq!(after.quarantined, 3);
+        let _ = std::fs::remove_file(&path);
+    }
+}
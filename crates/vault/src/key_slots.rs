@@ -0,0 +1,141 @@
+use crate::{KeyMaterial, Vault, unwrap_vault_key, wrap_vault_key};
+use color_eyre::Result;
+use color_eyre::eyre::eyre;
+use time::OffsetDateTime;
+
+/// Name under which the master key, which is not stored as a key slot, is shown next to them.
+pub const MASTER_KEY_SLOT: &str = "master";
+
+/// An additional password that unlocks the vault, like a LUKS key slot.
+///
+/// Every slot wraps the same vault key under its own KDF parameters, so slots can be added and
+/// revoked without re-encrypting any items.
+#[derive(Debug, Clone)]
+pub struct KeySlot {
+    pub id: u64,
+    pub name: String,
+    pub created_at: OffsetDateTime,
+}
+
+impl Vault {
+    /// Adds a key slot so the vault can also be unlocked with `password`.
+    ///
+    /// # Errors
+    /// Returns an error if the vault is locked, the name is empty, reserved or already taken, or
+    /// the slot cannot be stored.
+    pub fn add_key_slot(&mut self, name: &str, password: &str) -> Result<()> {
+        let vault_key = self.key.as_ref().ok_or_else(|| eyre!("Vault is locked"))?;
+        let name = name.trim();
+        if name.is_empty() {
+            return Err(eyre!("Key slot name cannot be empty"));
+        }
+        if name.eq_ignore_ascii_case(MASTER_KEY_SLOT) {
+            return Err(eyre!("'{MASTER_KEY_SLOT}' is reserved for the master key"));
+        }
+        if self.db.list_key_slots()?.iter().any(|s| s.name == name) {
+            return Err(eyre!("Key slot '{name}' already exists"));
+        }
+        if password.is_empty() {
+            return Err(eyre!("Key slot password cannot be empty"));
+        }
+
+        let kdf = self.fresh_kdf_params()?;
+        let derived = self.derive_master(password, &kdf)?;
+        let (wrapped, verifier) = wrap_vault_key(&derived, vault_key)?;
+        self.db.insert_key_slot(name, &kdf, &wrapped, &verifier)?;
+        Ok(())
+    }
+
+    /// Lists the key slots, without the master key.
+    ///
+    /// # Errors
+    /// Returns an error if the slots cannot be read.
+    pub fn list_key_slots(&self) -> Result<Vec<KeySlot>> {
+        Ok(self
+            .db
+            .list_key_slots()?
+            .into_iter()
+            .map(|s| KeySlot {
+                id: s.id,
+                name: s.name,
+                created_at: s.created_at,
+            })
+            .collect())
+    }
+
+    /// Revokes the key slot called `name`. Its password stops unlocking the vault; items are not
+    /// re-encrypted.
+    ///
+    /// # Errors
+    /// Returns an error if the vault is locked, the name refers to the master key or no such slot
+    /// exists.
+    pub fn remove_key_slot(&mut self, name: &str) -> Result<()> {
+        if self.key.is_none() {
+            return Err(eyre!("Vault is locked"));
+        }
+        if name.eq_ignore_ascii_case(MASTER_KEY_SLOT) {
+            return Err(eyre!("The master key cannot be removed"));
+        }
+        if !self.db.delete_key_slot(name.trim())? {
+            return Err(eyre!("Key slot '{name}' not found"));
+        }
+        Ok(())
+    }
+
+    /// Returns the vault key wrapped in the first key slot that `password` opens.
+    pub(crate) fn unwrap_key_slot(&self, password: &str) -> Result<Option<KeyMaterial>> {
+        for slot in self.db.list_key_slots()? {
+            let derived = self.derive_master(password, &slot.kdf)?;
+            if let Ok(vault_key) = unwrap_vault_key(&derived, &slot.wrapped, Some(&slot.verifier)) {
+                return Ok(Some(vault_key));
+            }
+        }
+        Ok(None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+    use super::*;
+    use crate::{ItemKind, NewItem};
+
+    #[test]
+    fn test_key_slots_unlock_and_revoke() {
+        let now = OffsetDateTime::now_utc().unix_timestamp_nanos();
+        let path = std::env::temp_dir().join(format!("chamber_key_slots_{}_{now}.db", std::process::id()));
+        let mut vault = Vault::open_or_create(Some(&path)).unwrap();
+        vault.initialize("master pw").unwrap();
+        assert!(vault.add_key_slot("alice", "alice pw").is_err());
+        vault.unlock("master pw").unwrap();
+        vault
+            .create_item(&NewItem {
+                name: "shared".into(),
+                kind: ItemKind::Password,
+                value: "s3cret".into(),
+                tags: Vec::new(),
+                folder: None,
+                fields: Vec::new(),
+            })
+            .unwrap();
+
+        vault.add_key_slot("alice", "alice pw").unwrap();
+        vault.add_key_slot("bob", "bob pw").unwrap();
+        assert!(vault.add_key_slot("alice", "other").is_err());
+        assert!(vault.add_key_slot("Master", "other").is_err());
+        let names: Vec<_> = vault.list_key_slots().unwrap().into_iter().map(|s| s.name).collect();
+        assert_eq!(names, ["alice", "bob"]);
+
+        let mut as_bob = Vault::open_or_create(Some(&path)).unwrap();
+        as_bob.unlock("bob pw").unwrap();
+        assert_eq!(as_bob.get_item_by_name("shared").unwrap().unwrap().value, "s3cret");
+        assert!(as_bob.remove_key_slot(MASTER_KEY_SLOT).is_err());
+        as_bob.remove_key_slot("alice").unwrap();
+        assert!(as_bob.remove_key_slot("alice").is_err());
+
+        let mut reopened = Vault::open_or_create(Some(&path)).unwrap();
+        assert!(reopened.unlock("alice pw").is_err());
+        reopened.unlock("master pw").unwrap();
+        let _ = std::fs::remove_file(&path);
+    }
+}
@@ -0,0 +1,122 @@
+use crate::{KdfParams, KeyMaterial, derive_key_from_secret};
+use color_eyre::Result;
+use color_eyre::eyre::eyre;
+use sha2::{Digest, Sha256};
+use std::io::{Read, Write};
+use std::path::Path;
+use zeroize::Zeroizing;
+
+/// Number of random bytes in a keyfile created by [`generate_keyfile`].
+pub const KEYFILE_LEN: usize = 64;
+
+/// Largest file accepted as a keyfile, so a device or huge file cannot stall unlocking.
+pub const MAX_KEYFILE_SIZE: u64 = 16 * 1024 * 1024;
+
+/// Creates a keyfile of [`KEYFILE_LEN`] random bytes at `path`, readable only by the owner.
+///
+/// # Errors
+/// Returns an error if `path` already exists or the file cannot be written.
+pub fn generate_keyfile(path: &Path) -> Result<()> {
+    let mut bytes = Zeroizing::new([0u8; KEYFILE_LEN]);
+    getrandom::fill(bytes.as_mut())?;
+
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut file = options
+        .open(path)
+        .map_err(|e| eyre!("Cannot create keyfile {}: {e}", path.display()))?;
+    file.write_all(bytes.as_ref())?;
+    file.sync_all()?;
+    Ok(())
+}
+
+/// Derives the key that wraps the vault key from a password and the contents of a keyfile.
+///
+/// Any file can be a keyfile: its SHA-256 digest is appended to the password before Argon2id,
+/// so both are needed to unlock. The password may be empty to unlock with the keyfile alone.
+///
+/// # Errors
+/// Returns an error if the keyfile cannot be read, is not a regular file, is empty or larger
+/// than [`MAX_KEYFILE_SIZE`], or the derivation fails.
+pub fn derive_key_with_keyfile(master: &str, keyfile: &Path, kdf: &KdfParams) -> Result<KeyMaterial> {
+    let file = std::fs::File::open(keyfile).map_err(|e| eyre!("Cannot read keyfile {}: {e}", keyfile.display()))?;
+    if !file.metadata()?.is_file() {
+        return Err(eyre!("Keyfile {} is not a regular file", keyfile.display()));
+    }
+    let mut hasher = Sha256::new();
+    // Read one byte past the limit to tell a file of exactly the maximum size from a larger one
+    match std::io::copy(&mut file.take(MAX_KEYFILE_SIZE + 1), &mut hasher)? {
+        0 => return Err(eyre!("Keyfile {} is empty", keyfile.display())),
+        len if len > MAX_KEYFILE_SIZE => {
+            return Err(eyre!(
+                "Keyfile {} is larger than {MAX_KEYFILE_SIZE} bytes",
+                keyfile.display()
+            ));
+        }
+        _ => {}
+    }
+
+    let mut secret = Zeroizing::new(master.as_bytes().to_vec());
+    secret.extend_from_slice(&hasher.finalize());
+    derive_key_from_secret(&secret, kdf)
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+    use super::*;
+    use crate::Vault;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_generate_keyfile() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("vault.key");
+        generate_keyfile(&path).unwrap();
+        assert_eq!(std::fs::read(&path).unwrap().len(), KEYFILE_LEN);
+        // Never overwrite an existing keyfile
+        assert!(generate_keyfile(&path).is_err());
+    }
+
+    #[test]
+    fn test_unlock_requires_keyfile() {
+        let dir = TempDir::new().unwrap();
+        let db = dir.path().join("vault.db");
+        let keyfile = dir.path().join("vault.key");
+        let other = dir.path().join("other.key");
+        generate_keyfile(&keyfile).unwrap();
+        generate_keyfile(&other).unwrap();
+
+        let mut vault = Vault::open_or_create(Some(&db)).unwrap();
+        vault.set_keyfile(Some(keyfile.clone()));
+        vault.initialize("pw").unwrap();
+        vault.unlock("pw").unwrap();
+
+        let mut reopened = Vault::open_or_create(Some(&db)).unwrap();
+        assert!(reopened.unlock("pw").is_err());
+        reopened.set_keyfile(Some(other));
+        assert!(reopened.unlock("pw").is_err());
+        reopened.set_keyfile(Some(keyfile));
+        assert!(reopened.unlock("wrong").is_err());
+        reopened.unlock("pw").unwrap();
+    }
+
+    #[test]
+    fn test_keyfile_must_be_a_small_regular_file() {
+        let dir = TempDir::new().unwrap();
+        let kdf = KdfParams::default_secure();
+        assert!(derive_key_with_keyfile("pw", dir.path(), &kdf).is_err());
+
+        let large = dir.path().join("large.key");
+        std::fs::File::create(&large)
+            .unwrap()
+            .set_len(MAX_KEYFILE_SIZE + 1)
+            .unwrap();
+        assert!(derive_key_with_keyfile("pw", &large, &kdf).is_err());
+    }
+}
@@ -0,0 +1,149 @@
+use crate::{ItemRow, KeyMaterial, Vault, aead_decrypt, aead_encrypt};
+use color_eyre::Result;
+use color_eyre::eyre::eyre;
+use serde::{Deserialize, Serialize};
+
+/// Tags and folder of an item, as serialized inside the encrypted labels blob.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Labels {
+    #[serde(default)]
+    tags: Vec<String>,
+    #[serde(default)]
+    folder: Option<String>,
+}
+
+/// Normalizes a list of tags: trims whitespace, drops empty tags and removes duplicates
+/// (compared case-insensitively), keeping the first spelling of each tag.
+#[must_use]
+pub fn normalize_tags<S: AsRef<str>>(tags: &[S]) -> Vec<String> {
+    let mut out: Vec<String> = Vec::with_capacity(tags.len());
+    for tag in tags {
+        let tag = tag.as_ref().trim();
+        if !tag.is_empty() && !out.iter().any(|t| t.eq_ignore_ascii_case(tag)) {
+            out.push(tag.to_string());
+        }
+    }
+    out
+}
+
+/// Normalizes a folder path such as `/work//prod/` into `work/prod`.
+///
+/// Returns `None` when the path has no segments.
+#[must_use]
+pub fn normalize_folder(folder: &str) -> Option<String> {
+    let segments: Vec<&str> = folder.split('/').map(str::trim).filter(|s| !s.is_empty()).collect();
+    if segments.is_empty() {
+        None
+    } else {
+        Some(segments.join("/"))
+    }
+}
+
+/// Returns `true` if `folder` is `filter` or lies below it, e.g. `work/prod` is in `work`.
+#[must_use]
+pub fn folder_matches(folder: Option<&str>, filter: &str) -> bool {
+    let Some(filter) = normalize_folder(filter) else {
+        return true;
+    };
+    folder.is_some_and(|f| {
+        f.eq_ignore_ascii_case(&filter)
+            || (f.len() > filter.len()
+                && f.as_bytes()[filter.len()] == b'/'
+                && f[..filter.len()].eq_ignore_ascii_case(&filter))
+    })
+}
+
+/// Encrypts the labels of an item.
+///
+/// Empty labels are sealed too, so removing an item's labels is detected rather than read as
+/// "no tags or folder". The labels are bound to the item's name and kind with their own
+/// associated data, so they cannot be swapped with another item's labels or with an item value
+/// without detection.
+pub(crate) fn seal_labels(
+    vk: &KeyMaterial,
+    name: &str,
+    kind: &str,
+    tags: &[String],
+    folder: Option<&str>,
+) -> Result<(Vec<u8>, Vec<u8>)> {
+    let labels = Labels {
+        tags: normalize_tags(tags),
+        folder: folder.and_then(normalize_folder),
+    };
+    let plaintext = serde_json::to_vec(&labels)?;
+    aead_encrypt(vk, &plaintext, &ItemRow::labels_ad_for_name_kind(name, kind))
+}
+
+/// Decrypts the labels stored with an item.
+///
+/// `ad` is the item's labels associated data (see [`ItemRow::labels_ad`]).
+///
+/// # Errors
+/// Returns an error if the labels are missing or do not decrypt.
+pub(crate) fn open_labels(
+    vk: &KeyMaterial,
+    ad: &[u8],
+    nonce: Option<&[u8]>,
+    ciphertext: Option<&[u8]>,
+) -> Result<(Vec<String>, Option<String>)> {
+    let (Some(nonce), Some(ciphertext)) = (nonce, ciphertext) else {
+        return Err(eyre!("Item labels are missing; the vault may have been tampered with"));
+    };
+    let plaintext = aead_decrypt(vk, nonce, ciphertext, ad)?;
+    let labels: Labels = serde_json::from_slice(&plaintext)?;
+    Ok((labels.tags, labels.folder))
+}
+
+impl Vault {
+    /// Seals empty labels for the items of a vault written before labels were always sealed,
+    /// and returns how many items were updated. Run after an unlock that found nothing to warn
+    /// about, since from then on the manifest records every item's labels.
+    pub(crate) fn seal_missing_labels(&mut self) -> Result<usize> {
+        let Some(vk) = &self.key else {
+            return Ok(0);
+        };
+        let mut sealed = Vec::new();
+        for row in self.db.list_item_headers()? {
+            if row.labels_nonce.is_none() {
+                let row = row.reveal(vk)?;
+                sealed.push((row.id, seal_labels(vk, &row.name, &row.kind, &[], None)?));
+            }
+        }
+        if sealed.is_empty() {
+            return Ok(0);
+        }
+        let tx = self.db.transaction()?;
+        for (id, (nonce, ciphertext)) in &sealed {
+            self.db.update_labels(*id, Some((nonce, ciphertext)))?;
+        }
+        tx.commit()?;
+        self.seal_manifest()?;
+        Ok(sealed.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_tags() {
+        let tags = normalize_tags(&[" prod ", "", "Prod", "db", "  "]);
+        assert_eq!(tags, vec!["prod".to_string(), "db".to_string()]);
+    }
+
+    #[test]
+    fn test_normalize_folder() {
+        assert_eq!(normalize_folder("/work//prod/"), Some("work/prod".to_string()));
+        assert_eq!(normalize_folder(" / "), None);
+    }
+
+    #[test]
+    fn test_folder_matches_prefix_by_segment() {
+        assert!(folder_matches(Some("work/prod"), "work"));
+        assert!(folder_matches(Some("work/prod"), "Work/Prod/"));
+        assert!(!folder_matches(Some("workshop"), "work"));
+        assert!(!folder_matches(None, "work"));
+        assert!(folder_matches(None, "/"));
+    }
+}
@@ -1,18 +1,48 @@
+mod attachments;
 mod autolock;
 mod autolock_service;
 pub mod config;
 pub mod crypto;
 pub mod db;
+mod fields;
+mod integrity;
+mod key_slots;
+mod keyfile;
+mod labels;
 mod manager;
+mod manifest;
+mod migrations;
+mod private_metadata;
+mod recovery;
 mod registry;
+mod rotation;
+#[cfg(test)]
+mod test_support;
+mod totp;
+mod trash;
 
 // Re-export commonly used types and functions for easier access
 pub use crypto::{
-    HmacSha256, KdfParams, KeyMaterial, WrappedVaultKey, aead_decrypt, aead_encrypt, derive_key, unwrap_vault_key,
+    DEFAULT_KDF_MEMORY_KIB, DEFAULT_KDF_PARALLELISM, DEFAULT_KDF_TIME, HmacSha256, KdfParams, KeyMaterial,
+    WrappedVaultKey, aead_decrypt, aead_encrypt, derive_key, derive_key_from_secret, derive_subkey, unwrap_vault_key,
     wrap_vault_key,
 };
 
-pub use db::{Db, ItemRow};
+pub use attachments::{ATTACHMENT_CHUNK_SIZE, Attachment, AttachmentUpload, MAX_ATTACHMENT_SIZE};
+pub use db::{AttachmentRow, Db, HistoryRow, ItemHeaderRow, ItemRow, ItemStateRow, KeySlotRow};
+pub use fields::{
+    FieldFormat, FieldSpec, ItemField, SSH_AGENT_CONFIRM_FIELD, normalize_fields, parse_flag, validate_fields,
+};
+pub use integrity::{IntegrityIssue, IntegrityReport, IntegrityRow};
+pub use key_slots::{KeySlot, MASTER_KEY_SLOT};
+pub use keyfile::{KEYFILE_LEN, MAX_KEYFILE_SIZE, derive_key_with_keyfile, generate_keyfile};
+pub use labels::{folder_matches, normalize_folder, normalize_tags};
+pub use manifest::TamperWarning;
+pub use migrations::{SCHEMA_VERSION, SchemaStatus};
+pub use recovery::{MIN_RECOVERY_THRESHOLD, RecoveryShare};
+pub use rotation::KeyRotation;
+pub use totp::{TOTP_FIELD, Totp, TotpAlgorithm, TotpCode, is_otpauth_uri};
+pub use trash::{DEFAULT_TRASH_RETENTION_DAYS, TrashedItem};
 
 pub use crate::autolock::AutoLockConfig;
 pub use crate::autolock_service::AutoLockCallback;
@@ -46,6 +76,7 @@ pub enum ItemKind {
     License,
     WifiPassword,
     Server,
+    Totp,
 }
 
 impl FromStr for ItemKind {
@@ -70,6 +101,7 @@ impl FromStr for ItemKind {
             "license" | "lic" | "key" => ItemKind::License,
             "server" | "srv" | "host" => ItemKind::Server,
             "wifi" | "wifi-password" | "wifi-pass" => ItemKind::WifiPassword,
+            "totp" | "otp" | "2fa" | "mfa" => ItemKind::Totp,
             _ => return Err(eyre!("Invalid item type: '{}'", s)),
         };
         Ok(result)
@@ -97,6 +129,7 @@ impl ItemKind {
             Self::Document => "document",
             Self::Recovery => "recovery",
             Self::OAuth => "oauth",
+            Self::Totp => "totp",
         }
     }
 
@@ -120,6 +153,7 @@ impl ItemKind {
             ItemKind::Document,
             ItemKind::Recovery,
             ItemKind::OAuth,
+            ItemKind::Totp,
         ]
     }
 
@@ -144,6 +178,7 @@ impl ItemKind {
             ItemKind::Document => "Document",
             ItemKind::Recovery => "Recovery",
             ItemKind::OAuth => "OAuth",
+            ItemKind::Totp => "TOTP",
         }
     }
 }
@@ -156,6 +191,79 @@ pub struct Item {
     pub value: String,
     pub created_at: OffsetDateTime,
     pub updated_at: OffsetDateTime,
+    /// Free-form tags; an item can carry any number of them and a tag can span many items.
+    pub tags: Vec<String>,
+    /// Optional slash-separated folder path, e.g. `work/prod`.
+    pub folder: Option<String>,
+    /// Typed fields of the item's kind and custom fields, encrypted together with `value`.
+    pub fields: Vec<ItemField>,
+}
+
+impl Item {
+    /// Returns the value of the field called `name` (compared case-insensitively).
+    #[must_use]
+    pub fn field(&self, name: &str) -> Option<&str> {
+        self.fields
+            .iter()
+            .find(|f| f.name.eq_ignore_ascii_case(name))
+            .map(|f| f.value.as_str())
+    }
+
+    /// Returns `true` if the item carries `tag` (compared case-insensitively).
+    #[must_use]
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.iter().any(|t| t.eq_ignore_ascii_case(tag.trim()))
+    }
+}
+
+/// The metadata of an item without its value or fields.
+///
+/// Headers are listed with [`Vault::list_item_headers`], which only decrypts the labels of
+/// each item. Use [`Vault::get_item_by_id`] to decrypt the value of an item once it is needed.
+#[derive(Debug, Clone)]
+pub struct ItemHeader {
+    pub id: u64,
+    pub name: String,
+    pub kind: ItemKind,
+    pub created_at: OffsetDateTime,
+    pub updated_at: OffsetDateTime,
+    pub tags: Vec<String>,
+    pub folder: Option<String>,
+}
+
+impl ItemHeader {
+    /// Returns `true` if the item carries `tag` (compared case-insensitively).
+    #[must_use]
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.iter().any(|t| t.eq_ignore_ascii_case(tag.trim()))
+    }
+}
+
+impl From<Item> for ItemHeader {
+    fn from(item: Item) -> Self {
+        Self {
+            id: item.id,
+            name: item.name,
+            kind: item.kind,
+            created_at: item.created_at,
+            updated_at: item.updated_at,
+            tags: item.tags,
+            folder: item.folder,
+        }
+    }
+}
+
+/// A previous value of an item, archived when the item was updated.
+#[derive(Debug, Clone)]
+pub struct ItemRevision {
+    /// Per-item revision number, starting at 1 for the item's original value.
+    pub revision: u64,
+    pub value: String,
+    pub fields: Vec<ItemField>,
+    /// When this value was written to the item.
+    pub created_at: OffsetDateTime,
+    /// When this value was replaced.
+    pub archived_at: OffsetDateTime,
 }
 
 #[derive(Debug, Clone)]
@@ -163,6 +271,9 @@ pub struct NewItem {
     pub name: String,
     pub kind: ItemKind,
     pub value: String,
+    pub tags: Vec<String>,
+    pub folder: Option<String>,
+    pub fields: Vec<ItemField>,
 }
 
 #[derive(Debug)]
@@ -170,6 +281,8 @@ pub struct Vault {
     db: Db,
     key: Option<KeyMaterial>,
     db_path: PathBuf,
+    keyfile: Option<PathBuf>,
+    tamper_warning: Option<TamperWarning>,
 }
 
 impl Vault {
@@ -205,7 +318,14 @@ impl Vault {
         }
 
         let db = Db::open(&db_path)?;
-        Ok(Self { db, key: None, db_path })
+        let keyfile = VaultRegistry::registered_keyfile(&db_path);
+        Ok(Self {
+            db,
+            key: None,
+            db_path,
+            keyfile,
+            tamper_warning: None,
+        })
     }
 
     /// Opens the default instance of a resource.
@@ -232,6 +352,27 @@ impl Vault {
         &self.db_path
     }
 
+    /// Returns the keyfile mixed into the master key derivation, if the vault requires one.
+    ///
+    /// Vaults opened from a path pick up the keyfile registered for them in the vault registry.
+    pub fn keyfile(&self) -> Option<&Path> {
+        self.keyfile.as_deref()
+    }
+
+    /// Sets the keyfile used by the next [`Vault::initialize`], [`Vault::unlock`] or master key
+    /// change, overriding the registered one.
+    pub fn set_keyfile(&mut self, keyfile: Option<PathBuf>) {
+        self.keyfile = keyfile;
+    }
+
+    /// Derives the key wrapping the vault key from a password and, if set, the keyfile.
+    pub(crate) fn derive_master(&self, password: &str, kdf: &KdfParams) -> Result<KeyMaterial> {
+        match &self.keyfile {
+            Some(keyfile) => derive_key_with_keyfile(password, keyfile, kdf),
+            None => derive_key(password, kdf),
+        }
+    }
+
     /// Retrieves the backup configuration for the system.
     ///
     /// This function attempts to load the backup configuration from a JSON file
@@ -365,14 +506,26 @@ impl Vault {
     ///   - Writing the required metadata to the database fails.
     /// - Errors are returned as a `Result::Err`, allowing the caller to handle the failure.
     pub fn initialize(&mut self, master: &str) -> Result<()> {
+        self.initialize_with_kdf(master, &KdfParams::default_secure())
+    }
+
+    /// Initializes the vault like [`Vault::initialize`], deriving the master key with `kdf`
+    /// instead of the default Argon2id costs.
+    ///
+    /// # Errors
+    /// Returns an error if the key derivation, wrapping or writing the metadata fails.
+    pub fn initialize_with_kdf(&mut self, master: &str, kdf: &KdfParams) -> Result<()> {
         if self.is_initialized() {
             return Ok(());
         }
-        let kdf = KdfParams::default_secure();
-        let master_derived = derive_key(master, &kdf)?;
+        let kdf = kdf.clone();
+        let master_derived = self.derive_master(master, &kdf)?;
         let vault_key = KeyMaterial::random();
         let (wrapped, verifier) = wrap_vault_key(&master_derived, &vault_key)?;
         self.db.write_meta(&kdf, &wrapped, &verifier)?;
+        self.db.write_key_check(&crypto::key_check_value(&vault_key)?)?;
+        let version = self.sign_manifest(&vault_key)?;
+        VaultRegistry::record_manifest_version(&self.db_path, Some(version));
         Ok(())
     }
 
@@ -407,22 +560,99 @@ impl Vault {
     /// 3. Verifies the derived key against the verifier. If the verification fails, returns an error.
     /// 4. Attempts to unwrap the vault key using the derived key. Upon success, stores the derived
     ///    vault key (`vk`) within the instance's `key` field.
+    /// 5. If the master key does not match, tries the password against every key slot.
+    /// 6. Compares the items with the signed manifest; see [`Vault::tamper_warning`].
+    /// 7. If nothing looked wrong, seals empty labels for items written by older versions and,
+    ///    when the manifest covers the trash, purges items that have been in the trash longer
+    ///    than the retention period.
     ///
     pub fn unlock(&mut self, master: &str) -> Result<()> {
         let (kdf, wrapped, verifier) = self.db.read_meta()?.ok_or_else(|| eyre!("Vault not initialized"))?;
-        let master_derived = derive_key(master, &kdf)?;
+        let master_derived = self.derive_master(master, &kdf)?;
         // Verify first
-        unwrap_vault_key(&master_derived, &wrapped, Some(&verifier)).map_err(|_| eyre!("Invalid master key"))?;
-        let vk = unwrap_vault_key(&master_derived, &wrapped, None)?;
+        let vk = match unwrap_vault_key(&master_derived, &wrapped, Some(&verifier)) {
+            Ok(_) => unwrap_vault_key(&master_derived, &wrapped, None)?,
+            Err(_) => self
+                .unwrap_key_slot(master)?
+                .ok_or_else(|| eyre!("Invalid master key"))?,
+        };
+        self.finish_unlock(vk)
+    }
+
+    /// Completes unlocking with the vault key `vk` once a secret has unwrapped it: vaults
+    /// created by older versions get their key check value, then [`Vault::after_unlock`] runs.
+    /// The vault stays locked if that fails.
+    fn finish_unlock(&mut self, vk: KeyMaterial) -> Result<()> {
+        if self.db.read_key_check()?.is_none() {
+            self.db.write_key_check(&crypto::key_check_value(&vk)?)?;
+        }
         self.key = Some(vk);
-        Ok(())
+        self.after_unlock().inspect_err(|_| self.key = None)
     }
 
     pub const fn is_unlocked(&self) -> bool {
         self.key.is_some()
     }
 
-    /// Retrieves a list of items from the database, decrypting their stored values.
+    /// Unlocks the vault with an already unwrapped vault key, skipping the master key derivation.
+    ///
+    /// This is how processes that hold unlocked vaults, such as `chamber agent`, hand a vault to
+    /// another process. The key is checked against the key check value stored with the vault,
+    /// so a stale key, e.g. from before the vault was re-created or its key rotated, is refused
+    /// even when the vault is empty. Vaults that were not unlocked with a password since they
+    /// gained a check value fall back to decrypting one of their items.
+    ///
+    /// # Errors
+    /// - `eyre!("Vault not initialized")` if the vault has no metadata.
+    /// - `eyre!("Invalid vault key")` if the key is not the vault's key.
+    pub fn unlock_with_key(&mut self, key: KeyMaterial) -> Result<()> {
+        if self.db.read_meta()?.is_none() {
+            return Err(eyre!("Vault not initialized"));
+        }
+        match self.db.read_key_check()? {
+            Some(check) => {
+                if !crypto::verify_key_check_value(&key, &check)? {
+                    return Err(eyre!("Invalid vault key"));
+                }
+            }
+            None => {
+                if let Some(header) = self.db.list_item_headers()?.first() {
+                    if let Some(row) = self.db.get_item(header.id)? {
+                        decrypt_item(&key, row).map_err(|_| eyre!("Invalid vault key"))?;
+                    }
+                }
+            }
+        }
+        self.key = Some(key);
+        self.after_unlock().inspect_err(|_| self.key = None)
+    }
+
+    /// Checks the vault against its manifest and, unless that found something to warn about,
+    /// seals the labels of items from older vaults and purges the trash of items past the
+    /// retention period. The trash is only purged when the manifest vouched for it.
+    fn after_unlock(&mut self) -> Result<()> {
+        let trash_verified = self.check_manifest()?;
+        if self.tamper_warning.is_none() {
+            self.seal_missing_labels()?;
+            // Trash dates the manifest did not vouch for could have been set to purge anything
+            if trash_verified {
+                self.purge_expired_trash()?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns a copy of the unwrapped vault key, for handing the unlocked vault to
+    /// [`Vault::unlock_with_key`] in another process.
+    ///
+    /// # Errors
+    /// Returns an error if the vault is locked.
+    pub fn vault_key(&self) -> Result<KeyMaterial> {
+        self.key.clone().ok_or_else(|| eyre!("Locked"))
+    }
+
+    /// Retrieves a list of items from the database, decrypting their stored values. Items in
+    /// the trash are left out; see [`Vault::list_trash`].
     ///
     /// # Returns
     /// - `Ok(Vec<Item>)`: A vector of decrypted items if the operation is successful.
@@ -456,27 +686,119 @@ impl Vault {
     /// - The database schema must provide the required fields for each item: `id`, `name`, `kind`, `ciphertext`, `nonce`, and timestamps.
     pub fn list_items(&self) -> Result<Vec<Item>> {
         let vk = self.key.as_ref().ok_or_else(|| eyre!("Locked"))?;
-        let rows = self.db.list_items()?;
-        let mut out = Vec::with_capacity(rows.len());
-        for r in rows {
-            let plaintext = aead_decrypt(vk, &r.nonce, &r.ciphertext, &r.ad())?;
-            let value = String::from_utf8(plaintext)?;
-            out.push(Item {
-                id: r.id,
-                name: r.name,
-                kind: ItemKind::from_str(&r.kind)?,
-                value,
-                created_at: r.created_at,
-                updated_at: r.updated_at,
-            });
-        }
-        Ok(out)
+        let mut items = self
+            .db
+            .list_items()?
+            .into_iter()
+            .filter(|r| r.deleted_at.is_none())
+            .map(|r| decrypt_item(vk, r))
+            .collect::<Result<Vec<_>>>()?;
+        // Private vaults store blind indexes in place of names, so order by the decrypted names
+        items.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(items)
+    }
+
+    /// Lists the metadata of all items, ordered by name, without decrypting their values.
+    ///
+    /// Only the labels (tags and folder) of each item are decrypted, which makes this much
+    /// cheaper than [`Vault::list_items`] on large vaults. Fetch an item with
+    /// [`Vault::get_item_by_id`] when its value is actually needed.
+    ///
+    /// # Errors
+    /// - If the vault is locked, an `eyre!("Locked")` error is returned.
+    /// - If the database query fails or the labels of an item cannot be decrypted.
+    pub fn list_item_headers(&self) -> Result<Vec<ItemHeader>> {
+        let vk = self.key.as_ref().ok_or_else(|| eyre!("Locked"))?;
+        let mut headers = self
+            .db
+            .list_item_headers()?
+            .into_iter()
+            .filter(|r| r.deleted_at.is_none())
+            .map(|r| {
+                let r = r.reveal(vk)?;
+                let (tags, folder) = labels::open_labels(
+                    vk,
+                    &r.labels_ad(),
+                    r.labels_nonce.as_deref(),
+                    r.labels_ciphertext.as_deref(),
+                )?;
+                Ok(ItemHeader {
+                    id: r.id,
+                    kind: ItemKind::from_str(&r.kind)?,
+                    name: r.name,
+                    created_at: r.created_at,
+                    updated_at: r.updated_at,
+                    tags,
+                    folder,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        headers.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(headers)
+    }
+
+    /// Fetches and decrypts a single item by its id.
+    ///
+    /// Only the requested item is read from the database and decrypted. Items in the trash are
+    /// not returned.
+    ///
+    /// # Errors
+    /// - If the vault is locked, an `eyre!("Locked")` error is returned.
+    /// - If the database query fails or the item cannot be decrypted.
+    pub fn get_item_by_id(&self, id: u64) -> Result<Option<Item>> {
+        let vk = self.key.as_ref().ok_or_else(|| eyre!("Locked"))?;
+        self.db
+            .get_item(id)?
+            .filter(|r| r.deleted_at.is_none())
+            .map(|r| decrypt_item(vk, r))
+            .transpose()
+    }
+
+    /// Returns the key used to protect encrypted backups of this vault.
+    ///
+    /// The backup key is a subkey derived from the vault key, so backups can be decrypted by
+    /// anyone able to unlock the vault, while the item encryption key itself never leaves the vault.
+    ///
+    /// # Errors
+    /// Returns an error if the vault is locked.
+    pub fn backup_key(&self) -> Result<KeyMaterial> {
+        let vk = self.key.as_ref().ok_or_else(|| eyre!("Locked"))?;
+        derive_subkey(vk, b"chamber-backup-key")
+    }
+
+    /// Writes a raw snapshot of the encrypted vault database to `dest`.
+    ///
+    /// Snapshots are taken with `SQLite`'s online backup API and contain the vault exactly as
+    /// stored on disk, so no key is needed and a locked vault can be backed up. Restoring a
+    /// snapshot with [`Vault::restore_snapshot`] reproduces the vault byte for byte.
+    ///
+    /// # Errors
+    /// Returns an error if the snapshot file cannot be written.
+    pub fn snapshot_to(&self, dest: &Path) -> Result<()> {
+        self.db.snapshot_to(dest)
     }
 
-    /// Retrieves an item by its name from the list of items.
+    /// Replaces this vault's database with a snapshot created by [`Vault::snapshot_to`].
     ///
-    /// This method searches for an item in the collection of items maintained by the instance.
-    /// It returns the first item that matches the provided name, if it exists.
+    /// The snapshot is verified before the current contents are overwritten. The vault is
+    /// locked afterwards, because the restored data may be protected by a different master
+    /// password than the one used to unlock it.
+    ///
+    /// # Errors
+    /// Returns an error if the snapshot is invalid or the restore fails.
+    pub fn restore_snapshot(&mut self, src: &Path) -> Result<()> {
+        self.db.restore_from(src)?;
+        self.key = None;
+        // Going back to an older snapshot is deliberate, not a rollback to warn about
+        VaultRegistry::record_manifest_version(&self.db_path, None);
+        self.tamper_warning = None;
+        Ok(())
+    }
+
+    /// Retrieves an item by its name.
+    ///
+    /// The item is looked up through the unique name index, so only the matching item is
+    /// decrypted.
     ///
     /// # Parameters
     /// - `name`: The name of the item to search for, provided as a string slice (`&str`).
@@ -487,10 +809,14 @@ impl Vault {
     /// - `Err(Error)`: If an error occurs while retrieving the list of items.
     ///
     /// # Errors
-    /// This function will return an error if the call to `self.list_items()` fails.
+    /// This function will return an error if the vault is locked, the lookup fails or the item
+    /// cannot be decrypted.
     pub fn get_item_by_name(&self, name: &str) -> Result<Option<Item>> {
-        let items = self.list_items()?;
-        Ok(items.into_iter().find(|i| i.name == name))
+        let vk = self.key.as_ref().ok_or_else(|| eyre!("Locked"))?;
+        self.db
+            .get_item_by_name(&self.stored_name(vk, name)?)?
+            .map(|r| decrypt_item(vk, r))
+            .transpose()
     }
 
     /// Creates a new item and inserts it into the database.
@@ -516,18 +842,64 @@ impl Vault {
     /// - If encryption fails for any reason.
     /// - If the database insertion fails.
     pub fn create_item(&mut self, item: &NewItem) -> Result<()> {
+        self.ensure_manifest_accepted()?;
         let vk = self.key.as_ref().ok_or_else(|| eyre!("Locked"))?;
+        let payload = fields::encode_payload(&item.value, &normalize_fields(item.kind, &item.fields))?;
         let nonce_cipher = aead_encrypt(
             vk,
-            item.value.as_bytes(),
+            &payload,
             ItemRow::ad_for_name_kind(&item.name, item.kind.as_str()).as_ref(),
         )?;
-        self.db
-            .insert_item(&item.name, item.kind.as_str(), &nonce_cipher.0, &nonce_cipher.1)?;
-        Ok(())
+        let labels = labels::seal_labels(vk, &item.name, item.kind.as_str(), &item.tags, item.folder.as_deref())?;
+        let (name, kind, header) = if self.db.private_metadata()? {
+            let (index, header) = private_metadata::seal_header(vk, &item.name, item.kind.as_str())?;
+            if self.db.get_item_by_name(&index)?.is_some() {
+                return Err(eyre!("An item named '{}' already exists", item.name));
+            }
+            (index, "", Some(header))
+        } else {
+            (item.name.clone(), item.kind.as_str(), None)
+        };
+        self.db.insert_item_with_labels(
+            &name,
+            kind,
+            &nonce_cipher.0,
+            &nonce_cipher.1,
+            Some((&labels.0, &labels.1)),
+            header.as_ref().map(|(n, c)| (n.as_slice(), c.as_slice())),
+        )?;
+        self.seal_manifest()
+    }
+
+    /// Replaces the tags and folder of an item.
+    ///
+    /// Tags are trimmed and de-duplicated and the folder path is normalized (see
+    /// [`normalize_tags`] and [`normalize_folder`]). Labels are encrypted with the vault key and
+    /// bound to the item, so they cannot be read or altered without detection.
+    ///
+    /// # Errors
+    /// - If the vault is locked, an `eyre!("Locked")` error is returned.
+    /// - If the item does not exist, an `eyre!("Item not found")` error is returned.
+    /// - If encryption or the database update fails.
+    pub fn set_item_labels(&mut self, id: u64, tags: &[String], folder: Option<&str>) -> Result<()> {
+        self.ensure_manifest_accepted()?;
+        let vk = self.key.as_ref().ok_or_else(|| eyre!("Locked"))?;
+        let row = self
+            .db
+            .get_item(id)?
+            .ok_or_else(|| eyre!("Item not found"))?
+            .reveal(vk)?;
+        let (nonce, ciphertext) = labels::seal_labels(vk, &row.name, &row.kind, tags, folder)?;
+        self.db.update_labels(id, Some((&nonce, &ciphertext)))?;
+        self.seal_manifest()
     }
 
-    /// Deletes an item from the database with the specified ID.
+    /// Moves the item with the specified ID to the trash.
+    ///
+    /// The item disappears from [`Vault::list_items`] but keeps its history and attachments
+    /// until it is purged, either with [`Vault::purge_item`] and [`Vault::empty_trash`] or
+    /// automatically once it has been in the trash longer than
+    /// [`Vault::trash_retention_days`]. Until then [`Vault::restore_item`] brings it back.
     ///
     /// # Parameters
     /// - `id` (i64): The unique identifier of the item to be deleted.
@@ -537,10 +909,14 @@ impl Vault {
     ///
     /// # Errors
     /// This function will return an error if:
-    /// - The item with the specified ID does not exist.
     /// - There is a failure in the underlying database operation.
     pub fn delete_item(&mut self, id: u64) -> Result<()> {
-        self.db.delete_item(id)
+        self.ensure_manifest_accepted()?;
+        if self.db.get_item(id)?.is_some_and(|r| r.deleted_at.is_none()) {
+            self.db.set_deleted_at(id, Some(OffsetDateTime::now_utc()))?;
+            self.seal_manifest()?;
+        }
+        Ok(())
     }
 
     /// Changes the master key for the vault.
@@ -593,14 +969,20 @@ impl Vault {
             self.db.read_meta()?.ok_or_else(|| eyre!("Vault not initialized"))?;
 
         // Verify the current master and unwrap the existing vault key
-        let current_derived = derive_key(current_master, &kdf_old)?;
+        let current_derived = self.derive_master(current_master, &kdf_old)?;
         let _ = unwrap_vault_key(&current_derived, &wrapped_old, Some(&verifier_old))
             .map_err(|_| eyre!("Invalid current master key"))?;
         let vault_key = unwrap_vault_key(&current_derived, &wrapped_old, None)?;
 
+        self.set_master_key(vault_key, new_master)
+    }
+
+    /// Wraps `vault_key` with a key derived from `new_master` under fresh KDF parameters, persists
+    /// it and keeps the vault unlocked with it.
+    fn set_master_key(&mut self, vault_key: KeyMaterial, new_master: &str) -> Result<()> {
         // Generate fresh KDF params and wrap with a new master-derived key
-        let kdf_new = KdfParams::default_secure();
-        let new_derived = derive_key(new_master, &kdf_new)?;
+        let kdf_new = self.fresh_kdf_params()?;
+        let new_derived = self.derive_master(new_master, &kdf_new)?;
         let (wrapped_new, verifier_new) = wrap_vault_key(&new_derived, &vault_key)?;
 
         // Persist new meta
@@ -611,6 +993,48 @@ impl Vault {
         Ok(())
     }
 
+    /// Returns the KDF parameters the master key is currently derived with.
+    ///
+    /// # Errors
+    /// Returns an error if the vault is not initialized or the metadata cannot be read.
+    pub fn kdf_params(&self) -> Result<KdfParams> {
+        let (kdf, _, _) = self.db.read_meta()?.ok_or_else(|| eyre!("Vault not initialized"))?;
+        Ok(kdf)
+    }
+
+    /// Re-wraps the vault key under a key derived from `master` with new KDF parameters.
+    ///
+    /// Only the wrapped vault key changes, so items are not re-encrypted. Key slots keep their
+    /// own parameters until they are added again.
+    ///
+    /// # Errors
+    /// Returns an error if the vault is not initialized, `master` is not the master key, or the
+    /// derivation, wrapping or writing the metadata fails.
+    pub fn rekdf(&mut self, master: &str, kdf: &KdfParams) -> Result<()> {
+        let (kdf_old, wrapped_old, verifier_old) =
+            self.db.read_meta()?.ok_or_else(|| eyre!("Vault not initialized"))?;
+        let current_derived = self.derive_master(master, &kdf_old)?;
+        let _ = unwrap_vault_key(&current_derived, &wrapped_old, Some(&verifier_old))
+            .map_err(|_| eyre!("Invalid master key"))?;
+        let vault_key = unwrap_vault_key(&current_derived, &wrapped_old, None)?;
+
+        let kdf_new = kdf.resalted();
+        let new_derived = self.derive_master(master, &kdf_new)?;
+        let (wrapped_new, verifier_new) = wrap_vault_key(&new_derived, &vault_key)?;
+        self.db.write_meta(&kdf_new, &wrapped_new, &verifier_new)?;
+        self.key = Some(vault_key);
+        Ok(())
+    }
+
+    /// Returns KDF parameters with a fresh salt for wrapping the vault key under a new password:
+    /// the costs the vault already uses, or the defaults when those are below recommendations.
+    pub(crate) fn fresh_kdf_params(&self) -> Result<KdfParams> {
+        Ok(match self.db.read_meta()? {
+            Some((kdf, _, _)) if kdf.meets_recommendations() => kdf.resalted(),
+            _ => KdfParams::default_secure(),
+        })
+    }
+
     /// Updates an item in the database with a new value, preserving the item's associated metadata.
     ///
     /// # Parameters
@@ -636,24 +1060,83 @@ impl Vault {
     /// - If the item is not found, an `eyre!("Item not found")` error is returned.
     /// - Any failures during encryption or database operations propagate as errors.
     pub fn update_item(&mut self, id: u64, new_value: &str) -> Result<()> {
-        let vk = self.key.as_ref().ok_or_else(|| eyre!("Locked"))?;
+        let item = self.get_item_by_id(id)?.ok_or_else(|| eyre!("Item not found"))?;
+        self.write_value(&item, new_value, &item.fields)
+    }
 
-        // Get the item to preserve name and kind for AD
-        let items = self.list_items()?;
-        let item = items
-            .iter()
-            .find(|i| i.id == id)
-            .ok_or_else(|| eyre!("Item not found"))?;
+    /// Updates the value and the fields of an item in one revision.
+    ///
+    /// `fields` replaces all fields of the item; typed fields left empty are dropped (see
+    /// [`normalize_fields`]). Like [`Vault::update_item`], the previous value and fields are
+    /// archived in the item's history.
+    ///
+    /// # Errors
+    /// - If the vault is locked, an `eyre!("Locked")` error is returned.
+    /// - If the item is not found, an `eyre!("Item not found")` error is returned.
+    /// - Any failures during encryption or database operations propagate as errors.
+    pub fn update_item_with_fields(&mut self, id: u64, new_value: &str, fields: &[ItemField]) -> Result<()> {
+        let item = self.get_item_by_id(id)?.ok_or_else(|| eyre!("Item not found"))?;
+        self.write_value(&item, new_value, &normalize_fields(item.kind, fields))
+    }
+
+    fn write_value(&mut self, item: &Item, value: &str, fields: &[ItemField]) -> Result<()> {
+        self.ensure_manifest_accepted()?;
+        let vk = self.key.as_ref().ok_or_else(|| eyre!("Locked"))?;
 
         // Encrypt new value with same AD (name and kind)
+        let payload = fields::encode_payload(value, fields)?;
         let nonce_cipher = aead_encrypt(
             vk,
-            new_value.as_bytes(),
+            &payload,
             ItemRow::ad_for_name_kind(&item.name, item.kind.as_str()).as_ref(),
         )?;
 
-        self.db.update_item(id, &nonce_cipher.0, &nonce_cipher.1)?;
-        Ok(())
+        self.db.update_item(item.id, &nonce_cipher.0, &nonce_cipher.1)?;
+        self.seal_manifest()
+    }
+
+    /// Returns the previous values of an item, oldest first.
+    ///
+    /// Every call to [`Vault::update_item`] archives the value it replaces, so the history
+    /// holds one revision per update. The current value is not part of the history.
+    ///
+    /// # Errors
+    /// - If the vault is locked, an `eyre!("Locked")` error is returned.
+    /// - If the item does not exist, an `eyre!("Item not found")` error is returned.
+    /// - If a revision fails to decrypt or is not valid UTF-8.
+    pub fn item_history(&self, id: u64) -> Result<Vec<ItemRevision>> {
+        let vk = self.key.as_ref().ok_or_else(|| eyre!("Locked"))?;
+        let ad = self.item_ad(vk, id)?;
+        self.db
+            .list_history(id)?
+            .into_iter()
+            .map(|h| decrypt_revision(vk, &ad, &h))
+            .collect()
+    }
+
+    /// Restores an item to the value it had at `revision`.
+    ///
+    /// The restore is itself an update: the current value is archived as a new revision first,
+    /// so a rollback can be undone the same way.
+    ///
+    /// # Errors
+    /// - If the vault is locked, an `eyre!("Locked")` error is returned.
+    /// - If the item or the revision does not exist.
+    /// - If decryption, re-encryption or the database update fails.
+    pub fn restore_revision(&mut self, id: u64, revision: u64) -> Result<()> {
+        let vk = self.key.as_ref().ok_or_else(|| eyre!("Locked"))?;
+        let ad = self.item_ad(vk, id)?;
+        let row = self
+            .db
+            .get_history(id, revision)?
+            .ok_or_else(|| eyre!("Revision {} not found", revision))?;
+        let restored = decrypt_revision(vk, &ad, &row)?;
+        self.update_item_with_fields(id, &restored.value, &restored.fields)
+    }
+
+    fn item_ad(&self, vk: &KeyMaterial, id: u64) -> Result<Vec<u8>> {
+        let row = self.db.get_item(id)?.ok_or_else(|| eyre!("Item not found"))?;
+        Ok(row.reveal(vk)?.ad())
     }
 
     /// Opens an existing vault by its ID.
@@ -684,7 +1167,9 @@ impl Vault {
             .get_vault(vault_id)
             .ok_or_else(|| eyre!("Vault '{}' not found", vault_id))?;
 
-        Self::open_or_create(Some(&vault_info.path))
+        let mut vault = Self::open_or_create(Some(&vault_info.path))?;
+        vault.set_keyfile(vault_info.keyfile.clone());
+        Ok(vault)
     }
 
     /// Creates a new vault with the specified parameters.
@@ -797,10 +1282,51 @@ impl Clone for Vault {
             db: Db::open(&self.db_path).expect("Failed to open database connection"),
             key: None, // Don't clone the key for security
             db_path: self.db_path.clone(),
+            keyfile: self.keyfile.clone(),
+            tamper_warning: None,
         }
     }
 }
 
+fn decrypt_item(vk: &KeyMaterial, r: ItemRow) -> Result<Item> {
+    open_item(vk, r.reveal(vk)?)
+}
+
+/// Decrypts an item row whose name and kind have already been revealed.
+fn open_item(vk: &KeyMaterial, r: ItemRow) -> Result<Item> {
+    let plaintext = aead_decrypt(vk, &r.nonce, &r.ciphertext, &r.ad())?;
+    let (value, fields) = fields::decode_payload(plaintext)?;
+    let (tags, folder) = labels::open_labels(
+        vk,
+        &r.labels_ad(),
+        r.labels_nonce.as_deref(),
+        r.labels_ciphertext.as_deref(),
+    )?;
+    Ok(Item {
+        id: r.id,
+        kind: ItemKind::from_str(&r.kind)?,
+        name: r.name,
+        value,
+        created_at: r.created_at,
+        updated_at: r.updated_at,
+        tags,
+        folder,
+        fields,
+    })
+}
+
+fn decrypt_revision(vk: &KeyMaterial, ad: &[u8], row: &HistoryRow) -> Result<ItemRevision> {
+    let plaintext = aead_decrypt(vk, &row.nonce, &row.ciphertext, ad)?;
+    let (value, fields) = fields::decode_payload(plaintext)?;
+    Ok(ItemRevision {
+        revision: row.revision,
+        value,
+        fields,
+        created_at: row.created_at,
+        archived_at: row.archived_at,
+    })
+}
+
 fn default_db_path() -> Result<PathBuf> {
     let base = dirs::config_dir().ok_or_else(|| eyre!("No config dir"))?;
     let dir = base.join("chamber");
@@ -930,11 +1456,17 @@ mod lib_module_tests {
             name: "alpha".into(),
             kind: ItemKind::Password,
             value: "A1".into(),
+            tags: Vec::new(),
+            folder: None,
+            fields: Vec::new(),
         })?;
         v.create_item(&NewItem {
             name: "beta".into(),
             kind: ItemKind::EnvVar,
             value: "B2".into(),
+            tags: Vec::new(),
+            folder: None,
+            fields: Vec::new(),
         })?;
 
         // List sorts by name
@@ -976,6 +1508,9 @@ mod lib_module_tests {
                 name: "one".into(),
                 kind: ItemKind::Note,
                 value: "first".into(),
+                tags: Vec::new(),
+                folder: None,
+                fields: Vec::new(),
             })?;
         }
 
@@ -1005,6 +1540,9 @@ mod lib_module_tests {
             name: "svc".into(),
             kind: ItemKind::ApiKey,
             value: "token-123".into(),
+            tags: Vec::new(),
+            folder: None,
+            fields: Vec::new(),
         })?;
 
         // Change master key
@@ -1025,6 +1563,120 @@ mod lib_module_tests {
         Ok(())
     }
 
+    #[test]
+    fn test_rekdf_keeps_items_and_changes_params() -> Result<()> {
+        let path = tmp_db("rekdf");
+        let weak = KdfParams::new(8 * 1024, 1, 1)?;
+        let mut v = Vault::open_or_create(Some(&path))?;
+        v.initialize_with_kdf("m", &weak)?;
+        v.unlock("m")?;
+        v.create_item(&NewItem {
+            name: "svc".into(),
+            kind: ItemKind::Password,
+            value: "token".into(),
+            tags: Vec::new(),
+            folder: None,
+            fields: Vec::new(),
+        })?;
+        assert!(!v.kdf_params()?.meets_recommendations());
+
+        let strong = KdfParams::new(32 * 1024, 2, 2)?;
+        assert!(v.rekdf("wrong", &strong).is_err());
+        v.rekdf("m", &strong)?;
+        let kdf = v.kdf_params()?;
+        assert_eq!((kdf.m_cost_kib, kdf.t_cost, kdf.p_cost), (32 * 1024, 2, 2));
+        assert_ne!(kdf.salt, strong.salt);
+
+        let mut reopened = Vault::open_or_create(Some(&path))?;
+        reopened.unlock("m")?;
+        assert_eq!(
+            reopened.get_item_by_name("svc")?.map(|i| i.value).as_deref(),
+            Some("token")
+        );
+
+        fs::remove_file(path).ok();
+        Ok(())
+    }
+
+    #[test]
+    fn test_item_headers_and_get_item_by_id() -> Result<()> {
+        let path = tmp_db("headers");
+        let mut v = Vault::open_or_create(Some(&path))?;
+        v.initialize("m")?;
+        v.unlock("m")?;
+        for (name, tag) in [("beta", "prod"), ("alpha", "dev")] {
+            v.create_item(&NewItem {
+                name: name.into(),
+                kind: ItemKind::Password,
+                value: format!("{name}-secret"),
+                tags: vec![tag.into()],
+                folder: Some("work".into()),
+                fields: Vec::new(),
+            })?;
+        }
+
+        let headers = v.list_item_headers()?;
+        let items = v.list_items()?;
+        assert_eq!(headers.len(), items.len());
+        for (header, item) in headers.iter().zip(&items) {
+            assert_eq!(header.id, item.id);
+            assert_eq!(header.name, item.name);
+            assert_eq!(header.kind, item.kind);
+            assert_eq!(header.tags, item.tags);
+            assert_eq!(header.folder, item.folder);
+        }
+        assert!(headers[0].has_tag("DEV"));
+
+        let item = v.get_item_by_id(headers[1].id)?.expect("exists");
+        assert_eq!(item.name, "beta");
+        assert_eq!(item.value, "beta-secret");
+        assert!(v.get_item_by_id(9999)?.is_none());
+
+        let v = Vault::open_or_create(Some(&path))?;
+        assert!(
+            v.list_item_headers().is_err(),
+            "headers need the vault key for the labels"
+        );
+        assert!(v.get_item_by_id(headers[0].id).is_err());
+
+        fs::remove_file(path).ok();
+        Ok(())
+    }
+
+    #[test]
+    fn test_unlock_with_key() -> Result<()> {
+        let path = tmp_db("unlock_with_key");
+        let mut v = Vault::open_or_create(Some(&path))?;
+        assert!(v.unlock_with_key(KeyMaterial::random()).is_err(), "not initialized");
+        v.initialize("m")?;
+        // An empty vault still recognizes a key that is not its own
+        assert!(
+            Vault::open_or_create(Some(&path))?
+                .unlock_with_key(KeyMaterial::random())
+                .is_err()
+        );
+        v.unlock("m")?;
+        v.create_item(&NewItem {
+            name: "token".into(),
+            kind: ItemKind::ApiKey,
+            value: "s3cret".into(),
+            tags: Vec::new(),
+            folder: None,
+            fields: Vec::new(),
+        })?;
+        let key = v.vault_key()?;
+
+        let mut other = Vault::open_or_create(Some(&path))?;
+        assert!(other.vault_key().is_err());
+        assert!(other.unlock_with_key(KeyMaterial::random()).is_err());
+        assert!(!other.is_unlocked());
+        other.unlock_with_key(key)?;
+        assert_eq!(other.get_item_by_name("token")?.expect("exists").value, "s3cret");
+
+        fs::remove_file(path).ok();
+        Ok(())
+    }
+
     #[test]
     fn test_get_item_by_name_not_found() -> Result<()> {
         let path = tmp_db("get_missing");
@@ -1035,6 +1687,9 @@ mod lib_module_tests {
             name: "exists".into(),
             kind: ItemKind::Note,
             value: "v".into(),
+            tags: Vec::new(),
+            folder: None,
+            fields: Vec::new(),
         })?;
 
         assert!(v.get_item_by_name("nope")?.is_none());
@@ -1042,4 +1697,209 @@ mod lib_module_tests {
         fs::remove_file(path).ok();
         Ok(())
     }
+
+    #[test]
+    fn test_backup_key_requires_unlock_and_is_stable() -> Result<()> {
+        let path = tmp_db("backup_key");
+        let mut v = Vault::open_or_create(Some(&path))?;
+        v.initialize("m")?;
+        assert!(v.backup_key().is_err(), "locked vault must not expose a backup key");
+
+        v.unlock("m")?;
+        let first = v.backup_key()?;
+
+        // Re-open and unlock: the backup key must be derived from the same vault key
+        let mut reopened = Vault::open_or_create(Some(&path))?;
+        reopened.unlock("m")?;
+        assert_eq!(first.0, reopened.backup_key()?.0);
+
+        fs::remove_file(path).ok();
+        Ok(())
+    }
+
+    #[test]
+    fn test_snapshot_of_locked_vault_restores_exactly() -> Result<()> {
+        let path = tmp_db("snapshot");
+        let snapshot = tmp_db("snapshot_copy");
+        let mut v = Vault::open_or_create(Some(&path))?;
+        v.initialize("m")?;
+        v.unlock("m")?;
+        v.create_item(&NewItem {
+            name: "kept".into(),
+            kind: ItemKind::Password,
+            value: "secret".into(),
+            tags: Vec::new(),
+            folder: None,
+            fields: Vec::new(),
+        })?;
+        let before = v.list_items()?;
+
+        // Snapshots need no key
+        let mut v = Vault::open_or_create(Some(&path))?;
+        v.snapshot_to(&snapshot)?;
+
+        v.unlock("m")?;
+        v.delete_item(before[0].id)?;
+        v.restore_snapshot(&snapshot)?;
+        assert!(v.list_items().is_err(), "restoring a snapshot locks the vault");
+
+        v.unlock("m")?;
+        let after = v.list_items()?;
+        assert_eq!(after.len(), 1);
+        assert_eq!(after[0].id, before[0].id);
+        assert_eq!(after[0].value, "secret");
+        assert_eq!(after[0].created_at, before[0].created_at);
+
+        fs::remove_file(path).ok();
+        fs::remove_file(snapshot).ok();
+        Ok(())
+    }
+
+    #[test]
+    fn test_item_tags_and_folder_are_encrypted_and_bound() -> Result<()> {
+        let path = tmp_db("labels");
+        let mut v = Vault::open_or_create(Some(&path))?;
+        v.initialize("m")?;
+        v.unlock("m")?;
+        v.create_item(&NewItem {
+            name: "db".into(),
+            kind: ItemKind::Database,
+            value: "postgres://".into(),
+            tags: vec![" prod ".into(), "PROD".into(), "db".into()],
+            folder: Some("/work/infra/".into()),
+            fields: Vec::new(),
+        })?;
+        v.create_item(&NewItem {
+            name: "plain".into(),
+            kind: ItemKind::Note,
+            value: "x".into(),
+            tags: Vec::new(),
+            folder: None,
+            fields: Vec::new(),
+        })?;
+
+        let items = v.list_items()?;
+        let db = items.iter().find(|i| i.name == "db").unwrap();
+        assert_eq!(db.tags, vec!["prod".to_string(), "db".to_string()]);
+        assert_eq!(db.folder.as_deref(), Some("work/infra"));
+        assert!(db.has_tag("Prod"));
+
+        let plain_id = items.iter().find(|i| i.name == "plain").unwrap().id;
+        v.set_item_labels(plain_id, &["personal".to_string()], None)?;
+        let plain = v.list_items()?.into_iter().find(|i| i.id == plain_id).unwrap();
+        assert_eq!(plain.tags, vec!["personal".to_string()]);
+
+        // Moving one item's labels onto another must be detected
+        let rows = v.db.list_items()?;
+        let db_row = rows.iter().find(|r| r.name == "db").unwrap();
+        v.db.update_labels(
+            plain_id,
+            Some((
+                db_row.labels_nonce.as_deref().unwrap(),
+                db_row.labels_ciphertext.as_deref().unwrap(),
+            )),
+        )?;
+        assert!(v.list_items().is_err());
+
+        // Stripping the labels of an item, even an unlabeled one, is detected as well
+        let db_id = db_row.id;
+        v.set_item_labels(plain_id, &[], None)?;
+        v.db.update_labels(plain_id, None)?;
+        v.db.update_labels(db_id, None)?;
+        assert!(v.get_item_by_id(plain_id).is_err());
+        let mut reopened = Vault::open_or_create(Some(&path))?;
+        reopened.unlock("m")?;
+        let warning = reopened.tamper_warning().unwrap();
+        assert_eq!(warning.changed_items.len(), 2);
+        assert!(reopened.list_items().is_err());
+
+        fs::remove_file(path).ok();
+        Ok(())
+    }
+
+    #[test]
+    fn test_item_fields_survive_updates_and_rollback() -> Result<()> {
+        let path = tmp_db("fields");
+        let mut v = Vault::open_or_create(Some(&path))?;
+        v.initialize("m")?;
+        v.unlock("m")?;
+        v.create_item(&NewItem {
+            name: "pg".into(),
+            kind: ItemKind::Database,
+            value: "s3cret".into(),
+            tags: Vec::new(),
+            folder: None,
+            fields: vec![
+                ItemField::new("host", "db.internal", false),
+                ItemField::new("port", "5432", false),
+                ItemField::new("replica", "db-ro.internal", false),
+            ],
+        })?;
+
+        let item = v.get_item_by_name("pg")?.unwrap();
+        assert_eq!(item.value, "s3cret");
+        assert_eq!(item.field("HOST"), Some("db.internal"));
+        assert!(
+            item.fields
+                .iter()
+                .any(|f| f.name == "replica" && f.is_custom(item.kind))
+        );
+
+        // Updating only the value keeps the fields
+        v.update_item(item.id, "rotated")?;
+        let updated = v.get_item_by_name("pg")?.unwrap();
+        assert_eq!(updated.value, "rotated");
+        assert_eq!(updated.fields, item.fields);
+
+        v.update_item_with_fields(item.id, "rotated", &[ItemField::new("port", "6432", false)])?;
+        let updated = v.get_item_by_name("pg")?.unwrap();
+        assert_eq!(updated.field("port"), Some("6432"));
+        assert_eq!(updated.field("host"), None);
+
+        // Rolling back restores the fields of the revision too
+        v.restore_revision(item.id, 1)?;
+        let restored = v.get_item_by_name("pg")?.unwrap();
+        assert_eq!(restored.value, "s3cret");
+        assert_eq!(restored.fields, item.fields);
+
+        fs::remove_file(path).ok();
+        Ok(())
+    }
+
+    #[test]
+    fn test_item_history_and_restore_revision() -> Result<()> {
+        let path = tmp_db("history");
+        let mut v = Vault::open_or_create(Some(&path))?;
+        v.initialize("m")?;
+        v.unlock("m")?;
+        v.create_item(&NewItem {
+            name: "token".into(),
+            kind: ItemKind::ApiKey,
+            value: "v1".into(),
+            tags: Vec::new(),
+            folder: None,
+            fields: Vec::new(),
+        })?;
+        let id = v.list_items()?[0].id;
+
+        v.update_item(id, "v2")?;
+        v.update_item(id, "v3")?;
+        let history = v.item_history(id)?;
+        let values: Vec<_> = history.iter().map(|r| (r.revision, r.value.as_str())).collect();
+        assert_eq!(values, vec![(1, "v1"), (2, "v2")]);
+
+        // Rolling back archives the current value, so it can be undone too
+        v.restore_revision(id, 1)?;
+        assert_eq!(v.list_items()?[0].value, "v1");
+        assert_eq!(v.item_history(id)?.last().map(|r| r.value.clone()), Some("v3".into()));
+
+        assert!(v.restore_revision(id, 42).is_err());
+        assert!(v.item_history(id + 100).is_err());
+
+        let locked = Vault::open_or_create(Some(&path))?;
+        assert!(locked.item_history(id).is_err());
+
+        fs::remove_file(path).ok();
+        Ok(())
+    }
 }
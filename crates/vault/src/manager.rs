@@ -85,8 +85,29 @@ impl VaultManager {
         category: VaultCategory,
         description: Option<String>,
         master_password: &str,
+    ) -> Result<String> {
+        self.create_vault_with_keyfile(name, path, category, description, master_password, None)
+    }
+
+    /// Creates a vault like [`VaultManager::create_vault`] that also requires `keyfile` to unlock.
+    /// The keyfile is recorded in the vault's registry entry.
+    ///
+    /// # Errors
+    /// Returns an error if the vault cannot be created, the keyfile cannot be read or the vault
+    /// cannot be initialized.
+    pub fn create_vault_with_keyfile(
+        &mut self,
+        name: String,
+        path: Option<PathBuf>,
+        category: VaultCategory,
+        description: Option<String>,
+        master_password: &str,
+        keyfile: Option<PathBuf>,
     ) -> Result<String> {
         let vault_id = self.registry.create_vault(name, path, category, description)?;
+        if keyfile.is_some() {
+            self.registry.set_keyfile(&vault_id, keyfile.clone())?;
+        }
 
         // Initialize the new vault
         let vault_info = self
@@ -94,6 +115,7 @@ impl VaultManager {
             .get_vault(&vault_id)
             .ok_or_else(|| eyre!("Vault with id {} not found", vault_id))?;
         let mut vault = Vault::open_or_create(Some(&vault_info.path))?;
+        vault.set_keyfile(keyfile);
         vault.initialize(master_password)?;
 
         Ok(vault_id)
@@ -123,6 +145,7 @@ impl VaultManager {
             .ok_or_else(|| eyre!("Vault '{}' not found", vault_id))?;
 
         let mut vault = Vault::open_or_create(Some(&vault_info.path))?;
+        vault.set_keyfile(vault_info.keyfile.clone());
         vault.unlock(master_password)?;
 
         self.open_vaults.insert(vault_id.to_string(), vault);
@@ -404,6 +427,8 @@ mod tests {
             category,
             is_active: false,
             is_favorite: false,
+            keyfile: None,
+            manifest_version: None,
         }
     }
 
@@ -422,6 +447,9 @@ mod tests {
             value: "test_value".to_string(),
             created_at: OffsetDateTime::now_utc(),
             updated_at: OffsetDateTime::now_utc(),
+            tags: Vec::new(),
+            folder: None,
+            fields: Vec::new(),
         }
     }
 
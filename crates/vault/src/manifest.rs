@@ -0,0 +1,344 @@
+use crate::{HmacSha256, ItemStateRow, KeyMaterial, Vault, VaultRegistry, derive_subkey};
+use color_eyre::Result;
+use color_eyre::eyre::eyre;
+use hmac::Mac;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+
+/// What the manifest records about one item.
+#[derive(Serialize, Deserialize)]
+struct ManifestEntry {
+    id: u64,
+    /// Newest archived revision; it grows with every update.
+    revision: u64,
+    /// Nonce of the current value, which changes whenever the value is written.
+    nonce: Vec<u8>,
+    /// Nonce of the encrypted labels; absent from manifests written before labels were always
+    /// sealed, which are signed again once the vault checks out.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    labels_nonce: Option<Vec<u8>>,
+    /// When the item was moved to the trash. Only compared for manifests that record the trash
+    /// retention period; older ones did not cover the trash.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    deleted_at: Option<String>,
+}
+
+/// The items of a vault at one point in time, authenticated with a key derived from the vault
+/// key. Per-item encryption stops rows from being modified; the manifest also reveals rows that
+/// were deleted or replaced by older copies.
+#[derive(Serialize, Deserialize)]
+struct Manifest {
+    /// Increases with every change, so an older copy of the whole file can be recognized.
+    version: u64,
+    items: Vec<ManifestEntry>,
+    /// Days deleted items stay in the trash; absent from manifests written before the trash was
+    /// covered, which are signed again once the vault checks out.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    trash_retention_days: Option<u32>,
+}
+
+/// Differences between a vault and its manifest, found when the vault is unlocked.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TamperWarning {
+    /// The manifest is missing or its authentication tag does not verify.
+    pub manifest_invalid: bool,
+    /// Items listed in the manifest that are no longer in the vault.
+    pub missing_items: Vec<u64>,
+    /// Items in the vault that the manifest does not list.
+    pub unexpected_items: Vec<u64>,
+    /// Items whose value, labels or trash state is not the one written last, e.g. because an
+    /// older row was copied back.
+    pub changed_items: Vec<u64>,
+    /// The trash retention period in the file and the one the manifest signed.
+    pub trash_retention_changed: Option<(u32, u32)>,
+    /// The manifest version in the file and the newer version this machine saw before.
+    pub rolled_back: Option<(u64, u64)>,
+}
+
+impl TamperWarning {
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+impl fmt::Display for TamperWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fn ids(ids: &[u64]) -> String {
+            ids.iter().map(u64::to_string).collect::<Vec<_>>().join(", ")
+        }
+
+        let mut problems = Vec::new();
+        if self.manifest_invalid {
+            problems.push("the vault manifest is missing or has been modified".to_string());
+        }
+        if !self.missing_items.is_empty() {
+            problems.push(format!(
+                "{} item(s) have disappeared (ids {})",
+                self.missing_items.len(),
+                ids(&self.missing_items)
+            ));
+        }
+        if !self.unexpected_items.is_empty() {
+            problems.push(format!(
+                "{} item(s) were added outside Chamber (ids {})",
+                self.unexpected_items.len(),
+                ids(&self.unexpected_items)
+            ));
+        }
+        if !self.changed_items.is_empty() {
+            problems.push(format!(
+                "{} item(s) hold an older or unexpected value (ids {})",
+                self.changed_items.len(),
+                ids(&self.changed_items)
+            ));
+        }
+        if let Some((found, signed)) = self.trash_retention_changed {
+            problems.push(format!(
+                "the trash retention period was changed from {signed} to {found} day(s) outside Chamber"
+            ));
+        }
+        if let Some((found, seen)) = self.rolled_back {
+            problems.push(format!(
+                "the vault file is at version {found}, but version {seen} was seen before; it may have been rolled back"
+            ));
+        }
+        write!(f, "{}", problems.join("; "))
+    }
+}
+
+fn manifest_hmac(vk: &KeyMaterial, manifest: &str) -> Result<HmacSha256> {
+    let key = derive_subkey(vk, b"chamber-manifest")?;
+    let mut mac = <HmacSha256 as Mac>::new_from_slice(&key.0)?;
+    mac.update(manifest.as_bytes());
+    Ok(mac)
+}
+
+impl Vault {
+    /// Returns what the last unlock found when it compared the vault with its manifest, if
+    /// anything looked wrong.
+    ///
+    /// While there is a warning the vault refuses changes, since signing the manifest after
+    /// them would also sign the differences. [`Vault::accept_manifest`] clears it.
+    #[must_use]
+    pub const fn tamper_warning(&self) -> Option<&TamperWarning> {
+        self.tamper_warning.as_ref()
+    }
+
+    /// Compares the vault with its manifest and with the newest version seen on this machine.
+    /// Run after every unlock.
+    ///
+    /// A vault without a manifest that was never seen before, such as one written by an older
+    /// Chamber, gets one signed now.
+    ///
+    /// Returns whether the trash was verified, i.e. the manifest checked out and covers which
+    /// items are in the trash and the retention period. Only then may expired items be purged.
+    pub(crate) fn check_manifest(&mut self) -> Result<bool> {
+        let Some(vk) = &self.key else {
+            return Ok(false);
+        };
+        let seen = VaultRegistry::seen_manifest_version(&self.db_path);
+        let mut warning = TamperWarning::default();
+        let mut version = None;
+        let mut outdated = false;
+        match self.db.read_manifest()? {
+            Some((manifest, mac)) if manifest_hmac(vk, &manifest)?.verify_slice(&mac).is_ok() => {
+                let manifest: Manifest = serde_json::from_str(&manifest)?;
+                self.compare_items(&manifest, &mut warning)?;
+                if let Some(signed) = manifest.trash_retention_days {
+                    let found = self.db.trash_retention_days()?;
+                    if found != signed {
+                        warning.trash_retention_changed = Some((found, signed));
+                    }
+                }
+                outdated =
+                    manifest.trash_retention_days.is_none() || manifest.items.iter().any(|e| e.labels_nonce.is_none());
+                version = Some(manifest.version);
+            }
+            Some(_) => warning.manifest_invalid = true,
+            None => warning.manifest_invalid = seen.is_some(),
+        }
+        if let (Some(found), Some(seen)) = (version, seen) {
+            if found < seen {
+                warning.rolled_back = Some((found, seen));
+            }
+        }
+
+        if !warning.is_empty() {
+            self.tamper_warning = Some(warning);
+            return Ok(false);
+        }
+        self.tamper_warning = None;
+        match version {
+            Some(version) if !outdated => {
+                VaultRegistry::record_manifest_version(&self.db_path, Some(version));
+                Ok(true)
+            }
+            _ => {
+                self.seal_manifest()?;
+                Ok(false)
+            }
+        }
+    }
+
+    /// Accepts the differences the last unlock warned about by signing the current items as the
+    /// new manifest, after which the vault can be changed again.
+    ///
+    /// Only call this once the user has confirmed that the differences are their own doing, for
+    /// example after copying the vault file between machines.
+    ///
+    /// # Errors
+    /// Returns an error if the vault is locked or the manifest cannot be written.
+    pub fn accept_manifest(&mut self) -> Result<()> {
+        let vk = self.key.as_ref().ok_or_else(|| eyre!("Locked"))?;
+        let version = self.sign_manifest(vk)?;
+        VaultRegistry::record_manifest_version(&self.db_path, Some(version));
+        self.tamper_warning = None;
+        Ok(())
+    }
+
+    /// Refuses changes while the last unlock's tamper warning has not been accepted.
+    pub(crate) fn ensure_manifest_accepted(&self) -> Result<()> {
+        if self.tamper_warning.is_some() {
+            return Err(eyre!(
+                "The vault does not match its signed manifest; restore a backup, or run \
+                 'chamber doctor --accept-manifest' to accept its current state before changing it"
+            ));
+        }
+        Ok(())
+    }
+
+    fn compare_items(&self, manifest: &Manifest, warning: &mut TamperWarning) -> Result<()> {
+        let covers_trash = manifest.trash_retention_days.is_some();
+        let mut current: HashMap<u64, ItemStateRow> =
+            self.db.item_states()?.into_iter().map(|row| (row.id, row)).collect();
+        for entry in &manifest.items {
+            match current.remove(&entry.id) {
+                None => warning.missing_items.push(entry.id),
+                Some(row)
+                    if row.revision != entry.revision
+                        || row.nonce != entry.nonce
+                        || (entry.labels_nonce.is_some() && row.labels_nonce != entry.labels_nonce)
+                        || (covers_trash && row.deleted_at != entry.deleted_at) =>
+                {
+                    warning.changed_items.push(entry.id);
+                }
+                Some(_) => {}
+            }
+        }
+        warning.unexpected_items = current.into_keys().collect();
+        warning.unexpected_items.sort_unstable();
+        Ok(())
+    }
+
+    /// Signs the current items as the new manifest after a change. Does nothing while the vault
+    /// is locked, and refuses while there is a tamper warning, which only
+    /// [`Vault::accept_manifest`] may sign over.
+    pub(crate) fn seal_manifest(&mut self) -> Result<()> {
+        self.ensure_manifest_accepted()?;
+        let Some(vk) = &self.key else {
+            return Ok(());
+        };
+        let version = self.sign_manifest(vk)?;
+        VaultRegistry::record_manifest_version(&self.db_path, Some(version));
+        Ok(())
+    }
+
+    /// Writes a manifest of the current items signed with `vk`, one version above both the
+    /// stored manifest and the newest version seen here, and returns its version.
+    pub(crate) fn sign_manifest(&self, vk: &KeyMaterial) -> Result<u64> {
+        let stored = self
+            .db
+            .read_manifest()?
+            .and_then(|(manifest, _)| serde_json::from_str::<Manifest>(&manifest).ok())
+            .map_or(0, |m| m.version);
+        let seen = VaultRegistry::seen_manifest_version(&self.db_path).unwrap_or(0);
+        let manifest = Manifest {
+            version: stored.max(seen).saturating_add(1),
+            items: self
+                .db
+                .item_states()?
+                .into_iter()
+                .map(|row| ManifestEntry {
+                    id: row.id,
+                    revision: row.revision,
+                    nonce: row.nonce,
+                    labels_nonce: row.labels_nonce,
+                    deleted_at: row.deleted_at,
+                })
+                .collect(),
+            trash_retention_days: Some(self.db.trash_retention_days()?),
+        };
+        let json = serde_json::to_string(&manifest)?;
+        self.db
+            .write_manifest(&json, &manifest_hmac(vk, &json)?.finalize().into_bytes())?;
+        Ok(manifest.version)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+    use super::*;
+    use crate::test_support::{new_item, unlocked_vault};
+    use rusqlite::Connection;
+
+    #[test]
+    fn test_manifest_reveals_deleted_and_replayed_rows() {
+        let (mut vault, path) = unlocked_vault("manifest");
+        assert!(vault.tamper_warning().is_none());
+        for name in ["a", "b", "c"] {
+            vault.create_item(&new_item(name)).unwrap();
+        }
+        let ids: Vec<_> = vault.list_items().unwrap().into_iter().map(|i| i.id).collect();
+        let conn = Connection::open(&path).unwrap();
+        let (old_nonce, old_ciphertext): (Vec<u8>, Vec<u8>) = conn
+            .query_row("SELECT nonce, ciphertext FROM items WHERE id = ?", [ids[1]], |r| {
+                Ok((r.get(0)?, r.get(1)?))
+            })
+            .unwrap();
+        vault.update_item(ids[1], "v2").unwrap();
+
+        let mut reopened = Vault::open_or_create(Some(&path)).unwrap();
+        reopened.unlock("m").unwrap();
+        assert!(reopened.tamper_warning().is_none());
+
+        // Delete one row and copy the old value of another back, both valid under AEAD
+        conn.execute("DELETE FROM items WHERE id = ?", [ids[0]]).unwrap();
+        conn.execute(
+            "UPDATE items SET nonce = ?, ciphertext = ? WHERE id = ?",
+            rusqlite::params![old_nonce, old_ciphertext, ids[1]],
+        )
+        .unwrap();
+        let mut tampered = Vault::open_or_create(Some(&path)).unwrap();
+        tampered.unlock("m").unwrap();
+        let warning = tampered.tamper_warning().unwrap();
+        assert_eq!(warning.missing_items, [ids[0]]);
+        assert_eq!(warning.changed_items, [ids[1]]);
+        assert!(!warning.manifest_invalid && warning.rolled_back.is_none());
+
+        // A forged manifest does not verify, and dropping it is noticed once a version was seen
+        conn.execute(
+            "UPDATE meta SET manifest = replace(manifest, '\"version\"', '\"version\" ')",
+            [],
+        )
+        .unwrap();
+        let mut forged = Vault::open_or_create(Some(&path)).unwrap();
+        forged.unlock("m").unwrap();
+        assert!(forged.tamper_warning().unwrap().manifest_invalid);
+
+        // Changes are refused until the current state is accepted explicitly
+        assert!(forged.create_item(&new_item("d")).is_err());
+        assert!(forged.update_item(ids[1], "v3").is_err());
+        let mut refused = Vault::open_or_create(Some(&path)).unwrap();
+        refused.unlock("m").unwrap();
+        assert!(refused.tamper_warning().unwrap().manifest_invalid);
+        forged.accept_manifest().unwrap();
+        forged.create_item(&new_item("d")).unwrap();
+        let mut accepted = Vault::open_or_create(Some(&path)).unwrap();
+        accepted.unlock("m").unwrap();
+        assert!(accepted.tamper_warning().is_none());
+        let _ = std::fs::remove_file(&path);
+    }
+}
@@ -0,0 +1,326 @@
+use color_eyre::Result;
+use color_eyre::eyre::eyre;
+use rusqlite::Connection;
+use std::path::{Path, PathBuf};
+
+/// Schema version of the vault databases written by this build of Chamber.
+///
+/// It is stored in `PRAGMA user_version`. Older vaults are migrated when they are opened;
+/// vaults with a newer version are refused.
+pub const SCHEMA_VERSION: u32 = 10;
+
+/// One step of the schema history. Step `n` (counting from one) upgrades a database from
+/// version `n - 1` to `n`.
+///
+/// Vaults created before the schema was versioned are at version 0 with any prefix of these
+/// steps already applied, so the steps up to version 6 must be idempotent.
+struct Migration {
+    description: &'static str,
+    apply: fn(&Connection) -> Result<()>,
+}
+
+const MIGRATIONS: &[Migration] = &[
+    Migration {
+        description: "Create the meta, items and item history tables",
+        apply: |conn| {
+            conn.execute_batch(
+                r"
+                CREATE TABLE IF NOT EXISTS meta (
+                    id INTEGER PRIMARY KEY CHECK (id = 1),
+                    kdf_params TEXT NOT NULL,
+                    wrapped_key BLOB NOT NULL,
+                    verifier BLOB NOT NULL
+                );
+
+                CREATE TABLE IF NOT EXISTS items (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    name TEXT NOT NULL,
+                    kind TEXT NOT NULL,
+                    nonce BLOB NOT NULL,
+                    ciphertext BLOB NOT NULL,
+                    created_at TEXT NOT NULL,
+                    updated_at TEXT NOT NULL
+                );
+
+                CREATE UNIQUE INDEX IF NOT EXISTS idx_items_name ON items(name);
+
+                CREATE TABLE IF NOT EXISTS item_history (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    item_id INTEGER NOT NULL REFERENCES items(id) ON DELETE CASCADE,
+                    revision INTEGER NOT NULL,
+                    nonce BLOB NOT NULL,
+                    ciphertext BLOB NOT NULL,
+                    created_at TEXT NOT NULL,
+                    archived_at TEXT NOT NULL,
+                    UNIQUE (item_id, revision)
+                );
+                ",
+            )?;
+            Ok(())
+        },
+    },
+    Migration {
+        description: "Add encrypted tags and folders to items",
+        apply: |conn| {
+            ensure_column(conn, "items", "labels_nonce", "BLOB")?;
+            ensure_column(conn, "items", "labels_ciphertext", "BLOB")
+        },
+    },
+    Migration {
+        description: "Add the attachments and attachment chunks tables",
+        apply: |conn| {
+            conn.execute_batch(
+                r"
+                CREATE TABLE IF NOT EXISTS attachments (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    item_id INTEGER NOT NULL REFERENCES items(id) ON DELETE CASCADE,
+                    meta_nonce BLOB,
+                    meta_ciphertext BLOB,
+                    created_at TEXT NOT NULL
+                );
+
+                CREATE INDEX IF NOT EXISTS idx_attachments_item ON attachments(item_id);
+
+                CREATE TABLE IF NOT EXISTS attachment_chunks (
+                    attachment_id INTEGER NOT NULL REFERENCES attachments(id) ON DELETE CASCADE,
+                    chunk_index INTEGER NOT NULL,
+                    nonce BLOB NOT NULL,
+                    ciphertext BLOB NOT NULL,
+                    PRIMARY KEY (attachment_id, chunk_index)
+                );
+                ",
+            )?;
+            Ok(())
+        },
+    },
+    Migration {
+        description: "Add the recovery key to the vault metadata",
+        apply: |conn| {
+            ensure_column(conn, "meta", "recovery_key", "BLOB")?;
+            ensure_column(conn, "meta", "recovery_verifier", "BLOB")
+        },
+    },
+    Migration {
+        description: "Add the key slots table",
+        apply: |conn| {
+            conn.execute_batch(
+                r"
+                CREATE TABLE IF NOT EXISTS key_slots (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    name TEXT NOT NULL UNIQUE,
+                    kdf_params TEXT NOT NULL,
+                    wrapped_key BLOB NOT NULL,
+                    verifier BLOB NOT NULL,
+                    created_at TEXT NOT NULL
+                );
+                ",
+            )?;
+            Ok(())
+        },
+    },
+    Migration {
+        description: "Add encrypted item names and kinds for private metadata",
+        apply: |conn| {
+            ensure_column(conn, "items", "header_nonce", "BLOB")?;
+            ensure_column(conn, "items", "header_ciphertext", "BLOB")?;
+            ensure_column(conn, "meta", "private_metadata", "INTEGER NOT NULL DEFAULT 0")
+        },
+    },
+    Migration {
+        description: "Add the quarantine table for rows that fail the integrity check",
+        apply: |conn| {
+            conn.execute_batch(
+                r"
+                CREATE TABLE quarantine (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    source_table TEXT NOT NULL,
+                    source_rowid INTEGER NOT NULL,
+                    problem TEXT NOT NULL,
+                    row_data TEXT NOT NULL,
+                    quarantined_at TEXT NOT NULL
+                );
+                ",
+            )?;
+            Ok(())
+        },
+    },
+    Migration {
+        description: "Add the signed manifest of the vault's items",
+        apply: |conn| {
+            ensure_column(conn, "meta", "manifest", "TEXT")?;
+            ensure_column(conn, "meta", "manifest_mac", "BLOB")
+        },
+    },
+    Migration {
+        description: "Add the trash: deleted items keep their row until they are purged",
+        apply: |conn| {
+            ensure_column(conn, "items", "deleted_at", "TEXT")?;
+            ensure_column(conn, "meta", "trash_retention_days", "INTEGER NOT NULL DEFAULT 30")?;
+            // Names only need to be unique among items that are not in the trash
+            conn.execute_batch(
+                r"
+                DROP INDEX IF EXISTS idx_items_name;
+                CREATE UNIQUE INDEX idx_items_name ON items(name) WHERE deleted_at IS NULL;
+                ",
+            )?;
+            Ok(())
+        },
+    },
+    Migration {
+        description: "Add the check value that recognizes the vault key",
+        apply: |conn| ensure_column(conn, "meta", "key_check", "BLOB"),
+    },
+];
+
+const _: () = assert!(MIGRATIONS.len() == SCHEMA_VERSION as usize);
+
+/// Where a vault's schema stands relative to this build, see [`crate::Db::schema_status`].
+#[derive(Debug, Clone)]
+pub struct SchemaStatus {
+    /// Version stored in the vault file.
+    pub version: u32,
+    /// Descriptions of the migrations opening the vault will apply, in order.
+    pub pending: Vec<&'static str>,
+    /// Where the vault file is copied before those migrations run.
+    pub snapshot_path: PathBuf,
+}
+
+impl SchemaStatus {
+    pub(crate) fn new(db_path: &Path, version: u32) -> Self {
+        let pending = MIGRATIONS
+            .iter()
+            .skip(usize::try_from(version).unwrap_or(usize::MAX))
+            .map(|m| m.description)
+            .collect();
+        Self {
+            version,
+            pending,
+            snapshot_path: pre_migration_snapshot_path(db_path, version),
+        }
+    }
+}
+
+/// Returns the path the vault file at `db_path` is copied to before migrating from `version`.
+fn pre_migration_snapshot_path(db_path: &Path, version: u32) -> PathBuf {
+    let mut name = db_path.file_name().unwrap_or_default().to_os_string();
+    name.push(format!(".pre-migration-v{version}"));
+    db_path.with_file_name(name)
+}
+
+/// Reads the schema version and refuses databases written by a newer Chamber.
+pub(crate) fn schema_version(conn: &Connection) -> Result<u32> {
+    let version: u32 = conn.query_row("PRAGMA user_version", [], |r| r.get(0))?;
+    if version > SCHEMA_VERSION {
+        return Err(eyre!(
+            "This vault uses schema version {version}, but this version of Chamber only supports up to \
+             {SCHEMA_VERSION}; upgrade Chamber to open it"
+        ));
+    }
+    Ok(version)
+}
+
+/// Returns whether the database has any tables, i.e. holds more than a freshly created file.
+pub(crate) fn has_tables(conn: &Connection) -> Result<bool> {
+    let tables: i64 = conn.query_row("SELECT COUNT(*) FROM sqlite_master WHERE type = 'table'", [], |r| {
+        r.get(0)
+    })?;
+    Ok(tables > 0)
+}
+
+/// Applies the pending migrations in one transaction and records the new version, so a
+/// failed step leaves the database as it was.
+pub(crate) fn migrate(conn: &Connection) -> Result<()> {
+    let tx = conn.unchecked_transaction()?;
+    // Read the version inside the transaction in case another process migrated meanwhile
+    let version = schema_version(&tx)?;
+    for (index, migration) in MIGRATIONS.iter().enumerate().skip(usize::try_from(version)?) {
+        (migration.apply)(&tx).map_err(|e| {
+            eyre!(
+                "Migration to schema version {} failed ({}): {e}",
+                index + 1,
+                migration.description
+            )
+        })?;
+    }
+    if version < SCHEMA_VERSION {
+        tx.pragma_update(None, "user_version", SCHEMA_VERSION)?;
+    }
+    tx.commit()?;
+    Ok(())
+}
+
+fn ensure_column(conn: &Connection, table: &str, column: &str, decl: &str) -> Result<()> {
+    let mut stmt = conn.prepare(&format!("PRAGMA table_info({table})"))?;
+    let exists = stmt
+        .query_map([], |r| r.get::<_, String>(1))?
+        .collect::<rusqlite::Result<Vec<_>>>()?
+        .iter()
+        .any(|name| name == column);
+    if !exists {
+        conn.execute_batch(&format!("ALTER TABLE {table} ADD COLUMN {column} {decl};"))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+    use super::*;
+    use crate::Db;
+
+    fn tmp_path(name: &str) -> PathBuf {
+        let now = time::OffsetDateTime::now_utc().unix_timestamp_nanos();
+        std::env::temp_dir().join(format!(
+            "chamber_migrations_{name}_{}_{now}.sqlite3",
+            std::process::id()
+        ))
+    }
+
+    #[test]
+    fn test_new_vault_starts_at_latest_version_without_snapshot() {
+        let path = tmp_path("fresh");
+        let db = Db::open(&path).unwrap();
+        assert_eq!(db.schema_version().unwrap(), SCHEMA_VERSION);
+        assert!(!pre_migration_snapshot_path(&path, 0).exists());
+        let status = Db::schema_status(&path).unwrap();
+        assert!(status.pending.is_empty());
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_unversioned_vault_is_migrated_after_snapshot() {
+        let path = tmp_path("legacy");
+        {
+            let conn = Connection::open(&path).unwrap();
+            (MIGRATIONS[0].apply)(&conn).unwrap();
+            conn.execute_batch(
+                "INSERT INTO meta (id, kdf_params, wrapped_key, verifier) VALUES (1, '{}', x'00', x'00');
+                 INSERT INTO items (name, kind, nonce, ciphertext, created_at, updated_at)
+                 VALUES ('old', 'note', x'00', x'01', '2024-01-01T00:00:00Z', '2024-01-01T00:00:00Z');",
+            )
+            .unwrap();
+        }
+        let status = Db::schema_status(&path).unwrap();
+        assert_eq!((status.version, status.pending.len()), (0, MIGRATIONS.len()));
+
+        let db = Db::open(&path).unwrap();
+        assert_eq!(db.schema_version().unwrap(), SCHEMA_VERSION);
+        assert_eq!(db.list_items().unwrap()[0].name, "old");
+        assert_eq!(Db::verify_snapshot(&status.snapshot_path).unwrap(), 1);
+        let _ = std::fs::remove_file(&status.snapshot_path);
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_newer_vault_is_refused() {
+        let path = tmp_path("newer");
+        drop(Db::open(&path).unwrap());
+        Connection::open(&path)
+            .unwrap()
+            .pragma_update(None, "user_version", SCHEMA_VERSION + 1)
+            .unwrap();
+        assert!(Db::open(&path).is_err());
+        assert!(Db::schema_status(&path).is_err());
+        let _ = std::fs::remove_file(&path);
+    }
+}
@@ -0,0 +1,197 @@
+use crate::db::ItemHeaderRow;
+use crate::{HmacSha256, ItemRow, KeyMaterial, Vault, aead_decrypt, aead_encrypt, derive_subkey};
+use color_eyre::Result;
+use color_eyre::eyre::eyre;
+use hmac::Mac;
+use serde::{Deserialize, Serialize};
+use std::fmt::Write;
+
+/// Name and kind of an item, encrypted in vaults with private metadata.
+#[derive(Serialize, Deserialize)]
+struct ItemMetadata {
+    name: String,
+    kind: String,
+}
+
+/// Returns the blind index stored in place of `name`: a keyed HMAC of the name, so equal names
+/// collide in the unique name index and can be looked up without revealing them.
+pub(crate) fn name_index(vk: &KeyMaterial, name: &str) -> Result<String> {
+    let key = derive_subkey(vk, b"chamber-name-index")?;
+    let mut mac = <HmacSha256 as Mac>::new_from_slice(&key.0)?;
+    mac.update(name.as_bytes());
+    let mut index = String::with_capacity(64);
+    for byte in mac.finalize().into_bytes() {
+        write!(index, "{byte:02x}")?;
+    }
+    Ok(index)
+}
+
+/// Associated data of an encrypted header, binding it to the row's blind index.
+fn header_ad(index: &str) -> Vec<u8> {
+    let mut ad = b"chamber-item-header".to_vec();
+    ad.push(0x1f);
+    ad.extend_from_slice(index.as_bytes());
+    ad
+}
+
+/// Encrypts the name and kind of an item, returning its blind index and the sealed header.
+pub(crate) fn seal_header(vk: &KeyMaterial, name: &str, kind: &str) -> Result<(String, (Vec<u8>, Vec<u8>))> {
+    let index = name_index(vk, name)?;
+    let plaintext = serde_json::to_vec(&ItemMetadata {
+        name: name.to_string(),
+        kind: kind.to_string(),
+    })?;
+    let sealed = aead_encrypt(vk, &plaintext, &header_ad(&index))?;
+    Ok((index, sealed))
+}
+
+/// Decrypts the header stored with a private item and checks it against the row's blind index.
+fn open_header(vk: &KeyMaterial, index: &str, nonce: &[u8], ciphertext: &[u8]) -> Result<(String, String)> {
+    let plaintext = aead_decrypt(vk, nonce, ciphertext, &header_ad(index))?;
+    let header: ItemMetadata = serde_json::from_slice(&plaintext)?;
+    if name_index(vk, &header.name)? != index {
+        return Err(eyre!("Item header does not match its name index"));
+    }
+    Ok((header.name, header.kind))
+}
+
+impl ItemRow {
+    /// Replaces the blind index and empty kind of a private item with its decrypted name and
+    /// kind. Rows stored in plaintext are returned as they are.
+    pub(crate) fn reveal(mut self, vk: &KeyMaterial) -> Result<Self> {
+        if let (Some(nonce), Some(ciphertext)) = (&self.header_nonce, &self.header_ciphertext) {
+            (self.name, self.kind) = open_header(vk, &self.name, nonce, ciphertext)?;
+        }
+        Ok(self)
+    }
+}
+
+impl ItemHeaderRow {
+    /// Like [`ItemRow::reveal`], for rows listed without their values.
+    pub(crate) fn reveal(mut self, vk: &KeyMaterial) -> Result<Self> {
+        if let (Some(nonce), Some(ciphertext)) = (&self.header_nonce, &self.header_ciphertext) {
+            (self.name, self.kind) = open_header(vk, &self.name, nonce, ciphertext)?;
+        }
+        Ok(self)
+    }
+}
+
+impl Vault {
+    /// Returns whether item names and kinds are encrypted in the vault file.
+    ///
+    /// # Errors
+    /// Returns an error if the flag cannot be read.
+    pub fn has_private_metadata(&self) -> Result<bool> {
+        self.db.private_metadata()
+    }
+
+    /// Turns private metadata on or off and converts every item accordingly.
+    ///
+    /// With private metadata, an item's name and kind are encrypted with the vault key and the
+    /// `name` column holds a keyed HMAC of the name (a blind index), which keeps names unique
+    /// and lookups by name fast without storing them. All items are converted in one
+    /// transaction and the database is vacuumed afterwards, so the previous form no longer
+    /// lingers in the file. Returns the number of items converted.
+    ///
+    /// # Errors
+    /// Returns an error if the vault is locked or not initialized, an item fails to decrypt, or
+    /// the database update fails.
+    pub fn set_private_metadata(&mut self, enabled: bool) -> Result<usize> {
+        let vk = self.key.as_ref().ok_or_else(|| eyre!("Locked"))?;
+        if self.db.private_metadata()? == enabled {
+            return Ok(0);
+        }
+        let tx = self.db.transaction()?;
+        let rows = self.db.list_item_headers()?;
+        let count = rows.len();
+        for row in rows {
+            let row = row.reveal(vk)?;
+            if enabled {
+                let (index, (nonce, ciphertext)) = seal_header(vk, &row.name, &row.kind)?;
+                self.db
+                    .update_item_header(row.id, &index, "", Some((&nonce, &ciphertext)))?;
+            } else {
+                self.db.update_item_header(row.id, &row.name, &row.kind, None)?;
+            }
+        }
+        self.db.set_private_metadata(enabled)?;
+        tx.commit()?;
+        self.db.vacuum()?;
+        Ok(count)
+    }
+
+    /// Returns the value of the `name` column under which an item called `name` is stored.
+    pub(crate) fn stored_name(&self, vk: &KeyMaterial, name: &str) -> Result<String> {
+        if self.db.private_metadata()? {
+            name_index(vk, name)
+        } else {
+            Ok(name.to_string())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+    use super::*;
+    use crate::{ItemKind, NewItem};
+
+    fn new_item(name: &str, kind: ItemKind) -> NewItem {
+        NewItem {
+            name: name.into(),
+            kind,
+            value: format!("{name}-secret"),
+            tags: vec!["team".into()],
+            folder: None,
+            fields: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_private_metadata_hides_names_on_disk() {
+        let now = time::OffsetDateTime::now_utc().unix_timestamp_nanos();
+        let path = std::env::temp_dir().join(format!("chamber_private_{}_{now}.db", std::process::id()));
+        let mut vault = Vault::open_or_create(Some(&path)).unwrap();
+        vault.initialize("m").unwrap();
+        vault.unlock("m").unwrap();
+        vault.create_item(&new_item("zebra-bank", ItemKind::Password)).unwrap();
+        vault.create_item(&new_item("acme-mail", ItemKind::ApiKey)).unwrap();
+
+        assert_eq!(vault.set_private_metadata(true).unwrap(), 2);
+        assert!(vault.has_private_metadata().unwrap());
+        vault.create_item(&new_item("middle-cloud", ItemKind::Note)).unwrap();
+        assert!(vault.create_item(&new_item("acme-mail", ItemKind::Note)).is_err());
+        drop(vault);
+        let raw = std::fs::read(&path).unwrap();
+        for name in ["zebra-bank", "acme-mail", "middle-cloud"] {
+            assert!(
+                !raw.windows(name.len()).any(|w| w == name.as_bytes()),
+                "{name} stored in plaintext"
+            );
+        }
+
+        let mut vault = Vault::open_or_create(Some(&path)).unwrap();
+        vault.unlock("m").unwrap();
+        let names: Vec<_> = vault.list_item_headers().unwrap().into_iter().map(|h| h.name).collect();
+        assert_eq!(names, ["acme-mail", "middle-cloud", "zebra-bank"]);
+        let item = vault.get_item_by_name("acme-mail").unwrap().unwrap();
+        assert_eq!((item.kind, item.value.as_str()), (ItemKind::ApiKey, "acme-mail-secret"));
+        assert_eq!(item.tags, ["team"]);
+
+        // Archived revisions of private items must survive the rotation too
+        let id = vault.get_item_by_name("zebra-bank").unwrap().unwrap().id;
+        vault.update_item(id, "zebra-bank-new").unwrap();
+        vault.rotate_key("m", |_, _| {}).unwrap();
+        assert_eq!(
+            vault.get_item_by_name("zebra-bank").unwrap().unwrap().value,
+            "zebra-bank-new"
+        );
+        assert_eq!(vault.item_history(id).unwrap()[0].value, "zebra-bank-secret");
+
+        assert_eq!(vault.set_private_metadata(false).unwrap(), 3);
+        let names: Vec<_> = vault.list_items().unwrap().into_iter().map(|i| i.name).collect();
+        assert_eq!(names, ["acme-mail", "middle-cloud", "zebra-bank"]);
+        assert!(vault.get_item_by_name("middle-cloud").unwrap().is_some());
+        let _ = std::fs::remove_file(&path);
+    }
+}
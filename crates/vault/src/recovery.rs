@@ -0,0 +1,413 @@
+use crate::totp::decode_base32;
+use crate::{KeyMaterial, Vault, unwrap_vault_key, wrap_vault_key};
+use color_eyre::Result;
+use color_eyre::eyre::eyre;
+use sha2::{Digest, Sha256};
+use std::fmt;
+use std::str::FromStr;
+use zeroize::Zeroizing;
+
+/// Smallest number of shares that may be required to recover the vault.
+pub const MIN_RECOVERY_THRESHOLD: u8 = 2;
+
+/// Version byte at the start of every encoded share.
+const SHARE_VERSION: u8 = 1;
+/// Length of the truncated SHA-256 checksum that catches typos in a share.
+const CHECKSUM_LEN: usize = 4;
+/// Length of an encoded share: version, threshold, index, key share and checksum.
+const SHARE_LEN: usize = 3 + 32 + CHECKSUM_LEN;
+/// Number of base32 characters between the dashes of a printed share.
+const GROUP_LEN: usize = 5;
+
+const BASE32_ALPHABET: &[u8; 32] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+
+/// One Shamir share of a vault's recovery key.
+///
+/// Shares are printed as dash-separated base32 groups that carry the threshold, the share index
+/// and a checksum, so a mistyped share is rejected before any key material is combined.
+#[derive(Clone, PartialEq, Eq)]
+pub struct RecoveryShare {
+    threshold: u8,
+    index: u8,
+    value: Zeroizing<[u8; 32]>,
+}
+
+impl RecoveryShare {
+    /// Number of shares needed to recover the vault.
+    #[must_use]
+    pub const fn threshold(&self) -> u8 {
+        self.threshold
+    }
+
+    /// Position of this share, from 1 to the number of shares created.
+    #[must_use]
+    pub const fn index(&self) -> u8 {
+        self.index
+    }
+
+    fn to_bytes(&self) -> Zeroizing<Vec<u8>> {
+        let mut bytes = Zeroizing::new(Vec::with_capacity(SHARE_LEN));
+        bytes.extend_from_slice(&[SHARE_VERSION, self.threshold, self.index]);
+        bytes.extend_from_slice(self.value.as_ref());
+        let checksum = Sha256::digest(bytes.as_slice());
+        bytes.extend_from_slice(&checksum[..CHECKSUM_LEN]);
+        bytes
+    }
+}
+
+impl fmt::Debug for RecoveryShare {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RecoveryShare")
+            .field("threshold", &self.threshold)
+            .field("index", &self.index)
+            .finish_non_exhaustive()
+    }
+}
+
+impl fmt::Display for RecoveryShare {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let encoded = Zeroizing::new(encode_base32(&self.to_bytes()));
+        for (i, group) in encoded.as_bytes().chunks(GROUP_LEN).enumerate() {
+            if i > 0 {
+                f.write_str("-")?;
+            }
+            // The alphabet is ASCII, so every group is valid UTF-8
+            f.write_str(std::str::from_utf8(group).map_err(|_| fmt::Error)?)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for RecoveryShare {
+    type Err = color_eyre::Report;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let bytes = Zeroizing::new(decode_base32(s.trim()).map_err(|_| eyre!("Recovery share is not valid base32"))?);
+        if bytes.len() != SHARE_LEN {
+            return Err(eyre!("Recovery share has the wrong length"));
+        }
+        let (body, checksum) = bytes.split_at(SHARE_LEN - CHECKSUM_LEN);
+        if Sha256::digest(body)[..CHECKSUM_LEN] != *checksum {
+            return Err(eyre!("Recovery share checksum does not match; check it for typos"));
+        }
+        if body[0] != SHARE_VERSION {
+            return Err(eyre!("Unsupported recovery share version {}", body[0]));
+        }
+        let (threshold, index) = (body[1], body[2]);
+        if threshold < MIN_RECOVERY_THRESHOLD || index == 0 {
+            return Err(eyre!("Recovery share is malformed"));
+        }
+        let mut value = Zeroizing::new([0u8; 32]);
+        value.copy_from_slice(&body[3..]);
+        Ok(Self {
+            threshold,
+            index,
+            value,
+        })
+    }
+}
+
+impl Vault {
+    /// Sets up master key recovery by wrapping the vault key with a new random recovery key and
+    /// splitting that key into `shares` Shamir shares, any `threshold` of which recover the vault.
+    ///
+    /// Creating shares again replaces the recovery key, so earlier shares stop working.
+    ///
+    /// # Errors
+    /// Returns an error if the vault is locked, the threshold is below
+    /// [`MIN_RECOVERY_THRESHOLD`] or above `shares`, or the recovery key cannot be stored.
+    pub fn create_recovery_shares(&mut self, shares: u8, threshold: u8) -> Result<Vec<RecoveryShare>> {
+        let vault_key = self.key.as_ref().ok_or_else(|| eyre!("Vault is locked"))?;
+        if threshold < MIN_RECOVERY_THRESHOLD {
+            return Err(eyre!("Threshold must be at least {MIN_RECOVERY_THRESHOLD}"));
+        }
+        if threshold > shares {
+            return Err(eyre!("Threshold cannot be larger than the number of shares"));
+        }
+
+        let recovery_key = KeyMaterial::random();
+        let (wrapped, verifier) = wrap_vault_key(&recovery_key, vault_key)?;
+        let values = shamir::split(&recovery_key.0, shares, threshold)?;
+        self.db.write_recovery(Some((&wrapped, &verifier)))?;
+
+        Ok(values
+            .into_iter()
+            .zip(1..=shares)
+            .map(|(value, index)| RecoveryShare {
+                threshold,
+                index,
+                value,
+            })
+            .collect())
+    }
+
+    /// Returns whether recovery shares have been created for this vault.
+    ///
+    /// # Errors
+    /// Returns an error if the vault metadata cannot be read.
+    pub fn has_recovery(&self) -> Result<bool> {
+        Ok(self.db.read_recovery()?.is_some())
+    }
+
+    /// Removes the recovery key so existing shares can no longer recover the vault.
+    ///
+    /// # Errors
+    /// Returns an error if the vault is locked or the metadata cannot be updated.
+    pub fn remove_recovery(&mut self) -> Result<()> {
+        if self.key.is_none() {
+            return Err(eyre!("Vault is locked"));
+        }
+        self.db.write_recovery(None)
+    }
+
+    /// Reconstructs the recovery key from `shares`, sets `new_master` as the master key and leaves
+    /// the vault unlocked. The recovery key itself stays valid.
+    ///
+    /// The vault is checked against its manifest like after [`Vault::unlock`], so a tampered
+    /// vault reports a [`Vault::tamper_warning`] and refuses changes.
+    ///
+    /// # Errors
+    /// Returns an error if recovery is not set up, fewer shares than the threshold are given,
+    /// the shares are duplicated or disagree on the threshold, or they do not belong to this vault.
+    pub fn recover_master_key(&mut self, shares: &[RecoveryShare], new_master: &str) -> Result<()> {
+        let (wrapped, verifier) = self
+            .db
+            .read_recovery()?
+            .ok_or_else(|| eyre!("Recovery is not set up for this vault"))?;
+        let threshold = shares
+            .first()
+            .ok_or_else(|| eyre!("No recovery shares given"))?
+            .threshold;
+        if shares.iter().any(|s| s.threshold != threshold) {
+            return Err(eyre!("Recovery shares are from different sets"));
+        }
+        if shares.len() < usize::from(threshold) {
+            return Err(eyre!("{threshold} recovery shares are needed, got {}", shares.len()));
+        }
+
+        let points: Vec<(u8, &[u8])> = shares.iter().map(|s| (s.index, s.value.as_slice())).collect();
+        let secret = shamir::combine(&points)?;
+        let mut recovery_key = KeyMaterial([0u8; 32]);
+        recovery_key.0.copy_from_slice(&secret);
+        let vault_key = unwrap_vault_key(&recovery_key, &wrapped, Some(&verifier))
+            .map_err(|_| eyre!("Recovery shares do not match this vault"))?;
+
+        self.set_master_key(vault_key.clone(), new_master)?;
+        self.finish_unlock(vault_key)
+    }
+}
+
+fn encode_base32(bytes: &[u8]) -> String {
+    let mut out = String::with_capacity(bytes.len().div_ceil(5) * 8);
+    let mut buffer: u32 = 0;
+    let mut bits = 0;
+    for &byte in bytes {
+        buffer = (buffer << 8) | u32::from(byte);
+        bits += 8;
+        while bits >= 5 {
+            bits -= 5;
+            out.push(char::from(BASE32_ALPHABET[((buffer >> bits) & 0x1f) as usize]));
+        }
+    }
+    if bits > 0 {
+        out.push(char::from(BASE32_ALPHABET[((buffer << (5 - bits)) & 0x1f) as usize]));
+    }
+    out
+}
+
+/// Shamir's secret sharing over GF(2^8), applied to each byte of the secret independently.
+mod shamir {
+    use color_eyre::Result;
+    use color_eyre::eyre::eyre;
+    use zeroize::Zeroizing;
+
+    /// Multiplies in GF(2^8) with the AES polynomial x^8 + x^4 + x^3 + x + 1.
+    const fn mul(mut a: u8, mut b: u8) -> u8 {
+        let mut product = 0;
+        while b != 0 {
+            if b & 1 != 0 {
+                product ^= a;
+            }
+            let carry = a & 0x80 != 0;
+            a <<= 1;
+            if carry {
+                a ^= 0x1b;
+            }
+            b >>= 1;
+        }
+        product
+    }
+
+    /// Inverts a non-zero element as a^254.
+    const fn inv(a: u8) -> u8 {
+        let mut result = 1;
+        let mut base = a;
+        let mut exp = 254u8;
+        while exp != 0 {
+            if exp & 1 != 0 {
+                result = mul(result, base);
+            }
+            base = mul(base, base);
+            exp >>= 1;
+        }
+        result
+    }
+
+    /// Splits `secret` into `shares` values for x = 1..=shares; any `threshold` of them recover it.
+    pub fn split(secret: &[u8; 32], shares: u8, threshold: u8) -> Result<Vec<Zeroizing<[u8; 32]>>> {
+        if threshold == 0 || threshold > shares {
+            return Err(eyre!("Invalid threshold {threshold} for {shares} shares"));
+        }
+        let mut values = vec![Zeroizing::new([0u8; 32]); usize::from(shares)];
+        let mut coefficients = Zeroizing::new(vec![0u8; usize::from(threshold)]);
+        for (byte, &secret_byte) in secret.iter().enumerate() {
+            coefficients[0] = secret_byte;
+            getrandom::fill(&mut coefficients[1..])?;
+            for (value, x) in values.iter_mut().zip(1..=shares) {
+                // Horner's rule, highest coefficient first
+                value[byte] = coefficients.iter().rev().fold(0, |acc, &c| mul(acc, x) ^ c);
+            }
+        }
+        Ok(values)
+    }
+
+    /// Recovers the secret from `(x, value)` points by Lagrange interpolation at x = 0.
+    pub fn combine(points: &[(u8, &[u8])]) -> Result<Zeroizing<Vec<u8>>> {
+        let len = points.first().map_or(0, |(_, value)| value.len());
+        for (i, &(x, value)) in points.iter().enumerate() {
+            if x == 0 || value.len() != len {
+                return Err(eyre!("Recovery share is malformed"));
+            }
+            if points[..i].iter().any(|&(other, _)| other == x) {
+                return Err(eyre!("Recovery share {x} was given more than once"));
+            }
+        }
+
+        let mut secret = Zeroizing::new(vec![0u8; len]);
+        for &(xi, value) in points {
+            // Lagrange basis polynomial for xi evaluated at 0; subtraction is xor in GF(2^8)
+            let basis = points
+                .iter()
+                .filter(|&&(xj, _)| xj != xi)
+                .fold(1, |acc, &(xj, _)| mul(acc, mul(xj, inv(xj ^ xi))));
+            for (out, &y) in secret.iter_mut().zip(value) {
+                *out ^= mul(y, basis);
+            }
+        }
+        Ok(secret)
+    }
+
+    #[cfg(test)]
+    mod tests {
+        #![allow(clippy::unwrap_used)]
+        use super::*;
+
+        #[test]
+        fn test_field_inverse() {
+            for a in 1..=255u8 {
+                assert_eq!(mul(a, inv(a)), 1);
+            }
+        }
+
+        #[test]
+        fn test_any_threshold_subset_recovers_secret() {
+            let secret = [7u8; 32];
+            let values = split(&secret, 5, 3).unwrap();
+            let point = |x: u8| (x, values[usize::from(x) - 1].as_slice());
+
+            for subset in [[1, 2, 3], [5, 1, 4], [2, 4, 5]] {
+                let points: Vec<_> = subset.iter().map(|&x| point(x)).collect();
+                assert_eq!(combine(&points).unwrap().as_slice(), secret);
+            }
+            // Below the threshold the result is unrelated to the secret
+            assert_ne!(combine(&[point(1), point(2)]).unwrap().as_slice(), secret);
+            assert!(combine(&[point(1), point(1), point(2)]).is_err());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+    use super::*;
+    use crate::test_support::new_item;
+
+    fn temp_vault(name: &str) -> (Vault, std::path::PathBuf) {
+        let now = time::OffsetDateTime::now_utc().unix_timestamp_nanos();
+        let path = std::env::temp_dir().join(format!("chamber_recovery_{name}_{}_{now}.db", std::process::id()));
+        let mut vault = Vault::open_or_create(Some(&path)).unwrap();
+        vault.initialize("old master").unwrap();
+        vault.unlock("old master").unwrap();
+        (vault, path)
+    }
+
+    #[test]
+    fn test_share_encoding_round_trip_and_checksum() {
+        let (mut vault, path) = temp_vault("encoding");
+        let share = vault.create_recovery_shares(3, 2).unwrap().remove(1);
+        let printed = share.to_string();
+        assert!(printed.split('-').all(|group| group.len() <= GROUP_LEN));
+
+        let parsed: RecoveryShare = printed.to_lowercase().parse().unwrap();
+        assert_eq!(parsed, share);
+        assert_eq!((parsed.threshold(), parsed.index()), (2, 2));
+
+        let typo = printed.replacen(&printed[..1], if printed.starts_with('A') { "B" } else { "A" }, 1);
+        assert!(typo.parse::<RecoveryShare>().is_err());
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_recover_master_key() {
+        let (mut vault, path) = temp_vault("recover");
+        assert!(!vault.has_recovery().unwrap());
+        let shares = vault.create_recovery_shares(5, 3).unwrap();
+        assert!(vault.has_recovery().unwrap());
+        let vault_key = vault.vault_key().unwrap();
+
+        // The recovery key survives a master key change
+        vault.change_master_key("old master", "forgotten").unwrap();
+
+        let mut locked = Vault::open_or_create(Some(&path)).unwrap();
+        assert!(locked.recover_master_key(&shares[..2], "new master").is_err());
+        assert!(
+            locked
+                .recover_master_key(&[shares[0].clone(), shares[0].clone(), shares[1].clone()], "new master")
+                .is_err()
+        );
+        locked
+            .recover_master_key(&[shares[4].clone(), shares[0].clone(), shares[2].clone()], "new master")
+            .unwrap();
+        assert_eq!(locked.vault_key().unwrap().0, vault_key.0);
+
+        let mut reopened = Vault::open_or_create(Some(&path)).unwrap();
+        assert!(reopened.unlock("forgotten").is_err());
+        reopened.unlock("new master").unwrap();
+
+        // Shares from a replaced recovery key no longer work
+        reopened.create_recovery_shares(5, 3).unwrap();
+        assert!(reopened.recover_master_key(&shares[..3], "other").is_err());
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_recovery_checks_the_vault_like_unlock() {
+        let (mut vault, path) = temp_vault("tampered");
+        vault.create_item(&new_item("a")).unwrap();
+        let shares = vault.create_recovery_shares(3, 2).unwrap();
+        drop(vault);
+
+        // Remove an item and the key check value, as in a vault from an older version
+        let conn = rusqlite::Connection::open(&path).unwrap();
+        conn.execute("DELETE FROM items", []).unwrap();
+        conn.execute("UPDATE meta SET key_check = NULL", []).unwrap();
+
+        let mut recovered = Vault::open_or_create(Some(&path)).unwrap();
+        recovered.recover_master_key(&shares[..2], "new master").unwrap();
+        assert_eq!(recovered.tamper_warning().unwrap().missing_items.len(), 1);
+        assert!(recovered.db.read_key_check().unwrap().is_some());
+        // The tampered state is not signed by the next change
+        assert!(recovered.create_item(&new_item("b")).is_err());
+        let _ = std::fs::remove_file(&path);
+    }
+}
@@ -1,8 +1,10 @@
 use color_eyre::Result;
 use color_eyre::eyre::eyre;
+use fs4::fs_std::FileExt;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::fs;
+use std::io::Write;
 use std::path::{Path, PathBuf};
 use time::OffsetDateTime;
 use uuid::Uuid;
@@ -27,6 +29,13 @@ pub struct VaultInfo {
     pub is_active: bool,
     /// Favorite/pinned status
     pub is_favorite: bool,
+    /// Keyfile mixed into the master key derivation, if the vault requires one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub keyfile: Option<PathBuf>,
+    /// Newest manifest version this machine has seen in the vault file, to notice when the
+    /// file is replaced by an older copy
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub manifest_version: Option<u64>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -131,6 +140,8 @@ impl VaultRegistry {
             category: VaultCategory::Personal,
             is_active: true,
             is_favorite: false,
+            keyfile: None,
+            manifest_version: None,
         };
 
         self.vaults.insert(vault_id.clone(), vault_info);
@@ -157,6 +168,8 @@ impl VaultRegistry {
             category: VaultCategory::Personal,
             is_active: true,
             is_favorite: false,
+            keyfile: None,
+            manifest_version: None,
         };
 
         self.vaults.insert(vault_id.clone(), vault_info);
@@ -245,6 +258,8 @@ impl VaultRegistry {
             category,
             is_active: false,
             is_favorite: false,
+            keyfile: None,
+            manifest_version: None,
         };
 
         self.vaults.insert(vault_id.clone(), vault_info);
@@ -386,6 +401,91 @@ impl VaultRegistry {
         Ok(())
     }
 
+    /// Sets or clears the keyfile required to unlock a vault.
+    ///
+    /// # Errors
+    /// Returns an error if the vault does not exist or the registry cannot be saved.
+    pub fn set_keyfile(&mut self, vault_id: &str, keyfile: Option<PathBuf>) -> Result<()> {
+        let vault = self
+            .vaults
+            .get_mut(vault_id)
+            .ok_or_else(|| eyre!("Vault with ID '{}' not found", vault_id))?;
+        vault.keyfile = keyfile;
+        self.save()
+    }
+
+    /// Finds the vault whose database is stored at `path`.
+    #[must_use]
+    pub fn find_by_path(&self, path: &Path) -> Option<&VaultInfo> {
+        self.vaults.values().find(|v| v.path == path)
+    }
+
+    /// Returns the keyfile registered for the vault stored at `path`. The registry is only read,
+    /// never created.
+    pub(crate) fn registered_keyfile(path: &Path) -> Option<PathBuf> {
+        let content = fs::read_to_string(Self::default_registry_path().ok()?).ok()?;
+        let registry: Self = serde_json::from_str(&content).ok()?;
+        registry.find_by_path(path)?.keyfile.clone()
+    }
+
+    /// Returns the newest manifest version recorded for the vault stored at `path`.
+    pub(crate) fn seen_manifest_version(path: &Path) -> Option<u64> {
+        Self::load_existing(&Self::default_registry_path().ok()?)?
+            .find_by_path(path)?
+            .manifest_version
+    }
+
+    /// Records `version` as seen for the vault stored at `path` if it is newer than the recorded
+    /// one, or forgets the recorded version with `None`. Vaults that are not registered are
+    /// skipped, and failures are ignored: the record only makes rollbacks detectable.
+    pub(crate) fn record_manifest_version(path: &Path, version: Option<u64>) {
+        if let Ok(registry_path) = Self::default_registry_path() {
+            let _ = Self::record_manifest_version_in(&registry_path, path, version);
+        }
+    }
+
+    /// Like [`VaultRegistry::record_manifest_version`], for the registry at `registry_path`.
+    ///
+    /// Every item change records a version, possibly from several processes at once, so the
+    /// registry is read and written back under its lock.
+    fn record_manifest_version_in(registry_path: &Path, path: &Path, version: Option<u64>) -> Result<()> {
+        let _lock = Self::lock(registry_path)?;
+        let Some(mut registry) = Self::load_existing(registry_path) else {
+            return Ok(());
+        };
+        let Some(info) = registry.vaults.values_mut().find(|v| v.path == path) else {
+            return Ok(());
+        };
+        if version.is_some() && info.manifest_version >= version {
+            return Ok(());
+        }
+        info.manifest_version = version;
+        registry.write()
+    }
+
+    /// Loads the registry at `registry_path` if it exists, without creating one.
+    fn load_existing(registry_path: &Path) -> Option<Self> {
+        let content = fs::read_to_string(registry_path).ok()?;
+        let mut registry: Self = serde_json::from_str(&content).ok()?;
+        registry.registry_path = registry_path.to_path_buf();
+        Some(registry)
+    }
+
+    /// Takes the exclusive lock that serializes writes to the registry at `registry_path`. The
+    /// lock is released when the returned file is dropped.
+    fn lock(registry_path: &Path) -> Result<fs::File> {
+        if let Some(parent) = registry_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let file = fs::OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(registry_path.with_extension("lock"))?;
+        file.lock_exclusive()?;
+        Ok(file)
+    }
+
     /// Deletes a vault identified by `vault_id` from the vault manager.
     ///
     /// # Parameters
@@ -452,12 +552,25 @@ impl VaultRegistry {
 
     /// Save registry to file
     fn save(&self) -> Result<()> {
-        if let Some(parent) = self.registry_path.parent() {
-            fs::create_dir_all(parent)?;
-        }
+        let _lock = Self::lock(&self.registry_path)?;
+        self.write()
+    }
+
+    /// Replaces the registry file with a temporary file renamed over it, so readers never see a
+    /// partly written registry. The caller holds the lock.
+    fn write(&self) -> Result<()> {
+        let dir = self
+            .registry_path
+            .parent()
+            .filter(|p| !p.as_os_str().is_empty())
+            .unwrap_or_else(|| Path::new("."));
+        fs::create_dir_all(dir)?;
 
         let content = serde_json::to_string_pretty(self)?;
-        fs::write(&self.registry_path, content)?;
+        let mut file = tempfile::NamedTempFile::new_in(dir)?;
+        file.write_all(content.as_bytes())?;
+        file.as_file().sync_all()?;
+        file.persist(&self.registry_path)?;
         Ok(())
     }
 
@@ -530,6 +643,8 @@ impl VaultRegistry {
             category,
             is_active: false,
             is_favorite: false,
+            keyfile: None,
+            manifest_version: None,
         };
 
         self.vaults.insert(vault_id.clone(), vault_info);
@@ -583,6 +698,8 @@ mod tests {
             category,
             is_active: false,
             is_favorite: false,
+            keyfile: None,
+            manifest_version: None,
         }
     }
 
@@ -1292,4 +1409,35 @@ mod tests {
             assert_eq!(id.chars().filter(|&c| c == '-').count(), 4); // Should have 4 hyphens
         }
     }
+
+    #[test]
+    fn test_record_manifest_version_from_many_threads_keeps_the_newest() {
+        let (mut registry, temp_dir) = create_temp_registry();
+        let vault = create_test_vault_info("v", "Vault", VaultCategory::Personal);
+        let vault_path = vault.path.clone();
+        registry.vaults.insert("v".to_string(), vault);
+        registry.save().unwrap();
+
+        let registry_path = registry.registry_path.clone();
+        std::thread::scope(|scope| {
+            for thread in 0..8u64 {
+                let (registry_path, vault_path) = (&registry_path, &vault_path);
+                scope.spawn(move || {
+                    for version in 1..=10 {
+                        VaultRegistry::record_manifest_version_in(
+                            registry_path,
+                            vault_path,
+                            Some(version * 8 + thread),
+                        )
+                        .unwrap();
+                    }
+                });
+            }
+        });
+
+        let loaded = VaultRegistry::load_existing(&registry_path).unwrap();
+        assert_eq!(loaded.vaults["v"].manifest_version, Some(87));
+        // Only the registry and its lock file are left, no temporary files
+        assert_eq!(fs::read_dir(temp_dir.path()).unwrap().count(), 2);
+    }
 }
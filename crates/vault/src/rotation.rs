@@ -0,0 +1,186 @@
+use crate::crypto::key_check_value;
+use crate::private_metadata::seal_header;
+use crate::{
+    KeyMaterial, Vault, VaultRegistry, aead_decrypt, aead_encrypt, decrypt_revision, open_item, unwrap_vault_key,
+    wrap_vault_key,
+};
+use color_eyre::Result;
+use color_eyre::eyre::eyre;
+use zeroize::Zeroizing;
+
+/// What [`Vault::rotate_key`] re-encrypted and revoked.
+#[derive(Debug, Clone, Default)]
+pub struct KeyRotation {
+    pub items: usize,
+    /// Archived revisions of the items.
+    pub revisions: usize,
+    pub attachments: usize,
+    /// Key slots that were removed because they wrap the old vault key.
+    pub revoked_key_slots: Vec<String>,
+    /// Whether recovery shares were set up; they wrap the old vault key and stop working.
+    pub recovery_removed: bool,
+}
+
+impl Vault {
+    /// Replaces the vault key with a new random key and re-encrypts every item, revision and
+    /// attachment with it.
+    ///
+    /// Unlike [`Vault::change_master_key`], which only re-wraps the existing vault key, this
+    /// protects the vault after the vault key itself has leaked. Everything happens in a single
+    /// transaction that is only committed once all data has been checked to decrypt with the
+    /// new key, so an interrupted rotation leaves the vault as it was. Key slots and recovery
+    /// shares wrap the old key and are removed. `progress` is called with the number of items
+    /// done and the total.
+    ///
+    /// # Errors
+    /// Returns an error if the vault is not initialized, `master` is not the master key, there
+    /// is a tamper warning that was not accepted, any stored data fails to decrypt with the old
+    /// or new key, or the database update fails.
+    pub fn rotate_key(&mut self, master: &str, mut progress: impl FnMut(usize, usize)) -> Result<KeyRotation> {
+        self.ensure_manifest_accepted()?;
+        let (kdf, wrapped, verifier) = self.db.read_meta()?.ok_or_else(|| eyre!("Vault not initialized"))?;
+        let derived = self.derive_master(master, &kdf)?;
+        let _ = unwrap_vault_key(&derived, &wrapped, Some(&verifier)).map_err(|_| eyre!("Invalid master key"))?;
+        let old = unwrap_vault_key(&derived, &wrapped, None)?;
+        let new = KeyMaterial::random();
+        let mut rotation = KeyRotation::default();
+
+        let tx = self.db.transaction()?;
+        // Unfinished uploads are encrypted with the old key and could never be completed
+        self.db.delete_pending_attachments()?;
+        let items = self.db.list_items()?;
+        let total = items.len();
+        for (done, row) in items.into_iter().enumerate() {
+            progress(done, total);
+            let row = row.reveal(&old)?;
+            // The blind index of a private item is keyed by the vault key, so it changes too
+            if row.header_nonce.is_some() {
+                let (index, (nonce, ciphertext)) = seal_header(&new, &row.name, &row.kind)?;
+                self.db
+                    .update_item_header(row.id, &index, "", Some((&nonce, &ciphertext)))?;
+            }
+            let ad = row.ad();
+            let plaintext = Zeroizing::new(aead_decrypt(&old, &row.nonce, &row.ciphertext, &ad)?);
+            let (nonce, ciphertext) = aead_encrypt(&new, &plaintext, &ad)?;
+            self.db.reseal_item(row.id, &nonce, &ciphertext)?;
+
+            if let (Some(nonce), Some(ciphertext)) = (&row.labels_nonce, &row.labels_ciphertext) {
+                let ad = row.labels_ad();
+                let labels = Zeroizing::new(aead_decrypt(&old, nonce, ciphertext, &ad)?);
+                let (nonce, ciphertext) = aead_encrypt(&new, &labels, &ad)?;
+                self.db.update_labels(row.id, Some((&nonce, &ciphertext)))?;
+            }
+
+            for revision in self.db.list_history(row.id)? {
+                let value = Zeroizing::new(aead_decrypt(&old, &revision.nonce, &revision.ciphertext, &ad)?);
+                let (nonce, ciphertext) = aead_encrypt(&new, &value, &ad)?;
+                self.db.reseal_history(row.id, revision.revision, &nonce, &ciphertext)?;
+                rotation.revisions += 1;
+            }
+
+            rotation.attachments += self.reencrypt_attachments(row.id, &old, &new)?;
+        }
+        rotation.items = total;
+        progress(total, total);
+
+        // Key slots and recovery shares cannot be re-wrapped without their secrets
+        for slot in self.db.list_key_slots()? {
+            self.db.delete_key_slot(&slot.name)?;
+            rotation.revoked_key_slots.push(slot.name);
+        }
+        if self.db.read_recovery()?.is_some() {
+            self.db.write_recovery(None)?;
+            rotation.recovery_removed = true;
+        }
+
+        let kdf_new = self.fresh_kdf_params()?;
+        let derived_new = self.derive_master(master, &kdf_new)?;
+        let (wrapped_new, verifier_new) = wrap_vault_key(&derived_new, &new)?;
+        self.db.write_meta(&kdf_new, &wrapped_new, &verifier_new)?;
+        self.db.write_key_check(&key_check_value(&new)?)?;
+
+        self.verify_rotation(master, &new)
+            .map_err(|e| eyre!("Key rotation could not be verified, the vault is unchanged: {e}"))?;
+        let version = self.sign_manifest(&new)?;
+        tx.commit()?;
+        VaultRegistry::record_manifest_version(&self.db_path, Some(version));
+        self.key = Some(new);
+        self.tamper_warning = None;
+        Ok(rotation)
+    }
+
+    /// Checks, before the rotation is committed, that the master key unwraps `new` and that
+    /// every item, revision and attachment decrypts with it.
+    fn verify_rotation(&self, master: &str, new: &KeyMaterial) -> Result<()> {
+        let (kdf, wrapped, verifier) = self.db.read_meta()?.ok_or_else(|| eyre!("Vault not initialized"))?;
+        let unwrapped = unwrap_vault_key(&self.derive_master(master, &kdf)?, &wrapped, Some(&verifier))?;
+        if unwrapped.0 != new.0 {
+            return Err(eyre!("The stored vault key does not match the new key"));
+        }
+        for row in self.db.list_items()? {
+            // Revisions are bound to the real name and kind, not the blind index of a private item
+            let row = row.reveal(new)?;
+            let (id, ad) = (row.id, row.ad());
+            open_item(new, row)?;
+            for revision in self.db.list_history(id)? {
+                decrypt_revision(new, &ad, &revision)?;
+            }
+            self.verify_attachments(id, new)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+    use super::*;
+    use crate::{ItemKind, NewItem};
+
+    #[test]
+    fn test_rotate_key_reencrypts_everything() {
+        let now = time::OffsetDateTime::now_utc().unix_timestamp_nanos();
+        let path = std::env::temp_dir().join(format!("chamber_rotation_{}_{now}.db", std::process::id()));
+        let mut vault = Vault::open_or_create(Some(&path)).unwrap();
+        vault.initialize("m").unwrap();
+        vault.unlock("m").unwrap();
+        vault
+            .create_item(&NewItem {
+                name: "db".into(),
+                kind: ItemKind::Password,
+                value: "v1".into(),
+                tags: vec!["prod".into()],
+                folder: Some("work".into()),
+                fields: Vec::new(),
+            })
+            .unwrap();
+        let id = vault.list_items().unwrap()[0].id;
+        vault.update_item(id, "v2").unwrap();
+        let chunks = vec![7u8; crate::ATTACHMENT_CHUNK_SIZE + 10];
+        vault.add_attachment(id, "blob.bin", &mut chunks.as_slice()).unwrap();
+        vault.add_key_slot("alice", "alice pw").unwrap();
+        vault.create_recovery_shares(3, 2).unwrap();
+        let old_key = vault.vault_key().unwrap();
+
+        assert!(vault.rotate_key("wrong", |_, _| {}).is_err());
+        let mut calls = Vec::new();
+        let rotation = vault.rotate_key("m", |done, total| calls.push((done, total))).unwrap();
+        assert_eq!((rotation.items, rotation.revisions, rotation.attachments), (1, 1, 1));
+        assert_eq!(rotation.revoked_key_slots, ["alice"]);
+        assert!(rotation.recovery_removed);
+        assert_eq!(calls.last(), Some(&(1, 1)));
+
+        let mut reopened = Vault::open_or_create(Some(&path)).unwrap();
+        assert!(reopened.unlock_with_key(old_key).is_err());
+        assert!(reopened.unlock("alice pw").is_err());
+        reopened.unlock("m").unwrap();
+        let item = reopened.get_item_by_id(id).unwrap().unwrap();
+        assert_eq!((item.value.as_str(), item.folder.as_deref()), ("v2", Some("work")));
+        assert_eq!(reopened.item_history(id).unwrap()[0].value, "v1");
+        let mut contents = Vec::new();
+        let attachment = reopened.list_attachments(id).unwrap()[0].id;
+        reopened.read_attachment(attachment, &mut contents).unwrap();
+        assert_eq!(contents, chunks);
+        let _ = std::fs::remove_file(&path);
+    }
+}
@@ -0,0 +1,27 @@
+//! Fixtures shared by the unit tests of the vault crate.
+
+use crate::{ItemKind, NewItem, Vault};
+use std::path::PathBuf;
+
+/// A password item called `name` whose value is `<name>-secret`.
+pub fn new_item(name: &str) -> NewItem {
+    NewItem {
+        name: name.into(),
+        kind: ItemKind::Password,
+        value: format!("{name}-secret"),
+        tags: Vec::new(),
+        folder: None,
+        fields: Vec::new(),
+    }
+}
+
+/// Creates a vault in the temp directory, initialized and unlocked with the master key `m`, and
+/// returns it with its path. `name` keeps the files of different tests apart.
+pub fn unlocked_vault(name: &str) -> (Vault, PathBuf) {
+    let now = time::OffsetDateTime::now_utc().unix_timestamp_nanos();
+    let path = std::env::temp_dir().join(format!("chamber_{name}_{}_{now}.db", std::process::id()));
+    let mut vault = Vault::open_or_create(Some(&path)).unwrap();
+    vault.initialize("m").unwrap();
+    vault.unlock("m").unwrap();
+    (vault, path)
+}
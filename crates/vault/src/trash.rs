@@ -0,0 +1,245 @@
+use crate::{ItemKind, Vault};
+use color_eyre::Result;
+use color_eyre::eyre::eyre;
+use std::str::FromStr;
+use time::{Duration, OffsetDateTime};
+
+/// Days a deleted item stays in the trash of a vault that has not configured otherwise.
+pub const DEFAULT_TRASH_RETENTION_DAYS: u32 = 30;
+
+/// An item that was deleted and can still be restored.
+#[derive(Debug, Clone)]
+pub struct TrashedItem {
+    pub id: u64,
+    pub name: String,
+    pub kind: ItemKind,
+    pub deleted_at: OffsetDateTime,
+    /// When the item is purged automatically; `None` if the vault keeps deleted items until the
+    /// trash is emptied.
+    pub purge_at: Option<OffsetDateTime>,
+}
+
+impl Vault {
+    /// Lists the items in the trash, most recently deleted first.
+    ///
+    /// # Errors
+    /// Returns an error if the vault is locked, the query fails or an item's name cannot be
+    /// decrypted.
+    pub fn list_trash(&self) -> Result<Vec<TrashedItem>> {
+        let vk = self.key.as_ref().ok_or_else(|| eyre!("Locked"))?;
+        let retention = self.db.trash_retention_days()?;
+        let mut items = self
+            .db
+            .list_item_headers()?
+            .into_iter()
+            .filter_map(|r| r.deleted_at.map(|deleted_at| (r, deleted_at)))
+            .map(|(r, deleted_at)| {
+                let r = r.reveal(vk)?;
+                Ok(TrashedItem {
+                    id: r.id,
+                    kind: ItemKind::from_str(&r.kind)?,
+                    name: r.name,
+                    deleted_at,
+                    purge_at: (retention > 0).then(|| deleted_at + Duration::days(i64::from(retention))),
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        items.sort_by_key(|t| std::cmp::Reverse(t.deleted_at));
+        Ok(items)
+    }
+
+    /// Takes an item out of the trash, with its history and attachments.
+    ///
+    /// # Errors
+    /// Returns an error if the vault is locked, the item is not in the trash, another item has
+    /// taken its name in the meantime, or the update fails.
+    pub fn restore_item(&mut self, id: u64) -> Result<()> {
+        self.ensure_manifest_accepted()?;
+        let vk = self.key.as_ref().ok_or_else(|| eyre!("Locked"))?;
+        let row = self
+            .db
+            .get_item(id)?
+            .filter(|r| r.deleted_at.is_some())
+            .ok_or_else(|| eyre!("Item {id} is not in the trash"))?;
+        // `name` is the stored name, so this also works for private metadata
+        if self.db.get_item_by_name(&row.name)?.is_some() {
+            let name = row.reveal(vk)?.name;
+            return Err(eyre!(
+                "An item named '{name}' already exists; rename or delete it before restoring"
+            ));
+        }
+        self.db.set_deleted_at(id, None)?;
+        self.seal_manifest()
+    }
+
+    /// Deletes an item in the trash for good, with its history and attachments.
+    ///
+    /// # Errors
+    /// Returns an error if the vault is locked, the item is not in the trash or the deletion
+    /// fails.
+    pub fn purge_item(&mut self, id: u64) -> Result<()> {
+        if self.key.is_none() {
+            return Err(eyre!("Locked"));
+        }
+        self.ensure_manifest_accepted()?;
+        if self.db.get_item(id)?.is_none_or(|r| r.deleted_at.is_none()) {
+            return Err(eyre!("Item {id} is not in the trash"));
+        }
+        self.db.delete_item(id)?;
+        self.seal_manifest()
+    }
+
+    /// Deletes every item in the trash for good and returns how many were deleted.
+    ///
+    /// # Errors
+    /// Returns an error if the vault is locked or the deletion fails; in that case nothing is
+    /// deleted.
+    pub fn empty_trash(&mut self) -> Result<usize> {
+        self.purge_trash_deleted_before(None)
+    }
+
+    /// Returns how many days deleted items stay in the trash before they are purged when the
+    /// vault is unlocked; 0 keeps them until the trash is emptied.
+    ///
+    /// # Errors
+    /// Returns an error if the setting cannot be read.
+    pub fn trash_retention_days(&self) -> Result<u32> {
+        self.db.trash_retention_days()
+    }
+
+    /// Sets how many days deleted items stay in the trash; 0 keeps them until the trash is
+    /// emptied. Items already past the new period are purged at the next unlock.
+    ///
+    /// The period is signed into the manifest, so it cannot be shortened outside Chamber.
+    ///
+    /// # Errors
+    /// Returns an error if the vault is locked or not initialized, or the update fails.
+    pub fn set_trash_retention_days(&mut self, days: u32) -> Result<()> {
+        if self.key.is_none() {
+            return Err(eyre!("Locked"));
+        }
+        self.ensure_manifest_accepted()?;
+        self.db.set_trash_retention_days(days)?;
+        self.seal_manifest()
+    }
+
+    /// Purges the items that have been in the trash longer than the retention period. Run
+    /// after every unlock.
+    pub(crate) fn purge_expired_trash(&mut self) -> Result<usize> {
+        let days = self.db.trash_retention_days()?;
+        if days == 0 {
+            return Ok(0);
+        }
+        let cutoff = OffsetDateTime::now_utc() - Duration::days(i64::from(days));
+        self.purge_trash_deleted_before(Some(cutoff))
+    }
+
+    fn purge_trash_deleted_before(&mut self, cutoff: Option<OffsetDateTime>) -> Result<usize> {
+        if self.key.is_none() {
+            return Err(eyre!("Locked"));
+        }
+        self.ensure_manifest_accepted()?;
+        let expired: Vec<_> = self
+            .db
+            .list_item_headers()?
+            .into_iter()
+            .filter(|r| r.deleted_at.is_some_and(|t| cutoff.is_none_or(|cutoff| t < cutoff)))
+            .map(|r| r.id)
+            .collect();
+        if expired.is_empty() {
+            return Ok(0);
+        }
+        let tx = self.db.transaction()?;
+        for id in &expired {
+            self.db.delete_item(*id)?;
+        }
+        tx.commit()?;
+        self.seal_manifest()?;
+        Ok(expired.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+    use super::*;
+    use crate::test_support::{new_item, unlocked_vault};
+    use rusqlite::Connection;
+
+    #[test]
+    fn test_deleted_items_can_be_restored_until_purged() {
+        let (mut vault, path) = unlocked_vault("trash");
+        for name in ["a", "b", "c"] {
+            vault.create_item(&new_item(name)).unwrap();
+        }
+        let a = vault.get_item_by_name("a").unwrap().unwrap().id;
+        let b = vault.get_item_by_name("b").unwrap().unwrap().id;
+        vault.delete_item(a).unwrap();
+        vault.delete_item(b).unwrap();
+        assert_eq!(vault.list_items().unwrap().len(), 1);
+        assert!(vault.get_item_by_id(a).unwrap().is_none());
+        let trash = vault.list_trash().unwrap();
+        assert_eq!(trash.len(), 2);
+        assert!(trash[0].purge_at.is_some());
+
+        // A name in the trash can be reused, but then the trashed item cannot come back
+        vault.create_item(&new_item("a")).unwrap();
+        assert!(vault.restore_item(a).is_err());
+        vault.restore_item(b).unwrap();
+        assert_eq!(vault.get_item_by_name("b").unwrap().unwrap().value, "b-secret");
+
+        // A deletion date set outside Chamber is noticed and purges nothing
+        vault.delete_item(b).unwrap();
+        Connection::open(&path)
+            .unwrap()
+            .execute("UPDATE items SET deleted_at = '2000-01-01T00:00:00Z' WHERE id = ?", [b])
+            .unwrap();
+        let mut reopened = Vault::open_or_create(Some(&path)).unwrap();
+        reopened.unlock("m").unwrap();
+        assert_eq!(reopened.tamper_warning().unwrap().changed_items, [b]);
+        assert_eq!(reopened.list_trash().unwrap().len(), 2);
+
+        // Once accepted, items past the retention period are purged when the vault is unlocked
+        reopened.accept_manifest().unwrap();
+        let mut reopened = Vault::open_or_create(Some(&path)).unwrap();
+        reopened.unlock("m").unwrap();
+        assert!(reopened.tamper_warning().is_none());
+        let trash = reopened.list_trash().unwrap();
+        assert_eq!(trash.iter().map(|t| t.id).collect::<Vec<_>>(), [a]);
+
+        assert_eq!(reopened.empty_trash().unwrap(), 1);
+        assert!(reopened.list_trash().unwrap().is_empty());
+        assert_eq!(reopened.list_items().unwrap().len(), 2);
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_trash_forged_outside_chamber_purges_nothing() {
+        let (mut vault, path) = unlocked_vault("trash_forged");
+        for name in ["a", "b"] {
+            vault.create_item(&new_item(name)).unwrap();
+        }
+        vault.set_trash_retention_days(7).unwrap();
+        drop(vault);
+
+        // Move every item into the trash long ago and shorten the retention period
+        let conn = Connection::open(&path).unwrap();
+        conn.execute("UPDATE items SET deleted_at = '2000-01-01T00:00:00Z'", [])
+            .unwrap();
+        conn.execute("UPDATE meta SET trash_retention_days = 1", []).unwrap();
+        let mut forged = Vault::open_or_create(Some(&path)).unwrap();
+        forged.unlock("m").unwrap();
+        let warning = forged.tamper_warning().unwrap();
+        assert_eq!(warning.changed_items.len(), 2);
+        assert_eq!(warning.trash_retention_changed, Some((1, 7)));
+        assert_eq!(forged.list_trash().unwrap().len(), 2);
+        drop(forged);
+
+        // Unlocking again still purges nothing
+        let mut again = Vault::open_or_create(Some(&path)).unwrap();
+        again.unlock("m").unwrap();
+        assert!(again.tamper_warning().is_some());
+        assert_eq!(again.list_trash().unwrap().len(), 2);
+        let _ = std::fs::remove_file(&path);
+    }
+}